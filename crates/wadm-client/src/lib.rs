@@ -133,6 +133,7 @@ impl Client {
         let body = if let Some(version) = version {
             serde_json::to_vec(&GetModelRequest {
                 version: Some(version.to_string()),
+                ..Default::default()
             })
             .map_err(SerializationError::from)?
         } else {
@@ -161,6 +162,9 @@ impl Client {
         let body = if let Some(version) = version {
             serde_json::to_vec(&DeleteModelRequest {
                 version: Some(version.to_string()),
+                archive: false,
+                check_dependents: false,
+                strict: false,
             })
             .map_err(SerializationError::from)?
         } else {
@@ -202,6 +206,7 @@ impl Client {
         let body = if let Some(version) = version {
             serde_json::to_vec(&DeployModelRequest {
                 version: Some(version.to_string()),
+                ..Default::default()
             })
             .map_err(SerializationError::from)?
         } else {
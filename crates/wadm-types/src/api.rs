@@ -1,15 +1,109 @@
+use std::collections::BTreeMap;
+
+use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 
-use crate::Manifest;
+use crate::{Component, Manifest, Metadata, Properties, Trait};
 
 /// The default topic prefix for the wadm API;
 pub const DEFAULT_WADM_TOPIC_PREFIX: &str = "wadm.api";
 
+/// A machine readable code describing why a request failed. This is included alongside the human
+/// readable message in error responses so that clients can tell retriable errors (such as storage,
+/// notification, or concurrent update errors) apart from fatal ones
+#[derive(Debug, Serialize, Deserialize, PartialEq, Eq, Clone, Copy)]
+#[serde(rename_all = "snake_case")]
+pub enum ErrorCode {
+    /// The request was malformed or had invalid parameters
+    InvalidRequest,
+    /// The manifest failed validation
+    ValidationFailed,
+    /// The request conflicts with an existing version or a concurrent update. Retrying may
+    /// succeed if the conflict was due to a concurrent update
+    VersionConflict,
+    /// The target of the request already exists
+    AlreadyExists,
+    /// The requested model or version was not found
+    NotFound,
+    /// An error occurred when reading from or writing to storage. This is likely transient
+    StorageError,
+    /// Processors could not be notified of a change. This is likely transient
+    NotificationError,
+    /// The account has reached a limit, such as the maximum number of models it is allowed to store
+    /// or the maximum number of watches it can run at once
+    QuotaExceeded,
+    /// The request was denied by the server's authorization policy
+    Forbidden,
+    /// The requested operation is not supported
+    Unsupported,
+    /// The request would break other deployed models that depend on the target
+    HasDependents,
+    /// Storage or processor notification took too long to respond. This is likely transient, but
+    /// the change may have been applied, so check the current state before retrying
+    Timeout,
+    /// The response was larger than the maximum message size allowed by NATS, so it couldn't be
+    /// sent. Request less data at once, such as by paginating
+    ResponseTooLarge,
+    /// Another deploy of the same model is in progress. Retry once it has finished
+    DeployInProgress,
+    /// Any other internal error
+    Internal,
+}
+
+impl ErrorCode {
+    /// Returns true if the request that caused this error may succeed if retried
+    pub fn is_retriable(&self) -> bool {
+        self.retry_after_ms().is_some()
+    }
+
+    /// Returns how long (in milliseconds) clients should wait before retrying a request that failed
+    /// with this error. Returns `None` for errors that won't succeed if retried
+    pub fn retry_after_ms(&self) -> Option<u64> {
+        match self {
+            // Conflicts clear up as soon as the concurrent update is done
+            ErrorCode::VersionConflict => Some(100),
+            ErrorCode::StorageError | ErrorCode::NotificationError | ErrorCode::Internal => {
+                Some(1000)
+            }
+            // Deploys only hold the lock for as long as it takes to store and notify
+            ErrorCode::DeployInProgress => Some(500),
+            // The change may still be applied after a timeout, so give it time to finish before
+            // the client checks the state and retries
+            ErrorCode::Timeout => Some(5000),
+            ErrorCode::InvalidRequest
+            | ErrorCode::ValidationFailed
+            | ErrorCode::AlreadyExists
+            | ErrorCode::NotFound
+            | ErrorCode::QuotaExceeded
+            | ErrorCode::Forbidden
+            | ErrorCode::Unsupported
+            | ErrorCode::HasDependents
+            | ErrorCode::ResponseTooLarge => None,
+        }
+    }
+}
+
 /// The request body for getting a manifest
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Default, Serialize, Deserialize)]
 pub struct GetModelRequest {
     #[serde(skip_serializing_if = "Option::is_none")]
     pub version: Option<String>,
+    /// Fetch several versions at once. The manifests are returned in `versions` on the response
+    /// rather than `manifest`. This can't be combined with `version`
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub versions: Vec<String>,
+    /// Fetch the version created just before or after `version` (or the deployed version if
+    /// `version` isn't set) instead of the version itself. This can't be combined with `versions`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub relative: Option<RelativeVersion>,
+}
+
+/// A version relative to another version of a model, in the order versions were created
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum RelativeVersion {
+    Previous,
+    Next,
 }
 
 /// The response from a get request
@@ -20,6 +114,28 @@ pub struct GetModelResponse {
     pub message: String,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub manifest: Option<Manifest>,
+    /// The manifest for each requested version when fetching several versions at once. Versions
+    /// that don't exist are set to `null`
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub versions: BTreeMap<String, Option<Manifest>>,
+}
+
+/// The response from a get_raw request, containing a model exactly as it is stored, without
+/// decoding it into a [`Manifest`]
+#[derive(Debug, Serialize, Deserialize)]
+pub struct GetRawModelResponse {
+    pub result: GetResult,
+    #[serde(default)]
+    pub message: String,
+    /// The revision of the stored model
+    #[serde(default)]
+    pub revision: u64,
+    /// The codec the stored data is compressed with (`none`, `gzip`, or `zstd`)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub encoding: Option<String>,
+    /// The stored bytes, base64 encoded
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub data: Option<String>,
 }
 
 /// Possible outcomes of a get request
@@ -43,6 +159,28 @@ pub struct PutModelResponse {
     pub message: String,
     #[serde(default)]
     pub name: String,
+    /// Set when the request asked for the new version to be deployed and it couldn't be. The
+    /// manifest is still stored when this is set
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub deploy_error: Option<String>,
+    /// The schema errors found in the manifest, if it failed schema validation
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub errors: Vec<SchemaError>,
+    /// The revision of the stored model after this request, which can be used for conditional
+    /// requests. This is 0 if the model wasn't stored
+    #[serde(default)]
+    pub revision: u64,
+}
+
+/// A single error found when validating a manifest against the OAM schema
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq)]
+pub struct SchemaError {
+    /// A JSON pointer to the location in the manifest that failed validation, such as
+    /// `/spec/components/0/properties`
+    pub path: String,
+    pub message: String,
+    /// The schema keyword that failed, such as `required` or `type`
+    pub keyword: String,
 }
 
 /// Possible outcomes of a put request
@@ -54,6 +192,70 @@ pub enum PutResult {
     NewVersion,
 }
 
+/// A request to put multiple manifests at once. The manifests are applied all or nothing: if any of
+/// them fail validation or can't be stored, none of them are kept
+#[derive(Debug, Serialize, Deserialize)]
+pub struct PutModelsRequest {
+    pub manifests: Vec<Manifest>,
+}
+
+/// A response from a multi-manifest put request, containing the outcome for each manifest in the
+/// order they were given
+#[derive(Debug, Serialize, Deserialize)]
+pub struct PutModelsResponse {
+    /// Set to [`PutModelsResult::Success`] only if every manifest was stored
+    pub result: PutModelsResult,
+    #[serde(default)]
+    pub message: String,
+    #[serde(default)]
+    pub models: Vec<ModelPutResult>,
+}
+
+/// All possible outcomes of a multi-manifest put request
+#[derive(Debug, Serialize, Deserialize, PartialEq, Eq, Clone, Copy)]
+#[serde(rename_all = "lowercase")]
+pub enum PutModelsResult {
+    Error,
+    Success,
+}
+
+/// The outcome of putting a single manifest as part of a multi-manifest put request
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct ModelPutResult {
+    pub name: String,
+    #[serde(default)]
+    pub version: String,
+    pub result: ModelPutOutcome,
+    #[serde(default)]
+    pub message: String,
+    /// The schema errors found in the manifest, if it failed schema validation
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub errors: Vec<SchemaError>,
+}
+
+/// All possible outcomes of putting a single manifest in a multi-manifest put request
+#[derive(Debug, Serialize, Deserialize, PartialEq, Eq, Clone, Copy)]
+#[serde(rename_all = "snake_case")]
+pub enum ModelPutOutcome {
+    Error,
+    Created,
+    NewVersion,
+    /// The manifest was valid but was not stored (or was rolled back) because another manifest in
+    /// the request failed
+    NotApplied,
+    /// The manifest was stored, but another manifest in the request failed and this one couldn't be
+    /// rolled back, so it is still stored
+    NotRolledBack,
+}
+
+/// The request body for listing models. An empty body lists all models
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct ListModelsRequest {
+    /// Only return models that currently have a deployed version
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub deployed_only: bool,
+}
+
 /// Summary of a given model returned when listing
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct ModelSummary {
@@ -63,6 +265,91 @@ pub struct ModelSummary {
     pub deployed_version: Option<String>,
     pub status: StatusType,
     pub status_message: Option<String>,
+    /// The well-known annotations (such as owner and team) of the current version. See
+    /// [`SUMMARY_ANNOTATION_KEYS`](crate::SUMMARY_ANNOTATION_KEYS) for the included keys
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub annotations: BTreeMap<String, String>,
+    /// Whether the model is archived. Archived models are only returned when listing archived
+    /// models
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub archived: bool,
+    /// The number of stored versions of the model
+    #[serde(default)]
+    pub version_count: usize,
+    /// The size in bytes of the stored data of the model serialized as JSON, before any storage
+    /// compression is applied
+    #[serde(default)]
+    pub size_bytes: usize,
+}
+
+/// Aggregate statistics about the models in a lattice
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ModelStatsResponse {
+    pub result: GetResult,
+    #[serde(default)]
+    pub message: String,
+    #[serde(default)]
+    pub total_models: usize,
+    /// The number of models that have a deployed version
+    #[serde(default)]
+    pub deployed: usize,
+    /// The number of models that do not have a deployed version
+    #[serde(default)]
+    pub undeployed: usize,
+    /// The total number of stored versions across all models
+    #[serde(default)]
+    pub total_versions: usize,
+    /// The total size in bytes of the stored data of all models serialized as JSON, before any
+    /// storage compression is applied
+    #[serde(default)]
+    pub total_size_bytes: usize,
+    /// The number of models in each status
+    #[serde(default)]
+    pub status_counts: StatusCounts,
+}
+
+/// The number of models in each [`StatusType`]
+#[derive(Debug, Serialize, Deserialize, Default, Clone, PartialEq, Eq)]
+pub struct StatusCounts {
+    pub undeployed: usize,
+    pub reconciling: usize,
+    pub deployed: usize,
+    pub failed: usize,
+}
+
+impl StatusCounts {
+    /// Increments the count for the given status type
+    pub fn add(&mut self, status: StatusType) {
+        match status {
+            StatusType::Undeployed => self.undeployed += 1,
+            StatusType::Reconciling => self.reconciling += 1,
+            StatusType::Deployed => self.deployed += 1,
+            StatusType::Failed => self.failed += 1,
+        }
+    }
+}
+
+/// The request body for listing the versions of a model. An empty body lists all versions, oldest
+/// first
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct VersionsRequest {
+    /// The maximum number of versions to return. All versions are returned if not set
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub limit: Option<usize>,
+    /// The number of versions to skip, after sorting
+    #[serde(default, skip_serializing_if = "is_zero")]
+    pub offset: usize,
+    #[serde(default)]
+    pub sort: VersionSort,
+}
+
+/// The order versions are listed in, by when they were created
+#[derive(Debug, Default, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum VersionSort {
+    #[default]
+    Asc,
+    Desc,
 }
 
 /// The response to a versions request
@@ -72,6 +359,9 @@ pub struct VersionResponse {
     #[serde(default)]
     pub message: String,
     pub versions: Vec<VersionInfo>,
+    /// The total number of versions of the model, regardless of the limit and offset
+    #[serde(default)]
+    pub total: usize,
 }
 
 /// Information about a given version of a model, returned as part of a list of all versions
@@ -79,6 +369,81 @@ pub struct VersionResponse {
 pub struct VersionInfo {
     pub version: String,
     pub deployed: bool,
+    /// The content hash of the version (see [`Manifest::content_hash`])
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub hash: String,
+    /// When the version was stored. This isn't known for versions stored by older versions of wadm
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub created_at: Option<DateTime<Utc>>,
+    /// The size in bytes of the version serialized as JSON
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub size_bytes: Option<usize>,
+}
+
+/// The response to a describe request, giving the full picture of a single model
+#[derive(Debug, Serialize, Deserialize)]
+pub struct DescribeModelResponse {
+    pub result: GetResult,
+    #[serde(default)]
+    pub message: String,
+    /// The latest version of the manifest
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub manifest: Option<Manifest>,
+    /// The version that is currently deployed, if any
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub deployed_version: Option<String>,
+    /// All versions of the model in creation order
+    #[serde(default)]
+    pub versions: Vec<VersionInfo>,
+    /// The total size in bytes of the model's stored data (all versions and their history)
+    /// serialized as JSON, before any storage compression is applied
+    #[serde(default)]
+    pub size_bytes: usize,
+    /// The latest reported status of the model
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub status: Option<StatusInfo>,
+}
+
+/// The response to a lint request, listing best practice warnings for a manifest. Nothing is stored
+/// by a lint request
+#[derive(Debug, Serialize, Deserialize)]
+pub struct LintModelResponse {
+    pub result: LintResult,
+    #[serde(default)]
+    pub message: String,
+    #[serde(default)]
+    pub warnings: Vec<LintWarning>,
+}
+
+/// All possible outcomes of a lint request
+#[derive(Debug, Serialize, Deserialize, Copy, Clone, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum LintResult {
+    Error,
+    /// The manifest follows every lint rule
+    Clean,
+    /// The manifest has at least one warning
+    Warnings,
+}
+
+/// A single warning from a lint rule
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq)]
+pub struct LintWarning {
+    /// The name of the rule that produced the warning
+    pub rule: String,
+    pub severity: LintSeverity,
+    pub message: String,
+    /// The component the warning is about, if it isn't about the manifest as a whole
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub component: Option<String>,
+}
+
+/// How important a lint warning is
+#[derive(Debug, Serialize, Deserialize, Copy, Clone, PartialEq, Eq, PartialOrd, Ord)]
+#[serde(rename_all = "lowercase")]
+pub enum LintSeverity {
+    Info,
+    Warning,
 }
 
 /// A request for deleting a model
@@ -86,6 +451,19 @@ pub struct VersionInfo {
 pub struct DeleteModelRequest {
     #[serde(default)]
     pub version: Option<String>,
+    /// Archive the model instead of deleting it. Archived models are undeployed and hidden from
+    /// listing, but can be restored with a restore request. Only whole models can be archived, so
+    /// this can't be combined with a version
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub archive: bool,
+    /// Check whether other deployed models link to components of the deployed version before
+    /// deleting it. Dependents are only listed as a warning in the response unless `strict` is set
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub check_dependents: bool,
+    /// Refuse to delete the deployed version if other deployed models link to its components. This
+    /// implies `check_dependents`
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub strict: bool,
 }
 
 /// A response from a delete request
@@ -96,6 +474,39 @@ pub struct DeleteModelResponse {
     pub message: String,
     #[serde(default)]
     pub undeploy: bool,
+    /// A machine readable code describing the failure. Only set when the result is an error
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error_code: Option<ErrorCode>,
+    /// How long to wait (in milliseconds) before retrying. Only set on errors that may succeed if
+    /// retried
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub retry_after_ms: Option<u64>,
+}
+
+/// A response from a restore request
+#[derive(Debug, Serialize, Deserialize)]
+pub struct RestoreModelResponse {
+    pub result: RestoreResult,
+    #[serde(default)]
+    pub message: String,
+    /// A machine readable code describing the failure. Only set when the result is an error
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error_code: Option<ErrorCode>,
+    /// How long to wait (in milliseconds) before retrying. Only set on errors that may succeed if
+    /// retried
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub retry_after_ms: Option<u64>,
+}
+
+/// All possible outcomes of a restore operation
+#[derive(Debug, Serialize, Deserialize, PartialEq, Eq, Clone, Copy)]
+#[serde(rename_all = "lowercase")]
+pub enum RestoreResult {
+    Error,
+    Restored,
+    /// The model exists but isn't archived
+    Noop,
+    NotFound,
 }
 
 /// All possible outcomes of a delete operation
@@ -107,13 +518,177 @@ pub enum DeleteResult {
     Noop,
 }
 
+/// A request to change the labels and annotations of a stored version of a model without storing a
+/// new version. Changes to the spec require putting a new version, so any other fields (such as
+/// `spec`) are rejected
+#[derive(Debug, Serialize, Deserialize, Default)]
+#[serde(deny_unknown_fields)]
+pub struct PatchMetadataRequest {
+    /// The version to patch. Defaults to the latest version
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub version: Option<String>,
+    /// Labels to add or overwrite
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub labels: BTreeMap<String, String>,
+    /// Annotations to add or overwrite
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub annotations: BTreeMap<String, String>,
+    /// Keys of labels to remove
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub remove_labels: Vec<String>,
+    /// Keys of annotations to remove
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub remove_annotations: Vec<String>,
+}
+
+/// A response from a patch metadata request
+#[derive(Debug, Serialize, Deserialize)]
+pub struct PatchMetadataResponse {
+    pub result: PatchMetadataResult,
+    #[serde(default)]
+    pub message: String,
+    /// The metadata of the version after the patch was applied
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub metadata: Option<Metadata>,
+    /// A machine readable code describing why the request failed. Only set on errors
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error_code: Option<ErrorCode>,
+    /// How long to wait (in milliseconds) before retrying. Only set on errors that may succeed if
+    /// retried
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub retry_after_ms: Option<u64>,
+}
+
+/// All possible outcomes of a patch metadata request
+#[derive(Debug, Serialize, Deserialize, PartialEq, Eq, Clone, Copy)]
+#[serde(rename_all = "lowercase")]
+pub enum PatchMetadataResult {
+    Error,
+    Patched,
+    /// The patch didn't change the metadata
+    Noop,
+    NotFound,
+}
+
+impl PatchMetadataResponse {
+    /// Returns an error response with the given code and message
+    pub fn error(error_code: ErrorCode, message: String) -> PatchMetadataResponse {
+        PatchMetadataResponse {
+            result: PatchMetadataResult::Error,
+            message,
+            metadata: None,
+            error_code: Some(error_code),
+            retry_after_ms: error_code.retry_after_ms(),
+        }
+    }
+}
+
+/// A request to delete every version of a model except the deployed (and staged) versions
+#[derive(Debug, Default, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct PruneModelRequest {
+    /// Also keep the latest version. This must be set if the model isn't deployed, so that pruning
+    /// can't delete every version of a model
+    #[serde(default)]
+    pub keep_latest: bool,
+}
+
+/// A response from a prune request
+#[derive(Debug, Serialize, Deserialize)]
+pub struct PruneModelResponse {
+    pub result: PruneResult,
+    #[serde(default)]
+    pub message: String,
+    /// The versions that were deleted, in creation order
+    #[serde(default)]
+    pub removed: Vec<String>,
+    /// A machine readable code describing why the request failed. Only set on errors
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error_code: Option<ErrorCode>,
+    /// How long to wait (in milliseconds) before retrying. Only set on errors that may succeed if
+    /// retried
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub retry_after_ms: Option<u64>,
+}
+
+/// All possible outcomes of a prune request
+#[derive(Debug, Serialize, Deserialize, PartialEq, Eq, Clone, Copy)]
+#[serde(rename_all = "lowercase")]
+pub enum PruneResult {
+    Error,
+    Pruned,
+    /// There were no versions to delete
+    Noop,
+    NotFound,
+}
+
+impl PruneModelResponse {
+    /// Returns an error response with the given code and message
+    pub fn error(error_code: ErrorCode, message: String) -> PruneModelResponse {
+        PruneModelResponse {
+            result: PruneResult::Error,
+            message,
+            removed: Vec::new(),
+            error_code: Some(error_code),
+            retry_after_ms: error_code.retry_after_ms(),
+        }
+    }
+}
+
 /// A request for deploying a model.
 ///
 /// If the given version is empty (or the body is empty), it will deploy the latest version. If the
 /// version is set to "latest", it will also deploy the latest version
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Default, Serialize, Deserialize)]
 pub struct DeployModelRequest {
     pub version: Option<String>,
+    /// If set, the response will not be sent until the model has finished reconciling (either
+    /// deployed or failed) or this many seconds have elapsed
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub wait_timeout_secs: Option<u64>,
+    /// Notify the processors of the deploy even if the requested version is already deployed. This
+    /// is useful for re-triggering reconciliation, such as after a processor lost its state
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub force: bool,
+    /// The content hash (see [`Manifest::content_hash`]) of the manifest to deploy. If a version is
+    /// also given, the deploy fails unless that version has this hash. Otherwise, the newest
+    /// version with this hash is deployed. This guarantees the deployed manifest is exactly the one
+    /// that was reviewed
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub manifest_hash: Option<String>,
+    /// Other models (such as ones providing capabilities this model links to) that must currently
+    /// be deployed at the given versions. The deploy is refused if any of them aren't
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub dependencies: Vec<ModelDependency>,
+    /// Components of the version to leave out of the deploy, such as while debugging. The stored
+    /// manifest is unchanged, and the components stay disabled until the model is deployed again.
+    /// Nothing else in the manifest can link to a disabled component
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub disabled_components: Vec<String>,
+}
+
+/// A model that must be deployed at a specific version for a deploy to go ahead
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq)]
+pub struct ModelDependency {
+    pub name: String,
+    pub version: String,
+}
+
+/// A request to stage a version of a model as a canary without deploying it. Staged versions can
+/// then be deployed with a promote request
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct StageModelRequest {
+    /// The version to stage. If not set (or set to `latest`), the latest version is staged
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub version: Option<String>,
+}
+
+/// A request to set which stored version of a model is deployed. Unlike a deploy request, this only
+/// flips the deployed version and doesn't support waiting, hashes, or disabling components
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SetDeployedRequest {
+    /// The version to deploy. Set to `latest` to deploy the latest version
+    pub version: String,
 }
 
 /// A response from a deploy or undeploy request
@@ -122,10 +697,43 @@ pub struct DeployModelResponse {
     pub result: DeployResult,
     #[serde(default)]
     pub message: String,
+    /// The final status of the model. Only set when a deploy request asked to wait for the model to
+    /// reconcile and it did so before the timeout
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub status: Option<StatusInfo>,
+    /// A machine readable code describing the failure. Only set when the result is an error
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error_code: Option<ErrorCode>,
+    /// How long to wait (in milliseconds) before retrying. Only set on errors that may succeed if
+    /// retried
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub retry_after_ms: Option<u64>,
+    /// The components that were disabled by the deploy request
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub disabled_components: Vec<String>,
+    /// The revision of the stored model after this request, which can be used for conditional
+    /// requests. This is 0 if the request failed
+    #[serde(default)]
+    pub revision: u64,
+}
+
+impl DeployModelResponse {
+    /// Returns an error response with the given code and message
+    pub fn error(error_code: ErrorCode, message: String) -> DeployModelResponse {
+        DeployModelResponse {
+            result: DeployResult::Error,
+            message,
+            status: None,
+            error_code: Some(error_code),
+            retry_after_ms: error_code.retry_after_ms(),
+            disabled_components: Vec::new(),
+            revision: 0,
+        }
+    }
 }
 
 /// All possible outcomes of a deploy operation
-#[derive(Debug, Serialize, Deserialize, PartialEq, Eq)]
+#[derive(Debug, Serialize, Deserialize, PartialEq, Eq, Clone, Copy)]
 #[serde(rename_all = "lowercase")]
 pub enum DeployResult {
     Error,
@@ -133,11 +741,532 @@ pub enum DeployResult {
     NotFound,
 }
 
-/// A request to undeploy a model
+/// A request to check whether deploying a version of a model would conflict with the models already
+/// deployed in the lattice, without deploying it
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct DeployPreflightRequest {
+    /// The version to check. If not set (or set to `latest`), the latest version is checked
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub version: Option<String>,
+}
+
+/// The response to a deploy preflight request. Nothing is deployed by a preflight request
+#[derive(Debug, Serialize, Deserialize)]
+pub struct DeployPreflightResponse {
+    pub result: PreflightResult,
+    #[serde(default)]
+    pub message: String,
+    /// Every conflict a deploy of the version would run into
+    #[serde(default)]
+    pub conflicts: Vec<DeployConflict>,
+}
+
+/// All possible outcomes of a deploy preflight request
+#[derive(Debug, Serialize, Deserialize, Copy, Clone, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum PreflightResult {
+    Error,
+    /// The version can be deployed without conflicting with any deployed model
+    Clear,
+    /// A deploy of the version would be refused because of at least one conflict
+    Conflicts,
+}
+
+/// A conflict between a model being deployed and a model that is already deployed
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq)]
+pub struct DeployConflict {
+    pub kind: DeployConflictKind,
+    /// The image of the component or provider being deployed
+    pub image: String,
+    /// The name of the deployed model it conflicts with
+    pub conflicting_model: String,
+    /// The version of the provider the deployed model is running. Only set for provider version
+    /// conflicts
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub deployed_version: Option<String>,
+    /// The version of the provider being deployed. Only set for provider version conflicts
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub requested_version: Option<String>,
+    /// The component ID used by both models. Only set for component ID conflicts
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub component_id: Option<String>,
+    /// The same message a deploy would be refused with
+    pub message: String,
+}
+
+/// The kinds of conflicts that stop a model from being deployed
+#[derive(Debug, Serialize, Deserialize, Copy, Clone, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum DeployConflictKind {
+    /// A provider is deployed by another model at a different version
+    ProviderVersion,
+    /// An explicit component ID is used by another model for a different image
+    ComponentId,
+}
+
+/// A request to diff two stored versions of a model. Either version can be "latest" to refer to
+/// the latest version
+#[derive(Debug, Serialize, Deserialize)]
+pub struct DiffModelRequest {
+    pub from: String,
+    pub to: String,
+}
+
+/// A response from a diff request
+#[derive(Debug, Serialize, Deserialize)]
+pub struct DiffModelResponse {
+    pub result: GetResult,
+    #[serde(default)]
+    pub message: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub diff: Option<ManifestDiff>,
+}
+
+/// The response to a normalize request, containing the manifest exactly as a put request would
+/// store it. Nothing is stored by a normalize request
+#[derive(Debug, Serialize, Deserialize)]
+pub struct NormalizeModelResponse {
+    pub result: GetResult,
+    #[serde(default)]
+    pub message: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub manifest: Option<Manifest>,
+    /// The schema migrations that were applied to the manifest, if any
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub migrations: Vec<String>,
+}
+
+/// A component and trait level diff between two manifests
+#[derive(Debug, Serialize, Deserialize, Default, Clone, PartialEq, Eq)]
+pub struct ManifestDiff {
+    pub from_version: String,
+    pub to_version: String,
+    /// Components that only exist in the newer manifest
+    #[serde(default)]
+    pub added_components: Vec<Component>,
+    /// Components that only exist in the older manifest
+    #[serde(default)]
+    pub removed_components: Vec<Component>,
+    /// Components that exist in both manifests but differ
+    #[serde(default)]
+    pub changed_components: Vec<ComponentDiff>,
+}
+
+/// The changes to a single component between two manifests
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq)]
+pub struct ComponentDiff {
+    pub name: String,
+    /// The old and new properties of the component, if they changed
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub properties: Option<PropertiesChange>,
+    /// Traits that only exist on the newer component
+    #[serde(default)]
+    pub added_traits: Vec<Trait>,
+    /// Traits that only exist on the older component
+    #[serde(default)]
+    pub removed_traits: Vec<Trait>,
+}
+
+/// The old and new properties of a changed component
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq)]
+pub struct PropertiesChange {
+    pub from: Properties,
+    pub to: Properties,
+}
+
+impl ManifestDiff {
+    /// Computes the diff between the two manifests. Components are matched by name. Since a
+    /// component can have multiple traits of the same type (such as links), changed traits show up
+    /// as the old trait being removed and the new trait being added
+    pub fn between(from: &Manifest, to: &Manifest) -> ManifestDiff {
+        let old_components = from.component_lookup();
+        let new_components = to.component_lookup();
+
+        let added_components = to
+            .components()
+            .filter(|c| !old_components.contains_key(&c.name))
+            .cloned()
+            .collect();
+        let removed_components = from
+            .components()
+            .filter(|c| !new_components.contains_key(&c.name))
+            .cloned()
+            .collect();
+        let changed_components = to
+            .components()
+            .filter_map(|new| {
+                let old = old_components.get(&new.name)?;
+                let old_traits = old.traits.as_deref().unwrap_or_default();
+                let new_traits = new.traits.as_deref().unwrap_or_default();
+                let diff = ComponentDiff {
+                    name: new.name.clone(),
+                    properties: (old.properties != new.properties).then(|| PropertiesChange {
+                        from: old.properties.clone(),
+                        to: new.properties.clone(),
+                    }),
+                    added_traits: new_traits
+                        .iter()
+                        .filter(|t| !old_traits.contains(t))
+                        .cloned()
+                        .collect(),
+                    removed_traits: old_traits
+                        .iter()
+                        .filter(|t| !new_traits.contains(t))
+                        .cloned()
+                        .collect(),
+                };
+                (diff.properties.is_some()
+                    || !diff.added_traits.is_empty()
+                    || !diff.removed_traits.is_empty())
+                .then_some(diff)
+            })
+            .collect();
+
+        ManifestDiff {
+            from_version: from.version().to_owned(),
+            to_version: to.version().to_owned(),
+            added_components,
+            removed_components,
+            changed_components,
+        }
+    }
+
+    /// Returns true if there are no differences between the manifests
+    pub fn is_empty(&self) -> bool {
+        self.added_components.is_empty()
+            && self.removed_components.is_empty()
+            && self.changed_components.is_empty()
+    }
+}
+
+/// A request for copying a model to a new name.
 ///
-/// Right now this is just an empty struct, but it is reserved for future use
+/// The copied version is stored as a brand new (undeployed) model under the destination name. If
+/// the version is empty or set to "latest", the latest version of the source model will be copied
+#[derive(Debug, Serialize, Deserialize)]
+pub struct CopyModelRequest {
+    /// The name of the model to create
+    pub destination: String,
+    #[serde(default)]
+    pub version: Option<String>,
+    /// Whether or not to replace the destination model if it already exists
+    #[serde(default)]
+    pub overwrite: bool,
+}
+
+/// A response from a copy request
+#[derive(Debug, Serialize, Deserialize)]
+pub struct CopyModelResponse {
+    pub result: CopyResult,
+    #[serde(default)]
+    pub message: String,
+    #[serde(default)]
+    pub name: String,
+    #[serde(default)]
+    pub current_version: String,
+}
+
+/// All possible outcomes of a copy operation
+#[derive(Debug, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum CopyResult {
+    Error,
+    Created,
+    Overwritten,
+    NotFound,
+}
+
+/// A request to undeploy a model
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct UndeployModelRequest {
+    /// Refuse to undeploy the model if other deployed models link to its components. By default,
+    /// the model is undeployed anyway and the response warns about the dependent models
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub strict: bool,
+}
+
+/// A request to deploy multiple models at once. Conflicts are checked once across the whole batch
+/// and the models already deployed in the lattice, and the models are deployed all or nothing: if
+/// any of them can't be deployed, none of them are
+#[derive(Debug, Serialize, Deserialize)]
+pub struct DeployModelsRequest {
+    pub models: Vec<ModelDeployTarget>,
+}
+
+/// A model to deploy as part of a multi-model deploy request
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct ModelDeployTarget {
+    pub name: String,
+    /// The version to deploy. Defaults to the latest version
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub version: Option<String>,
+    /// Other models that must currently be deployed at the given versions. See
+    /// [`DeployModelRequest::dependencies`]
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub dependencies: Vec<ModelDependency>,
+    /// Components of the version to leave out of the deploy. See
+    /// [`DeployModelRequest::disabled_components`]
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub disabled_components: Vec<String>,
+}
+
+/// A response from a multi-model deploy request, containing the outcome for each model in the
+/// order they were given
+#[derive(Debug, Serialize, Deserialize)]
+pub struct DeployModelsResponse {
+    /// Set to [`DeployResult::Acknowledged`] only if every model was deployed
+    pub result: DeployResult,
+    #[serde(default)]
+    pub message: String,
+    #[serde(default)]
+    pub models: Vec<ModelDeployResult>,
+}
+
+/// The outcome of deploying a single model as part of a multi-model deploy request
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct ModelDeployResult {
+    pub name: String,
+    /// The version that was (or would have been) deployed
+    #[serde(default)]
+    pub version: String,
+    pub result: DeployResult,
+    #[serde(default)]
+    pub message: String,
+    /// A machine readable code describing why the model couldn't be deployed. Only set on errors
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error_code: Option<ErrorCode>,
+    /// How long to wait (in milliseconds) before retrying. Only set on errors that may succeed if
+    /// retried
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub retry_after_ms: Option<u64>,
+}
+
+/// A response from an undeploy all request, containing the outcome for each model that was deployed
+#[derive(Debug, Serialize, Deserialize)]
+pub struct UndeployAllResponse {
+    /// Set to [`DeployResult::Acknowledged`] if every model was undeployed, otherwise
+    /// [`DeployResult::Error`]
+    pub result: DeployResult,
+    #[serde(default)]
+    pub message: String,
+    #[serde(default)]
+    pub models: Vec<ModelUndeployResult>,
+}
+
+/// The outcome of undeploying a single model as part of an undeploy all request
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct ModelUndeployResult {
+    pub name: String,
+    pub result: DeployResult,
+    #[serde(default)]
+    pub message: String,
+}
+
+/// A single model with all of its versions, as exported by an export request
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct ExportedModel {
+    pub name: String,
+    /// All stored versions of the model in the order they were created
+    pub versions: Vec<Manifest>,
+    /// The version of the model that is deployed, if any
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub deployed_version: Option<String>,
+    /// The version of the model that is staged as a canary, if any
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub staged_version: Option<String>,
+    /// Components of the deployed version that were disabled when it was deployed
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub disabled_components: Vec<String>,
+    /// When the model was archived, if it is archived
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub archived_at: Option<DateTime<Utc>>,
+    /// When the model was last undeployed, if it isn't deployed. Together with when its current
+    /// version was created, this is what the model's TTL counts from
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub undeployed_at: Option<DateTime<Utc>>,
+    /// When each version was created, keyed by version. Versions stored by older versions of wadm
+    /// won't have one
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub version_created_at: BTreeMap<String, DateTime<Utc>>,
+}
+
+/// A response from an export request containing every model stored for the lattice
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ExportModelsResponse {
+    pub result: ExportResult,
+    #[serde(default)]
+    pub message: String,
+    #[serde(default)]
+    pub models: Vec<ExportedModel>,
+}
+
+/// All possible outcomes of an export operation
+#[derive(Debug, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum ExportResult {
+    Error,
+    Success,
+}
+
+/// A request to import models previously exported with an export request
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ImportModelsRequest {
+    pub models: Vec<ExportedModel>,
+    /// Whether or not to replace models that already exist. Models that are currently deployed
+    /// are never replaced
+    #[serde(default)]
+    pub overwrite: bool,
+}
+
+/// A response from an import request, containing the outcome for each model in the request
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ImportModelsResponse {
+    /// Set to [`ImportResult::Success`] if every model was imported, otherwise
+    /// [`ImportResult::Error`]
+    pub result: ImportResult,
+    #[serde(default)]
+    pub message: String,
+    #[serde(default)]
+    pub models: Vec<ModelImportResult>,
+}
+
+/// All possible outcomes of an import operation
+#[derive(Debug, Serialize, Deserialize, PartialEq, Eq, Clone, Copy)]
+#[serde(rename_all = "lowercase")]
+pub enum ImportResult {
+    Error,
+    Success,
+}
+
+/// The outcome of importing a single model as part of an import request
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct ModelImportResult {
+    pub name: String,
+    pub result: ModelImportOutcome,
+    #[serde(default)]
+    pub message: String,
+}
+
+/// All possible outcomes of importing a single model
+#[derive(Debug, Serialize, Deserialize, PartialEq, Eq, Clone, Copy)]
+#[serde(rename_all = "lowercase")]
+pub enum ModelImportOutcome {
+    Error,
+    Created,
+    Overwritten,
+    /// The model already exists and overwrite was not set
+    Skipped,
+}
+
+/// A response to a server info request, describing what the server expects of manifests
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ServerInfoResponse {
+    pub result: GetResult,
+    #[serde(default)]
+    pub message: String,
+    /// The version of wadm the server is running
+    #[serde(default)]
+    pub wadm_version: String,
+    /// The `$id` of the JSON schema manifests are validated against, if it has one
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub schema_id: Option<String>,
+    /// Whether the server validates against a custom schema rather than the built in OAM schema
+    #[serde(default)]
+    pub custom_schema: bool,
+    /// The `apiVersion` that manifests are stored as
+    #[serde(default)]
+    pub api_version: String,
+    /// All `apiVersion`s accepted by the server. Manifests using an older version are migrated to
+    /// `api_version` when they are put
+    #[serde(default)]
+    pub supported_api_versions: Vec<String>,
+    /// The version string that can be used to refer to the newest version of a model
+    #[serde(default)]
+    pub latest_version: String,
+}
+
+/// A response to a request for all lattices with stored models
 #[derive(Debug, Serialize, Deserialize)]
-pub struct UndeployModelRequest {}
+pub struct ListLatticesResponse {
+    pub result: GetResult,
+    #[serde(default)]
+    pub message: String,
+    /// Every lattice with at least one stored model, sorted by lattice ID
+    #[serde(default)]
+    pub lattices: Vec<LatticeSummary>,
+}
+
+/// A lattice with stored models
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq)]
+pub struct LatticeSummary {
+    pub lattice_id: String,
+    /// The number of models stored in the lattice, including archived models
+    pub model_count: usize,
+}
+
+/// A response to a health request, describing whether the server can reach everything it needs to
+/// serve requests
+#[derive(Debug, Serialize, Deserialize)]
+pub struct HealthResponse {
+    pub result: HealthResult,
+    #[serde(default)]
+    pub message: String,
+    /// The result of checking each dependency of the server
+    #[serde(default)]
+    pub checks: Vec<HealthCheck>,
+}
+
+/// Possible outcomes of a health request
+#[derive(Debug, Serialize, Deserialize, PartialEq, Eq, Clone, Copy)]
+#[serde(rename_all = "lowercase")]
+pub enum HealthResult {
+    /// Every dependency is reachable
+    Ok,
+    /// At least one dependency couldn't be reached, so some requests will fail
+    Degraded,
+}
+
+/// The result of checking a single dependency of the server
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct HealthCheck {
+    /// The dependency that was checked, such as `store` or `status_stream`
+    pub component: String,
+    pub healthy: bool,
+    /// Why the check failed, if it did
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub message: String,
+    /// How long the check took, in milliseconds
+    #[serde(default)]
+    pub latency_ms: u64,
+}
+
+/// A response to a deploy history request, containing the most recent deploys and undeploys of a
+/// model, oldest first
+#[derive(Debug, Serialize, Deserialize)]
+pub struct DeployHistoryResponse {
+    pub result: GetResult,
+    #[serde(default)]
+    pub message: String,
+    #[serde(default)]
+    pub events: Vec<DeployEvent>,
+}
+
+/// A record of a model being deployed or undeployed
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq)]
+pub struct DeployEvent {
+    /// The version that was deployed or undeployed
+    pub version: String,
+    pub action: DeployAction,
+    pub timestamp: DateTime<Utc>,
+}
+
+/// The actions recorded in a model's deploy history
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum DeployAction {
+    Deployed,
+    Undeployed,
+}
 
 /// A response to a status request
 #[derive(Debug, Serialize, Deserialize)]
@@ -149,6 +1278,117 @@ pub struct StatusResponse {
     pub status: Option<Status>,
 }
 
+/// A request for the recent status history of a model
+#[derive(Debug, Serialize, Deserialize, Default)]
+pub struct StatusHistoryRequest {
+    /// The maximum number of status updates to return. Defaults to (and is capped at) the number of
+    /// updates wadm retains per model
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub limit: Option<usize>,
+}
+
+/// A response to a status history request
+#[derive(Debug, Serialize, Deserialize)]
+pub struct StatusHistoryResponse {
+    pub result: StatusResult,
+    #[serde(default)]
+    pub message: String,
+    /// The status updates for the model, ordered from oldest to newest
+    #[serde(default)]
+    pub history: Vec<StatusHistoryEntry>,
+}
+
+/// A single status update for a model along with when it was recorded
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct StatusHistoryEntry {
+    pub timestamp: DateTime<Utc>,
+    #[serde(rename = "status")]
+    pub info: StatusInfo,
+}
+
+/// A response to a status topic request
+#[derive(Debug, Serialize, Deserialize)]
+pub struct StatusTopicResponse {
+    pub result: StatusResult,
+    #[serde(default)]
+    pub message: String,
+    /// The topic status updates for the model are published on
+    #[serde(default)]
+    pub topic: String,
+    /// Whether a status update for the model is currently stored in the status stream
+    #[serde(default)]
+    pub has_status: bool,
+}
+
+/// A request to watch the status of a model.
+///
+/// Each status update for the model (starting with the current status) will be published as a
+/// [`StatusInfo`] to the given subject until the subscriber goes away or the watch expires
+#[derive(Debug, Serialize, Deserialize)]
+pub struct WatchStatusRequest {
+    /// The subject to publish status updates to. This must be an inbox, such as one created with
+    /// `Client::new_inbox`, under a prefix the server allows (`_INBOX` by default)
+    pub subject: String,
+    /// How long to watch the status for before stopping. Defaults to 5 minutes and is capped at an
+    /// hour, after which the watch must be renewed
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_duration_secs: Option<u64>,
+}
+
+/// A response to a watch status request
+#[derive(Debug, Serialize, Deserialize)]
+pub struct WatchStatusResponse {
+    pub result: StatusResult,
+    #[serde(default)]
+    pub message: String,
+}
+
+/// A request to watch the lifecycle events of a model.
+///
+/// Each time the model is put, deployed, undeployed, or deleted (including as part of a request
+/// that changes many models, such as an import), a [`ModelEvent`] is published to the given subject
+/// until the subscriber goes away or the watch expires. The model must already exist
+#[derive(Debug, Serialize, Deserialize)]
+pub struct WatchModelRequest {
+    /// The subject to publish model events to. This must be an inbox, such as one created with
+    /// `Client::new_inbox`, under a prefix the server allows (`_INBOX` by default)
+    pub subject: String,
+    /// How long to watch the model for before stopping. Defaults to 5 minutes and is capped at an
+    /// hour, after which the watch must be renewed
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_duration_secs: Option<u64>,
+}
+
+/// A response to a watch model request
+#[derive(Debug, Serialize, Deserialize)]
+pub struct WatchModelResponse {
+    pub result: StatusResult,
+    #[serde(default)]
+    pub message: String,
+}
+
+/// A lifecycle event for a model, published to the subject given in a [`WatchModelRequest`]
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq)]
+pub struct ModelEvent {
+    #[serde(rename = "type")]
+    pub event_type: ModelEventType,
+    pub model_name: String,
+    /// The version affected by the event, if it applies to a single version
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub version: Option<String>,
+    pub timestamp: DateTime<Utc>,
+}
+
+/// The types of lifecycle events published for a model
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum ModelEventType {
+    Put,
+    Deploy,
+    Undeploy,
+    Delete,
+}
+
 /// All possible outcomes of a status operation
 #[derive(Debug, Serialize, Deserialize, PartialEq, Eq)]
 #[serde(rename_all = "lowercase")]
@@ -162,6 +1402,12 @@ pub enum StatusResult {
 #[derive(Debug, Serialize, Deserialize, Default, Clone)]
 pub struct Status {
     pub version: String,
+    /// The version that is currently deployed, if any
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub deployed_version: Option<String>,
+    /// The version that is staged as a canary, if any
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub staged_version: Option<String>,
     #[serde(rename = "status")]
     pub info: StatusInfo,
     pub components: Vec<ComponentStatus>,
@@ -194,6 +1440,15 @@ pub struct StatusInfo {
     pub status_type: StatusType,
     #[serde(skip_serializing_if = "String::is_empty", default)]
     pub message: String,
+    /// How long ago (in seconds) this status was reported. This is only set on statuses read back
+    /// from the status stream by the API
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub age_secs: Option<u64>,
+    /// Whether this status was reported longer ago than the server's staleness threshold. A stale
+    /// status may no longer reflect reality, for example if the processor responsible for the
+    /// model has died
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub stale: bool,
 }
 
 impl StatusInfo {
@@ -201,6 +1456,7 @@ impl StatusInfo {
         StatusInfo {
             status_type: StatusType::Undeployed,
             message: message.to_owned(),
+            ..Default::default()
         }
     }
 
@@ -208,6 +1464,7 @@ impl StatusInfo {
         StatusInfo {
             status_type: StatusType::Deployed,
             message: message.to_owned(),
+            ..Default::default()
         }
     }
 
@@ -215,6 +1472,7 @@ impl StatusInfo {
         StatusInfo {
             status_type: StatusType::Failed,
             message: message.to_owned(),
+            ..Default::default()
         }
     }
 
@@ -222,6 +1480,7 @@ impl StatusInfo {
         StatusInfo {
             status_type: StatusType::Reconciling,
             message: message.to_owned(),
+            ..Default::default()
         }
     }
 }
@@ -274,6 +1533,10 @@ impl std::iter::Sum for StatusType {
     }
 }
 
+fn is_zero(value: &usize) -> bool {
+    *value == 0
+}
+
 #[cfg(test)]
 mod test {
     use super::*;
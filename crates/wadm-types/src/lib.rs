@@ -1,8 +1,11 @@
 use std::collections::{BTreeMap, HashMap};
+use std::time::Duration;
 
 use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
 
 pub mod api;
+pub mod migration;
 pub mod validation;
 
 /// The default weight for a spread
@@ -19,6 +22,25 @@ pub const VERSION_ANNOTATION_KEY: &str = "version";
 /// The description key, as predefined by the [OAM
 /// spec](https://github.com/oam-dev/spec/blob/master/metadata.md#annotations-format)
 pub const DESCRIPTION_ANNOTATION_KEY: &str = "description";
+/// The annotation key for the person or system that owns a manifest
+pub const OWNER_ANNOTATION_KEY: &str = "owner";
+/// The annotation key for the team responsible for a manifest
+pub const TEAM_ANNOTATION_KEY: &str = "team";
+/// The annotation key for the minimum version of wadm (e.g. `0.13.0`) a manifest needs. Manifests
+/// that use features from newer versions of wadm should set this so older versions refuse to deploy
+/// them rather than ignoring what they don't support
+pub const MINIMUM_WADM_VERSION_ANNOTATION_KEY: &str = "minimumWadmVersion";
+/// The annotation key for how long a model can stay undeployed before wadm deletes it, such as
+/// `30m`, `12h`, or `7d` (see [`parse_ttl`]). Models without this annotation are never deleted
+/// automatically
+pub const TTL_ANNOTATION_KEY: &str = "wadm.io/ttl";
+/// The well-known annotation keys that are included when summarizing a manifest (e.g. when listing
+/// models). All other annotations are only available from the full manifest
+pub const SUMMARY_ANNOTATION_KEYS: &[&str] = &[
+    DESCRIPTION_ANNOTATION_KEY,
+    OWNER_ANNOTATION_KEY,
+    TEAM_ANNOTATION_KEY,
+];
 /// The identifier for the builtin spreadscaler trait type
 pub const SPREADSCALER_TRAIT: &str = "spreadscaler";
 /// The identifier for the builtin daemonscaler trait type
@@ -29,6 +51,26 @@ pub const LINK_TRAIT: &str = "link";
 /// for a manifest
 pub const LATEST_VERSION: &str = "latest";
 
+/// Parses a TTL given as a whole number followed by a unit of `s`, `m`, `h`, or `d` (such as `90s`
+/// or `7d`). A number without a unit is in seconds. Returns `None` for anything else, including a
+/// TTL of zero
+pub fn parse_ttl(value: &str) -> Option<Duration> {
+    let value = value.trim();
+    let (number, unit_secs) = match value.char_indices().last()? {
+        (idx, 's') => (&value[..idx], 1),
+        (idx, 'm') => (&value[..idx], 60),
+        (idx, 'h') => (&value[..idx], 60 * 60),
+        (idx, 'd') => (&value[..idx], 24 * 60 * 60),
+        _ => (value, 1),
+    };
+    number
+        .parse::<u64>()
+        .ok()
+        .filter(|n| *n > 0)
+        .and_then(|n| n.checked_mul(unit_secs))
+        .map(Duration::from_secs)
+}
+
 /// An OAM manifest
 #[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq)]
 pub struct Manifest {
@@ -53,6 +95,21 @@ impl Manifest {
             .unwrap_or_default()
     }
 
+    /// Returns a hash of the manifest's content as a lowercase hex encoded SHA-256 digest. The
+    /// manifest is hashed as JSON with all keys sorted, so the hash only changes when the content
+    /// (including the version) does
+    pub fn content_hash(&self) -> String {
+        // Converting to a value first sorts the keys of all maps. This can't fail, as manifests
+        // only have string keys
+        let canonical = serde_json::to_value(self)
+            .and_then(|value| serde_json::to_vec(&value))
+            .unwrap_or_default();
+        Sha256::digest(canonical)
+            .iter()
+            .map(|b| format!("{b:02x}"))
+            .collect()
+    }
+
     /// Returns a reference to the current description if it exists
     pub fn description(&self) -> Option<&str> {
         self.metadata
@@ -61,6 +118,35 @@ impl Manifest {
             .map(|v| v.as_str())
     }
 
+    /// Returns the minimum version of wadm this manifest needs, if it declares one
+    pub fn minimum_wadm_version(&self) -> Option<&str> {
+        self.metadata
+            .annotations
+            .get(MINIMUM_WADM_VERSION_ANNOTATION_KEY)
+            .map(|v| v.as_str())
+    }
+
+    /// Returns how long the model can stay undeployed before it is deleted, from the
+    /// [`TTL_ANNOTATION_KEY`] annotation. Returns `None` if the annotation isn't set or isn't a
+    /// valid TTL
+    pub fn ttl(&self) -> Option<Duration> {
+        self.metadata
+            .annotations
+            .get(TTL_ANNOTATION_KEY)
+            .and_then(|v| parse_ttl(v))
+    }
+
+    /// Returns the well-known annotations (see [`SUMMARY_ANNOTATION_KEYS`]) that are set on this
+    /// manifest
+    pub fn summary_annotations(&self) -> BTreeMap<String, String> {
+        self.metadata
+            .annotations
+            .iter()
+            .filter(|(k, _)| SUMMARY_ANNOTATION_KEYS.contains(&k.as_str()))
+            .map(|(k, v)| (k.clone(), v.clone()))
+            .collect()
+    }
+
     /// Returns the components in the manifest
     pub fn components(&self) -> impl Iterator<Item = &Component> {
         self.spec.components.iter()
@@ -286,6 +372,10 @@ pub struct LinkProperty {
     /// Configuration to apply to the target of the link
     #[serde(default, skip_serializing_if = "Vec::is_empty")]
     pub target_config: Vec<ConfigProperty>,
+    /// Config keys the target of the link must be configured with (in its own config or the
+    /// `target_config` of this link) for the link to work. This is only used for validation
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub required_target_config_keys: Vec<String>,
     /// The name of this link
     #[serde(skip_serializing_if = "Option::is_none")]
     pub name: Option<String>,
@@ -415,6 +505,27 @@ mod test {
             .is_some_and(|v| v == "/tmp/mycache.json")));
     }
 
+    #[test]
+    fn test_content_hash() {
+        let manifest = deserialize_yaml("./oam/config.yaml").expect("Should be able to parse");
+        let json = deserialize_json("./oam/simple1.json").expect("Should be able to parse");
+        let hash = manifest.content_hash();
+        assert_eq!(hash.len(), 64);
+        assert_eq!(
+            hash,
+            manifest.clone().content_hash(),
+            "Hashing should be deterministic"
+        );
+        assert_ne!(hash, json.content_hash());
+
+        let mut changed = manifest.clone();
+        changed
+            .metadata
+            .annotations
+            .insert(VERSION_ANNOTATION_KEY.to_string(), "v9.9.9".to_string());
+        assert_ne!(hash, changed.content_hash());
+    }
+
     #[test]
     fn test_component_matching() {
         let manifest = deserialize_yaml("./oam/simple2.yaml").expect("Should be able to parse");
@@ -519,6 +630,7 @@ mod test {
                 properties: Some(HashMap::from([("port".to_string(), "8080".to_string())])),
             }],
             target_config: vec![],
+            required_target_config_keys: vec![],
             name: Some("default".to_string()),
         };
         let trait_item = Trait::new_link(linkdefprop);
@@ -640,4 +752,21 @@ mod test {
             "Should have found custom properties"
         );
     }
+
+    #[test]
+    fn test_parse_ttl() {
+        assert_eq!(parse_ttl("90"), Some(Duration::from_secs(90)));
+        assert_eq!(parse_ttl("90s"), Some(Duration::from_secs(90)));
+        assert_eq!(parse_ttl("30m"), Some(Duration::from_secs(30 * 60)));
+        assert_eq!(parse_ttl(" 12h "), Some(Duration::from_secs(12 * 60 * 60)));
+        assert_eq!(parse_ttl("7d"), Some(Duration::from_secs(7 * 24 * 60 * 60)));
+
+        for invalid in ["", "0", "0d", "-1h", "1.5h", "5ms", "h", "1w", "forever"] {
+            assert_eq!(
+                parse_ttl(invalid),
+                None,
+                "{invalid:?} should not be a valid TTL"
+            );
+        }
+    }
 }
@@ -0,0 +1,118 @@
+//! Migrations that upgrade manifests authored against older versions of the OAM schema to the
+//! current one ([`OAM_VERSION`]). Migrations are keyed off of the manifest's `apiVersion` and are
+//! pure transformations of the manifest.
+//!
+//! Some older field shapes (such as the `actor` component type or the `replicas` spreadscaler
+//! property) are already handled when deserializing and don't need a migration here
+
+use crate::{Manifest, LINK_TRAIT, OAM_VERSION};
+
+/// The `v1alpha1` OAM api version used by older manifests
+pub const OAM_V1ALPHA1_VERSION: &str = "core.oam.dev/v1alpha1";
+/// The trait type used for links in `v1alpha1` manifests
+const V1ALPHA1_LINK_TRAIT: &str = "linkdef";
+
+/// Upgrades the given manifest to the current schema if it was written against a known older
+/// version. Returns the migrated manifest along with a description of each migration that was
+/// applied, which is empty if the manifest was already current (or its version is unknown)
+pub fn migrate_manifest(mut manifest: Manifest) -> (Manifest, Vec<String>) {
+    let mut applied = Vec::new();
+    if manifest.api_version == OAM_V1ALPHA1_VERSION {
+        migrate_v1alpha1(&mut manifest, &mut applied);
+    }
+    (manifest, applied)
+}
+
+/// Upgrades a `v1alpha1` manifest to `v1beta1`
+fn migrate_v1alpha1(manifest: &mut Manifest, applied: &mut Vec<String>) {
+    let renamed = manifest
+        .spec
+        .components
+        .iter_mut()
+        .flat_map(|component| component.traits.iter_mut().flatten())
+        .filter(|t| t.trait_type == V1ALPHA1_LINK_TRAIT)
+        .fold(0, |count, t| {
+            t.trait_type = LINK_TRAIT.to_owned();
+            count + 1
+        });
+    if renamed > 0 {
+        applied.push(format!(
+            "renamed {renamed} {V1ALPHA1_LINK_TRAIT} trait(s) to {LINK_TRAIT}"
+        ));
+    }
+
+    manifest.api_version = OAM_VERSION.to_owned();
+    applied.push(format!(
+        "upgraded apiVersion from {OAM_V1ALPHA1_VERSION} to {OAM_VERSION}"
+    ));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const V1ALPHA1_MANIFEST: &str = r#"
+apiVersion: core.oam.dev/v1alpha1
+kind: Application
+metadata:
+  name: old-app
+  annotations:
+    version: v0.0.1
+spec:
+  components:
+    - name: echo
+      type: actor
+      properties:
+        image: ghcr.io/wasmcloud/components/http-hello-world-rust:0.1.0
+      traits:
+        - type: spreadscaler
+          properties:
+            replicas: 1
+        - type: linkdef
+          properties:
+            namespace: wasi
+            package: http
+            interfaces: [incoming-handler]
+            target:
+              name: httpserver
+"#;
+
+    #[test]
+    fn test_migrate_v1alpha1() {
+        let manifest: Manifest =
+            serde_yaml::from_str(V1ALPHA1_MANIFEST).expect("Should be able to parse manifest");
+        let (migrated, applied) = migrate_manifest(manifest.clone());
+
+        assert_eq!(migrated.api_version, OAM_VERSION);
+        assert_eq!(applied.len(), 2, "Should have applied both migrations");
+        let traits = migrated.spec.components[0]
+            .traits
+            .as_ref()
+            .expect("Traits should exist");
+        assert_eq!(
+            traits[0].trait_type,
+            manifest.spec.components[0].traits.as_ref().unwrap()[0].trait_type,
+            "Other traits should be untouched"
+        );
+        assert_eq!(traits[1].trait_type, LINK_TRAIT);
+        assert_eq!(
+            traits[1].properties,
+            manifest.spec.components[0].traits.as_ref().unwrap()[1].properties,
+            "Trait properties should be unchanged"
+        );
+    }
+
+    #[test]
+    fn test_current_manifest_is_unchanged() {
+        let manifest: Manifest = serde_yaml::from_str(
+            &V1ALPHA1_MANIFEST
+                .replace(OAM_V1ALPHA1_VERSION, OAM_VERSION)
+                .replace("type: linkdef", "type: link"),
+        )
+        .expect("Should be able to parse manifest");
+        let (migrated, applied) = migrate_manifest(manifest.clone());
+
+        assert!(applied.is_empty(), "No migrations should be applied");
+        assert_eq!(migrated, manifest);
+    }
+}
@@ -1,7 +1,7 @@
 //! Logic for model ([`Manifest`]) validation
 //!
 
-use std::collections::HashMap;
+use std::collections::{BTreeSet, HashMap, HashSet};
 use std::path::Path;
 use std::sync::OnceLock;
 
@@ -9,7 +9,10 @@ use anyhow::{Context as _, Result};
 use regex::Regex;
 use serde::{Deserialize, Serialize};
 
-use crate::{LinkProperty, Manifest, TraitProperty, LATEST_VERSION};
+use crate::{
+    parse_ttl, ConfigProperty, LinkProperty, Manifest, Properties, TraitProperty,
+    DAEMONSCALER_TRAIT, LATEST_VERSION, SPREADSCALER_TRAIT, TTL_ANNOTATION_KEY,
+};
 
 /// A namespace -> package -> interface lookup
 type KnownInterfaceLookup = HashMap<String, HashMap<String, HashMap<String, ()>>>;
@@ -90,6 +93,21 @@ fn get_manifest_name_regex() -> &'static Regex {
     })
 }
 
+/// The maximum length (in bytes) of a manifest name. Names are part of the key each model is stored
+/// under (along with the account and lattice IDs) and of API subjects, so they are kept short
+/// enough for both
+pub const MAX_MANIFEST_NAME_LENGTH: usize = 128;
+
+/// Names that can't be used for manifests because they are used as keywords elsewhere in the API
+pub const RESERVED_MANIFEST_NAMES: [&str; 3] = ["all", "deployed", LATEST_VERSION];
+
+/// Check whether a manifest name is one of the [`RESERVED_MANIFEST_NAMES`] (ignoring case)
+pub fn is_reserved_manifest_name(name: &str) -> bool {
+    RESERVED_MANIFEST_NAMES
+        .iter()
+        .any(|reserved| reserved.eq_ignore_ascii_case(name))
+}
+
 /// Check whether a manifest name matches requirements, returning all validation errors
 pub fn validate_manifest_name(name: &str) -> impl ValidationOutput {
     let mut errors = Vec::new();
@@ -99,6 +117,24 @@ pub fn validate_manifest_name(name: &str) -> impl ValidationOutput {
             format!("manifest name [{name}] is not allowed (should match regex [{MANIFEST_NAME_REGEX_STR}])"),
         ))
     }
+    if name.len() > MAX_MANIFEST_NAME_LENGTH {
+        errors.push(ValidationFailure::new(
+            ValidationFailureLevel::Error,
+            format!(
+                "manifest name [{name}] is {} bytes long, which exceeds the maximum of {MAX_MANIFEST_NAME_LENGTH} bytes",
+                name.len()
+            ),
+        ))
+    }
+    if is_reserved_manifest_name(name) {
+        errors.push(ValidationFailure::new(
+            ValidationFailureLevel::Error,
+            format!(
+                "manifest name [{name}] is reserved (reserved names: [{}])",
+                RESERVED_MANIFEST_NAMES.join(", ")
+            ),
+        ))
+    }
     errors
 }
 
@@ -292,12 +328,29 @@ pub async fn validate_manifest_bytes(
     Ok((manifest, failures))
 }
 
+/// Options for [`validate_manifest_with_options`]
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct ValidationOptions {
+    /// Report link targets that are missing config keys required by the link as errors rather
+    /// than warnings
+    pub strict_link_config: bool,
+    /// Check for spread requirements that contradict the manifest's labels. This is off by default
+    /// since existing manifests may rely on it
+    pub check_label_conflicts: bool,
+    /// Report conflicting labels as errors rather than warnings. Only applies if
+    /// `check_label_conflicts` is set
+    pub strict_label_conflicts: bool,
+}
+
 /// Validate a WADM application manifest, returning a list of validation failures
 ///
 /// At present this can check for:
 /// - unsupported interfaces (i.e. typos, etc)
 /// - unknown packages under known namespaces
 /// - "dangling" links (missing components)
+/// - link config references that don't resolve to config declared in the manifest
+/// - link targets that are missing config keys required by the link
+/// - spread requirements that contradict the manifest's labels (opt in, see [`ValidationOptions`])
 ///
 /// Since `[ValidationFailure]` implements `ValidationOutput`, you can call `valid()` and other
 /// trait methods on it:
@@ -311,6 +364,15 @@ pub async fn validate_manifest_bytes(
 ///
 /// * `manifest` - The [`Manifest`] that should be validated
 pub async fn validate_manifest(manifest: &Manifest) -> Result<Vec<ValidationFailure>> {
+    validate_manifest_with_options(manifest, &ValidationOptions::default()).await
+}
+
+/// Validate a WADM application manifest with the given options. See [`validate_manifest`] for
+/// what is checked
+pub async fn validate_manifest_with_options(
+    manifest: &Manifest,
+    options: &ValidationOptions,
+) -> Result<Vec<ValidationFailure>> {
     // Check for known failures with the manifest
     let mut failures = Vec::new();
     failures.extend(
@@ -325,11 +387,69 @@ pub async fn validate_manifest(manifest: &Manifest) -> Result<Vec<ValidationFail
             .into_iter()
             .cloned(),
     );
+    failures.extend(check_ttl(manifest));
+    failures.extend(check_conflicting_scalers(manifest));
     failures.extend(check_misnamed_interfaces(manifest));
     failures.extend(check_dangling_links(manifest));
+    failures.extend(check_unresolved_link_config(manifest));
+    failures.extend(check_required_link_config(
+        manifest,
+        options.strict_link_config,
+    ));
+    if options.check_label_conflicts {
+        failures.extend(check_label_conflicts(
+            manifest,
+            options.strict_label_conflicts,
+        ));
+    }
     Ok(failures)
 }
 
+/// Check that the TTL annotation, if set, is a valid TTL. An invalid TTL would otherwise mean the
+/// model silently never expires
+fn check_ttl(manifest: &Manifest) -> Vec<ValidationFailure> {
+    match manifest.metadata.annotations.get(TTL_ANNOTATION_KEY) {
+        Some(ttl) if parse_ttl(ttl).is_none() => vec![ValidationFailure::new(
+            ValidationFailureLevel::Error,
+            format!(
+                "invalid [{TTL_ANNOTATION_KEY}] annotation [{ttl}], expected a whole number of seconds, minutes, hours, or days such as 30m or 7d"
+            ),
+        )],
+        _ => Vec::new(),
+    }
+}
+
+/// The trait types that each control how many instances of a component run and where. Only one of
+/// these can be set on a component, as each would scale the component without regard to the others
+const SCALER_TRAITS: [&str; 2] = [SPREADSCALER_TRAIT, DAEMONSCALER_TRAIT];
+
+/// Check for components with more than one scaler trait. Scalers can be combined with any number of
+/// links and custom traits, and the order of a component's traits doesn't matter
+fn check_conflicting_scalers(manifest: &Manifest) -> Vec<ValidationFailure> {
+    let mut failures = Vec::new();
+    for component in manifest.components() {
+        let scalers: Vec<&str> = component
+            .traits
+            .iter()
+            .flatten()
+            .map(|t| t.trait_type.as_str())
+            .filter(|trait_type| SCALER_TRAITS.contains(trait_type))
+            .collect();
+        if scalers.len() > 1 {
+            failures.push(ValidationFailure::new(
+                ValidationFailureLevel::Error,
+                format!(
+                    "component [{}] has conflicting scaler traits [{}], a component can only have one scaler trait",
+                    component.name,
+                    scalers.join(", ")
+                ),
+            ));
+        }
+    }
+
+    failures
+}
+
 /// Check for misnamed host-supported interfaces in the manifest
 fn check_misnamed_interfaces(manifest: &Manifest) -> Vec<ValidationFailure> {
     let mut failures = Vec::new();
@@ -403,9 +523,165 @@ fn check_dangling_links(manifest: &Manifest) -> Vec<ValidationFailure> {
     failures
 }
 
+/// Check for link config references that don't resolve to any config declared in the manifest.
+/// Config is considered declared if it is given properties anywhere in the manifest (on a component,
+/// provider, or link).
+///
+/// A problem of this type only constitutes a warning, because config without properties is assumed
+/// to be managed externally to WADM. It is however a common place for typos to sneak in.
+fn check_unresolved_link_config(manifest: &Manifest) -> Vec<ValidationFailure> {
+    let component_config =
+        manifest
+            .components()
+            .flat_map(|component| match &component.properties {
+                Properties::Component { properties } => properties.config.iter(),
+                Properties::Capability { properties } => properties.config.iter(),
+            });
+    let link_config = manifest
+        .links()
+        .filter_map(|link_trait| match &link_trait.properties {
+            TraitProperty::Link(link) => Some(link),
+            _ => None,
+        })
+        .flat_map(|link| link.source_config.iter().chain(link.target_config.iter()))
+        .collect::<Vec<&ConfigProperty>>();
+
+    let declared = component_config
+        .chain(link_config.iter().copied())
+        .filter(|config| config.properties.is_some())
+        .map(|config| config.name.as_str())
+        .collect::<HashSet<&str>>();
+
+    let unresolved = link_config
+        .into_iter()
+        .filter(|config| config.properties.is_none() && !declared.contains(config.name.as_str()))
+        .map(|config| config.name.as_str())
+        .collect::<BTreeSet<&str>>();
+    if unresolved.is_empty() {
+        return Vec::new();
+    }
+
+    vec![ValidationFailure::new(
+        ValidationFailureLevel::Warning,
+        format!(
+            "link config reference(s) [{}] do not resolve to config declared in the manifest",
+            unresolved.into_iter().collect::<Vec<_>>().join(", ")
+        ),
+    )]
+}
+
+/// Check that the target of each link is configured with the config keys the link requires. Keys
+/// can be set in the config of the target component or in the `target_config` of the link.
+///
+/// Targets that aren't in the manifest are reported by [`check_dangling_links`] instead. Targets
+/// with config that is managed externally to WADM (config without properties) are skipped, since we
+/// can't know which keys that config has. Missing keys are a warning unless `strict` is set
+fn check_required_link_config(manifest: &Manifest, strict: bool) -> Vec<ValidationFailure> {
+    let lookup = manifest.component_lookup();
+    let level = if strict {
+        ValidationFailureLevel::Error
+    } else {
+        ValidationFailureLevel::Warning
+    };
+    let mut failures = Vec::new();
+    for link_trait in manifest.links() {
+        let TraitProperty::Link(link) = &link_trait.properties else {
+            continue;
+        };
+        if link.required_target_config_keys.is_empty() {
+            continue;
+        }
+        let Some(target) = lookup.get(&link.target) else {
+            continue;
+        };
+        let target_config = match &target.properties {
+            Properties::Component { properties } => properties.config.iter(),
+            Properties::Capability { properties } => properties.config.iter(),
+        };
+        let Some(configured) = target_config
+            .chain(link.target_config.iter())
+            .map(|config| config.properties.as_ref())
+            .collect::<Option<Vec<_>>>()
+        else {
+            continue;
+        };
+        let keys = configured
+            .into_iter()
+            .flat_map(|properties| properties.keys().map(String::as_str))
+            .collect::<HashSet<&str>>();
+        let missing = link
+            .required_target_config_keys
+            .iter()
+            .map(String::as_str)
+            .filter(|key| !keys.contains(key))
+            .collect::<BTreeSet<&str>>();
+        if missing.is_empty() {
+            continue;
+        }
+
+        let link_identifier = link
+            .name
+            .as_ref()
+            .map(|n| format!("(name [{n}])"))
+            .unwrap_or_else(|| format!("(target [{}])", link.target));
+        failures.push(ValidationFailure::new(
+            level.clone(),
+            format!(
+                "link {link_identifier} requires config key(s) [{}] that are not set for target [{}]",
+                missing.into_iter().collect::<Vec<_>>().join(", "),
+                link.target
+            ),
+        ));
+    }
+
+    failures
+}
+
+/// Check for spread requirements that require a different value for a label than the one set in
+/// the manifest's metadata. Scalers place components on hosts matching their spread requirements,
+/// so a component requiring `zone=west` in a manifest labeled `zone=east` ends up deployed somewhere
+/// other than what the manifest's labels say. Conflicts are a warning unless `strict` is set
+fn check_label_conflicts(manifest: &Manifest, strict: bool) -> Vec<ValidationFailure> {
+    let level = if strict {
+        ValidationFailureLevel::Error
+    } else {
+        ValidationFailureLevel::Warning
+    };
+    let mut failures = Vec::new();
+    for component in manifest.components() {
+        let spreads = component
+            .traits
+            .iter()
+            .flatten()
+            .filter_map(|t| match &t.properties {
+                TraitProperty::SpreadScaler(props) => Some(props.spread.iter()),
+                _ => None,
+            })
+            .flatten();
+        for spread in spreads {
+            for (key, required) in spread.requirements.iter() {
+                match manifest.metadata.labels.get(key) {
+                    Some(label) if label != required => failures.push(ValidationFailure::new(
+                        level.clone(),
+                        format!(
+                            "component [{}] spread [{}] requires label [{key}={required}], which conflicts with manifest label [{key}={label}]",
+                            component.name, spread.name
+                        ),
+                    )),
+                    _ => {}
+                }
+            }
+        }
+    }
+
+    failures
+}
+
 #[cfg(test)]
 mod tests {
-    use super::is_valid_manifest_name;
+    use super::{
+        is_valid_manifest_name, validate_manifest_name, ValidationOutput, MAX_MANIFEST_NAME_LENGTH,
+    };
 
     const VALID_MANIFEST_NAMES: [&str; 4] = [
         "mymanifest",
@@ -432,4 +708,29 @@ mod tests {
             assert!(!is_valid_manifest_name(invalid))
         }
     }
+
+    /// Ensure names that are too long or reserved fail with a reason
+    #[test]
+    fn manifest_names_length_and_reserved() {
+        assert!(is_valid_manifest_name(
+            &"a".repeat(MAX_MANIFEST_NAME_LENGTH)
+        ));
+        let errors = validate_manifest_name(&"a".repeat(MAX_MANIFEST_NAME_LENGTH + 1));
+        assert!(errors
+            .errors()
+            .iter()
+            .any(|e| e.msg.contains("exceeds the maximum")));
+
+        for reserved in ["all", "deployed", "latest", "Latest"] {
+            let errors = validate_manifest_name(reserved);
+            assert!(
+                errors
+                    .errors()
+                    .iter()
+                    .any(|e| e.msg.contains("is reserved")),
+                "{reserved} should be reserved"
+            );
+        }
+        assert!(is_valid_manifest_name("all-apps"));
+    }
 }
@@ -527,6 +527,14 @@ event_impl!(
 pub struct ManifestPublished {
     #[serde(flatten)]
     pub manifest: Manifest,
+    /// Set when the manifest was staged as a canary rather than deployed. Canary versions are
+    /// not reconciled in place of the deployed version
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub canary: bool,
+    /// The trace ID of the API request that caused this event, used to correlate it with the
+    /// request in logs
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub trace_id: Option<String>,
 }
 
 event_impl!(ManifestPublished, "com.wadm.manifest_published");
@@ -534,6 +542,10 @@ event_impl!(ManifestPublished, "com.wadm.manifest_published");
 #[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq)]
 pub struct ManifestUnpublished {
     pub name: String,
+    /// The trace ID of the API request that caused this event, used to correlate it with the
+    /// request in logs
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub trace_id: Option<String>,
 }
 
 event_impl!(ManifestUnpublished, "com.wadm.manifest_unpublished");
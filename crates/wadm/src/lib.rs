@@ -26,6 +26,11 @@ pub const DEFAULT_MULTITENANT_EVENTS_TOPIC: &str = "*.wasmbus.evt.*.>";
 pub const DEFAULT_COMMANDS_TOPIC: &str = "wadm.cmd.*";
 /// Default topic to listen to for all status updates. wadm.status.<lattice_id>.<manifest_name>
 pub const DEFAULT_STATUS_TOPIC: &str = "wadm.status.*.*";
+/// Default topic to listen to for all status updates of models owned by an account.
+/// wadm.status.<account_id>.<lattice_id>.<manifest_name>
+pub const DEFAULT_ACCOUNT_STATUS_TOPIC: &str = "wadm.status.*.*.*";
+/// The prefix of the topics status updates are published on
+pub const STATUS_TOPIC_PREFIX: &str = "wadm.status";
 /// The default listen topic for the merged wadm events stream. This topic is an amalgamation of
 /// wasmbus.evt topics plus the wadm.internal topics
 pub const DEFAULT_WADM_EVENTS_TOPIC: &str = "wadm.evt.*";
@@ -39,3 +44,20 @@ pub const APP_SPEC_ANNOTATION: &str = "wasmcloud.dev/appspec";
 pub const SCALER_KEY: &str = "wasmcloud.dev/scaler";
 /// The default link name. In the future, this will likely be pulled in from another crate
 pub const DEFAULT_LINK_NAME: &str = "default";
+
+/// Returns the prefix of the topics that status updates for models in the given account and
+/// lattice are published on. This is `wadm.status.<account_id>.<lattice_id>` for models owned by an
+/// account and `wadm.status.<lattice_id>` otherwise, so accounts sharing a lattice don't see each
+/// other's statuses
+pub fn status_topic_prefix(account_id: Option<&str>, lattice_id: &str) -> String {
+    match account_id {
+        Some(account_id) => format!("{STATUS_TOPIC_PREFIX}.{account_id}.{lattice_id}"),
+        None => format!("{STATUS_TOPIC_PREFIX}.{lattice_id}"),
+    }
+}
+
+/// Returns the topic that status updates for the given model are published on. See
+/// [`status_topic_prefix`] for how the account is included
+pub fn status_topic(account_id: Option<&str>, lattice_id: &str, name: &str) -> String {
+    format!("{}.{name}", status_topic_prefix(account_id, lattice_id))
+}
@@ -1,8 +1,18 @@
 //! Contains the internal storage definition of a manifest
+use std::collections::{HashMap, VecDeque};
+
+use chrono::{DateTime, Utc};
 use indexmap::IndexMap;
 use serde::{Deserialize, Serialize};
 
-use wadm_types::{Manifest, LATEST_VERSION, VERSION_ANNOTATION_KEY};
+use wadm_types::{
+    api::{DeployAction, DeployEvent, RelativeVersion},
+    Manifest, LATEST_VERSION, VERSION_ANNOTATION_KEY,
+};
+
+/// The maximum number of deploy events kept in a model's deploy history. Older events are dropped
+/// once this is reached
+pub(crate) const MAX_DEPLOY_EVENTS: usize = 50;
 
 /// This struct represents a single manifest, with its version history. Internally these are stored
 /// as an indexmap keyed by version name
@@ -15,6 +25,27 @@ pub(crate) struct StoredManifest {
     manifests: IndexMap<String, Manifest>,
     // Set only if a version is deployed
     deployed_version: Option<String>,
+    // Set only if a version is staged as a canary. This is separate from the deployed version until
+    // it is promoted
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    staged_version: Option<String>,
+    // When each version was stored. Versions stored by older versions of wadm won't have one
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    version_created_at: HashMap<String, DateTime<Utc>>,
+    // A bounded log of deploys and undeploys, oldest first. Older stored models won't have this
+    #[serde(default, skip_serializing_if = "VecDeque::is_empty")]
+    deploy_events: VecDeque<DeployEvent>,
+    // Set when the model was archived (soft deleted). Archived models are kept so they can be
+    // restored, but can't be deployed or changed until they are
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    archived_at: Option<DateTime<Utc>>,
+    // Components of the deployed version that were disabled when it was deployed. These are left
+    // out of what is run, but the stored manifest is unchanged. Any other deploy clears this
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    disabled_components: Vec<String>,
+    // Set when the model was last undeployed, and cleared when it is deployed again
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    undeployed_at: Option<DateTime<Utc>>,
 }
 
 impl StoredManifest {
@@ -45,20 +76,73 @@ impl StoredManifest {
         if self.manifests.contains_key(&version) {
             return false;
         }
+        self.version_created_at.insert(version.clone(), Utc::now());
         self.manifests.insert(version, manifest);
         true
     }
 
+    /// Replaces the stored manifest for an existing version with the given manifest, which must
+    /// have the same version. Returns `false` if that version isn't stored
+    pub fn replace_version(&mut self, manifest: Manifest) -> bool {
+        match self.manifests.get_mut(manifest.version()) {
+            Some(existing) => {
+                *existing = manifest;
+                true
+            }
+            None => false,
+        }
+    }
+
     /// Deletes the given version from the manifest. Returning true if it was deleted
     pub fn delete_version(&mut self, version: &str) -> bool {
+        self.version_created_at.remove(version);
+        if self.staged_version.as_deref() == Some(version) {
+            self.staged_version = None;
+        }
         self.manifests.shift_remove(version).is_some()
     }
 
+    /// Deletes every version except the deployed and staged versions (and the latest version if
+    /// `keep_latest` is set), returning the deleted versions in creation order
+    pub fn prune(&mut self, keep_latest: bool) -> Vec<String> {
+        let latest = self.current_version().to_owned();
+        let pruned: Vec<String> = self
+            .manifests
+            .keys()
+            .filter(|v| {
+                !self.is_deployed(v)
+                    && self.staged_version.as_ref() != Some(*v)
+                    && !(keep_latest && **v == latest)
+            })
+            .cloned()
+            .collect();
+        for version in pruned.iter() {
+            self.delete_version(version);
+        }
+        pruned
+    }
+
+    /// Returns when the given version was stored, if known
+    pub fn version_created_at(&self, version: &str) -> Option<DateTime<Utc>> {
+        self.version_created_at.get(version).copied()
+    }
+
     /// Returns an iterator over all stored versions in creation order
     pub fn all_versions(&self) -> impl IntoIterator<Item = &String> {
         self.manifests.keys()
     }
 
+    /// Returns the version created just before or after the given version. Returns `None` if the
+    /// given version doesn't exist or is the first (or last) version
+    pub fn relative_version(&self, version: &str, relative: RelativeVersion) -> Option<&str> {
+        let idx = self.manifests.get_index_of(version)?;
+        let idx = match relative {
+            RelativeVersion::Previous => idx.checked_sub(1)?,
+            RelativeVersion::Next => idx + 1,
+        };
+        self.manifests.get_index(idx).map(|(v, _)| v.as_str())
+    }
+
     #[allow(unused)]
     /// Returns a reference to the deployed version (if it is set)
     pub fn deployed_version(&self) -> Option<&str> {
@@ -73,9 +157,36 @@ impl StoredManifest {
             .unwrap_or(false)
     }
 
-    /// Sets this manifest as undeployed. Returning true if it was currently deployed
+    /// Returns a reference to the staged version (if it is set)
+    pub fn staged_version(&self) -> Option<&str> {
+        self.staged_version.as_deref()
+    }
+
+    /// Sets this manifest as undeployed, also clearing any staged version. Returning true if it was
+    /// currently deployed
     pub fn undeploy(&mut self) -> bool {
-        self.deployed_version.take().is_some()
+        self.staged_version = None;
+        self.disabled_components.clear();
+        match self.deployed_version.take() {
+            Some(version) => {
+                self.record_deploy_event(version, DeployAction::Undeployed);
+                self.undeployed_at = Some(Utc::now());
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Undeploys the model if its deployed version is no longer stored, returning the missing
+    /// version. Deleting the deployed version should always undeploy it, so this is a safeguard
+    /// against ever leaving a model deployed at a version that doesn't exist
+    pub fn clear_missing_deployed_version(&mut self) -> Option<String> {
+        let deployed = self.deployed_version.clone()?;
+        if self.manifests.contains_key(&deployed) {
+            return None;
+        }
+        self.undeploy();
+        Some(deployed)
     }
 
     /// Attempts to deploy the given version. If none is passed or the version is "latest", it will
@@ -83,26 +194,140 @@ impl StoredManifest {
     ///
     /// Returns true if it was deployed, false otherwise
     pub fn deploy(&mut self, version: Option<String>) -> bool {
-        match version {
-            Some(v) if v == LATEST_VERSION => {
-                self.deployed_version = Some(self.current_version().to_owned());
-                true
-            }
-            None => {
-                self.deployed_version = Some(self.current_version().to_owned());
-                true
+        let version = match version {
+            Some(v) if v == LATEST_VERSION => self.current_version().to_owned(),
+            None => self.current_version().to_owned(),
+            Some(v) => {
+                if !self.manifests.contains_key(&v) {
+                    return false;
+                }
+                v
             }
+        };
+        if self.staged_version.as_ref() == Some(&version) {
+            self.staged_version = None;
+        }
+        self.deployed_version = Some(version.clone());
+        self.disabled_components.clear();
+        self.undeployed_at = None;
+        self.record_deploy_event(version, DeployAction::Deployed);
+        true
+    }
+
+    /// Sets the components of the deployed version that shouldn't be run. This must be called
+    /// after [`Self::deploy`], which clears them
+    pub fn set_disabled_components(&mut self, components: Vec<String>) {
+        self.disabled_components = components;
+    }
+
+    /// Returns the components of the deployed version that shouldn't be run
+    pub fn disabled_components(&self) -> &[String] {
+        &self.disabled_components
+    }
+
+    /// Stages the given version as a canary without deploying it. If none is passed or the version
+    /// is "latest", it will stage the latest version.
+    ///
+    /// Returns true if it was staged, false otherwise
+    pub fn stage(&mut self, version: Option<String>) -> bool {
+        let version = match version {
+            Some(v) if v == LATEST_VERSION => self.current_version().to_owned(),
+            None => self.current_version().to_owned(),
             Some(v) => {
-                if self.manifests.contains_key(&v) {
-                    self.deployed_version = Some(v);
-                    true
-                } else {
-                    false
+                if !self.manifests.contains_key(&v) {
+                    return false;
                 }
+                v
+            }
+        };
+        self.staged_version = Some(version);
+        true
+    }
+
+    /// Deploys the staged version, returning the version that was promoted. Returns None if no
+    /// version is staged
+    pub fn promote(&mut self) -> Option<String> {
+        let version = self.staged_version.take()?;
+        self.deploy(Some(version.clone())).then_some(version)
+    }
+
+    /// Archives this manifest, undeploying it if it was deployed. Returns true if it was deployed
+    pub fn archive(&mut self) -> bool {
+        self.archived_at = Some(Utc::now());
+        self.undeploy()
+    }
+
+    /// Restores this manifest from being archived. Returns false if it wasn't archived
+    pub fn restore(&mut self) -> bool {
+        self.archived_at.take().is_some()
+    }
+
+    /// Returns whether or not this manifest is archived
+    pub fn is_archived(&self) -> bool {
+        self.archived_at.is_some()
+    }
+
+    /// Returns when this manifest was archived, if it is archived
+    pub fn archived_at(&self) -> Option<DateTime<Utc>> {
+        self.archived_at
+    }
+
+    /// Returns when this manifest was last undeployed, if it isn't deployed
+    pub fn undeployed_at(&self) -> Option<DateTime<Utc>> {
+        self.undeployed_at
+    }
+
+    /// Keeps the given timestamps from an exported manifest so importing it doesn't reset when it
+    /// was archived, undeployed, or its versions were created. Timestamps for versions that aren't
+    /// stored are ignored, and the undeployed time is only kept if the manifest isn't deployed
+    pub fn set_imported_timestamps(
+        &mut self,
+        archived_at: Option<DateTime<Utc>>,
+        undeployed_at: Option<DateTime<Utc>>,
+        version_created_at: impl IntoIterator<Item = (String, DateTime<Utc>)>,
+    ) {
+        self.archived_at = archived_at;
+        if self.deployed_version.is_none() {
+            self.undeployed_at = undeployed_at;
+        }
+        for (version, created_at) in version_created_at {
+            if self.manifests.contains_key(&version) {
+                self.version_created_at.insert(version, created_at);
             }
         }
     }
 
+    /// Returns when this model should be deleted for having been undeployed longer than the TTL
+    /// set on its current version (see [`Manifest::ttl`]). The TTL counts from when the model was
+    /// undeployed or its newest version was stored, whichever is later. Returns `None` if the model
+    /// is deployed or staged, has no TTL, or it isn't known when it was last used
+    pub fn expires_at(&self) -> Option<DateTime<Utc>> {
+        if self.deployed_version.is_some() || self.staged_version.is_some() {
+            return None;
+        }
+        let ttl = chrono::Duration::from_std(self.get_current().ttl()?).ok()?;
+        let last_used = self
+            .undeployed_at
+            .max(self.version_created_at(self.current_version()))?;
+        last_used.checked_add_signed(ttl)
+    }
+
+    /// Returns the recorded deploys and undeploys of this manifest, oldest first
+    pub fn deploy_events(&self) -> impl Iterator<Item = &DeployEvent> {
+        self.deploy_events.iter()
+    }
+
+    fn record_deploy_event(&mut self, version: String, action: DeployAction) {
+        if self.deploy_events.len() >= MAX_DEPLOY_EVENTS {
+            self.deploy_events.pop_front();
+        }
+        self.deploy_events.push_back(DeployEvent {
+            version,
+            action,
+            timestamp: chrono::Utc::now(),
+        });
+    }
+
     /// Returns a reference to the current manifest
     pub fn get_current(&self) -> &Manifest {
         // SAFETY: This is internal usage only so we will always have at least one thing in here.
@@ -130,6 +355,20 @@ impl StoredManifest {
             .and_then(|v| self.manifests.get(v))
     }
 
+    /// Returns the deployed version of the manifest as it should be run, without any disabled
+    /// components (if a version is deployed)
+    pub fn get_deployed_enabled(&self) -> Option<Manifest> {
+        self.get_deployed()
+            .map(|manifest| without_components(manifest, &self.disabled_components))
+    }
+
+    /// Returns the staged version of the manifest (if one is staged)
+    pub fn get_staged(&self) -> Option<&Manifest> {
+        self.staged_version
+            .as_ref()
+            .and_then(|v| self.manifests.get(v))
+    }
+
     /// Returns whether or not this is a new (empty) manifest
     pub fn is_empty(&self) -> bool {
         self.manifests.is_empty()
@@ -139,6 +378,33 @@ impl StoredManifest {
     pub fn count(&self) -> usize {
         self.manifests.len()
     }
+
+    /// Returns the size in bytes of this stored manifest serialized as JSON. This is the size
+    /// before any storage compression is applied
+    pub fn size_bytes(&self) -> usize {
+        serde_json::to_vec(self)
+            .map(|data| data.len())
+            .unwrap_or_default()
+    }
+
+    /// Returns the size in bytes of the given version serialized as JSON, if it exists
+    pub fn version_size_bytes(&self, version: &str) -> Option<usize> {
+        self.manifests
+            .get(version)
+            .and_then(|manifest| serde_json::to_vec(manifest).ok())
+            .map(|data| data.len())
+    }
+}
+
+/// Returns a copy of the manifest without the named components. Links to the removed components are
+/// left in place, so callers should make sure nothing links to them first
+pub(crate) fn without_components(manifest: &Manifest, names: &[String]) -> Manifest {
+    let mut manifest = manifest.clone();
+    manifest
+        .spec
+        .components
+        .retain(|component| !names.contains(&component.name));
+    manifest
 }
 
 #[cfg(test)]
@@ -148,7 +414,7 @@ mod test {
     use std::{io::BufReader, path::Path};
 
     use anyhow::Result;
-    use wadm_types::VERSION_ANNOTATION_KEY;
+    use wadm_types::{TTL_ANNOTATION_KEY, VERSION_ANNOTATION_KEY};
 
     pub(crate) fn deserialize_yaml(filepath: impl AsRef<Path>) -> Result<Manifest> {
         let file = std::fs::File::open(filepath)?;
@@ -187,5 +453,396 @@ mod test {
             !stored.add_version(manifest),
             "Adding duplicate version should fail"
         );
+
+        assert!(
+            stored.version_created_at("v0.0.1").is_some(),
+            "Stored versions should have a creation time"
+        );
+        assert!(stored.delete_version("v0.0.1"));
+        assert!(
+            stored.version_created_at("v0.0.1").is_none(),
+            "Deleted versions should not have a creation time"
+        );
+    }
+
+    #[test]
+    fn test_expires_at() {
+        let mut manifest = deserialize_yaml("./oam/simple2.yaml").expect("Should be able to parse");
+        manifest
+            .metadata
+            .annotations
+            .insert(VERSION_ANNOTATION_KEY.to_string(), "v1".to_string());
+        let mut stored = StoredManifest::default();
+        assert!(stored.add_version(manifest.clone()));
+        assert!(
+            stored.expires_at().is_none(),
+            "Models without a TTL should never expire"
+        );
+
+        manifest
+            .metadata
+            .annotations
+            .insert(VERSION_ANNOTATION_KEY.to_string(), "v2".to_string());
+        manifest
+            .metadata
+            .annotations
+            .insert(TTL_ANNOTATION_KEY.to_string(), "1h".to_string());
+        assert!(stored.add_version(manifest));
+        let created = stored.version_created_at("v2").unwrap();
+        assert_eq!(
+            stored.expires_at(),
+            Some(created + chrono::Duration::hours(1)),
+            "Models that were never deployed should expire after their newest version was stored"
+        );
+
+        assert!(stored.deploy(None));
+        assert!(
+            stored.expires_at().is_none(),
+            "Deployed models should never expire"
+        );
+
+        assert!(stored.undeploy());
+        let undeployed = stored
+            .undeployed_at
+            .expect("Undeploying should be recorded");
+        assert_eq!(
+            stored.expires_at(),
+            Some(undeployed + chrono::Duration::hours(1)),
+            "Undeployed models should expire after they were undeployed"
+        );
+
+        assert!(stored.stage(None));
+        assert!(
+            stored.expires_at().is_none(),
+            "Models with a staged version should never expire"
+        );
+    }
+
+    #[test]
+    fn test_relative_version() {
+        let manifest = deserialize_yaml("./oam/simple2.yaml").expect("Should be able to parse");
+        let mut stored = StoredManifest::default();
+        for version in ["v1", "v2", "v3"] {
+            let mut manifest = manifest.clone();
+            manifest
+                .metadata
+                .annotations
+                .insert(VERSION_ANNOTATION_KEY.to_string(), version.to_string());
+            assert!(stored.add_version(manifest));
+        }
+
+        assert_eq!(
+            stored.relative_version("v2", RelativeVersion::Previous),
+            Some("v1")
+        );
+        assert_eq!(
+            stored.relative_version("v2", RelativeVersion::Next),
+            Some("v3")
+        );
+        assert_eq!(
+            stored.relative_version("v1", RelativeVersion::Previous),
+            None
+        );
+        assert_eq!(stored.relative_version("v3", RelativeVersion::Next), None);
+        assert_eq!(
+            stored.relative_version("v4", RelativeVersion::Previous),
+            None,
+            "Unknown versions have no relative versions"
+        );
+    }
+
+    #[test]
+    fn test_deployed_version_survives_deletes() {
+        let manifest = deserialize_yaml("./oam/simple2.yaml").expect("Should be able to parse");
+        let mut stored = StoredManifest::default();
+        for version in ["v1", "v2", "v3"] {
+            let mut manifest = manifest.clone();
+            manifest
+                .metadata
+                .annotations
+                .insert(VERSION_ANNOTATION_KEY.to_string(), version.to_string());
+            assert!(stored.add_version(manifest));
+        }
+        assert!(stored.deploy(Some("v2".to_string())));
+
+        // Deleting versions around the deployed one should leave it deployed
+        for version in ["v1", "v3"] {
+            assert!(stored.delete_version(version));
+            assert!(stored.clear_missing_deployed_version().is_none());
+            assert_eq!(stored.deployed_version(), Some("v2"));
+            assert!(
+                stored.get_deployed().is_some(),
+                "Deployed version should still resolve"
+            );
+        }
+
+        // If the deployed version goes missing, the model should be undeployed
+        stored.manifests.shift_remove("v2");
+        assert_eq!(
+            stored.clear_missing_deployed_version().as_deref(),
+            Some("v2")
+        );
+        assert!(stored.deployed_version().is_none());
+        assert!(stored.clear_missing_deployed_version().is_none());
+    }
+
+    #[test]
+    fn test_disabled_components() {
+        let manifest = deserialize_yaml("./oam/simple2.yaml").expect("Should be able to parse");
+        let mut stored = StoredManifest::default();
+        assert!(stored.add_version(manifest));
+        assert!(stored.deploy(None));
+        stored.set_disabled_components(vec!["ledblinky".to_string()]);
+
+        let enabled = stored
+            .get_deployed_enabled()
+            .expect("Should have a deployed version");
+        assert!(!enabled
+            .spec
+            .components
+            .iter()
+            .any(|c| c.name == "ledblinky"));
+        assert!(
+            stored
+                .get_deployed()
+                .unwrap()
+                .spec
+                .components
+                .iter()
+                .any(|c| c.name == "ledblinky"),
+            "The stored manifest should be unchanged"
+        );
+
+        assert!(stored.deploy(None));
+        assert!(
+            stored.disabled_components().is_empty(),
+            "Deploying again should enable everything"
+        );
+    }
+
+    #[test]
+    fn test_size_bytes() {
+        let manifest = deserialize_yaml("./oam/simple2.yaml").expect("Should be able to parse");
+        let mut stored = StoredManifest::default();
+        let empty_size = stored.size_bytes();
+        assert!(stored.version_size_bytes("v1").is_none());
+
+        for version in ["v1", "v2"] {
+            let mut manifest = manifest.clone();
+            manifest
+                .metadata
+                .annotations
+                .insert(VERSION_ANNOTATION_KEY.to_string(), version.to_string());
+            assert!(stored.add_version(manifest));
+        }
+        let version_sizes: usize = ["v1", "v2"]
+            .iter()
+            .map(|v| {
+                stored
+                    .version_size_bytes(v)
+                    .expect("Version should have a size")
+            })
+            .sum();
+        assert!(version_sizes > 0);
+        assert!(
+            stored.size_bytes() >= empty_size + version_sizes,
+            "The total size should include every version"
+        );
+    }
+
+    #[test]
+    fn test_prune() {
+        let manifest = deserialize_yaml("./oam/simple2.yaml").expect("Should be able to parse");
+        let mut stored = StoredManifest::default();
+        for version in ["v1", "v2", "v3", "v4", "v5"] {
+            let mut manifest = manifest.clone();
+            manifest
+                .metadata
+                .annotations
+                .insert(VERSION_ANNOTATION_KEY.to_string(), version.to_string());
+            assert!(stored.add_version(manifest));
+        }
+        assert!(stored.deploy(Some("v2".to_string())));
+        assert!(stored.stage(Some("v3".to_string())));
+
+        assert_eq!(stored.prune(true), vec!["v1", "v4"]);
+        assert_eq!(
+            stored.all_versions().into_iter().collect::<Vec<_>>(),
+            vec!["v2", "v3", "v5"]
+        );
+        assert!(stored.version_created_at("v1").is_none());
+
+        assert_eq!(stored.prune(false), vec!["v5"]);
+        assert_eq!(stored.current_version(), "v3");
+        assert!(
+            stored.prune(false).is_empty(),
+            "Pruning again should be a noop"
+        );
+    }
+
+    #[test]
+    fn test_deploy_events() {
+        let mut manifest = deserialize_yaml("./oam/simple2.yaml").expect("Should be able to parse");
+        manifest
+            .metadata
+            .annotations
+            .insert(VERSION_ANNOTATION_KEY.to_string(), "v0.0.1".to_string());
+        let mut stored = StoredManifest::default();
+        stored.add_version(manifest);
+
+        assert!(!stored.undeploy(), "Nothing should be deployed yet");
+        assert!(!stored.deploy(Some("v0.0.2".to_string())));
+        assert_eq!(
+            stored.deploy_events().count(),
+            0,
+            "Failed deploys and undeploys should not be recorded"
+        );
+
+        assert!(stored.deploy(None));
+        assert!(stored.undeploy());
+        let events = stored
+            .deploy_events()
+            .map(|e| (e.version.as_str(), e.action))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            events,
+            [
+                ("v0.0.1", DeployAction::Deployed),
+                ("v0.0.1", DeployAction::Undeployed)
+            ]
+        );
+
+        for _ in 0..MAX_DEPLOY_EVENTS {
+            stored.deploy(None);
+        }
+        assert_eq!(
+            stored.deploy_events().count(),
+            MAX_DEPLOY_EVENTS,
+            "Deploy history should be capped"
+        );
+        assert_eq!(
+            stored.deploy_events().next().map(|e| e.action),
+            Some(DeployAction::Deployed),
+            "Oldest events should be dropped first"
+        );
+
+        // Models stored before deploy history existed should deserialize with an empty history
+        let old: StoredManifest =
+            serde_json::from_str(r#"{"manifests":{},"deployed_version":null}"#)
+                .expect("Should be able to deserialize model without deploy history");
+        assert_eq!(old.deploy_events().count(), 0);
+    }
+
+    #[test]
+    fn test_archive() {
+        let manifest = deserialize_yaml("./oam/simple2.yaml").expect("Should be able to parse");
+        let mut stored = StoredManifest::default();
+        stored.add_version(manifest);
+        stored.deploy(None);
+
+        assert!(stored.archive(), "Archiving should undeploy the manifest");
+        assert!(stored.is_archived());
+        assert!(stored.deployed_version().is_none());
+
+        assert!(
+            stored.restore(),
+            "Should be able to restore an archived manifest"
+        );
+        assert!(!stored.is_archived());
+        assert!(
+            stored.deployed_version().is_none(),
+            "Restoring should not redeploy the manifest"
+        );
+        assert!(!stored.restore(), "Restoring twice should be a no-op");
+    }
+
+    #[test]
+    fn test_replace_version() {
+        let mut manifest = deserialize_yaml("./oam/simple2.yaml").expect("Should be able to parse");
+        manifest
+            .metadata
+            .annotations
+            .insert(VERSION_ANNOTATION_KEY.to_string(), "v1".to_string());
+        let mut stored = StoredManifest::default();
+        stored.add_version(manifest.clone());
+        stored.deploy(None);
+
+        manifest
+            .metadata
+            .labels
+            .insert("team".to_string(), "platform".to_string());
+        assert!(stored.replace_version(manifest.clone()));
+        assert_eq!(
+            stored
+                .get_version("v1")
+                .unwrap()
+                .metadata
+                .labels
+                .get("team"),
+            Some(&"platform".to_string())
+        );
+        assert_eq!(stored.count(), 1, "Replacing should not add a version");
+        assert_eq!(
+            stored.deployed_version(),
+            Some("v1"),
+            "Replacing should not change the deployed version"
+        );
+
+        manifest
+            .metadata
+            .annotations
+            .insert(VERSION_ANNOTATION_KEY.to_string(), "v2".to_string());
+        assert!(
+            !stored.replace_version(manifest),
+            "Replacing a missing version should fail"
+        );
+    }
+
+    #[test]
+    fn test_stage_and_promote() {
+        let mut manifest = deserialize_yaml("./oam/simple2.yaml").expect("Should be able to parse");
+        let mut stored = StoredManifest::default();
+        manifest
+            .metadata
+            .annotations
+            .insert(VERSION_ANNOTATION_KEY.to_string(), "v1".to_string());
+        stored.add_version(manifest.clone());
+        stored.deploy(None);
+        manifest
+            .metadata
+            .annotations
+            .insert(VERSION_ANNOTATION_KEY.to_string(), "v2".to_string());
+        stored.add_version(manifest);
+
+        assert!(
+            !stored.stage(Some("v3".to_string())),
+            "Staging a missing version should fail"
+        );
+        assert!(stored.promote().is_none(), "Nothing should be staged yet");
+
+        assert!(
+            stored.stage(None),
+            "Should be able to stage the latest version"
+        );
+        assert_eq!(stored.staged_version(), Some("v2"));
+        assert_eq!(
+            stored.deployed_version(),
+            Some("v1"),
+            "Staging should not change the deployed version"
+        );
+
+        assert_eq!(stored.promote().as_deref(), Some("v2"));
+        assert_eq!(stored.deployed_version(), Some("v2"));
+        assert!(
+            stored.staged_version().is_none(),
+            "Promoting should clear the staged version"
+        );
+
+        stored.stage(Some("v1".to_string()));
+        stored.undeploy();
+        assert!(
+            stored.staged_version().is_none(),
+            "Undeploying should clear the staged version"
+        );
     }
 }
@@ -195,7 +195,7 @@ where
         let scalers: HashMap<String, ScalerList> = all_manifests
             .into_iter()
             .filter_map(|manifest| {
-                let data = manifest.get_deployed()?;
+                let data = manifest.get_deployed_enabled()?;
                 let name = manifest.name().to_owned();
                 let scalers = components_to_scalers(
                     &data.spec.components,
@@ -0,0 +1,257 @@
+use std::cell::RefCell;
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use wadm_types::api::{ModelEvent, ModelEventType};
+
+use super::{notifier::current_trace_id, ModelOperation};
+
+/// The default topic prefix audit records are published under. Records for a lattice are published
+/// on `{prefix}.{lattice-id}`
+pub const DEFAULT_AUDIT_TOPIC_PREFIX: &str = "wadm.audit";
+
+tokio::task_local! {
+    /// The audit record for the mutating API request currently being handled. This is set by the
+    /// server around each call to a handler that changes models, filled in by the handler as it
+    /// learns which model and version are affected, and completed when the reply is sent
+    pub(crate) static CURRENT_AUDIT: RefCell<Option<AuditContext>>;
+}
+
+/// A record of a single API operation that changed (or tried to change) a model
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct AuditRecord {
+    /// The account that made the request. This is only set when running in multitenant mode
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub account_id: Option<String>,
+    pub lattice_id: String,
+    /// The model affected. This is not set on the record for a request that changes many models at
+    /// once, such as an import. A separate record is published for each model such a request changes
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub model_name: Option<String>,
+    /// The version of the model affected, if the operation applies to a single version
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub version: Option<String>,
+    pub operation: String,
+    /// The result of the operation, as given in the `result` field of the response
+    pub result: String,
+    pub timestamp: DateTime<Utc>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub trace_id: Option<String>,
+}
+
+impl AuditRecord {
+    /// Returns the lifecycle event for this record, if it is a successful put (or import), deploy,
+    /// undeploy, or delete of a single model
+    pub fn model_event(&self) -> Option<ModelEvent> {
+        let event_type = match self.operation.as_str() {
+            "put" | "import" => ModelEventType::Put,
+            "deploy" => ModelEventType::Deploy,
+            "undeploy" => ModelEventType::Undeploy,
+            "delete" => ModelEventType::Delete,
+            _ => return None,
+        };
+        if matches!(self.result.as_str(), "error" | "notfound" | "noop") {
+            return None;
+        }
+        Some(ModelEvent {
+            event_type,
+            model_name: self.model_name.clone()?,
+            version: self.version.clone(),
+            timestamp: self.timestamp,
+        })
+    }
+}
+
+/// Returns the record for a single model changed by the current request, for requests that change
+/// many models at once. These are published alongside the request's own record, which doesn't name
+/// a model, so consumers that follow a model (such as model watches) see every change to it
+pub(crate) fn model_record(
+    account_id: Option<&str>,
+    lattice_id: &str,
+    operation: ModelOperation,
+    name: &str,
+    version: Option<&str>,
+    result: &str,
+) -> AuditRecord {
+    AuditRecord {
+        account_id: account_id.map(ToOwned::to_owned),
+        lattice_id: lattice_id.to_owned(),
+        model_name: Some(name.to_owned()),
+        version: version.map(ToOwned::to_owned),
+        operation: operation.to_string(),
+        result: result.to_owned(),
+        timestamp: Utc::now(),
+        trace_id: current_trace_id(),
+    }
+}
+
+/// The details of an in progress request that end up in its [`AuditRecord`]
+#[derive(Debug, Clone)]
+pub(crate) struct AuditContext {
+    pub operation: ModelOperation,
+    pub account_id: Option<String>,
+    pub lattice_id: String,
+    pub model_name: Option<String>,
+    pub version: Option<String>,
+    pub trace_id: Option<String>,
+}
+
+impl AuditContext {
+    /// Returns the operation for the given API operation name, if it is one that changes models
+    /// and should be audited
+    pub fn operation(operation: &str) -> Option<ModelOperation> {
+        Some(match operation {
+            "put" | "apply" => ModelOperation::Put,
+            "copy" => ModelOperation::Copy,
+            "import" => ModelOperation::Import,
+            "del" => ModelOperation::Delete,
+            "restore" => ModelOperation::Restore,
+            "deploy" | "deploy_many" | "set_deployed" => ModelOperation::Deploy,
+            "undeploy" | "undeploy_all" => ModelOperation::Undeploy,
+            "stage" => ModelOperation::Stage,
+            "promote" => ModelOperation::Promote,
+            "patch_metadata" => ModelOperation::PatchMetadata,
+            "prune" => ModelOperation::Prune,
+            _ => return None,
+        })
+    }
+
+    /// Completes the record for this request with the given result
+    pub fn into_record(self, result: String) -> AuditRecord {
+        AuditRecord {
+            account_id: self.account_id,
+            lattice_id: self.lattice_id,
+            model_name: self.model_name,
+            version: self.version,
+            operation: self.operation.to_string(),
+            result,
+            timestamp: Utc::now(),
+            trace_id: self.trace_id,
+        }
+    }
+}
+
+/// Sets the model name in the current request's audit record, for requests where it isn't part of
+/// the subject
+pub(crate) fn record_model(name: &str) {
+    let _ = CURRENT_AUDIT.try_with(|ctx| {
+        if let Some(ctx) = ctx.borrow_mut().as_mut() {
+            ctx.model_name = Some(name.to_owned());
+        }
+    });
+}
+
+/// Sets the version affected in the current request's audit record
+pub(crate) fn record_version(version: &str) {
+    let _ = CURRENT_AUDIT.try_with(|ctx| {
+        if let Some(ctx) = ctx.borrow_mut().as_mut() {
+            ctx.version = Some(version.to_owned());
+        }
+    });
+}
+
+/// Takes the audit context for the current request so its record can be completed once the reply
+/// is sent, which may happen in another task. Only the first reply to a request is audited, so this
+/// returns `None` after the first call or outside of a mutating request
+pub(crate) fn take_context() -> Option<AuditContext> {
+    CURRENT_AUDIT
+        .try_with(|ctx| ctx.borrow_mut().take())
+        .ok()
+        .flatten()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_audit_record_is_finished_once() {
+        let finish_record =
+            |result: &str| take_context().map(|ctx| ctx.into_record(result.to_string()));
+        // Outside of a mutating request, nothing is recorded
+        record_version("v1");
+        assert!(finish_record("success").is_none());
+
+        let ctx = AuditContext {
+            operation: ModelOperation::Deploy,
+            account_id: None,
+            lattice_id: "default".to_string(),
+            model_name: Some("app".to_string()),
+            version: None,
+            trace_id: Some("abc".to_string()),
+        };
+        CURRENT_AUDIT
+            .scope(RefCell::new(Some(ctx)), async {
+                record_version("v2");
+                let record = finish_record("acknowledged").expect("Should finish a record");
+                assert_eq!(record.operation, "deploy");
+                assert_eq!(record.model_name.as_deref(), Some("app"));
+                assert_eq!(record.version.as_deref(), Some("v2"));
+                assert_eq!(record.result, "acknowledged");
+                assert_eq!(record.trace_id.as_deref(), Some("abc"));
+
+                assert!(
+                    finish_record("error").is_none(),
+                    "Only the first reply should be audited"
+                );
+            })
+            .await;
+    }
+
+    #[test]
+    fn test_model_event() {
+        let record = AuditRecord {
+            account_id: None,
+            lattice_id: "default".to_string(),
+            model_name: Some("app".to_string()),
+            version: Some("v1".to_string()),
+            operation: "deploy".to_string(),
+            result: "acknowledged".to_string(),
+            timestamp: Utc::now(),
+            trace_id: None,
+        };
+        let event = record
+            .model_event()
+            .expect("Deploys should be model events");
+        assert_eq!(event.event_type, ModelEventType::Deploy);
+        assert_eq!(event.model_name, "app");
+        assert_eq!(event.version.as_deref(), Some("v1"));
+
+        for (operation, result) in [
+            ("deploy", "error"),
+            ("delete", "noop"),
+            ("undeploy", "notfound"),
+            ("stage", "acknowledged"),
+        ] {
+            let record = AuditRecord {
+                operation: operation.to_string(),
+                result: result.to_string(),
+                ..record.clone()
+            };
+            assert!(
+                record.model_event().is_none(),
+                "A {operation} with result {result} shouldn't be a model event"
+            );
+        }
+
+        let record = AuditRecord {
+            operation: "import".to_string(),
+            result: "overwritten".to_string(),
+            ..record
+        };
+        assert_eq!(
+            record.model_event().map(|e| e.event_type),
+            Some(ModelEventType::Put),
+            "Imports should be put events"
+        );
+
+        let record = AuditRecord {
+            model_name: None,
+            ..record
+        };
+        assert!(
+            record.model_event().is_none(),
+            "Operations on many models shouldn't be model events"
+        );
+    }
+}
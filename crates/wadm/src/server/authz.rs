@@ -0,0 +1,76 @@
+use std::fmt::Display;
+
+use async_trait::async_trait;
+use wadm_types::Metadata;
+
+/// The API operations that change models and are checked with an [`Authorizer`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ModelOperation {
+    Put,
+    Copy,
+    Import,
+    Delete,
+    Restore,
+    Deploy,
+    Undeploy,
+    Stage,
+    Promote,
+    PatchMetadata,
+    Prune,
+}
+
+impl Display for ModelOperation {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ModelOperation::Put => write!(f, "put"),
+            ModelOperation::Copy => write!(f, "copy"),
+            ModelOperation::Import => write!(f, "import"),
+            ModelOperation::Delete => write!(f, "delete"),
+            ModelOperation::Restore => write!(f, "restore"),
+            ModelOperation::Deploy => write!(f, "deploy"),
+            ModelOperation::Undeploy => write!(f, "undeploy"),
+            ModelOperation::Stage => write!(f, "stage"),
+            ModelOperation::Promote => write!(f, "promote"),
+            ModelOperation::PatchMetadata => write!(f, "patch_metadata"),
+            ModelOperation::Prune => write!(f, "prune"),
+        }
+    }
+}
+
+/// A request to perform an operation on a model, passed to an [`Authorizer`] to decide whether it
+/// is allowed
+#[derive(Debug, Clone, Copy)]
+pub struct AuthorizationRequest<'a> {
+    /// The account making the request. This is only set when running in multitenant mode
+    pub account_id: Option<&'a str>,
+    pub lattice_id: &'a str,
+    pub operation: ModelOperation,
+    /// The name of the model being changed. For copies, this is the destination model
+    pub model_name: &'a str,
+    /// The metadata of the current version of the model as it is stored, if the model exists
+    pub current: Option<&'a Metadata>,
+    /// The metadata of the manifest being stored, for operations that store a new manifest (puts,
+    /// copies and imports) or change a manifest's metadata
+    pub proposed: Option<&'a Metadata>,
+}
+
+/// A policy that decides whether operations that change models are allowed, such as only allowing
+/// an account to change models with a matching `owner` label.
+///
+/// Authorizers are invoked before a model is changed. Returning an error denies the request, and the
+/// error message is sent back to the caller along with a forbidden error code
+#[async_trait]
+pub trait Authorizer: Send + Sync {
+    async fn authorize(&self, request: AuthorizationRequest<'_>) -> Result<(), String>;
+}
+
+/// The default [`Authorizer`], which allows every request
+#[derive(Debug, Default, Clone, Copy)]
+pub struct AllowAll;
+
+#[async_trait]
+impl Authorizer for AllowAll {
+    async fn authorize(&self, _request: AuthorizationRequest<'_>) -> Result<(), String> {
+        Ok(())
+    }
+}
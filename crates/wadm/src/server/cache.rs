@@ -0,0 +1,204 @@
+use std::{
+    collections::HashMap,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc, RwLock,
+    },
+    time::Duration,
+};
+
+use async_nats::jetstream::kv::Store;
+use futures::StreamExt;
+use indexmap::IndexMap;
+use tracing::{debug, trace, warn};
+
+use crate::model::StoredManifest;
+
+/// How long to wait before re-establishing the KV watch after it fails
+const WATCH_RETRY_INTERVAL: Duration = Duration::from_secs(5);
+
+/// An in-process cache of stored models, keyed by their storage key (which includes the account,
+/// lattice, and model name).
+///
+/// Other wadm replicas can change the store at any time, so entries are invalidated by a watch on
+/// the underlying bucket (see [`ModelCache::watch`]). The cache is only used while the watch is
+/// running, so reads fall through to the store whenever changes could have been missed
+#[derive(Clone)]
+pub(crate) struct ModelCache {
+    inner: Arc<RwLock<CacheState>>,
+    watching: Arc<AtomicBool>,
+}
+
+#[derive(Default)]
+struct CacheState {
+    capacity: usize,
+    /// Cached models and their revisions, oldest insert first so they can be evicted in order
+    entries: IndexMap<String, (StoredManifest, u64)>,
+    /// The latest revision seen by the watch for each key. A read that raced with a change can
+    /// finish after the change was seen, so anything older than this is never cached
+    latest_revisions: HashMap<String, u64>,
+}
+
+impl ModelCache {
+    /// Creates an empty cache holding up to `capacity` models. It isn't used until [`Self::watch`]
+    /// is running
+    pub fn new(capacity: usize) -> ModelCache {
+        ModelCache {
+            inner: Arc::new(RwLock::new(CacheState {
+                capacity,
+                ..Default::default()
+            })),
+            watching: Arc::new(AtomicBool::new(false)),
+        }
+    }
+
+    /// Returns true if the cache is currently kept up to date by the watch and can be used
+    pub fn is_active(&self) -> bool {
+        self.watching.load(Ordering::Acquire)
+    }
+
+    /// Returns the cached model and revision for the given key, if any
+    pub fn get(&self, key: &str) -> Option<(StoredManifest, u64)> {
+        if !self.is_active() {
+            return None;
+        }
+        self.inner.read().ok()?.entries.get(key).cloned()
+    }
+
+    /// Caches the model read from the store at the given revision. This is ignored if a newer
+    /// change to the key has already been seen
+    pub fn insert(&self, key: &str, model: StoredManifest, revision: u64) {
+        if !self.is_active() {
+            return;
+        }
+        let Ok(mut state) = self.inner.write() else {
+            return;
+        };
+        if state
+            .latest_revisions
+            .get(key)
+            .is_some_and(|latest| *latest > revision)
+        {
+            trace!(%key, revision, "Not caching stale read");
+            return;
+        }
+        if state.capacity == 0 {
+            return;
+        }
+        if !state.entries.contains_key(key) && state.entries.len() >= state.capacity {
+            state.entries.shift_remove_index(0);
+        }
+        state.entries.insert(key.to_owned(), (model, revision));
+    }
+
+    /// Removes the given key from the cache, recording that it changed at the given revision
+    pub fn invalidate(&self, key: &str, revision: Option<u64>) {
+        let Ok(mut state) = self.inner.write() else {
+            return;
+        };
+        state.entries.shift_remove(key);
+        if let Some(revision) = revision {
+            let latest = state.latest_revisions.entry(key.to_owned()).or_default();
+            *latest = (*latest).max(revision);
+        }
+    }
+
+    /// Removes everything from the cache
+    fn clear(&self) {
+        if let Ok(mut state) = self.inner.write() {
+            state.entries.clear();
+            state.latest_revisions.clear();
+        }
+    }
+
+    /// Watches the bucket for changes, invalidating cached entries as they change. This runs until
+    /// the task is dropped, re-establishing the watch if it fails. The cache is cleared and unused
+    /// while the watch is down, as changes made in the meantime would be missed
+    pub async fn watch(self, store: Store) {
+        loop {
+            match store.watch_all().await {
+                Ok(mut watch) => {
+                    debug!("Watching model store to invalidate cached models");
+                    self.watching.store(true, Ordering::Release);
+                    while let Some(entry) = watch.next().await {
+                        match entry {
+                            Ok(entry) => self.invalidate(&entry.key, Some(entry.revision)),
+                            Err(e) => {
+                                warn!("Error watching model store, disabling cache: {e:?}");
+                                break;
+                            }
+                        }
+                    }
+                }
+                Err(e) => warn!("Unable to watch model store, disabling cache: {e:?}"),
+            }
+            self.watching.store(false, Ordering::Release);
+            self.clear();
+            tokio::time::sleep(WATCH_RETRY_INTERVAL).await;
+        }
+    }
+
+    #[cfg(test)]
+    fn activate(&self) {
+        self.watching.store(true, Ordering::Release);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn model(name: &str) -> StoredManifest {
+        let mut manifest: wadm_types::Manifest =
+            serde_yaml::from_slice(&std::fs::read("./oam/simple1.yaml").unwrap())
+                .expect("Should be able to parse");
+        manifest.metadata.name = name.to_owned();
+        let mut stored = StoredManifest::default();
+        stored.add_version(manifest);
+        stored
+    }
+
+    #[test]
+    fn test_cache_is_unused_until_watching() {
+        let cache = ModelCache::new(10);
+        cache.insert("default-app", model("app"), 1);
+        assert!(cache.get("default-app").is_none());
+
+        cache.activate();
+        cache.insert("default-app", model("app"), 1);
+        assert_eq!(cache.get("default-app").map(|(_, rev)| rev), Some(1));
+    }
+
+    #[test]
+    fn test_cache_invalidation() {
+        let cache = ModelCache::new(10);
+        cache.activate();
+        cache.insert("default-app", model("app"), 1);
+        cache.invalidate("default-app", Some(2));
+        assert!(cache.get("default-app").is_none());
+
+        // A read that started before the change shouldn't be cached once the change was seen
+        cache.insert("default-app", model("app"), 1);
+        assert!(
+            cache.get("default-app").is_none(),
+            "Stale reads should not be cached"
+        );
+        cache.insert("default-app", model("app"), 2);
+        assert_eq!(cache.get("default-app").map(|(_, rev)| rev), Some(2));
+    }
+
+    #[test]
+    fn test_cache_eviction() {
+        let cache = ModelCache::new(2);
+        cache.activate();
+        for (rev, name) in ["one", "two", "three"].into_iter().enumerate() {
+            cache.insert(&format!("default-{name}"), model(name), rev as u64 + 1);
+        }
+        assert!(
+            cache.get("default-one").is_none(),
+            "The oldest entry should be evicted"
+        );
+        assert!(cache.get("default-two").is_some());
+        assert!(cache.get("default-three").is_some());
+    }
+}
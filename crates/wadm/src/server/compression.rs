@@ -0,0 +1,207 @@
+use std::{fmt::Display, io::Read, str::FromStr};
+
+use anyhow::Context;
+use async_nats::HeaderMap;
+use flate2::{read::GzDecoder, write::GzEncoder, Compression};
+
+/// The header a client sets (to a list of encodings including `gzip`) to accept compressed replies
+pub const ACCEPT_ENCODING_HEADER: &str = "accept-encoding";
+/// The header set on replies that were compressed, giving the encoding used
+pub const CONTENT_ENCODING_HEADER: &str = "content-encoding";
+/// The default size (in bytes) a reply must be before it is compressed for clients that accept it
+pub const DEFAULT_REPLY_COMPRESSION_THRESHOLD: usize = 16 * 1024;
+
+/// The marker byte prefixed to models compressed with gzip
+const GZIP_MARKER: u8 = 0x01;
+/// The marker byte prefixed to models compressed with zstd
+const ZSTD_MARKER: u8 = 0x02;
+/// The zstd compression level. This is the library default, which is a good tradeoff between speed
+/// and size for documents the size of a manifest
+const ZSTD_LEVEL: i32 = 3;
+
+tokio::task_local! {
+    /// Whether the client that sent the request currently being handled accepts gzipped replies.
+    /// This is set by the server around each call to a handler
+    pub(crate) static ACCEPTS_GZIP: bool;
+}
+
+/// Returns true if the given request headers advertise support for gzipped replies
+pub(crate) fn accepts_gzip(headers: Option<&HeaderMap>) -> bool {
+    headers
+        .and_then(|headers| headers.get(ACCEPT_ENCODING_HEADER))
+        .map(|value| {
+            value
+                .as_str()
+                .split(',')
+                // Ignore any quality values, such as `gzip;q=0.5`
+                .filter_map(|encoding| encoding.split(';').next())
+                .any(|encoding| encoding.trim().eq_ignore_ascii_case("gzip"))
+        })
+        .unwrap_or(false)
+}
+
+/// Gzips a serialized reply. Unlike stored models, no marker byte is added as the encoding is given
+/// in the [`CONTENT_ENCODING_HEADER`] of the reply
+pub(crate) fn gzip_reply(data: &[u8]) -> anyhow::Result<Vec<u8>> {
+    let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+    std::io::Write::write_all(&mut encoder, data)
+        .and_then(|_| encoder.finish())
+        .context("unable to gzip reply")
+}
+
+/// The codec used to compress models before they are written to storage.
+///
+/// Compressed data is prefixed with a marker byte identifying the codec. Uncompressed models are
+/// stored as plain JSON, which can never start with one of the marker bytes, so data written with
+/// any codec (or by older versions of wadm) can always be read back regardless of the configured
+/// codec
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum StorageCodec {
+    #[default]
+    None,
+    Gzip,
+    Zstd,
+}
+
+impl Display for StorageCodec {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            StorageCodec::None => write!(f, "none"),
+            StorageCodec::Gzip => write!(f, "gzip"),
+            StorageCodec::Zstd => write!(f, "zstd"),
+        }
+    }
+}
+
+impl FromStr for StorageCodec {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_lowercase().as_str() {
+            "none" => Ok(StorageCodec::None),
+            "gzip" => Ok(StorageCodec::Gzip),
+            "zstd" => Ok(StorageCodec::Zstd),
+            _ => Err(format!(
+                "unknown storage codec {s:?}, expected one of none, gzip, or zstd"
+            )),
+        }
+    }
+}
+
+impl StorageCodec {
+    /// Compresses the serialized model with this codec, prefixing the codec's marker byte
+    pub(crate) fn encode(&self, data: Vec<u8>) -> anyhow::Result<Vec<u8>> {
+        match self {
+            StorageCodec::None => Ok(data),
+            StorageCodec::Gzip => {
+                let mut encoder = GzEncoder::new(vec![GZIP_MARKER], Compression::default());
+                std::io::Write::write_all(&mut encoder, &data)
+                    .and_then(|_| encoder.finish())
+                    .context("unable to gzip model")
+            }
+            StorageCodec::Zstd => {
+                let mut encoded = vec![ZSTD_MARKER];
+                zstd::stream::copy_encode(data.as_slice(), &mut encoded, ZSTD_LEVEL)
+                    .context("unable to compress model with zstd")?;
+                Ok(encoded)
+            }
+        }
+    }
+
+    /// Returns the codec the given stored data was written with, using its marker byte
+    pub(crate) fn detect(data: &[u8]) -> StorageCodec {
+        match data.first() {
+            Some(&GZIP_MARKER) => StorageCodec::Gzip,
+            Some(&ZSTD_MARKER) => StorageCodec::Zstd,
+            _ => StorageCodec::None,
+        }
+    }
+
+    /// Decompresses stored data written with any codec, using its marker byte to tell which codec
+    /// was used
+    pub(crate) fn decode(data: &[u8]) -> anyhow::Result<Vec<u8>> {
+        match data.split_first() {
+            Some((&GZIP_MARKER, rest)) => {
+                let mut decoded = Vec::new();
+                GzDecoder::new(rest)
+                    .read_to_end(&mut decoded)
+                    .context("unable to decompress gzipped model")?;
+                Ok(decoded)
+            }
+            Some((&ZSTD_MARKER, rest)) => {
+                zstd::stream::decode_all(rest).context("unable to decompress zstd model")
+            }
+            _ => Ok(data.to_vec()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_codecs_round_trip() {
+        let data = br#"{"manifests":{"v1":{"apiVersion":"core.oam.dev/v1beta1"}}}"#.repeat(20);
+        for codec in [StorageCodec::None, StorageCodec::Gzip, StorageCodec::Zstd] {
+            let encoded = codec
+                .encode(data.clone())
+                .expect("Should be able to encode");
+            if codec != StorageCodec::None {
+                assert!(
+                    encoded.len() < data.len(),
+                    "{codec} should shrink repetitive data"
+                );
+            }
+            assert_eq!(
+                StorageCodec::decode(&encoded).expect("Should be able to decode"),
+                data,
+                "{codec} should round trip"
+            );
+            assert_eq!(
+                StorageCodec::detect(&encoded),
+                codec,
+                "{codec} should be detected from the encoded data"
+            );
+        }
+    }
+
+    #[test]
+    fn test_accepts_gzip() {
+        assert!(!accepts_gzip(None));
+        for (value, expected) in [
+            ("gzip", true),
+            ("br, GZIP;q=0.8", true),
+            ("deflate, br", false),
+            ("x-gzip", false),
+        ] {
+            let mut headers = HeaderMap::new();
+            headers.insert(ACCEPT_ENCODING_HEADER, value);
+            assert_eq!(accepts_gzip(Some(&headers)), expected, "{value}");
+        }
+
+        let data = br#"{"result":"success","models":[]}"#.repeat(20);
+        let mut decoded = Vec::new();
+        GzDecoder::new(
+            gzip_reply(&data)
+                .expect("Should be able to gzip")
+                .as_slice(),
+        )
+        .read_to_end(&mut decoded)
+        .expect("Should be able to gunzip");
+        assert_eq!(decoded, data);
+    }
+
+    #[test]
+    fn test_legacy_entries_are_read_as_is() {
+        let data = br#"{"manifests":{}}"#;
+        assert_eq!(
+            StorageCodec::decode(data).expect("Should be able to decode"),
+            data.to_vec()
+        );
+        assert!(
+            StorageCodec::decode(&[GZIP_MARKER, 0xff]).is_err(),
+            "Corrupt compressed data should fail to decode"
+        );
+    }
+}
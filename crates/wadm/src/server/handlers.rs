@@ -1,160 +1,591 @@
-use std::collections::{HashMap, HashSet};
+use std::borrow::Cow;
+use std::collections::{BTreeMap, HashMap, HashSet, VecDeque};
+use std::future::Future;
+use std::sync::Arc;
+use std::time::Duration;
 
-use anyhow::{anyhow, bail, ensure};
-use async_nats::{jetstream::stream::Stream, Client, Message, Subject};
+use anyhow::anyhow;
+use async_nats::{
+    jetstream::{
+        consumer::{pull::Config as PullConfig, AckPolicy, DeliverPolicy},
+        stream::{LastRawMessageErrorKind, Stream},
+    },
+    Client, HeaderMap, Message, StatusCode, Subject,
+};
 use base64::{engine::general_purpose::STANDARD as B64decoder, Engine};
-use jsonschema::{paths::PathChunk, Draft, JSONSchema};
+use chrono::{DateTime, Utc};
+use futures::StreamExt;
+use serde::Deserialize;
 use serde_json::json;
-use tokio::sync::OnceCell;
-use tracing::{debug, error, instrument, log::warn, trace};
-use wadm_types::validation::{is_valid_manifest_name, validate_manifest_version, ValidationOutput};
+use tracing::{debug, error, instrument, log::warn, trace, Instrument};
+use wadm_types::validation::{
+    is_reserved_manifest_name, is_valid_manifest_name, validate_manifest_version, ValidationOutput,
+    MAX_MANIFEST_NAME_LENGTH, RESERVED_MANIFEST_NAMES,
+};
 use wadm_types::{
     api::{
-        DeleteModelRequest, DeleteModelResponse, DeleteResult, DeployModelRequest,
-        DeployModelResponse, DeployResult, GetModelRequest, GetModelResponse, GetResult,
-        PutModelResponse, PutResult, Status, StatusInfo, StatusResponse, StatusResult, StatusType,
-        UndeployModelRequest, VersionInfo, VersionResponse,
+        CopyModelRequest, CopyModelResponse, CopyResult, DeleteModelRequest, DeleteModelResponse,
+        DeleteResult, DeployConflict, DeployConflictKind, DeployHistoryResponse,
+        DeployModelRequest, DeployModelResponse, DeployModelsRequest, DeployModelsResponse,
+        DeployPreflightRequest, DeployPreflightResponse, DeployResult, DescribeModelResponse,
+        DiffModelRequest, DiffModelResponse, ErrorCode, ExportModelsResponse, ExportResult,
+        ExportedModel, GetModelRequest, GetModelResponse, GetRawModelResponse, GetResult,
+        HealthCheck, HealthResponse, HealthResult, ImportModelsRequest, ImportModelsResponse,
+        ImportResult, LatticeSummary, LintModelResponse, LintResult, ListLatticesResponse,
+        ListModelsRequest, ManifestDiff, ModelDependency, ModelDeployResult, ModelDeployTarget,
+        ModelImportOutcome, ModelImportResult, ModelPutOutcome, ModelPutResult, ModelStatsResponse,
+        ModelUndeployResult, NormalizeModelResponse, PatchMetadataRequest, PatchMetadataResponse,
+        PatchMetadataResult, PreflightResult, PruneModelRequest, PruneModelResponse, PruneResult,
+        PutModelResponse, PutModelsResponse, PutModelsResult, PutResult, RelativeVersion,
+        RestoreModelResponse, RestoreResult, SchemaError, ServerInfoResponse, SetDeployedRequest,
+        StageModelRequest, Status, StatusCounts, StatusHistoryEntry, StatusHistoryRequest,
+        StatusHistoryResponse, StatusInfo, StatusResponse, StatusResult, StatusTopicResponse,
+        StatusType, UndeployAllResponse, UndeployModelRequest, VersionInfo, VersionResponse,
+        VersionSort, VersionsRequest, WatchModelRequest, WatchModelResponse, WatchStatusRequest,
+        WatchStatusResponse,
     },
-    CapabilityProperties, ComponentProperties, LinkProperty, Manifest, Properties, Trait,
-    TraitProperty, LATEST_VERSION,
+    migration::migrate_manifest,
+    CapabilityProperties, ComponentProperties, LinkProperty, Manifest, Metadata, Properties,
+    TraitProperty, LATEST_VERSION, MINIMUM_WADM_VERSION_ANNOTATION_KEY, OAM_VERSION,
+    VERSION_ANNOTATION_KEY,
+};
+
+use crate::{
+    model::{without_components, StoredManifest},
+    publisher::Publisher,
+    status_topic, STATUS_TOPIC_PREFIX,
 };
 
-use crate::{model::StoredManifest, publisher::Publisher};
+use super::{
+    audit::{self, AuditContext, AuditRecord},
+    authz::{AuthorizationRequest, Authorizer, ModelOperation},
+    compression::{gzip_reply, StorageCodec, ACCEPTS_GZIP, CONTENT_ENCODING_HEADER},
+    idempotency::{IdempotencyCache, IDEMPOTENCY_KEY_HEADER},
+    lint::{lint, LintRule},
+    metrics::{response_result, ServerMetrics},
+    parser::parse_manifest,
+    storage::{DeployLock, ModelStorage, RevisionConflict, MAX_REVISION_CONFLICT_ATTEMPTS},
+    template::render_payload,
+    timeout::{with_timeout, TimedOut},
+    validation::{parse_wadm_version, schema_value, validate, SUPPORTED_API_VERSIONS},
+    versioning::VersionGenerator,
+    watch::{validate_watch_subject, WatchLimiter},
+    ManifestNotifier, ModelNamePolicy, ServerConfig,
+};
 
-use super::{parser::parse_manifest, storage::ModelStorage, ManifestNotifier};
+/// The name of the header in a put request that, when set to `true`, deploys the new version as
+/// soon as it is stored
+pub const DEPLOY_ON_PUT_HEADER: &str = "wadm-deploy";
+/// The name of the header in a put request containing the name of the model the manifest is for.
+/// If set, the put is rejected when it doesn't match the name in the manifest
+pub const MODEL_NAME_HEADER: &str = "wadm-model-name";
 
-const JSON_SCHEMA: &str = include_str!("../../oam.schema.json");
-static JSON_SCHEMA_VALUE: OnceCell<serde_json::Value> = OnceCell::const_new();
-static OAM_JSON_SCHEMA: OnceCell<JSONSchema> = OnceCell::const_new();
+/// The maximum number of times reading a model's status from the status stream is attempted
+const MAX_STATUS_READ_ATTEMPTS: u32 = 3;
+/// The delay before the first retry of a failed status read. This doubles on every retry
+const INITIAL_STATUS_READ_BACKOFF: Duration = Duration::from_millis(50);
+/// How long each dependency check in a health request can take before it is considered unhealthy
+const HEALTH_CHECK_TIMEOUT: Duration = Duration::from_secs(5);
+/// How often to check the status of a model when a deploy request is waiting for it to reconcile
+const DEPLOY_WAIT_POLL_INTERVAL: Duration = Duration::from_secs(1);
+/// The longest a deploy request is allowed to wait for a model to reconcile
+const MAX_DEPLOY_WAIT: Duration = Duration::from_secs(300);
+/// How long a status watch lasts if the request doesn't specify a duration
+const DEFAULT_STATUS_WATCH: Duration = Duration::from_secs(300);
+/// The longest a single status watch is allowed to last before it must be renewed
+const MAX_STATUS_WATCH: Duration = Duration::from_secs(3600);
+/// The maximum number of status updates returned by a status history request. This matches the
+/// number of messages per model kept by the status stream
+const MAX_STATUS_HISTORY: usize = 10;
+/// The status message reported for a model when its status couldn't be read from the status stream
+const STATUS_UNAVAILABLE_MESSAGE: &str =
+    "Status is unknown: unable to fetch status. This is likely a transient error";
+/// The maximum number of manifests that can be put in a single multi-manifest put request
+const MAX_PUT_MODELS_MANIFESTS: usize = 100;
+/// The maximum number of models that can be deployed in a single multi-model deploy request
+const MAX_DEPLOY_MODELS: usize = 100;
 
+#[derive(Clone)]
 pub(crate) struct Handler<P> {
     pub(crate) store: ModelStorage,
     pub(crate) client: Client,
     pub(crate) notifier: ManifestNotifier<P>,
     pub(crate) status_stream: Stream,
+    pub(crate) config: ServerConfig,
+    pub(crate) idempotency: Arc<IdempotencyCache>,
+    pub(crate) metrics: Option<ServerMetrics>,
+    /// The policy used to check requests that change models. If not set, everything is allowed
+    pub(crate) authorizer: Option<Arc<dyn Authorizer>>,
+    /// Assigns versions to manifests that are put without one
+    pub(crate) version_generator: Arc<dyn VersionGenerator>,
+    /// The rules run against manifests for lint requests
+    pub(crate) lint_rules: Vec<Arc<dyn LintRule>>,
+    /// The prefix of the API topics the server listens on
+    pub(crate) api_prefix: String,
+    /// The status and model watches running for each account
+    pub(crate) watches: Arc<WatchLimiter>,
 }
 
-impl<P: Publisher> Handler<P> {
-    #[instrument(level = "debug", skip(self, msg))]
-    pub async fn put_model(&self, msg: Message, account_id: Option<&str>, lattice_id: &str) {
-        trace!("Parsing incoming manifest");
-        let manifest = match parse_manifest(msg.payload.into(), msg.headers.as_ref()) {
-            Ok(m) => m,
-            Err(e) => {
-                self.send_error(msg.reply, format!("Unable to parse manifest: {e:?}"))
-                    .await;
-                return;
+/// Everything needed to finish replying to a request. Replies are normally sent from the request's
+/// task with [`Handler::send_reply`], but this can be moved into a spawned task for replies sent
+/// later, such as deploys that wait for the model to reconcile
+#[derive(Clone)]
+struct ReplySender {
+    client: Client,
+    idempotency: Arc<IdempotencyCache>,
+    metrics: Option<ServerMetrics>,
+    compression_threshold: Option<usize>,
+    audit_topic_prefix: Option<String>,
+}
+
+impl ReplySender {
+    /// Sends the reply, compressing it if the client accepts gzip, and publishes the audit record
+    /// for the request if it was given one
+    async fn send(
+        &self,
+        reply_topic: Subject,
+        data: Vec<u8>,
+        accepts_gzip: bool,
+        audit: Option<AuditContext>,
+    ) {
+        let compress = self
+            .compression_threshold
+            .is_some_and(|threshold| data.len() >= threshold)
+            && accepts_gzip;
+        let mut compressed = compress.then(|| gzip_reply(&data));
+        // NATS rejects messages larger than the max payload, which would leave the client waiting
+        // for a reply that never comes, so send an error it can act on instead
+        let size = match compressed.as_ref() {
+            Some(Ok(c)) => c.len(),
+            _ => data.len(),
+        };
+        let mut data = data;
+        if let Some(error) = oversized_reply(size, self.client.server_info().max_payload) {
+            error!(
+                size,
+                "Reply is larger than the NATS max payload, sending an error instead"
+            );
+            data = error;
+            compressed = None;
+        }
+
+        self.idempotency.complete(&reply_topic, &data);
+        let result = response_result(&data);
+        if let Some(metrics) = self.metrics.as_ref() {
+            metrics.record_reply(&result);
+        }
+        let published = match compressed {
+            Some(Ok(compressed)) => {
+                trace!(
+                    original = data.len(),
+                    compressed = compressed.len(),
+                    "Sending compressed reply"
+                );
+                let mut headers = HeaderMap::new();
+                headers.insert(CONTENT_ENCODING_HEADER, "gzip");
+                self.client
+                    .publish_with_headers(reply_topic, headers, compressed.into())
+                    .await
+            }
+            compressed => {
+                if let Some(Err(e)) = compressed {
+                    warn!("Unable to compress reply, sending it uncompressed: {e:?}");
+                }
+                self.client.publish(reply_topic, data.into()).await
             }
         };
+        if let Err(e) = published {
+            error!(error = %e, "Unable to send reply");
+        }
+        if let Some(ctx) = audit {
+            publish_audit_record(
+                &self.client,
+                self.audit_topic_prefix.as_deref(),
+                ctx.into_record(result),
+            )
+            .await;
+        }
+    }
+}
+
+impl<P: Publisher> Handler<P> {
+    #[instrument(
+        level = "debug",
+        skip(self, msg, account_id, lattice_id),
+        fields(account_id = account_id.unwrap_or_default(), lattice_id = %lattice_id)
+    )]
+    pub async fn put_model(
+        &self,
+        msg: Message,
+        account_id: Option<&str>,
+        lattice_id: &str,
+        name: Option<&str>,
+    ) {
+        if self
+            .replay_idempotent_response(&msg, "put", account_id, lattice_id)
+            .await
+        {
+            return;
+        }
+
+        if let Err(message) = self.check_payload_size(msg.payload.len()) {
+            self.send_error(msg.reply, ErrorCode::InvalidRequest, message)
+                .await;
+            return;
+        }
+
+        let deploy = msg
+            .headers
+            .as_ref()
+            .and_then(|headers| headers.get(DEPLOY_ON_PUT_HEADER))
+            .is_some_and(|value| value.as_str().eq_ignore_ascii_case("true"));
+        let header_name = msg
+            .headers
+            .as_ref()
+            .and_then(|headers| headers.get(MODEL_NAME_HEADER))
+            .map(|value| value.as_str().to_owned());
+
+        let (manifest, migrations) =
+            match self.prepare_manifest(msg.payload.into(), msg.headers.as_ref()) {
+                Ok(prepared) => prepared,
+                Err(e) => {
+                    self.send_error(msg.reply, ErrorCode::InvalidRequest, e)
+                        .await;
+                    return;
+                }
+            };
+
+        if let Err(e) = check_name_hint(
+            manifest.metadata.name.trim(),
+            [name, header_name.as_deref()],
+        ) {
+            self.send_error(msg.reply, ErrorCode::InvalidRequest, e)
+                .await;
+            return;
+        }
 
         trace!(
             ?manifest,
             "Manifest is valid. Fetching current manifests from store"
         );
+        audit::record_model(manifest.metadata.name.trim());
+        audit::record_version(manifest.version());
 
-        let manifest_validation_output = validate_manifest_version(manifest.version());
-        let manifest_validation_errors = manifest_validation_output.errors();
-        if !manifest_validation_errors.is_empty() {
+        if let Err((code, message)) = self
+            .authorize(
+                account_id,
+                lattice_id,
+                ModelOperation::Put,
+                manifest.metadata.name.trim(),
+                Some(&manifest.metadata),
+            )
+            .await
+        {
+            self.send_error(msg.reply, code, message).await;
+            return;
+        }
+
+        let manifest_name = match self.validate_new_manifest(account_id, &manifest).await {
+            Ok(name) => name,
+            Err(rejection) => {
+                self.send_rejection(msg.reply, rejection).await;
+                return;
+            }
+        };
+
+        let mut resp = match self
+            .store_new_version(account_id, lattice_id, &manifest_name, &manifest)
+            .await
+        {
+            Ok((mut resp, _)) => {
+                audit::record_version(&resp.current_version);
+                if !migrations.is_empty() {
+                    resp.message = format!(
+                        "{}. Migrated manifest to the current schema: {}",
+                        resp.message,
+                        migrations.join(", ")
+                    );
+                }
+                resp
+            }
+            Err((code, message)) => {
+                self.send_error(msg.reply, code, message).await;
+                return;
+            }
+        };
+
+        if deploy {
+            trace!("Deploying newly put version");
+            match self
+                .deploy_put_version(
+                    account_id,
+                    lattice_id,
+                    &manifest_name,
+                    &resp.current_version,
+                )
+                .await
+            {
+                Ok(revision) => {
+                    resp.revision = revision;
+                    resp.message = format!(
+                        "{}. Deployed version {}",
+                        resp.message, resp.current_version
+                    );
+                }
+                Err(e) => {
+                    warn!("Stored manifest {manifest_name} but was unable to deploy it: {e}");
+                    resp.deploy_error = Some(e);
+                }
+            }
+        }
+
+        trace!("Storage complete, sending reply");
+        self.send_reply(
+            msg.reply,
+            // NOTE: We are constructing all data here, so this shouldn't fail, but just in case we
+            // unwrap to nothing
+            serde_json::to_vec(&resp).unwrap_or_default(),
+        )
+        .await
+    }
+
+    #[instrument(
+        level = "debug",
+        skip(self, msg, account_id, lattice_id),
+        fields(account_id = account_id.unwrap_or_default(), lattice_id = %lattice_id)
+    )]
+    pub async fn put_models(&self, msg: Message, account_id: Option<&str>, lattice_id: &str) {
+        if self
+            .replay_idempotent_response(&msg, "apply", account_id, lattice_id)
+            .await
+        {
+            return;
+        }
+
+        if msg.payload.len() > self.config.max_put_models_bytes {
             self.send_error(
                 msg.reply,
+                ErrorCode::InvalidRequest,
                 format!(
-                    "invalid manifest version, errors: {:#?}",
-                    manifest_validation_errors
-                        .iter()
-                        .map(|e| e.msg.clone())
-                        .collect::<Vec<String>>()
-                        .join("\n")
+                    "Request is too large ({} bytes). The maximum allowed size is {} bytes",
+                    msg.payload.len(),
+                    self.config.max_put_models_bytes
                 ),
             )
             .await;
             return;
         }
 
-        let manifest_name = manifest.metadata.name.trim().to_string();
-        if !is_valid_manifest_name(&manifest_name) {
+        let req: RawPutModelsRequest =
+            match serde_json::from_reader(std::io::Cursor::new(msg.payload)) {
+                Ok(r) => r,
+                Err(e) => {
+                    self.send_error(
+                        msg.reply,
+                        ErrorCode::InvalidRequest,
+                        format!("Unable to parse put models request: {e:?}"),
+                    )
+                    .await;
+                    return;
+                }
+            };
+        if req.manifests.is_empty() || req.manifests.len() > MAX_PUT_MODELS_MANIFESTS {
             self.send_error(
                 msg.reply,
+                ErrorCode::InvalidRequest,
                 format!(
-                    "Manifest name {} contains invalid characters. Manifest names can only contain alphanumeric characters, dashes, and underscores.",
-                    manifest_name
+                    "Put models requests must contain between 1 and {MAX_PUT_MODELS_MANIFESTS} manifests, got {}",
+                    req.manifests.len()
                 ),
             )
             .await;
             return;
         }
 
-        let (mut current_manifests, current_revision) =
-            match self.store.get(account_id, lattice_id, &manifest_name).await {
-                Ok(Some(data)) => data,
-                Ok(None) => (StoredManifest::default(), 0),
-                Err(e) => {
-                    error!(error = %e, "Unable to fetch data from store");
-                    self.send_error(msg.reply, "Internal storage error".to_string())
-                        .await;
-                    return;
+        // Validate everything up front so that we don't store anything if any manifest is invalid
+        let mut results = Vec::with_capacity(req.manifests.len());
+        let mut manifests = Vec::with_capacity(req.manifests.len());
+        let mut seen = HashSet::new();
+        for raw in req.manifests {
+            // NOTE: This was parsed from JSON, so it shouldn't fail to serialize, but just in case
+            // we fall back to nothing, which fails to parse below
+            let payload = serde_json::to_vec(&raw).unwrap_or_default();
+            let prepared = self
+                .check_payload_size(payload.len())
+                .and_then(|_| self.prepare_manifest(payload, msg.headers.as_ref()));
+            let manifest = match prepared {
+                Ok((manifest, _)) => manifest,
+                Err(message) => {
+                    results.push(ModelPutResult {
+                        name: raw
+                            .pointer("/metadata/name")
+                            .and_then(serde_json::Value::as_str)
+                            .unwrap_or_default()
+                            .trim()
+                            .to_owned(),
+                        version: String::new(),
+                        result: ModelPutOutcome::Error,
+                        message,
+                        errors: Vec::new(),
+                    });
+                    continue;
                 }
             };
-
-        if let Some(error_message) = validate_manifest(manifest.clone()).await.err() {
-            self.send_error(msg.reply, error_message.to_string()).await;
-            return;
+            let version = manifest.version().to_owned();
+            let validated = match self
+                .authorize(
+                    account_id,
+                    lattice_id,
+                    ModelOperation::Put,
+                    manifest.metadata.name.trim(),
+                    Some(&manifest.metadata),
+                )
+                .await
+            {
+                Ok(()) => self.validate_new_manifest(account_id, &manifest).await,
+                Err(e) => Err(e.into()),
+            };
+            let (name, result, message, errors) = match validated {
+                // Manifests without a version are each assigned a new one when stored
+                Ok(name)
+                    if !version.is_empty() && !seen.insert((name.clone(), version.clone())) =>
+                {
+                    (
+                        name,
+                        ModelPutOutcome::Error,
+                        "Manifest version is given more than once in the request".to_string(),
+                        Vec::new(),
+                    )
+                }
+                Ok(name) => (name, ModelPutOutcome::NotApplied, String::new(), Vec::new()),
+                Err(rejection) => (
+                    manifest.metadata.name.trim().to_owned(),
+                    ModelPutOutcome::Error,
+                    rejection.message,
+                    rejection.errors,
+                ),
+            };
+            manifests.push((name.clone(), manifest));
+            results.push(ModelPutResult {
+                name,
+                version,
+                result,
+                message,
+                errors,
+            });
         }
 
-        let mut resp = PutModelResponse {
-            // If we successfully insert, the given manifest version will be the new current version
-            current_version: manifest.version().to_owned(),
-            result: if current_manifests.is_empty() {
-                PutResult::Created
-            } else {
-                PutResult::NewVersion
-            },
-            name: manifest_name.clone(),
-            total_versions: 0,
-            message: format!(
-                "Successfully put manifest {} {}",
-                manifest_name,
-                manifest.version()
-            ),
-        };
-
-        if !current_manifests.add_version(manifest) {
-            self.send_error(
+        let invalid = results
+            .iter()
+            .filter(|r| matches!(r.result, ModelPutOutcome::Error))
+            .count();
+        if invalid > 0 {
+            for result in results
+                .iter_mut()
+                .filter(|r| !matches!(r.result, ModelPutOutcome::Error))
+            {
+                result.message =
+                    "Not applied because other manifests in the request are invalid".to_string();
+            }
+            self.send_put_models_reply(
                 msg.reply,
-                format!("Manifest version {} already exists", resp.current_version),
+                PutModelsResult::Error,
+                format!(
+                    "{invalid} of {} manifests are invalid, no manifests were stored",
+                    results.len()
+                ),
+                results,
             )
             .await;
             return;
         }
-        resp.total_versions = current_manifests.count();
 
-        trace!(total_manifests = %resp.total_versions, "Storing manifests");
-        if let Err(e) = self
-            .store
-            .set(
-                account_id,
-                lattice_id,
-                current_manifests,
-                Some(current_revision),
-            )
-            .await
-        {
-            error!(error = %e, "Unable to store updated data");
-            self.send_error(msg.reply, "Internal storage error".to_string())
-                .await;
-            return;
+        let mut stored = Vec::with_capacity(manifests.len());
+        for (idx, (name, manifest)) in manifests.iter().enumerate() {
+            trace!(%name, version = %manifest.version(), "Storing manifest");
+            match self
+                .store_new_version(account_id, lattice_id, name, manifest)
+                .await
+            {
+                Ok((resp, previous)) => {
+                    results[idx].result = match resp.result {
+                        PutResult::Created => ModelPutOutcome::Created,
+                        _ => ModelPutOutcome::NewVersion,
+                    };
+                    results[idx].message = resp.message;
+                    results[idx].version = resp.current_version;
+                    stored.push((name.as_str(), previous, resp.revision));
+                }
+                Err((_, message)) => {
+                    warn!(
+                        "Unable to store manifest {name}, rolling back stored manifests: {message}"
+                    );
+                    let not_reverted = self
+                        .revert_stored_models(account_id, lattice_id, stored)
+                        .await;
+                    for (i, result) in results.iter_mut().enumerate() {
+                        if i == idx {
+                            result.result = ModelPutOutcome::Error;
+                            result.message = message.clone();
+                        } else if not_reverted.contains(&manifests[i].0.as_str()) {
+                            result.result = ModelPutOutcome::NotRolledBack;
+                            result.message = format!(
+                                "Stored, but could not be rolled back after manifest {name} {} could not be stored",
+                                manifest.version()
+                            );
+                        } else {
+                            result.result = ModelPutOutcome::NotApplied;
+                            result.message = format!(
+                                "Not applied because manifest {name} {} could not be stored",
+                                manifest.version()
+                            );
+                        }
+                    }
+                    let message = if not_reverted.is_empty() {
+                        format!("Unable to store manifest {name}, no manifests were stored")
+                    } else {
+                        format!("Unable to store manifest {name} and some previously stored manifests could not be rolled back")
+                    };
+                    self.send_put_models_reply(msg.reply, PutModelsResult::Error, message, results)
+                        .await;
+                    return;
+                }
+            }
         }
 
-        trace!("Storage complete, sending reply");
-        self.send_reply(
-            msg.reply,
-            // NOTE: We are constructing all data here, so this shouldn't fail, but just in case we
-            // unwrap to nothing
-            serde_json::to_vec(&resp).unwrap_or_default(),
-        )
-        .await
+        let message = format!(
+            "Successfully put {} manifests in lattice {lattice_id}",
+            results.len()
+        );
+        let records = results
+            .iter()
+            .map(|r| {
+                let result = match r.result {
+                    ModelPutOutcome::Created => "created",
+                    _ => "newversion",
+                };
+                audit::model_record(
+                    account_id,
+                    lattice_id,
+                    ModelOperation::Put,
+                    &r.name,
+                    Some(&r.version),
+                    result,
+                )
+            })
+            .collect::<Vec<_>>();
+        self.send_put_models_reply(msg.reply, PutModelsResult::Success, message, results)
+            .await;
+        for record in records {
+            self.publish_audit_record(record).await;
+        }
     }
 
-    #[instrument(level = "debug", skip(self, msg))]
+    #[instrument(
+        level = "debug",
+        skip(self, msg, account_id, lattice_id),
+        fields(account_id = account_id.unwrap_or_default(), lattice_id = %lattice_id)
+    )]
     pub async fn get_model(
         &self,
         msg: Message,
@@ -164,13 +595,14 @@ impl<P: Publisher> Handler<P> {
     ) {
         // For empty payloads, just fetch the latest version
         let req: GetModelRequest = if msg.payload.is_empty() {
-            GetModelRequest { version: None }
+            GetModelRequest::default()
         } else {
             match serde_json::from_reader(std::io::Cursor::new(msg.payload)) {
                 Ok(r) => r,
                 Err(e) => {
                     self.send_error(
                         msg.reply,
+                        ErrorCode::InvalidRequest,
                         format!("Unable to parse get model request: {e:?}"),
                     )
                     .await;
@@ -179,6 +611,25 @@ impl<P: Publisher> Handler<P> {
             }
         };
 
+        if req.version.is_some() && !req.versions.is_empty() {
+            self.send_error(
+                msg.reply,
+                ErrorCode::InvalidRequest,
+                "Only one of version or versions can be set".to_string(),
+            )
+            .await;
+            return;
+        }
+        if req.relative.is_some() && !req.versions.is_empty() {
+            self.send_error(
+                msg.reply,
+                ErrorCode::InvalidRequest,
+                "A relative version can't be combined with versions".to_string(),
+            )
+            .await;
+            return;
+        }
+
         let (manifests, _) = match self.store.get(account_id, lattice_id, name).await {
             Ok(Some(m)) => m,
             Ok(None) => {
@@ -190,6 +641,7 @@ impl<P: Publisher> Handler<P> {
                         result: GetResult::NotFound,
                         message: format!("Model with the name {name} not found"),
                         manifest: None,
+                        versions: BTreeMap::new(),
                     })
                     .unwrap_or_default(),
                 )
@@ -198,18 +650,103 @@ impl<P: Publisher> Handler<P> {
             }
             Err(e) => {
                 error!(error = %e, "Unable to fetch data");
-                self.send_error(msg.reply, "Internal storage error".to_string())
+                self.send_error(msg.reply, storage_error_code(&e), storage_error_message(&e))
                     .await;
                 return;
             }
         };
-        let reply = match req.version {
+        if !req.versions.is_empty() {
+            let versions: BTreeMap<String, Option<Manifest>> = req
+                .versions
+                .into_iter()
+                .map(|version| {
+                    // Same as below, manifests are upgraded to the current schema on the way out
+                    let manifest = manifests
+                        .get_version(&version)
+                        .map(|m| migrate_manifest(m.to_owned()).0);
+                    (version, manifest)
+                })
+                .collect();
+            let missing: Vec<&str> = versions
+                .iter()
+                .filter(|(_, manifest)| manifest.is_none())
+                .map(|(version, _)| version.as_str())
+                .collect();
+            let message = if missing.is_empty() {
+                format!(
+                    "Successfully fetched {} versions of model {name}",
+                    versions.len()
+                )
+            } else {
+                format!(
+                    "Fetched {} of {} versions of model {name}. Missing versions: {}",
+                    versions.len() - missing.len(),
+                    versions.len(),
+                    missing.join(", ")
+                )
+            };
+            let reply = GetModelResponse {
+                result: GetResult::Success,
+                message,
+                manifest: None,
+                versions,
+            };
+            // NOTE: We _just_ deserialized this from the store above, so we should be just fine.
+            // but just in case we unwrap to the default
+            self.send_reply(msg.reply, serde_json::to_vec(&reply).unwrap_or_default())
+                .await;
+            return;
+        }
+
+        let mut version = req.version;
+        if let Some(relative) = req.relative {
+            let not_found = match version.as_deref().or(manifests.deployed_version()) {
+                Some(base) => match manifests.relative_version(base, relative) {
+                    Some(resolved) => {
+                        version = Some(resolved.to_owned());
+                        None
+                    }
+                    None if manifests.get_version(base).is_none() => {
+                        Some(format!("Model {name} with version {base} doesn't exist"))
+                    }
+                    None => Some(format!(
+                        "Model {name} has no version {} {base}",
+                        match relative {
+                            RelativeVersion::Previous => "before",
+                            RelativeVersion::Next => "after",
+                        }
+                    )),
+                },
+                None => Some(format!(
+                    "Model {name} isn't deployed, so a version is required to resolve a relative version"
+                )),
+            };
+            if let Some(message) = not_found {
+                self.send_reply(
+                    msg.reply,
+                    // NOTE: We are constructing all data here, so this shouldn't fail, but just in
+                    // case we unwrap to nothing
+                    serde_json::to_vec(&GetModelResponse {
+                        result: GetResult::NotFound,
+                        message,
+                        manifest: None,
+                        versions: BTreeMap::new(),
+                    })
+                    .unwrap_or_default(),
+                )
+                .await;
+                return;
+            }
+        }
+
+        let mut reply = match version {
             Some(version) => {
                 if let Some(current) = manifests.get_version(&version) {
                     GetModelResponse {
                         manifest: Some(current.to_owned()),
                         result: GetResult::Success,
                         message: format!("Successfully fetched model {name} {version}"),
+                        versions: BTreeMap::new(),
                     }
                 } else {
                     self.send_reply(
@@ -220,6 +757,7 @@ impl<P: Publisher> Handler<P> {
                             result: GetResult::NotFound,
                             message: format!("Model {name} with version {} doesn't exist", version),
                             manifest: None,
+                            versions: BTreeMap::new(),
                         })
                         .unwrap_or_default(),
                     )
@@ -231,464 +769,371 @@ impl<P: Publisher> Handler<P> {
                 manifest: Some(manifests.get_current().to_owned()),
                 result: GetResult::Success,
                 message: format!("Successfully fetched model {name}"),
+                versions: BTreeMap::new(),
             },
         };
+        // Manifests stored before a schema change are upgraded on the way out so clients always get
+        // a manifest that validates against the current schema
+        if let Some(manifest) = reply.manifest.take() {
+            let (manifest, migrations) = migrate_manifest(manifest);
+            if !migrations.is_empty() {
+                trace!(?migrations, "Migrated manifest to current schema");
+                reply.message = format!(
+                    "{}. Migrated manifest to the current schema: {}",
+                    reply.message,
+                    migrations.join(", ")
+                );
+            }
+            reply.manifest = Some(manifest);
+        }
         // NOTE: We _just_ deserialized this from the store above, so we should be just fine. but
         // just in case we unwrap to the default
         self.send_reply(msg.reply, serde_json::to_vec(&reply).unwrap_or_default())
             .await
     }
 
-    #[instrument(level = "debug", skip(self, msg))]
-    pub async fn list_models(&self, msg: Message, account_id: Option<&str>, lattice_id: &str) {
-        let mut data = match self.store.list(account_id, lattice_id).await {
-            Ok(d) => d,
-            Err(e) => {
-                error!(error = %e, "Unable to fetch data");
-                self.send_error(msg.reply, "Internal storage error".to_string())
-                    .await;
-                return;
-            }
-        };
-
-        for model in &mut data {
-            if let Some(status) = self.get_manifest_status(lattice_id, &model.name).await {
-                model.status = status.status_type;
-                model.status_message = Some(status.message);
-            } else {
-                warn!("Could not fetch status for model, assuming undeployed");
-                model.status = StatusType::Undeployed;
-                model.status_message = None;
-            }
-        }
-
-        // NOTE: We _just_ deserialized this from the store above and then manually constructed it,
-        // so we should be just fine. Just in case though, we unwrap to default
-        self.send_reply(msg.reply, serde_json::to_vec(&data).unwrap_or_default())
-            .await
-    }
-
-    // NOTE(thomastaylor312): This method differs from the wadm 0.3 docs as it doesn't include
-    // timestamp (at least for now). However, this is guaranteed to return the list of versions
-    // ordered by time of creation. When we document, we should change this to reflect that
-    #[instrument(level = "debug", skip(self, msg))]
-    pub async fn list_versions(
+    #[instrument(
+        level = "debug",
+        skip(self, msg, account_id, lattice_id),
+        fields(account_id = account_id.unwrap_or_default(), lattice_id = %lattice_id)
+    )]
+    pub async fn get_raw_model(
         &self,
         msg: Message,
         account_id: Option<&str>,
         lattice_id: &str,
         name: &str,
     ) {
-        let data: VersionResponse = match self.store.get(account_id, lattice_id, name).await {
-            Ok(Some((manifest, _))) => VersionResponse {
+        if !self.config.enable_raw_model_access {
+            self.send_error(
+                msg.reply,
+                ErrorCode::Unsupported,
+                "Fetching raw stored models is disabled on this server".to_string(),
+            )
+            .await;
+            return;
+        }
+
+        let reply = match self.store.get_raw(account_id, lattice_id, name).await {
+            Ok(Some((data, revision))) => GetRawModelResponse {
                 result: GetResult::Success,
-                message: format!("Successfully fetched versions for model {name}"),
-                versions: manifest
-                    .all_versions()
-                    .into_iter()
-                    .cloned()
-                    .map(|v| {
-                        let deployed = manifest.is_deployed(&v);
-                        VersionInfo {
-                            version: v,
-                            deployed,
-                        }
-                    })
-                    .collect(),
+                message: format!("Successfully fetched raw data for model {name}"),
+                revision,
+                encoding: Some(StorageCodec::detect(&data).to_string()),
+                data: Some(B64decoder.encode(data)),
             },
-            Ok(None) => VersionResponse {
+            Ok(None) => GetRawModelResponse {
                 result: GetResult::NotFound,
                 message: format!("Model with the name {name} not found"),
-                versions: Vec::with_capacity(0),
+                revision: 0,
+                encoding: None,
+                data: None,
             },
             Err(e) => {
-                error!(error = %e, "Unable to fetch data");
-                self.send_error(msg.reply, "Internal storage error".to_string())
+                error!(error = %e, "Unable to fetch raw data from store");
+                self.send_error(msg.reply, storage_error_code(&e), storage_error_message(&e))
                     .await;
                 return;
             }
         };
-        // NOTE: We _just_ deserialized this from the store above and then manually constructed it,
-        // so we should be just fine. Just in case though, we unwrap to default
-        self.send_reply(msg.reply, serde_json::to_vec(&data).unwrap_or_default())
+        // NOTE: We are constructing all data here, so this shouldn't fail, but just in case we
+        // unwrap to nothing
+        self.send_reply(msg.reply, serde_json::to_vec(&reply).unwrap_or_default())
             .await
     }
 
-    #[instrument(level = "debug", skip(self, msg))]
-    pub async fn delete_model(
+    #[instrument(
+        level = "debug",
+        skip(self, msg, account_id, lattice_id),
+        fields(account_id = account_id.unwrap_or_default(), lattice_id = %lattice_id)
+    )]
+    pub async fn normalize_model(
         &self,
         msg: Message,
         account_id: Option<&str>,
         lattice_id: &str,
-        name: &str,
+        name: Option<&str>,
     ) {
-        let req: DeleteModelRequest =
-            match serde_json::from_reader(std::io::Cursor::new(msg.payload)) {
-                Ok(r) => r,
+        if let Err(message) = self.check_payload_size(msg.payload.len()) {
+            self.send_error(msg.reply, ErrorCode::InvalidRequest, message)
+                .await;
+            return;
+        }
+        let header_name = msg
+            .headers
+            .as_ref()
+            .and_then(|headers| headers.get(MODEL_NAME_HEADER))
+            .map(|value| value.as_str().to_owned());
+
+        let (manifest, migrations) =
+            match self.prepare_manifest(msg.payload.into(), msg.headers.as_ref()) {
+                Ok(prepared) => prepared,
                 Err(e) => {
-                    self.send_error(
-                        msg.reply,
-                        format!("Unable to parse delete model request: {e:?}"),
-                    )
-                    .await;
+                    self.send_error(msg.reply, ErrorCode::InvalidRequest, e)
+                        .await;
                     return;
                 }
             };
-        let reply_data = if let Some(version) = req.version {
-            match self.store.get(account_id, lattice_id, name).await {
-                Ok(Some((mut current, current_revision))) => {
-                    let deleted = current.delete_version(&version);
-                    if deleted && !current.is_empty() {
-                        // If the version we deleted was the deployed one, undeploy it
-                        let deployed_version = current.deployed_version();
-                        let undeploy = if deployed_version.map(|v| v == version).unwrap_or(false) {
-                            trace!(?deployed_version, deleted_version = %version, "Deployed version matches deleted. Will undeploy");
-                            current.undeploy();
-                            true
-                        } else {
-                            trace!(?deployed_version, deleted_version = %version, "Deployed version does not match deleted version. Will not undeploy");
-                            false
-                        };
-                        self.store
-                            .set(account_id, lattice_id, current, Some(current_revision))
-                            .await
-                            .map(|_| DeleteModelResponse {
-                                result: DeleteResult::Deleted,
-                                message: format!(
-                                    "Successfully deleted version {} of model {}",
-                                    version, name
-                                ),
-                                undeploy,
-                            })
-                            .unwrap_or_else(|e| {
-                                error!(error = %e, "Unable to delete data");
-                                DeleteModelResponse {
-                                    result: DeleteResult::Error,
-                                    message: "Internal storage error".to_string(),
-                                    undeploy: false,
-                                }
-                            })
-                    } else if deleted && current.is_empty() {
-                        // If we deleted the last one, delete the model from the store
-                        self.store
-                            .delete(account_id, lattice_id, name)
-                            .await
-                            .map(|_| DeleteModelResponse {
-                                result: DeleteResult::Deleted,
-                                message: format!(
-                                    "Successfully deleted last version of model {}",
-                                    name
-                                ),
-                                // By default if it is all gone, we definitely undeployed things
-                                undeploy: true,
-                            })
-                            .unwrap_or_else(|e| {
-                                error!(error = %e, "Unable to delete data");
-                                DeleteModelResponse {
-                                    result: DeleteResult::Deleted,
-                                    message: "Internal storage error".to_string(),
-                                    undeploy: false,
-                                }
-                            })
-                    } else {
-                        DeleteModelResponse {
-                            result: DeleteResult::Noop,
-                            message: format!("Model version {} doesn't exist", version),
-                            undeploy: false,
-                        }
-                    }
-                }
-                Ok(None) => DeleteModelResponse {
-                    result: DeleteResult::Noop,
-                    message: format!("Model {name} doesn't exist"),
-                    undeploy: false,
-                },
+
+        let manifest_name = manifest.metadata.name.trim().to_string();
+        if let Err(e) = check_name_hint(&manifest_name, [name, header_name.as_deref()]) {
+            self.send_error(msg.reply, ErrorCode::InvalidRequest, e)
+                .await;
+            return;
+        }
+
+        // A put without a version gets one assigned from the versions already stored, so preview
+        // the version it would get right now
+        let manifest = if manifest.version().trim().is_empty() {
+            let current = match self.store.get(account_id, lattice_id, &manifest_name).await {
+                Ok(Some((current, _))) => current,
+                Ok(None) => StoredManifest::default(),
                 Err(e) => {
-                    error!(error = %e, "Unable to fetch current data data");
-                    DeleteModelResponse {
-                        result: DeleteResult::Error,
-                        message: "Internal storage error".to_string(),
-                        undeploy: false,
-                    }
+                    error!(error = %e, "Unable to fetch data from store");
+                    self.send_error(msg.reply, storage_error_code(&e), storage_error_message(&e))
+                        .await;
+                    return;
                 }
+            };
+            self.with_assigned_version(&manifest_name, &manifest, &current)
+                .into_owned()
+        } else {
+            manifest
+        };
+
+        let message = if migrations.is_empty() {
+            format!("Normalized manifest {manifest_name} {}", manifest.version())
+        } else {
+            format!(
+                "Normalized manifest {manifest_name} {}. Migrated manifest to the current schema: {}",
+                manifest.version(),
+                migrations.join(", ")
+            )
+        };
+        self.send_reply(
+            msg.reply,
+            // NOTE: We are constructing all data here, so this shouldn't fail, but just in
+            // case we unwrap to nothing
+            serde_json::to_vec(&NormalizeModelResponse {
+                result: GetResult::Success,
+                message,
+                manifest: Some(manifest),
+                migrations,
+            })
+            .unwrap_or_default(),
+        )
+        .await
+    }
+
+    #[instrument(level = "debug", skip(self, msg))]
+    pub async fn lint_model(&self, msg: Message) {
+        if let Err(message) = self.check_payload_size(msg.payload.len()) {
+            self.send_error(msg.reply, ErrorCode::InvalidRequest, message)
+                .await;
+            return;
+        }
+
+        // Lint the manifest as it would be stored, so warnings match what put would store
+        let manifest = match self.prepare_manifest(msg.payload.into(), msg.headers.as_ref()) {
+            Ok((manifest, _)) => manifest,
+            Err(e) => {
+                self.send_error(msg.reply, ErrorCode::InvalidRequest, e)
+                    .await;
+                return;
             }
+        };
+
+        let warnings = lint(&manifest, &self.lint_rules);
+        let (result, message) = if warnings.is_empty() {
+            (
+                LintResult::Clean,
+                format!("Manifest {} has no lint warnings", manifest.metadata.name),
+            )
         } else {
-            match self.store.delete(account_id, lattice_id, name).await {
-                Ok(_) => {
-                    DeleteModelResponse {
-                        result: DeleteResult::Deleted,
-                        message: format!("Successfully deleted model {}", name),
-                        // By default if it is all gone, we definitely undeployed things
-                        undeploy: true,
-                    }
-                }
-                Err(e) => {
-                    error!(error = %e, "Unable to delete data");
-                    DeleteModelResponse {
-                        result: DeleteResult::Error,
-                        message: "Internal storage error".to_string(),
-                        undeploy: false,
-                    }
-                }
+            (
+                LintResult::Warnings,
+                format!(
+                    "Manifest {} has {} lint warning(s)",
+                    manifest.metadata.name,
+                    warnings.len()
+                ),
+            )
+        };
+        self.send_reply(
+            msg.reply,
+            // NOTE: We are constructing all data here, so this shouldn't fail, but just in
+            // case we unwrap to nothing
+            serde_json::to_vec(&LintModelResponse {
+                result,
+                message,
+                warnings,
+            })
+            .unwrap_or_default(),
+        )
+        .await
+    }
+
+    #[instrument(
+        level = "debug",
+        skip(self, msg, account_id, lattice_id),
+        fields(account_id = account_id.unwrap_or_default(), lattice_id = %lattice_id)
+    )]
+    pub async fn diff_model(
+        &self,
+        msg: Message,
+        account_id: Option<&str>,
+        lattice_id: &str,
+        name: &str,
+    ) {
+        let req: DiffModelRequest = match serde_json::from_reader(std::io::Cursor::new(msg.payload))
+        {
+            Ok(r) => r,
+            Err(e) => {
+                self.send_error(
+                    msg.reply,
+                    ErrorCode::InvalidRequest,
+                    format!("Unable to parse diff model request: {e:?}"),
+                )
+                .await;
+                return;
             }
         };
+        trace!(?req, "Got request");
 
-        // On a noop, we should still send an undeploy in case of notification failure
-        // TODO(thomastaylor312): We might want to come back and revisit how we handle a failure
-        // like this in the delete case. If the data gets deleted, but we can't send it, we get into
-        // an odd state. So I'd rather err on the side of caution and send a notification that gets
-        // ignored
-        if reply_data.undeploy || matches!(reply_data.result, DeleteResult::Noop) {
-            trace!("Sending undeploy notification");
-            if let Err(e) = self.notifier.undeployed(lattice_id, name).await {
-                error!(error = ?e, "Error when attempting to send undeploy notification during delete");
+        let manifests = match self.store.get(account_id, lattice_id, name).await {
+            Ok(Some((m, _))) => m,
+            Ok(None) => {
                 self.send_reply(
                     msg.reply,
                     // NOTE: We are constructing all data here, so this shouldn't fail, but just in
                     // case we unwrap to nothing
-                    serde_json::to_vec(&DeleteModelResponse {
-                        result: DeleteResult::Error,
-                        message: "Error notifying processors of newly undeployed manifest on delete. This is likely a transient error, so please retry the request. Please note that the response will say it is a noop, but will notify the processors".to_string(),
-                        undeploy: false,
+                    serde_json::to_vec(&DiffModelResponse {
+                        result: GetResult::NotFound,
+                        message: format!("Model with the name {name} not found"),
+                        diff: None,
                     })
                     .unwrap_or_default(),
                 )
                 .await;
                 return;
             }
-        }
+            Err(e) => {
+                error!(error = %e, "Unable to fetch data");
+                self.send_error(msg.reply, storage_error_code(&e), storage_error_message(&e))
+                    .await;
+                return;
+            }
+        };
 
-        // NOTE: We control all the data getting sent in here, but we unwrap to default just in case
+        let get_version = |version: &str| {
+            if version == LATEST_VERSION {
+                Some(manifests.get_current())
+            } else {
+                manifests.get_version(version)
+            }
+        };
+        let reply = match (get_version(&req.from), get_version(&req.to)) {
+            (Some(from), Some(to)) => {
+                let diff = ManifestDiff::between(from, to);
+                DiffModelResponse {
+                    result: GetResult::Success,
+                    message: diff_message(name, &diff),
+                    diff: Some(diff),
+                }
+            }
+            (from, _) => DiffModelResponse {
+                result: GetResult::NotFound,
+                message: format!(
+                    "Model {name} with version {} doesn't exist",
+                    if from.is_none() { &req.from } else { &req.to }
+                ),
+                diff: None,
+            },
+        };
+
+        trace!(resp = ?reply, "Sending response");
         self.send_reply(
             msg.reply,
-            serde_json::to_vec(&reply_data).unwrap_or_default(),
+            // NOTE: We are constructing all data here, so this shouldn't fail, but just in
+            // case we unwrap to nothing
+            serde_json::to_vec(&reply).unwrap_or_default(),
         )
-        .await
+        .await;
     }
 
-    #[instrument(level = "debug", skip(self, msg))]
-    pub async fn deploy_model(
+    /// Diffs a candidate manifest sent in the request against the stored model of the same name
+    /// without storing it. The candidate is compared to the deployed version or, if the model
+    /// isn't deployed, the latest version
+    #[instrument(
+        level = "debug",
+        skip(self, msg, account_id, lattice_id),
+        fields(account_id = account_id.unwrap_or_default(), lattice_id = %lattice_id)
+    )]
+    pub async fn diff_candidate(
         &self,
         msg: Message,
         account_id: Option<&str>,
         lattice_id: &str,
-        name: &str,
+        name: Option<&str>,
     ) {
-        let req: DeployModelRequest = if msg.payload.is_empty() {
-            DeployModelRequest { version: None }
-        } else {
-            match serde_json::from_reader(std::io::Cursor::new(msg.payload)) {
-                Ok(r) => r,
-                Err(e) => {
-                    self.send_error(
-                        msg.reply,
-                        format!("Unable to parse deploy model request: {e:?}"),
-                    )
+        if let Err(message) = self.check_payload_size(msg.payload.len()) {
+            self.send_error(msg.reply, ErrorCode::InvalidRequest, message)
+                .await;
+            return;
+        }
+
+        // Diff the manifest as it would be stored, so the diff matches what put would store
+        let candidate = match self.prepare_manifest(msg.payload.into(), msg.headers.as_ref()) {
+            Ok((manifest, _)) => manifest,
+            Err(e) => {
+                self.send_error(msg.reply, ErrorCode::InvalidRequest, e)
                     .await;
-                    return;
-                }
+                return;
             }
         };
-        trace!(?req, "Got request");
 
-        trace!("Fetching current data from store");
-        let (mut manifests, current_revision) =
-            match self.store.get(account_id, lattice_id, name).await {
-                Ok(Some(m)) => m,
-                Ok(None) => {
-                    self.send_reply(
-                        msg.reply,
-                        // NOTE: We are constructing all data here, so this shouldn't fail, but just in
-                        // case we unwrap to nothing
-                        serde_json::to_vec(&DeployModelResponse {
-                            result: DeployResult::NotFound,
-                            message: format!("Model with the name {name} not found"),
-                        })
-                        .unwrap_or_default(),
-                    )
-                    .await;
-                    return;
-                }
-                Err(e) => {
-                    error!(error = %e, "Unable to fetch data");
-                    self.send_error(msg.reply, "Internal storage error".to_string())
-                        .await;
-                    return;
-                }
-            };
+        let manifest_name = candidate.metadata.name.trim();
+        if let Err(e) = check_name_hint(manifest_name, [name]) {
+            self.send_error(msg.reply, ErrorCode::InvalidRequest, e)
+                .await;
+            return;
+        }
 
-        // Retrieve all stored models in the lattice
-        let stored_models = match self.store.list(account_id, lattice_id).await {
-            Ok(d) => d,
+        let manifests = match self.store.get(account_id, lattice_id, manifest_name).await {
+            Ok(Some((m, _))) => m,
+            Ok(None) => {
+                self.send_reply(
+                    msg.reply,
+                    // NOTE: We are constructing all data here, so this shouldn't fail, but just in
+                    // case we unwrap to nothing
+                    serde_json::to_vec(&DiffModelResponse {
+                        result: GetResult::NotFound,
+                        message: format!("Model with the name {manifest_name} not found"),
+                        diff: None,
+                    })
+                    .unwrap_or_default(),
+                )
+                .await;
+                return;
+            }
             Err(e) => {
                 error!(error = %e, "Unable to fetch data");
-                self.send_error(msg.reply, "Internal storage error".to_string())
+                self.send_error(msg.reply, storage_error_code(&e), storage_error_message(&e))
                     .await;
                 return;
             }
         };
 
-        let staged_model = match req.version.clone() {
-            Some(v) if v == LATEST_VERSION => manifests.get_current(),
-            Some(v) => {
-                if let Some(model) = manifests.get_version(&v) {
-                    model
-                } else {
-                    trace!("Requested version does not exist");
-                    self.send_reply(
-                        msg.reply,
-                        // NOTE: We are constructing all data here, so this shouldn't fail, but just in
-                        // case we unwrap to nothing
-                        serde_json::to_vec(&DeployModelResponse {
-                            result: DeployResult::Error,
-                            message: format!(
-                        "Model with the name {name} does not have the specified version to deploy"
-                    ),
-                        })
-                        .unwrap_or_default(),
-                    )
-                    .await;
-                    return;
-                }
-            }
-            // Get the current version if payload version is None, since deploy() does the same
-            None => manifests.get_current(),
+        let stored = manifests
+            .get_deployed()
+            .unwrap_or_else(|| manifests.get_current());
+        let diff = ManifestDiff::between(stored, &candidate);
+        let reply = DiffModelResponse {
+            result: GetResult::Success,
+            message: diff_message(manifest_name, &diff),
+            diff: Some(diff),
         };
 
-        // Retrieve all the existing provider refs in store that are currently deployed
-        let mut existing_provider_refs: HashMap<String, (String, String)> = HashMap::new();
-        for model_summary in stored_models.iter() {
-            // Excluding models that do not have a deployed version at present
-            if model_summary.deployed_version.is_some() {
-                let (stored_manifest, _) = match self
-                    .store
-                    .get(account_id, lattice_id, &model_summary.name)
-                    .await
-                {
-                    Ok(Some(m)) => m,
-                    Ok(None) => (StoredManifest::default(), 0),
-                    Err(e) => {
-                        error!(error = %e, "Unable to fetch data");
-                        self.send_error(msg.reply, "Internal storage error".to_string())
-                            .await;
-                        return;
-                    }
-                };
-
-                // Performing checks against all other manifests except previous versions of the current manifest
-                // Because upgrading versions is a valid case for adding providers of updated versions
-                if stored_manifest.name() != name {
-                    if let Some(deployed_manifest) = stored_manifest.get_deployed() {
-                        for component in deployed_manifest.spec.components.iter() {
-                            if let Properties::Capability {
-                                properties:
-                                    CapabilityProperties {
-                                        image: image_name, ..
-                                    },
-                            } = &component.properties
-                            {
-                                if let Some((ref_link, ref_version)) = parse_image_ref(image_name) {
-                                    existing_provider_refs.insert(
-                                        ref_link,
-                                        (ref_version, stored_manifest.name().to_string()),
-                                    );
-                                }
-                            }
-                        }
-                    };
-                }
-            }
-        }
-
-        // Compare if any of the provider refs in the staged model are duplicates
-        for component in staged_model.spec.components.iter() {
-            if let Properties::Capability {
-                properties:
-                    CapabilityProperties {
-                        image: image_name, ..
-                    },
-            } = &component.properties
-            {
-                if let Some((ref_link, ref_version)) = parse_image_ref(image_name) {
-                    if let Some((old_version, old_manifest_name)) =
-                        existing_provider_refs.get(&ref_link)
-                    {
-                        if old_version != &ref_version {
-                            error!(
-                                "Provider {image_name} is already deployed with a different version in {old_manifest_name}.",
-                            );
-                            self.send_error(
-                                msg.reply,
-                                format!(
-                                "Provider {image_name} is already deployed with a different version in {old_manifest_name}."
-                            ),
-                            )
-                            .await;
-                            return;
-                        }
-                    }
-                }
-            }
-        }
-
-        if !manifests.deploy(req.version) {
-            trace!("Requested version does not exist");
-            self.send_reply(
-                msg.reply,
-                // NOTE: We are constructing all data here, so this shouldn't fail, but just in
-                // case we unwrap to nothing
-                serde_json::to_vec(&DeployModelResponse {
-                    result: DeployResult::Error,
-                    message: format!(
-                        "Model with the name {name} does not have the specified version to deploy"
-                    ),
-                })
-                .unwrap_or_default(),
-            )
-            .await;
-            return;
-        }
-        // SAFETY: We can unwrap here because we know we _just_ successfully deployed the manifest so they should all exist
-        let manifest = manifests
-            .get_version(manifests.deployed_version().unwrap())
-            .unwrap()
-            .to_owned();
-
-        let reply = self
-            .store
-            .set(account_id, lattice_id, manifests, Some(current_revision))
-            .await
-            .map(|_| DeployModelResponse {
-                result: DeployResult::Acknowledged,
-                message: format!(
-                    "Successfully deployed model {} {}",
-                    name,
-                    manifest.version()
-                ),
-            })
-            .unwrap_or_else(|e| {
-                error!(error = %e, "Unable to store updated data");
-                DeployModelResponse {
-                    result: DeployResult::Error,
-                    message: "Internal storage error".to_string(),
-                }
-            });
-        trace!("Manifest saved in store, sending notification");
-        if let Err(e) = self.notifier.deployed(lattice_id, manifest).await {
-            error!(error = ?e, "Error when attempting to send deployed notification");
-            self.send_reply(
-                msg.reply,
-                // NOTE: We are constructing all data here, so this shouldn't fail, but just in
-                // case we unwrap to nothing
-                serde_json::to_vec(&DeployModelResponse {
-                    result: DeployResult::Error,
-                    message: "Error notifying processors of newly deployed manifest. This is likely a transient error, so please retry the request".to_string(),
-                })
-                .unwrap_or_default(),
-            )
-            .await;
-            return;
-        }
         trace!(resp = ?reply, "Sending response");
         self.send_reply(
             msg.reply,
@@ -699,130 +1144,68 @@ impl<P: Publisher> Handler<P> {
         .await;
     }
 
-    #[instrument(level = "debug", skip(self, msg))]
-    pub async fn undeploy_model(
+    #[instrument(
+        level = "debug",
+        skip(self, msg, account_id, lattice_id),
+        fields(account_id = account_id.unwrap_or_default(), lattice_id = %lattice_id)
+    )]
+    pub async fn copy_model(
         &self,
         msg: Message,
         account_id: Option<&str>,
         lattice_id: &str,
         name: &str,
     ) {
-        let req: UndeployModelRequest = if msg.payload.is_empty() {
-            UndeployModelRequest {}
-        } else {
-            match serde_json::from_reader(std::io::Cursor::new(msg.payload)) {
-                Ok(r) => r,
-                Err(e) => {
-                    self.send_error(
-                        msg.reply,
-                        format!("Unable to parse deploy model request: {e:?}"),
-                    )
-                    .await;
-                    return;
-                }
-            }
-        };
-        trace!(?req, "Got request");
-
-        trace!("Fetching current data from store");
-        let (mut manifests, current_revision) =
-            match self.store.get(account_id, lattice_id, name).await {
-                Ok(Some(m)) => m,
-                Ok(None) => {
-                    self.send_reply(
-                        msg.reply,
-                        // NOTE: We are constructing all data here, so this shouldn't fail, but just in
-                        // case we unwrap to nothing
-                        serde_json::to_vec(&DeployModelResponse {
-                            result: DeployResult::NotFound,
-                            message: format!("Model with the name {name} not found"),
-                        })
-                        .unwrap_or_default(),
-                    )
-                    .await;
-                    return;
-                }
-                Err(e) => {
-                    error!(error = %e, "Unable to fetch data");
-                    self.send_error(msg.reply, "Internal storage error".to_string())
-                        .await;
-                    return;
-                }
-            };
-
-        let reply = if manifests.undeploy() {
-            trace!("Manifest undeployed. Storing updated manifest");
-
-            self.store
-                .set(account_id, lattice_id, manifests, Some(current_revision))
-                .await
-                .map(|_| DeployModelResponse {
-                    result: DeployResult::Acknowledged,
-                    message: format!("Successfully undeployed model {}", name),
-                })
-                .unwrap_or_else(|e| {
-                    error!(error = %e, "Unable to store updated data");
-                    DeployModelResponse {
-                        result: DeployResult::Error,
-                        message: "Internal storage error".to_string(),
-                    }
-                })
-        } else {
-            trace!("Manifest was already undeployed");
-            DeployModelResponse {
-                result: DeployResult::Acknowledged,
-                message: format!("Model {} was already undeployed", name),
-            }
-        };
-        // We always want to resend in an undeploy in case things failed last time
-        if matches!(reply.result, DeployResult::Acknowledged) {
-            trace!("Sending undeploy notification");
-            if let Err(e) = self.notifier.undeployed(lattice_id, name).await {
-                error!(error = ?e, "Error when attempting to send undeploy notification");
-                self.send_reply(
+        let req: CopyModelRequest = match serde_json::from_reader(std::io::Cursor::new(msg.payload))
+        {
+            Ok(r) => r,
+            Err(e) => {
+                self.send_error(
                     msg.reply,
-                    // NOTE: We are constructing all data here, so this shouldn't fail, but just in
-                    // case we unwrap to nothing
-                    serde_json::to_vec(&DeployModelResponse {
-                        result: DeployResult::Error,
-                        message: "Error notifying processors of undeployed manifest. This is likely a transient error, so please retry the request".to_string(),
-                    })
-                    .unwrap_or_default(),
+                    ErrorCode::InvalidRequest,
+                    format!("Unable to parse copy model request: {e:?}"),
                 )
                 .await;
                 return;
             }
+        };
+        trace!(?req, "Got request");
+
+        let destination = req.destination.trim().to_string();
+        // The destination is the model that is changed by a copy
+        audit::record_model(&destination);
+        if let Some(message) = invalid_manifest_name(&destination) {
+            self.send_error(msg.reply, ErrorCode::InvalidRequest, message)
+                .await;
+            return;
+        }
+        if let Some(message) = self.check_reserved_name(&destination, account_id) {
+            self.send_error(msg.reply, ErrorCode::InvalidRequest, message)
+                .await;
+            return;
+        }
+        if destination == name {
+            self.send_error(
+                msg.reply,
+                ErrorCode::InvalidRequest,
+                format!("Cannot copy model {name} to itself"),
+            )
+            .await;
+            return;
         }
-        trace!(resp = ?reply, "Sending response");
-        self.send_reply(
-            msg.reply,
-            // NOTE: We are constructing all data here, so this shouldn't fail, but just in
-            // case we unwrap to nothing
-            serde_json::to_vec(&reply).unwrap_or_default(),
-        )
-        .await;
-    }
 
-    #[instrument(level = "debug", skip(self, msg))]
-    pub async fn model_status(
-        &self,
-        msg: Message,
-        account_id: Option<&str>,
-        lattice_id: &str,
-        name: &str,
-    ) {
-        trace!("Fetching current manifest from store");
-        let manifests: StoredManifest = match self.store.get(account_id, lattice_id, name).await {
+        let source = match self.store.get(account_id, lattice_id, name).await {
             Ok(Some((m, _))) => m,
             Ok(None) => {
                 self.send_reply(
                     msg.reply,
                     // NOTE: We are constructing all data here, so this shouldn't fail, but just in
                     // case we unwrap to nothing
-                    serde_json::to_vec(&StatusResponse {
-                        result: StatusResult::NotFound,
+                    serde_json::to_vec(&CopyModelResponse {
+                        result: CopyResult::NotFound,
                         message: format!("Model with the name {name} not found"),
-                        status: None,
+                        name: destination,
+                        current_version: String::new(),
                     })
                     .unwrap_or_default(),
                 )
@@ -831,274 +1214,5842 @@ impl<P: Publisher> Handler<P> {
             }
             Err(e) => {
                 error!(error = %e, "Unable to fetch data");
-                self.send_error(msg.reply, "Internal storage error".to_string())
+                self.send_error(msg.reply, storage_error_code(&e), storage_error_message(&e))
                     .await;
                 return;
             }
         };
 
-        let current = manifests.get_current();
-
-        let status = Status {
-            version: current.version().to_owned(),
-            info: self
-                .get_manifest_status(lattice_id, name)
-                .await
-                .unwrap_or_default(),
-            components: vec![],
+        let mut manifest = match req.version.as_deref() {
+            Some(v) if v != LATEST_VERSION => match source.get_version(v) {
+                Some(m) => m.to_owned(),
+                None => {
+                    self.send_reply(
+                        msg.reply,
+                        // NOTE: We are constructing all data here, so this shouldn't fail, but just
+                        // in case we unwrap to nothing
+                        serde_json::to_vec(&CopyModelResponse {
+                            result: CopyResult::NotFound,
+                            message: format!("Model {name} with version {v} doesn't exist"),
+                            name: destination,
+                            current_version: String::new(),
+                        })
+                        .unwrap_or_default(),
+                    )
+                    .await;
+                    return;
+                }
+            },
+            _ => source.get_current().to_owned(),
         };
+        manifest.metadata.name = destination.clone();
 
-        self.send_reply(
-            msg.reply,
-            // NOTE: We are constructing all data here, so this shouldn't fail, but just in
-            // case we unwrap to nothing
-            serde_json::to_vec(&StatusResponse {
-                result: StatusResult::Ok,
-                message: format!("Successfully fetched status for model {}", name),
-                status: Some(status),
-            })
-            .unwrap_or_default(),
-        )
-        .await;
+        if let Err((code, message)) = self
+            .authorize(
+                account_id,
+                lattice_id,
+                ModelOperation::Copy,
+                &destination,
+                Some(&manifest.metadata),
+            )
+            .await
+        {
+            self.send_error(msg.reply, code, message).await;
+            return;
+        }
+
+        if let Some(error_message) = validate(&manifest, &self.config.manifest_limits)
+            .await
+            .into_result()
+            .err()
+        {
+            self.send_error(
+                msg.reply,
+                ErrorCode::ValidationFailed,
+                error_message.to_string(),
+            )
+            .await;
+            return;
+        }
+
+        let (result, current_revision) = match self
+            .store
+            .get(account_id, lattice_id, &destination)
+            .await
+        {
+            Ok(None) => {
+                match self.check_model_quota(account_id, lattice_id).await {
+                    Ok(None) => (),
+                    Ok(Some(message)) => {
+                        self.send_error(msg.reply, ErrorCode::QuotaExceeded, message)
+                            .await;
+                        return;
+                    }
+                    Err(e) => {
+                        error!(error = %e, "Unable to list models to check quota");
+                        self.send_error(
+                            msg.reply,
+                            storage_error_code(&e),
+                            storage_error_message(&e),
+                        )
+                        .await;
+                        return;
+                    }
+                }
+                match self
+                    .check_name_collision(account_id, lattice_id, &destination)
+                    .await
+                {
+                    Ok(None) => (CopyResult::Created, 0),
+                    Ok(Some(message)) => {
+                        self.send_error(msg.reply, ErrorCode::AlreadyExists, message)
+                            .await;
+                        return;
+                    }
+                    Err(e) => {
+                        error!(error = %e, "Unable to check other accounts for the model name");
+                        self.send_error(
+                            msg.reply,
+                            storage_error_code(&e),
+                            storage_error_message(&e),
+                        )
+                        .await;
+                        return;
+                    }
+                }
+            }
+            Ok(Some(_)) if !req.overwrite => {
+                self.send_error(
+                    msg.reply,
+                    ErrorCode::AlreadyExists,
+                    format!("Model {destination} already exists. Set overwrite to replace it"),
+                )
+                .await;
+                return;
+            }
+            Ok(Some((existing, _))) if existing.deployed_version().is_some() => {
+                // Replacing a deployed model would leave whatever is running orphaned, so make
+                // the user undeploy it explicitly first
+                self.send_error(
+                        msg.reply,
+                        ErrorCode::AlreadyExists,
+                        format!("Model {destination} is currently deployed and cannot be overwritten. Undeploy it first"),
+                    )
+                    .await;
+                return;
+            }
+            Ok(Some((_, revision))) => (CopyResult::Overwritten, revision),
+            Err(e) => {
+                error!(error = %e, "Unable to fetch data");
+                self.send_error(msg.reply, storage_error_code(&e), storage_error_message(&e))
+                    .await;
+                return;
+            }
+        };
+
+        let current_version = manifest.version().to_owned();
+        audit::record_version(&current_version);
+        let mut copied = StoredManifest::default();
+        copied.add_version(manifest);
+
+        trace!("Storing copied manifest");
+        if let Err(e) = self
+            .store
+            .set(account_id, lattice_id, copied, Some(current_revision))
+            .await
+        {
+            error!(error = %e, "Unable to store copied data");
+            self.send_error(msg.reply, storage_error_code(&e), storage_error_message(&e))
+                .await;
+            return;
+        }
+
+        self.send_reply(
+            msg.reply,
+            // NOTE: We are constructing all data here, so this shouldn't fail, but just in case we
+            // unwrap to nothing
+            serde_json::to_vec(&CopyModelResponse {
+                result,
+                message: format!(
+                    "Successfully copied model {name} {current_version} to {destination}"
+                ),
+                name: destination,
+                current_version,
+            })
+            .unwrap_or_default(),
+        )
+        .await
+    }
+
+    #[instrument(
+        level = "debug",
+        skip(self, msg, account_id, lattice_id),
+        fields(account_id = account_id.unwrap_or_default(), lattice_id = %lattice_id)
+    )]
+    pub async fn list_models(&self, msg: Message, account_id: Option<&str>, lattice_id: &str) {
+        let req: ListModelsRequest = if msg.payload.is_empty() {
+            ListModelsRequest::default()
+        } else {
+            match serde_json::from_reader(std::io::Cursor::new(msg.payload)) {
+                Ok(r) => r,
+                Err(e) => {
+                    self.send_error(
+                        msg.reply,
+                        ErrorCode::InvalidRequest,
+                        format!("Unable to parse list models request: {e:?}"),
+                    )
+                    .await;
+                    return;
+                }
+            }
+        };
+
+        let mut data = match self.store.list(account_id, lattice_id).await {
+            Ok(d) => d,
+            Err(e) => {
+                error!(error = %e, "Unable to fetch data");
+                self.send_error(msg.reply, storage_error_code(&e), storage_error_message(&e))
+                    .await;
+                return;
+            }
+        };
+        // Filter before fetching statuses so we don't look up statuses we'd throw away. Archived
+        // models are only returned when listing archived models
+        data.retain(|model| {
+            !model.archived && (!req.deployed_only || model.deployed_version.is_some())
+        });
+
+        let mut statuses = self
+            .fetch_statuses(
+                account_id,
+                lattice_id,
+                data.iter().map(|model| model.name.clone()),
+            )
+            .await;
+        for model in &mut data {
+            if let Some(status) = statuses.remove(&model.name).flatten() {
+                model.status = status.status_type;
+                model.status_message = Some(status.message);
+            } else {
+                warn!("Could not fetch status for model, assuming undeployed");
+                model.status = StatusType::Undeployed;
+                model.status_message = None;
+            }
+        }
+
+        // NOTE: We _just_ deserialized this from the store above and then manually constructed it,
+        // so we should be just fine. Just in case though, we unwrap to default
+        self.send_reply(msg.reply, serde_json::to_vec(&data).unwrap_or_default())
+            .await
+    }
+
+    #[instrument(
+        level = "debug",
+        skip(self, msg, account_id, lattice_id),
+        fields(account_id = account_id.unwrap_or_default(), lattice_id = %lattice_id)
+    )]
+    pub async fn model_stats(&self, msg: Message, account_id: Option<&str>, lattice_id: &str) {
+        let models = match self.store.list(account_id, lattice_id).await {
+            Ok(d) => d,
+            Err(e) => {
+                error!(error = %e, "Unable to fetch data");
+                self.send_error(msg.reply, storage_error_code(&e), storage_error_message(&e))
+                    .await;
+                return;
+            }
+        };
+
+        // Archived models are only kept to be restored, so they aren't part of the stats
+        let models: Vec<_> = models.into_iter().filter(|model| !model.archived).collect();
+
+        let mut stats = ModelStatsResponse {
+            result: GetResult::Success,
+            message: format!("Successfully fetched model stats for lattice {lattice_id}"),
+            total_models: models.len(),
+            deployed: 0,
+            undeployed: 0,
+            total_versions: 0,
+            total_size_bytes: 0,
+            status_counts: StatusCounts::default(),
+        };
+        let mut statuses = self
+            .fetch_statuses(
+                account_id,
+                lattice_id,
+                models.iter().map(|model| model.name.clone()),
+            )
+            .await;
+        for model in models {
+            if model.deployed_version.is_some() {
+                stats.deployed += 1;
+            } else {
+                stats.undeployed += 1;
+            }
+
+            stats.total_versions += model.version_count;
+            stats.total_size_bytes += model.size_bytes;
+
+            let status = statuses
+                .remove(&model.name)
+                .flatten()
+                .map(|status| status.status_type)
+                // Same as when listing, a missing status means the model is undeployed
+                .unwrap_or(StatusType::Undeployed);
+            stats.status_counts.add(status);
+        }
+
+        self.send_reply(
+            msg.reply,
+            // NOTE: We are constructing all data here, so this shouldn't fail, but just in
+            // case we unwrap to nothing
+            serde_json::to_vec(&stats).unwrap_or_default(),
+        )
+        .await
+    }
+
+    /// Replies with the versions of a model ordered by when they were created, oldest first unless
+    /// the request sorts them newest first. Each version includes its `created_at` timestamp, which
+    /// is only missing for versions stored by older versions of wadm
+    #[instrument(
+        level = "debug",
+        skip(self, msg, account_id, lattice_id),
+        fields(account_id = account_id.unwrap_or_default(), lattice_id = %lattice_id)
+    )]
+    pub async fn list_versions(
+        &self,
+        msg: Message,
+        account_id: Option<&str>,
+        lattice_id: &str,
+        name: &str,
+    ) {
+        let req: VersionsRequest = if msg.payload.is_empty() {
+            VersionsRequest::default()
+        } else {
+            match serde_json::from_reader(std::io::Cursor::new(msg.payload)) {
+                Ok(r) => r,
+                Err(e) => {
+                    self.send_error(
+                        msg.reply,
+                        ErrorCode::InvalidRequest,
+                        format!("Unable to parse versions request: {e:?}"),
+                    )
+                    .await;
+                    return;
+                }
+            }
+        };
+
+        let data: VersionResponse = match self.store.get(account_id, lattice_id, name).await {
+            Ok(Some((manifest, _))) => {
+                // Versions are stored in creation order
+                let mut versions: Vec<&String> = manifest.all_versions().into_iter().collect();
+                if req.sort == VersionSort::Desc {
+                    versions.reverse();
+                }
+                let total = versions.len();
+                VersionResponse {
+                    result: GetResult::Success,
+                    message: format!("Successfully fetched versions for model {name}"),
+                    versions: versions
+                        .into_iter()
+                        .skip(req.offset)
+                        .take(req.limit.unwrap_or(usize::MAX))
+                        .map(|v| VersionInfo {
+                            deployed: manifest.is_deployed(v),
+                            hash: manifest
+                                .get_version(v)
+                                .map(Manifest::content_hash)
+                                .unwrap_or_default(),
+                            created_at: manifest.version_created_at(v),
+                            size_bytes: manifest.version_size_bytes(v),
+                            version: v.to_owned(),
+                        })
+                        .collect(),
+                    total,
+                }
+            }
+            Ok(None) => VersionResponse {
+                result: GetResult::NotFound,
+                message: format!("Model with the name {name} not found"),
+                versions: Vec::with_capacity(0),
+                total: 0,
+            },
+            Err(e) => {
+                error!(error = %e, "Unable to fetch data");
+                self.send_error(msg.reply, storage_error_code(&e), storage_error_message(&e))
+                    .await;
+                return;
+            }
+        };
+        // NOTE: We _just_ deserialized this from the store above and then manually constructed it,
+        // so we should be just fine. Just in case though, we unwrap to default
+        self.send_reply(msg.reply, serde_json::to_vec(&data).unwrap_or_default())
+            .await
+    }
+
+    /// Replies with the latest manifest, versions, and status of a model so clients don't need a
+    /// separate request for each
+    #[instrument(
+        level = "debug",
+        skip(self, msg, account_id, lattice_id),
+        fields(account_id = account_id.unwrap_or_default(), lattice_id = %lattice_id)
+    )]
+    pub async fn describe_model(
+        &self,
+        msg: Message,
+        account_id: Option<&str>,
+        lattice_id: &str,
+        name: &str,
+    ) {
+        let manifests = match self.store.get(account_id, lattice_id, name).await {
+            Ok(Some((manifests, _))) => manifests,
+            Ok(None) => {
+                self.send_reply(
+                    msg.reply,
+                    // NOTE: We are constructing all data here, so this shouldn't fail, but just in
+                    // case we unwrap to nothing
+                    serde_json::to_vec(&DescribeModelResponse {
+                        result: GetResult::NotFound,
+                        message: format!("Model with the name {name} not found"),
+                        manifest: None,
+                        deployed_version: None,
+                        versions: Vec::with_capacity(0),
+                        size_bytes: 0,
+                        status: None,
+                    })
+                    .unwrap_or_default(),
+                )
+                .await;
+                return;
+            }
+            Err(e) => {
+                error!(error = %e, "Unable to fetch data");
+                self.send_error(msg.reply, storage_error_code(&e), storage_error_message(&e))
+                    .await;
+                return;
+            }
+        };
+
+        let status = match self.get_manifest_status(account_id, lattice_id, name).await {
+            Ok(status) => status.unwrap_or_default(),
+            Err(e) => {
+                error!(error = %e, "Unable to fetch status for model");
+                StatusInfo::undeployed(STATUS_UNAVAILABLE_MESSAGE)
+            }
+        };
+        // Same as a get, manifests are upgraded to the current schema on the way out
+        let (manifest, _) = migrate_manifest(manifests.get_current().to_owned());
+        let reply = DescribeModelResponse {
+            result: GetResult::Success,
+            message: format!("Successfully described model {name}"),
+            manifest: Some(manifest),
+            deployed_version: manifests.deployed_version().map(ToOwned::to_owned),
+            size_bytes: manifests.size_bytes(),
+            versions: manifests
+                .all_versions()
+                .into_iter()
+                .map(|v| VersionInfo {
+                    deployed: manifests.is_deployed(v),
+                    hash: manifests
+                        .get_version(v)
+                        .map(Manifest::content_hash)
+                        .unwrap_or_default(),
+                    created_at: manifests.version_created_at(v),
+                    size_bytes: manifests.version_size_bytes(v),
+                    version: v.to_owned(),
+                })
+                .collect(),
+            status: Some(status),
+        };
+        // NOTE: We _just_ deserialized this from the store above and then manually constructed it,
+        // so we should be just fine. Just in case though, we unwrap to default
+        self.send_reply(msg.reply, serde_json::to_vec(&reply).unwrap_or_default())
+            .await
+    }
+
+    #[instrument(
+        level = "debug",
+        skip(self, msg, account_id, lattice_id),
+        fields(account_id = account_id.unwrap_or_default(), lattice_id = %lattice_id)
+    )]
+    pub async fn list_archived(&self, msg: Message, account_id: Option<&str>, lattice_id: &str) {
+        let mut data = match self.store.list(account_id, lattice_id).await {
+            Ok(d) => d,
+            Err(e) => {
+                error!(error = %e, "Unable to fetch data");
+                self.send_error(msg.reply, storage_error_code(&e), storage_error_message(&e))
+                    .await;
+                return;
+            }
+        };
+        // Archived models are always undeployed, so there is no status to fetch
+        data.retain(|model| model.archived);
+
+        // NOTE: We _just_ deserialized this from the store above and then manually constructed it,
+        // so we should be just fine. Just in case though, we unwrap to default
+        self.send_reply(msg.reply, serde_json::to_vec(&data).unwrap_or_default())
+            .await
+    }
+
+    #[instrument(
+        level = "debug",
+        skip(self, msg, account_id, lattice_id),
+        fields(account_id = account_id.unwrap_or_default(), lattice_id = %lattice_id)
+    )]
+    pub async fn restore_model(
+        &self,
+        msg: Message,
+        account_id: Option<&str>,
+        lattice_id: &str,
+        name: &str,
+    ) {
+        if let Err((code, message)) = self
+            .authorize(account_id, lattice_id, ModelOperation::Restore, name, None)
+            .await
+        {
+            self.send_error(msg.reply, code, message).await;
+            return;
+        }
+
+        let mut attempt = 0;
+        let reply = loop {
+            attempt += 1;
+            let (mut manifests, current_revision) =
+                match self.store.get(account_id, lattice_id, name).await {
+                    Ok(Some(m)) => m,
+                    Ok(None) => {
+                        break RestoreModelResponse {
+                            result: RestoreResult::NotFound,
+                            message: format!("Model with the name {name} not found"),
+                            error_code: Some(ErrorCode::NotFound),
+                            retry_after_ms: None,
+                        }
+                    }
+                    Err(e) => {
+                        error!(error = %e, "Unable to fetch data");
+                        break RestoreModelResponse {
+                            result: RestoreResult::Error,
+                            message: storage_error_message(&e),
+                            error_code: Some(storage_error_code(&e)),
+                            retry_after_ms: storage_error_code(&e).retry_after_ms(),
+                        };
+                    }
+                };
+            if !manifests.restore() {
+                break RestoreModelResponse {
+                    result: RestoreResult::Noop,
+                    message: format!("Model {name} is not archived"),
+                    error_code: None,
+                    retry_after_ms: None,
+                };
+            }
+            // Archived models don't count against the quota, so restoring one is like creating it
+            match self.check_model_quota(account_id, lattice_id).await {
+                Ok(None) => (),
+                Ok(Some(message)) => {
+                    break RestoreModelResponse {
+                        result: RestoreResult::Error,
+                        message,
+                        error_code: Some(ErrorCode::QuotaExceeded),
+                        retry_after_ms: None,
+                    }
+                }
+                Err(e) => {
+                    error!(error = %e, "Unable to list models to check quota");
+                    break RestoreModelResponse {
+                        result: RestoreResult::Error,
+                        message: storage_error_message(&e),
+                        error_code: Some(storage_error_code(&e)),
+                        retry_after_ms: storage_error_code(&e).retry_after_ms(),
+                    };
+                }
+            }
+
+            match self
+                .store
+                .set(account_id, lattice_id, manifests, Some(current_revision))
+                .await
+            {
+                Ok(_) => {
+                    break RestoreModelResponse {
+                        result: RestoreResult::Restored,
+                        message: format!("Successfully restored model {name}"),
+                        error_code: None,
+                        retry_after_ms: None,
+                    }
+                }
+                Err(e)
+                    if RevisionConflict::is_conflict(&e)
+                        && attempt < MAX_REVISION_CONFLICT_ATTEMPTS =>
+                {
+                    debug!(error = %e, %attempt, "Model was updated concurrently, retrying");
+                    continue;
+                }
+                Err(e) => {
+                    error!(error = %e, "Unable to store updated data");
+                    break RestoreModelResponse {
+                        result: RestoreResult::Error,
+                        message: storage_error_message(&e),
+                        error_code: Some(storage_error_code(&e)),
+                        retry_after_ms: storage_error_code(&e).retry_after_ms(),
+                    };
+                }
+            }
+        };
+
+        trace!(resp = ?reply, "Sending response");
+        self.send_reply(
+            msg.reply,
+            // NOTE: We are constructing all data here, so this shouldn't fail, but just in
+            // case we unwrap to nothing
+            serde_json::to_vec(&reply).unwrap_or_default(),
+        )
+        .await;
+    }
+
+    #[instrument(
+        level = "debug",
+        skip(self, msg, account_id, lattice_id),
+        fields(account_id = account_id.unwrap_or_default(), lattice_id = %lattice_id)
+    )]
+    pub async fn delete_model(
+        &self,
+        msg: Message,
+        account_id: Option<&str>,
+        lattice_id: &str,
+        name: &str,
+    ) {
+        let req: DeleteModelRequest =
+            match serde_json::from_reader(std::io::Cursor::new(msg.payload)) {
+                Ok(r) => r,
+                Err(e) => {
+                    self.send_error(
+                        msg.reply,
+                        ErrorCode::InvalidRequest,
+                        format!("Unable to parse delete model request: {e:?}"),
+                    )
+                    .await;
+                    return;
+                }
+            };
+
+        if let Some(version) = req.version.as_deref() {
+            audit::record_version(version);
+        }
+
+        if let Err((code, message)) = self
+            .authorize(account_id, lattice_id, ModelOperation::Delete, name, None)
+            .await
+        {
+            self.send_error(msg.reply, code, message).await;
+            return;
+        }
+        if req.archive && req.version.is_some() {
+            self.send_error(
+                msg.reply,
+                ErrorCode::InvalidRequest,
+                format!(
+                    "Only whole models can be archived. Remove the version to archive model {name}"
+                ),
+            )
+            .await;
+            return;
+        }
+
+        let dependents = if req.check_dependents || req.strict {
+            match self
+                .find_deletion_dependents(account_id, lattice_id, name, req.version.as_deref())
+                .await
+            {
+                Ok(d) => d,
+                Err((code, message)) => {
+                    self.send_error(msg.reply, code, message).await;
+                    return;
+                }
+            }
+        } else {
+            Vec::new()
+        };
+        if !dependents.is_empty() && req.strict {
+            self.send_error(
+                msg.reply,
+                ErrorCode::HasDependents,
+                format!(
+                    "Model {name} can't be deleted because deployed model(s) link to components of its deployed version: {}. Undeploy them first or retry without strict",
+                    dependents.join(", ")
+                ),
+            )
+            .await;
+            return;
+        }
+
+        // Deletes can undeploy the model, so they hold the deploy lock like a deploy or undeploy
+        let reply_data = match self
+            .with_deploy_locks(
+                account_id,
+                lattice_id,
+                &[name],
+                self.delete_locked_model(account_id, lattice_id, name, req, dependents),
+            )
+            .await
+        {
+            Ok(reply_data) => reply_data,
+            Err((code, message)) => {
+                self.send_error(msg.reply, code, message).await;
+                return;
+            }
+        };
+
+        // NOTE: We control all the data getting sent in here, but we unwrap to default just in case
+        self.send_reply(
+            msg.reply,
+            serde_json::to_vec(&reply_data).unwrap_or_default(),
+        )
+        .await
+    }
+
+    /// Deletes or archives a model (or one of its versions) while holding its deploy lock and
+    /// notifies processors if it was undeployed, returning the response to send. See
+    /// [`Handler::delete_model`]
+    async fn delete_locked_model(
+        &self,
+        account_id: Option<&str>,
+        lattice_id: &str,
+        name: &str,
+        req: DeleteModelRequest,
+        dependents: Vec<String>,
+    ) -> DeleteModelResponse {
+        let mut reply_data = if req.archive {
+            self.archive_model(account_id, lattice_id, name).await
+        } else if let Some(version) = req.version {
+            self.delete_model_version(account_id, lattice_id, name, &version)
+                .await
+        } else {
+            match self.store.delete(account_id, lattice_id, name).await {
+                Ok(_) => {
+                    DeleteModelResponse {
+                        result: DeleteResult::Deleted,
+                        message: format!("Successfully deleted model {}", name),
+                        // By default if it is all gone, we definitely undeployed things
+                        undeploy: true,
+                        error_code: None,
+                        retry_after_ms: None,
+                    }
+                }
+                Err(e) => {
+                    error!(error = %e, "Unable to delete data");
+                    DeleteModelResponse {
+                        result: DeleteResult::Error,
+                        message: storage_error_message(&e),
+                        undeploy: false,
+                        error_code: Some(storage_error_code(&e)),
+                        retry_after_ms: storage_error_code(&e).retry_after_ms(),
+                    }
+                }
+            }
+        };
+
+        if !dependents.is_empty() && matches!(reply_data.result, DeleteResult::Deleted) {
+            warn!(
+                "Deleted model {name}, which deployed model(s) link to: {}",
+                dependents.join(", ")
+            );
+            reply_data.message = format!(
+                "{}. Warning: deployed model(s) link to components of this model and may stop working: {}",
+                reply_data.message,
+                dependents.join(", ")
+            );
+        }
+
+        // On a noop, we should still send an undeploy in case of notification failure
+        // TODO(thomastaylor312): We might want to come back and revisit how we handle a failure
+        // like this in the delete case. If the data gets deleted, but we can't send it, we get into
+        // an odd state. So I'd rather err on the side of caution and send a notification that gets
+        // ignored
+        if reply_data.undeploy || matches!(reply_data.result, DeleteResult::Noop) {
+            trace!("Sending undeploy notification");
+            if let Err(e) = self.notifier.undeployed(lattice_id, name).await {
+                error!(error = ?e, "Error when attempting to send undeploy notification during delete");
+                return DeleteModelResponse {
+                    result: DeleteResult::Error,
+                    message: "Error notifying processors of newly undeployed manifest on delete. This is likely a transient error, so please retry the request. Please note that the response will say it is a noop, but will notify the processors".to_string(),
+                    undeploy: false,
+                    error_code: Some(notification_error_code(&e)),
+                    retry_after_ms: notification_error_code(&e).retry_after_ms(),
+                };
+            }
+        }
+        reply_data
+    }
+
+    #[instrument(
+        level = "debug",
+        skip(self, msg, account_id, lattice_id),
+        fields(account_id = account_id.unwrap_or_default(), lattice_id = %lattice_id)
+    )]
+    pub async fn deploy_model(
+        &self,
+        msg: Message,
+        account_id: Option<&str>,
+        lattice_id: &str,
+        name: &str,
+    ) {
+        if self
+            .replay_idempotent_response(&msg, &format!("deploy.{name}"), account_id, lattice_id)
+            .await
+        {
+            return;
+        }
+
+        let req: DeployModelRequest = if msg.payload.is_empty() {
+            DeployModelRequest::default()
+        } else {
+            match serde_json::from_reader(std::io::Cursor::new(msg.payload)) {
+                Ok(r) => r,
+                Err(e) => {
+                    self.send_error(
+                        msg.reply,
+                        ErrorCode::InvalidRequest,
+                        format!("Unable to parse deploy model request: {e:?}"),
+                    )
+                    .await;
+                    return;
+                }
+            }
+        };
+        trace!(?req, "Got request");
+
+        self.deploy(msg.reply, req, account_id, lattice_id, name)
+            .await;
+    }
+
+    /// Deploys a version of a model for a deploy or set deployed request, sending the reply
+    /// (once the model has reconciled if the request asks to wait). Stored manifests are not
+    /// validated again, but the request's pinned dependencies, disabled components and manifest
+    /// hash are checked along with conflicts against other deployed models
+    async fn deploy(
+        &self,
+        reply: Option<Subject>,
+        req: DeployModelRequest,
+        account_id: Option<&str>,
+        lattice_id: &str,
+        name: &str,
+    ) {
+        if let Err((code, message)) = self
+            .authorize(account_id, lattice_id, ModelOperation::Deploy, name, None)
+            .await
+        {
+            self.send_error(reply, code, message).await;
+            return;
+        }
+
+        if let Err((code, message)) = self
+            .with_deploy_locks(
+                account_id,
+                lattice_id,
+                &[name],
+                self.deploy_locked_model(reply.clone(), req, account_id, lattice_id, name),
+            )
+            .await
+        {
+            self.send_error(reply, code, message).await;
+        }
+    }
+
+    /// Deploys a model while holding its deploy lock. See [`Handler::deploy`]
+    async fn deploy_locked_model(
+        &self,
+        reply_to: Option<Subject>,
+        mut req: DeployModelRequest,
+        account_id: Option<&str>,
+        lattice_id: &str,
+        name: &str,
+    ) {
+        req.disabled_components.sort();
+        req.disabled_components.dedup();
+
+        if let Err((code, message)) = self
+            .check_pinned_dependencies(account_id, lattice_id, &req.dependencies)
+            .await
+        {
+            self.send_error(reply_to, code, message).await;
+            return;
+        }
+
+        let mut attempt = 0;
+        let (mut reply, manifest) = loop {
+            attempt += 1;
+            trace!("Fetching current data from store");
+            let (mut manifests, current_revision) = match self
+                .store
+                .get(account_id, lattice_id, name)
+                .await
+            {
+                Ok(Some(m)) => m,
+                Ok(None) => {
+                    self.send_reply(
+                        reply_to,
+                        // NOTE: We are constructing all data here, so this shouldn't fail, but just in
+                        // case we unwrap to nothing
+                        serde_json::to_vec(&DeployModelResponse {
+                            result: DeployResult::NotFound,
+                            message: format!("Model with the name {name} not found"),
+                            status: None,
+                            error_code: Some(ErrorCode::NotFound),
+                            retry_after_ms: None,
+                            disabled_components: Vec::new(),
+                            revision: 0,
+                        })
+                        .unwrap_or_default(),
+                    )
+                    .await;
+                    return;
+                }
+                Err(e) => {
+                    error!(error = %e, "Unable to fetch data");
+                    self.send_error(reply_to, storage_error_code(&e), storage_error_message(&e))
+                        .await;
+                    return;
+                }
+            };
+            if manifests.is_archived() {
+                self.send_error(
+                    reply_to,
+                    ErrorCode::InvalidRequest,
+                    format!("Model {name} is archived. Restore it before deploying"),
+                )
+                .await;
+                return;
+            }
+
+            let staged_model = match req.version.clone() {
+                Some(v) if v == LATEST_VERSION => manifests.get_current(),
+                Some(v) => {
+                    if let Some(model) = manifests.get_version(&v) {
+                        model
+                    } else {
+                        trace!("Requested version does not exist");
+                        self.send_reply(
+                            reply_to,
+                            // NOTE: We are constructing all data here, so this shouldn't fail, but just in
+                            // case we unwrap to nothing
+                            serde_json::to_vec(&DeployModelResponse {
+                                result: DeployResult::Error,
+                                message: format!(
+                            "Model with the name {name} does not have the specified version to deploy"
+                        ),
+                                status: None,
+                                error_code: Some(ErrorCode::NotFound),
+                                retry_after_ms: None,
+                                disabled_components: Vec::new(),
+                                revision: 0,
+                            })
+                            .unwrap_or_default(),
+                        )
+                        .await;
+                        return;
+                    }
+                }
+                // Deploy the newest version with the given hash, if there is one
+                None if req.manifest_hash.is_some() => {
+                    let hash = req.manifest_hash.as_deref().unwrap_or_default();
+                    let matching = manifests
+                        .all_versions()
+                        .into_iter()
+                        .filter_map(|v| manifests.get_version(v))
+                        .filter(|m| m.content_hash().eq_ignore_ascii_case(hash))
+                        .last();
+                    if let Some(model) = matching {
+                        model
+                    } else {
+                        self.send_error(
+                            reply_to,
+                            ErrorCode::NotFound,
+                            format!(
+                                "Model {name} does not have a version with manifest hash {hash}"
+                            ),
+                        )
+                        .await;
+                        return;
+                    }
+                }
+                // Get the current version if payload version is None, since deploy() does the same
+                None => manifests.get_current(),
+            };
+
+            if let Some(hash) = req.manifest_hash.as_deref() {
+                let actual = staged_model.content_hash();
+                if !actual.eq_ignore_ascii_case(hash) {
+                    self.send_error(
+                        reply_to,
+                        ErrorCode::VersionConflict,
+                        format!(
+                            "Model {name} {} has manifest hash {actual}, which doesn't match the requested hash {hash}",
+                            staged_model.version()
+                        ),
+                    )
+                    .await;
+                    return;
+                }
+            }
+
+            if let Err(message) = check_disabled_components(staged_model, &req.disabled_components)
+            {
+                self.send_error(reply_to, ErrorCode::InvalidRequest, message)
+                    .await;
+                return;
+            }
+
+            audit::record_version(staged_model.version());
+
+            // Deploying the version that is already deployed (with the same components disabled)
+            // doesn't change anything, so we only notify the processors again if the request was
+            // forced
+            if manifests.deployed_version() == Some(staged_model.version())
+                && manifests.disabled_components() == req.disabled_components.as_slice()
+            {
+                let manifest = staged_model.to_owned();
+                let reply = DeployModelResponse {
+                    result: DeployResult::Acknowledged,
+                    message: format!(
+                        "Model {name} {} is already deployed{}",
+                        manifest.version(),
+                        if req.force { ", redeploying" } else { "" }
+                    ),
+                    status: None,
+                    error_code: None,
+                    retry_after_ms: None,
+                    disabled_components: Vec::new(),
+                    revision: current_revision,
+                };
+                if req.force {
+                    break (reply, manifest);
+                }
+                trace!(resp = ?reply, "Sending response");
+                self.send_reply(
+                    reply_to,
+                    // NOTE: We are constructing all data here, so this shouldn't fail, but just in
+                    // case we unwrap to nothing
+                    serde_json::to_vec(&reply).unwrap_or_default(),
+                )
+                .await;
+                return;
+            }
+
+            if let Err(message) = check_minimum_wadm_version(staged_model) {
+                self.send_error(reply_to, ErrorCode::Unsupported, message)
+                    .await;
+                return;
+            }
+            if let Err((code, message)) = self
+                .check_deploy_conflicts(account_id, lattice_id, name, staged_model)
+                .await
+            {
+                self.send_error(reply_to, code, message).await;
+                return;
+            }
+
+            // Deploy the version resolved above, as it may have been chosen by its hash
+            let staged_version = staged_model.version().to_owned();
+            if !manifests.deploy(Some(staged_version)) {
+                trace!("Requested version does not exist");
+                self.send_reply(
+                    reply_to,
+                    // NOTE: We are constructing all data here, so this shouldn't fail, but just in
+                    // case we unwrap to nothing
+                    serde_json::to_vec(&DeployModelResponse {
+                        result: DeployResult::Error,
+                        message: format!(
+                            "Model with the name {name} does not have the specified version to deploy"
+                        ),
+                        status: None,
+                        error_code: Some(ErrorCode::NotFound),
+                        retry_after_ms: None,
+                        disabled_components: Vec::new(),
+                        revision: 0,
+                    })
+                    .unwrap_or_default(),
+                )
+                .await;
+                return;
+            }
+            manifests.set_disabled_components(req.disabled_components.clone());
+            // SAFETY: We can unwrap here because we know we _just_ successfully deployed the manifest so they should all exist
+            let manifest = manifests
+                .get_version(manifests.deployed_version().unwrap())
+                .unwrap()
+                .to_owned();
+
+            match self
+                .store
+                .set(account_id, lattice_id, manifests, Some(current_revision))
+                .await
+            {
+                Ok(revision) => {
+                    break (
+                        DeployModelResponse {
+                            result: DeployResult::Acknowledged,
+                            message: format!(
+                                "Successfully deployed model {} {}",
+                                name,
+                                manifest.version()
+                            ),
+                            status: None,
+                            error_code: None,
+                            retry_after_ms: None,
+                            disabled_components: Vec::new(),
+                            revision,
+                        },
+                        manifest,
+                    )
+                }
+                Err(e)
+                    if RevisionConflict::is_conflict(&e)
+                        && attempt < MAX_REVISION_CONFLICT_ATTEMPTS =>
+                {
+                    debug!(error = %e, %attempt, "Model was updated concurrently, retrying");
+                    continue;
+                }
+                Err(e) => {
+                    error!(error = %e, "Unable to store updated data");
+                    break (
+                        DeployModelResponse {
+                            result: DeployResult::Error,
+                            message: storage_error_message(&e),
+                            status: None,
+                            error_code: Some(storage_error_code(&e)),
+                            retry_after_ms: storage_error_code(&e).retry_after_ms(),
+                            disabled_components: Vec::new(),
+                            revision: 0,
+                        },
+                        manifest,
+                    );
+                }
+            }
+        };
+        if matches!(reply.result, DeployResult::Acknowledged) {
+            reply
+                .disabled_components
+                .clone_from(&req.disabled_components);
+        }
+        let manifest = without_components(&manifest, &req.disabled_components);
+        // Grab the sequence of the current status before notifying so that if we wait, we don't
+        // mistake a status from a previous deploy for this one
+        let last_status_sequence = if req.wait_timeout_secs.is_some() {
+            get_status_message(&self.status_stream, account_id, lattice_id, name)
+                .await
+                .ok()
+                .flatten()
+                .map(|(sequence, _)| sequence)
+        } else {
+            None
+        };
+        trace!("Sending deployed notification");
+        if let Err(e) = self.notifier.deployed(lattice_id, manifest).await {
+            error!(error = ?e, "Error when attempting to send deployed notification");
+            self.send_reply(
+                reply_to,
+                // NOTE: We are constructing all data here, so this shouldn't fail, but just in
+                // case we unwrap to nothing
+                serde_json::to_vec(&DeployModelResponse {
+                    result: DeployResult::Error,
+                    message: "Error notifying processors of newly deployed manifest. This is likely a transient error, so please retry the request".to_string(),
+                    status: None,
+                    error_code: Some(notification_error_code(&e)),
+                    retry_after_ms: notification_error_code(&e).retry_after_ms(),
+                    disabled_components: Vec::new(),
+                    revision: 0,
+                })
+                .unwrap_or_default(),
+            )
+            .await;
+            return;
+        }
+
+        match (req.wait_timeout_secs, reply_to) {
+            (Some(secs), Some(reply_topic))
+                if matches!(reply.result, DeployResult::Acknowledged) =>
+            {
+                // Waiting happens in a separate task so we don't block the API from handling other
+                // requests while this deploy reconciles
+                let timeout = Duration::from_secs(secs).min(MAX_DEPLOY_WAIT);
+                let replies = self.reply_sender();
+                let accepts_gzip = ACCEPTS_GZIP.try_with(|accepts| *accepts).unwrap_or(false);
+                // The task runs outside of this request's scope, so it finishes the audit record
+                let audit = audit::take_context();
+                let status_stream = self.status_stream.clone();
+                let account_id = account_id.map(ToOwned::to_owned);
+                let lattice_id = lattice_id.to_owned();
+                let name = name.to_owned();
+                trace!(?timeout, "Waiting for model to reconcile before responding");
+                tokio::spawn(
+                    async move {
+                        let reply = match wait_for_reconciled_status(
+                            &status_stream,
+                            account_id.as_deref(),
+                            &lattice_id,
+                            &name,
+                            last_status_sequence,
+                            timeout,
+                        )
+                        .await
+                        {
+                            Some(status) => DeployModelResponse {
+                                message: format!(
+                                    "{}. Model finished reconciling with status {:?}",
+                                    reply.message, status.status_type
+                                ),
+                                status: Some(status),
+                                ..reply
+                            },
+                            None => DeployModelResponse {
+                                message: format!(
+                                    "{}. Reconciliation is still pending after {}s",
+                                    reply.message,
+                                    timeout.as_secs()
+                                ),
+                                ..reply
+                            },
+                        };
+                        trace!(resp = ?reply, "Sending response");
+                        // NOTE: We are constructing all data here, so this shouldn't fail, but
+                        // just in case we unwrap to nothing
+                        let data = serde_json::to_vec(&reply).unwrap_or_default();
+                        replies.send(reply_topic, data, accepts_gzip, audit).await;
+                    }
+                    .in_current_span(),
+                );
+            }
+            (_, reply_topic) => {
+                trace!(resp = ?reply, "Sending response");
+                self.send_reply(
+                    reply_topic,
+                    // NOTE: We are constructing all data here, so this shouldn't fail, but just in
+                    // case we unwrap to nothing
+                    serde_json::to_vec(&reply).unwrap_or_default(),
+                )
+                .await;
+            }
+        }
+    }
+
+    /// Reports every conflict that deploying a version of the model would run into with the models
+    /// already deployed in the lattice. This runs the same checks as a deploy, but nothing is
+    /// changed and no one is notified
+    #[instrument(
+        level = "debug",
+        skip(self, msg, account_id, lattice_id),
+        fields(account_id = account_id.unwrap_or_default(), lattice_id = %lattice_id)
+    )]
+    pub async fn deploy_preflight(
+        &self,
+        msg: Message,
+        account_id: Option<&str>,
+        lattice_id: &str,
+        name: &str,
+    ) {
+        let req: DeployPreflightRequest = if msg.payload.is_empty() {
+            DeployPreflightRequest::default()
+        } else {
+            match serde_json::from_slice(&msg.payload) {
+                Ok(r) => r,
+                Err(e) => {
+                    self.send_error(
+                        msg.reply,
+                        ErrorCode::InvalidRequest,
+                        format!("Unable to parse deploy preflight request: {e:?}"),
+                    )
+                    .await;
+                    return;
+                }
+            }
+        };
+        trace!(?req, "Got request");
+
+        let manifests = match self.store.get(account_id, lattice_id, name).await {
+            Ok(Some((manifests, _))) => manifests,
+            Ok(None) => {
+                self.send_error(
+                    msg.reply,
+                    ErrorCode::NotFound,
+                    format!("Model with the name {name} not found"),
+                )
+                .await;
+                return;
+            }
+            Err(e) => {
+                error!(error = %e, "Unable to fetch data");
+                self.send_error(msg.reply, storage_error_code(&e), storage_error_message(&e))
+                    .await;
+                return;
+            }
+        };
+        let staged_model = match req.version.as_deref() {
+            None | Some(LATEST_VERSION) => manifests.get_current(),
+            Some(version) => match manifests.get_version(version) {
+                Some(model) => model,
+                None => {
+                    self.send_error(
+                        msg.reply,
+                        ErrorCode::NotFound,
+                        format!("Model with the name {name} does not have the version {version}"),
+                    )
+                    .await;
+                    return;
+                }
+            },
+        };
+
+        let conflicts = match self
+            .deployed_claims(account_id, lattice_id, &HashSet::from([name]))
+            .await
+        {
+            Ok(claims) => claims.conflicts(staged_model),
+            Err((code, message)) => {
+                self.send_error(msg.reply, code, message).await;
+                return;
+            }
+        };
+        let (result, message) = if conflicts.is_empty() {
+            (
+                PreflightResult::Clear,
+                format!(
+                    "Version {} of model {name} can be deployed without conflicts",
+                    staged_model.version()
+                ),
+            )
+        } else {
+            (
+                PreflightResult::Conflicts,
+                format!(
+                    "Version {} of model {name} conflicts with deployed models in {} place(s)",
+                    staged_model.version(),
+                    conflicts.len()
+                ),
+            )
+        };
+        self.send_reply(
+            msg.reply,
+            // NOTE: We are constructing all data here, so this shouldn't fail, but just in
+            // case we unwrap to nothing
+            serde_json::to_vec(&DeployPreflightResponse {
+                result,
+                message,
+                conflicts,
+            })
+            .unwrap_or_default(),
+        )
+        .await
+    }
+
+    #[instrument(
+        level = "debug",
+        skip(self, msg, account_id, lattice_id),
+        fields(account_id = account_id.unwrap_or_default(), lattice_id = %lattice_id)
+    )]
+    pub async fn deploy_models(&self, msg: Message, account_id: Option<&str>, lattice_id: &str) {
+        if self
+            .replay_idempotent_response(&msg, "deploy_many", account_id, lattice_id)
+            .await
+        {
+            return;
+        }
+
+        let req: DeployModelsRequest =
+            match serde_json::from_reader(std::io::Cursor::new(msg.payload)) {
+                Ok(r) => r,
+                Err(e) => {
+                    self.send_error(
+                        msg.reply,
+                        ErrorCode::InvalidRequest,
+                        format!("Unable to parse deploy models request: {e:?}"),
+                    )
+                    .await;
+                    return;
+                }
+            };
+        if req.models.is_empty() || req.models.len() > MAX_DEPLOY_MODELS {
+            self.send_error(
+                msg.reply,
+                ErrorCode::InvalidRequest,
+                format!(
+                    "Deploy models requests must contain between 1 and {MAX_DEPLOY_MODELS} models, got {}",
+                    req.models.len()
+                ),
+            )
+            .await;
+            return;
+        }
+
+        let reply = msg.reply.clone();
+        let names: Vec<String> = req
+            .models
+            .iter()
+            .map(|m| m.name.trim().to_owned())
+            .collect();
+        if let Err((code, message)) = self
+            .with_deploy_locks(
+                account_id,
+                lattice_id,
+                &names,
+                self.deploy_locked_models(msg.reply, req.models, account_id, lattice_id),
+            )
+            .await
+        {
+            self.send_error(reply, code, message).await;
+        }
+    }
+
+    /// Deploys the models of a multi-model deploy request while holding all of their deploy locks.
+    /// See [`Handler::deploy_models`]
+    async fn deploy_locked_models(
+        &self,
+        reply: Option<Subject>,
+        models: Vec<ModelDeployTarget>,
+        account_id: Option<&str>,
+        lattice_id: &str,
+    ) {
+        // Resolve every model up front so that we don't deploy anything if any of them can't be
+        let mut results = Vec::with_capacity(models.len());
+        let mut resolved = Vec::with_capacity(models.len());
+        let mut seen = HashSet::new();
+        for mut target in models {
+            let name = target.name.trim().to_owned();
+            target.disabled_components.sort();
+            target.disabled_components.dedup();
+            let outcome = if seen.insert(name.clone()) {
+                self.resolve_deploy_target(account_id, lattice_id, &name, &target)
+                    .await
+            } else {
+                Err((
+                    ErrorCode::InvalidRequest,
+                    "Model is given more than once in the request".to_string(),
+                ))
+            };
+            match outcome {
+                Ok((manifests, revision, manifest)) => {
+                    results.push(ModelDeployResult {
+                        name: name.clone(),
+                        version: manifest.version().to_owned(),
+                        result: DeployResult::Acknowledged,
+                        message: String::new(),
+                        error_code: None,
+                        retry_after_ms: None,
+                    });
+                    resolved.push((manifests, revision, manifest, target.disabled_components));
+                }
+                Err((code, message)) => results.push(ModelDeployResult {
+                    name,
+                    version: target.version.unwrap_or_default(),
+                    result: if code == ErrorCode::NotFound {
+                        DeployResult::NotFound
+                    } else {
+                        DeployResult::Error
+                    },
+                    message,
+                    error_code: Some(code),
+                    retry_after_ms: code.retry_after_ms(),
+                }),
+            }
+        }
+        let invalid = results.iter().filter(|r| r.error_code.is_some()).count();
+        if invalid > 0 {
+            let message = format!(
+                "{invalid} of {} models can't be deployed, no models were deployed",
+                results.len()
+            );
+            self.send_deploy_models_reply(reply, message, results).await;
+            return;
+        }
+
+        // Check for conflicts once across the batch and everything already deployed. Each model
+        // is checked against the ones before it in the batch as well, so the batch can't conflict
+        // with itself
+        let names: Vec<String> = results.iter().map(|r| r.name.clone()).collect();
+        let excluded: HashSet<&str> = names.iter().map(String::as_str).collect();
+        let mut claims = match self
+            .deployed_claims(account_id, lattice_id, &excluded)
+            .await
+        {
+            Ok(claims) => claims,
+            Err((code, message)) => {
+                self.send_error(reply, code, message).await;
+                return;
+            }
+        };
+        for ((_, _, manifest, _), result) in resolved.iter().zip(results.iter_mut()) {
+            if let Err((code, message)) = claims.check(manifest) {
+                result.result = DeployResult::Error;
+                result.message = message;
+                result.error_code = Some(code);
+                result.retry_after_ms = code.retry_after_ms();
+            }
+            claims.add(&result.name, manifest);
+        }
+        let conflicts = results.iter().filter(|r| r.error_code.is_some()).count();
+        if conflicts > 0 {
+            let message = format!(
+                "{conflicts} of {} models conflict with deployed models or each other, no models were deployed",
+                results.len()
+            );
+            self.send_deploy_models_reply(reply, message, results).await;
+            return;
+        }
+
+        let mut stored: Vec<(&str, Option<StoredManifest>, u64)> =
+            Vec::with_capacity(resolved.len());
+        let mut deployed = Vec::with_capacity(resolved.len());
+        for (idx, (manifests, revision, manifest, disabled)) in resolved.into_iter().enumerate() {
+            let name = results[idx].name.clone();
+            let version = manifest.version().to_owned();
+            audit::record_version(&version);
+            if manifests.deployed_version() == Some(version.as_str())
+                && manifests.disabled_components() == disabled.as_slice()
+            {
+                results[idx].message = format!("Model {name} {version} is already deployed");
+                continue;
+            }
+            trace!(%name, %version, "Deploying model");
+            let (previous, stored_revision) = match self
+                .store_batch_deploy(
+                    account_id, lattice_id, &name, &version, &disabled, manifests, revision,
+                )
+                .await
+            {
+                Ok(stored) => stored,
+                Err((code, message)) => {
+                    error!(%name, %message, "Unable to store updated data, rolling back deployed models");
+                    let not_reverted = self
+                        .revert_stored_models(account_id, lattice_id, stored)
+                        .await;
+                    for (i, result) in results.iter_mut().enumerate() {
+                        result.result = DeployResult::Error;
+                        if i == idx {
+                            result.message = message.clone();
+                            result.error_code = Some(code);
+                            result.retry_after_ms = code.retry_after_ms();
+                        } else if not_reverted.contains(&names[i].as_str()) {
+                            result.message = format!(
+                                "Stored as deployed, but could not be rolled back after model {name} could not be deployed. Deploy or undeploy it again to resolve"
+                            );
+                        } else {
+                            result.message =
+                                format!("Not deployed because model {name} could not be deployed");
+                        }
+                    }
+                    let message = if not_reverted.is_empty() {
+                        format!("Unable to deploy model {name}, no models were deployed")
+                    } else {
+                        format!("Unable to deploy model {name} and some previously deployed models could not be rolled back")
+                    };
+                    self.send_deploy_models_reply(reply, message, results).await;
+                    return;
+                }
+            };
+            results[idx].message = format!("Successfully deployed model {name} {version}");
+            stored.push((names[idx].as_str(), Some(previous), stored_revision));
+            deployed.push((idx, without_components(&manifest, &disabled)));
+        }
+
+        // Every model is stored as deployed at this point, so notification failures are reported
+        // per model rather than rolled back
+        let mut records = Vec::with_capacity(deployed.len());
+        for (idx, manifest) in deployed {
+            trace!(name = %results[idx].name, "Sending deployed notification");
+            if let Err(e) = self.notifier.deployed(lattice_id, manifest).await {
+                error!(error = ?e, "Error when attempting to send deployed notification");
+                results[idx].result = DeployResult::Error;
+                results[idx].message = "Error notifying processors of newly deployed manifest. This is likely a transient error, so please retry by sending a deploy request for this model".to_string();
+                results[idx].error_code = Some(notification_error_code(&e));
+                results[idx].retry_after_ms = notification_error_code(&e).retry_after_ms();
+            } else {
+                records.push(audit::model_record(
+                    account_id,
+                    lattice_id,
+                    ModelOperation::Deploy,
+                    &results[idx].name,
+                    Some(&results[idx].version),
+                    "acknowledged",
+                ));
+            }
+        }
+        let failed = results.iter().filter(|r| r.error_code.is_some()).count();
+        let message = if failed == 0 {
+            format!(
+                "Successfully deployed {} models in lattice {lattice_id}",
+                results.len()
+            )
+        } else {
+            format!(
+                "Deployed {} models, but processors could not be notified about {failed} of them",
+                results.len()
+            )
+        };
+        self.send_deploy_models_reply(reply, message, results).await;
+        for record in records {
+            self.publish_audit_record(record).await;
+        }
+    }
+
+    /// Stores a model from a multi-model deploy as deployed at the given version with the given
+    /// components disabled, retrying if the model is changed concurrently. Returns the model as it
+    /// was before the deploy along with the revision it was stored at, so the deploy can be rolled
+    /// back if a later model in the batch fails
+    #[allow(clippy::too_many_arguments)]
+    async fn store_batch_deploy(
+        &self,
+        account_id: Option<&str>,
+        lattice_id: &str,
+        name: &str,
+        version: &str,
+        disabled: &[String],
+        mut manifests: StoredManifest,
+        mut current_revision: u64,
+    ) -> Result<(StoredManifest, u64), (ErrorCode, String)> {
+        let mut attempt = 0;
+        loop {
+            attempt += 1;
+            let previous = manifests.clone();
+            if !manifests.deploy(Some(version.to_owned())) {
+                return Err((
+                    ErrorCode::NotFound,
+                    format!(
+                        "Version {version} of model {name} was removed before it could be deployed"
+                    ),
+                ));
+            }
+            manifests.set_disabled_components(disabled.to_vec());
+            match self
+                .store
+                .set(account_id, lattice_id, manifests, Some(current_revision))
+                .await
+            {
+                Ok(revision) => return Ok((previous, revision)),
+                Err(e)
+                    if RevisionConflict::is_conflict(&e)
+                        && attempt < MAX_REVISION_CONFLICT_ATTEMPTS =>
+                {
+                    debug!(error = %e, %attempt, "Model was updated concurrently, retrying");
+                }
+                Err(e) => return Err((storage_error_code(&e), storage_error_message(&e))),
+            }
+            (manifests, current_revision) = match self.store.get(account_id, lattice_id, name).await
+            {
+                Ok(Some((m, _))) if m.is_archived() => {
+                    return Err((
+                        ErrorCode::InvalidRequest,
+                        format!("Model {name} was archived before it could be deployed"),
+                    ))
+                }
+                Ok(Some(m)) => m,
+                Ok(None) => {
+                    return Err((
+                        ErrorCode::NotFound,
+                        format!("Model {name} was deleted before it could be deployed"),
+                    ))
+                }
+                Err(e) => {
+                    error!(error = %e, "Unable to fetch data");
+                    return Err((storage_error_code(&e), storage_error_message(&e)));
+                }
+            };
+        }
+    }
+
+    #[instrument(
+        level = "debug",
+        skip(self, msg, account_id, lattice_id),
+        fields(account_id = account_id.unwrap_or_default(), lattice_id = %lattice_id)
+    )]
+    pub async fn undeploy_model(
+        &self,
+        msg: Message,
+        account_id: Option<&str>,
+        lattice_id: &str,
+        name: &str,
+    ) {
+        let req: UndeployModelRequest = if msg.payload.is_empty() {
+            UndeployModelRequest::default()
+        } else {
+            match serde_json::from_reader(std::io::Cursor::new(msg.payload)) {
+                Ok(r) => r,
+                Err(e) => {
+                    self.send_error(
+                        msg.reply,
+                        ErrorCode::InvalidRequest,
+                        format!("Unable to parse deploy model request: {e:?}"),
+                    )
+                    .await;
+                    return;
+                }
+            }
+        };
+        trace!(?req, "Got request");
+
+        let dependents = match self.find_dependents(account_id, lattice_id, name).await {
+            Ok(d) => d,
+            Err((code, message)) => {
+                self.send_error(msg.reply, code, message).await;
+                return;
+            }
+        };
+        if !dependents.is_empty() && req.strict {
+            self.send_error(
+                msg.reply,
+                ErrorCode::HasDependents,
+                format!(
+                    "Model {name} can't be undeployed because deployed model(s) link to its components: {}. Undeploy them first or retry without strict",
+                    dependents.join(", ")
+                ),
+            )
+            .await;
+            return;
+        }
+
+        let mut reply = self.undeploy(account_id, lattice_id, name).await;
+        if !dependents.is_empty() && matches!(reply.result, DeployResult::Acknowledged) {
+            warn!(
+                "Undeployed model {name}, which deployed model(s) link to: {}",
+                dependents.join(", ")
+            );
+            reply.message = format!(
+                "{}. Warning: deployed model(s) link to components of this model and may stop working: {}",
+                reply.message,
+                dependents.join(", ")
+            );
+        }
+        trace!(resp = ?reply, "Sending response");
+        self.send_reply(
+            msg.reply,
+            // NOTE: We are constructing all data here, so this shouldn't fail, but just in
+            // case we unwrap to nothing
+            serde_json::to_vec(&reply).unwrap_or_default(),
+        )
+        .await;
+    }
+
+    #[instrument(
+        level = "debug",
+        skip(self, msg, account_id, lattice_id),
+        fields(account_id = account_id.unwrap_or_default(), lattice_id = %lattice_id)
+    )]
+    pub async fn stage_model(
+        &self,
+        msg: Message,
+        account_id: Option<&str>,
+        lattice_id: &str,
+        name: &str,
+    ) {
+        if self
+            .replay_idempotent_response(&msg, &format!("stage.{name}"), account_id, lattice_id)
+            .await
+        {
+            return;
+        }
+
+        let req: StageModelRequest = if msg.payload.is_empty() {
+            StageModelRequest::default()
+        } else {
+            match serde_json::from_reader(std::io::Cursor::new(msg.payload)) {
+                Ok(r) => r,
+                Err(e) => {
+                    self.send_error(
+                        msg.reply,
+                        ErrorCode::InvalidRequest,
+                        format!("Unable to parse stage model request: {e:?}"),
+                    )
+                    .await;
+                    return;
+                }
+            }
+        };
+        trace!(?req, "Got request");
+
+        let reply = self.stage(account_id, lattice_id, name, req.version).await;
+        trace!(resp = ?reply, "Sending response");
+        self.send_reply(
+            msg.reply,
+            // NOTE: We are constructing all data here, so this shouldn't fail, but just in
+            // case we unwrap to nothing
+            serde_json::to_vec(&reply).unwrap_or_default(),
+        )
+        .await;
+    }
+
+    #[instrument(
+        level = "debug",
+        skip(self, msg, account_id, lattice_id),
+        fields(account_id = account_id.unwrap_or_default(), lattice_id = %lattice_id)
+    )]
+    pub async fn promote_model(
+        &self,
+        msg: Message,
+        account_id: Option<&str>,
+        lattice_id: &str,
+        name: &str,
+    ) {
+        if self
+            .replay_idempotent_response(&msg, &format!("promote.{name}"), account_id, lattice_id)
+            .await
+        {
+            return;
+        }
+
+        let reply = self.promote(account_id, lattice_id, name).await;
+        trace!(resp = ?reply, "Sending response");
+        self.send_reply(
+            msg.reply,
+            // NOTE: We are constructing all data here, so this shouldn't fail, but just in
+            // case we unwrap to nothing
+            serde_json::to_vec(&reply).unwrap_or_default(),
+        )
+        .await;
+    }
+
+    #[instrument(
+        level = "debug",
+        skip(self, msg, account_id, lattice_id),
+        fields(account_id = account_id.unwrap_or_default(), lattice_id = %lattice_id)
+    )]
+    pub async fn set_deployed_model(
+        &self,
+        msg: Message,
+        account_id: Option<&str>,
+        lattice_id: &str,
+        name: &str,
+    ) {
+        if self
+            .replay_idempotent_response(
+                &msg,
+                &format!("set_deployed.{name}"),
+                account_id,
+                lattice_id,
+            )
+            .await
+        {
+            return;
+        }
+
+        let req: SetDeployedRequest =
+            match serde_json::from_reader(std::io::Cursor::new(msg.payload)) {
+                Ok(r) => r,
+                Err(e) => {
+                    self.send_error(
+                        msg.reply,
+                        ErrorCode::InvalidRequest,
+                        format!("Unable to parse set deployed request: {e:?}"),
+                    )
+                    .await;
+                    return;
+                }
+            };
+        trace!(?req, "Got request");
+        if req.version.trim().is_empty() {
+            self.send_error(
+                msg.reply,
+                ErrorCode::InvalidRequest,
+                "A version is required to set the deployed version".to_string(),
+            )
+            .await;
+            return;
+        }
+
+        // Setting the deployed version is a deploy of that version with all of its components
+        let req = DeployModelRequest {
+            version: Some(req.version),
+            ..Default::default()
+        };
+        self.deploy(msg.reply, req, account_id, lattice_id, name)
+            .await;
+    }
+
+    #[instrument(
+        level = "debug",
+        skip(self, msg, account_id, lattice_id),
+        fields(account_id = account_id.unwrap_or_default(), lattice_id = %lattice_id)
+    )]
+    pub async fn patch_model_metadata(
+        &self,
+        msg: Message,
+        account_id: Option<&str>,
+        lattice_id: &str,
+        name: &str,
+    ) {
+        if self
+            .replay_idempotent_response(
+                &msg,
+                &format!("patch_metadata.{name}"),
+                account_id,
+                lattice_id,
+            )
+            .await
+        {
+            return;
+        }
+
+        let req: PatchMetadataRequest = if msg.payload.is_empty() {
+            PatchMetadataRequest::default()
+        } else {
+            match serde_json::from_reader(std::io::Cursor::new(msg.payload)) {
+                Ok(r) => r,
+                Err(e) => {
+                    self.send_error(
+                        msg.reply,
+                        ErrorCode::InvalidRequest,
+                        format!("Unable to parse patch metadata request: {e}. Only labels and annotations can be patched, changes to the spec require putting a new version"),
+                    )
+                    .await;
+                    return;
+                }
+            }
+        };
+        trace!(?req, "Got request");
+
+        let reply = self.patch_metadata(account_id, lattice_id, name, req).await;
+        trace!(resp = ?reply, "Sending response");
+        self.send_reply(
+            msg.reply,
+            // NOTE: We are constructing all data here, so this shouldn't fail, but just in
+            // case we unwrap to nothing
+            serde_json::to_vec(&reply).unwrap_or_default(),
+        )
+        .await;
+    }
+
+    /// Deletes every version of a model except the deployed and staged versions, and optionally the
+    /// latest version
+    #[instrument(
+        level = "debug",
+        skip(self, msg, account_id, lattice_id),
+        fields(account_id = account_id.unwrap_or_default(), lattice_id = %lattice_id)
+    )]
+    pub async fn prune_model(
+        &self,
+        msg: Message,
+        account_id: Option<&str>,
+        lattice_id: &str,
+        name: &str,
+    ) {
+        if self
+            .replay_idempotent_response(&msg, &format!("prune.{name}"), account_id, lattice_id)
+            .await
+        {
+            return;
+        }
+
+        let req: PruneModelRequest = if msg.payload.is_empty() {
+            PruneModelRequest::default()
+        } else {
+            match serde_json::from_reader(std::io::Cursor::new(msg.payload)) {
+                Ok(r) => r,
+                Err(e) => {
+                    self.send_error(
+                        msg.reply,
+                        ErrorCode::InvalidRequest,
+                        format!("Unable to parse prune model request: {e:?}"),
+                    )
+                    .await;
+                    return;
+                }
+            }
+        };
+        trace!(?req, "Got request");
+
+        let reply = self.prune(account_id, lattice_id, name, req).await;
+        trace!(resp = ?reply, "Sending response");
+        self.send_reply(
+            msg.reply,
+            // NOTE: We are constructing all data here, so this shouldn't fail, but just in
+            // case we unwrap to nothing
+            serde_json::to_vec(&reply).unwrap_or_default(),
+        )
+        .await;
+    }
+
+    #[instrument(
+        level = "debug",
+        skip(self, msg, account_id, lattice_id),
+        fields(account_id = account_id.unwrap_or_default(), lattice_id = %lattice_id)
+    )]
+    pub async fn undeploy_all(&self, msg: Message, account_id: Option<&str>, lattice_id: &str) {
+        let models = match self.store.list(account_id, lattice_id).await {
+            Ok(d) => d,
+            Err(e) => {
+                error!(error = %e, "Unable to fetch data");
+                self.send_error(msg.reply, storage_error_code(&e), storage_error_message(&e))
+                    .await;
+                return;
+            }
+        };
+
+        let mut results = Vec::new();
+        for model in models.into_iter().filter(|m| m.deployed_version.is_some()) {
+            trace!(name = %model.name, "Undeploying model");
+            let resp = self.undeploy(account_id, lattice_id, &model.name).await;
+            if !matches!(resp.result, DeployResult::Acknowledged) {
+                warn!(
+                    "Unable to undeploy model {}, continuing with remaining models: {}",
+                    model.name, resp.message
+                );
+            }
+            results.push(ModelUndeployResult {
+                name: model.name,
+                result: resp.result,
+                message: resp.message,
+            });
+        }
+
+        let records = results
+            .iter()
+            .filter(|r| matches!(r.result, DeployResult::Acknowledged))
+            .map(|r| {
+                audit::model_record(
+                    account_id,
+                    lattice_id,
+                    ModelOperation::Undeploy,
+                    &r.name,
+                    None,
+                    "acknowledged",
+                )
+            })
+            .collect::<Vec<_>>();
+        let failed = results
+            .iter()
+            .filter(|r| !matches!(r.result, DeployResult::Acknowledged))
+            .count();
+        let reply = UndeployAllResponse {
+            result: if failed == 0 {
+                DeployResult::Acknowledged
+            } else {
+                DeployResult::Error
+            },
+            message: format!(
+                "Undeployed {} of {} deployed models in lattice {lattice_id}",
+                results.len() - failed,
+                results.len()
+            ),
+            models: results,
+        };
+        trace!(resp = ?reply, "Sending response");
+        self.send_reply(
+            msg.reply,
+            // NOTE: We are constructing all data here, so this shouldn't fail, but just in
+            // case we unwrap to nothing
+            serde_json::to_vec(&reply).unwrap_or_default(),
+        )
+        .await;
+        for record in records {
+            self.publish_audit_record(record).await;
+        }
+    }
+
+    #[instrument(
+        level = "debug",
+        skip(self, msg, account_id, lattice_id),
+        fields(account_id = account_id.unwrap_or_default(), lattice_id = %lattice_id)
+    )]
+    pub async fn export_models(&self, msg: Message, account_id: Option<&str>, lattice_id: &str) {
+        let summaries = match self.store.list(account_id, lattice_id).await {
+            Ok(d) => d,
+            Err(e) => {
+                error!(error = %e, "Unable to fetch data");
+                self.send_error(msg.reply, storage_error_code(&e), storage_error_message(&e))
+                    .await;
+                return;
+            }
+        };
+
+        let mut models = Vec::with_capacity(summaries.len());
+        for summary in summaries {
+            match self.store.get(account_id, lattice_id, &summary.name).await {
+                Ok(Some((stored, _))) => models.push(exported_model(summary.name, &stored)),
+                // The model was deleted after we listed it, so there is nothing to export
+                Ok(None) => trace!(name = %summary.name, "Model no longer exists, skipping"),
+                Err(e) => {
+                    error!(error = %e, "Unable to fetch data");
+                    self.send_error(msg.reply, storage_error_code(&e), storage_error_message(&e))
+                        .await;
+                    return;
+                }
+            }
+        }
+
+        self.send_reply(
+            msg.reply,
+            // NOTE: We are constructing all data here, so this shouldn't fail, but just in
+            // case we unwrap to nothing
+            serde_json::to_vec(&ExportModelsResponse {
+                result: ExportResult::Success,
+                message: format!("Exported {} models from lattice {lattice_id}", models.len()),
+                models,
+            })
+            .unwrap_or_default(),
+        )
+        .await;
+    }
+
+    #[instrument(
+        level = "debug",
+        skip(self, msg, account_id, lattice_id),
+        fields(account_id = account_id.unwrap_or_default(), lattice_id = %lattice_id)
+    )]
+    pub async fn import_models(&self, msg: Message, account_id: Option<&str>, lattice_id: &str) {
+        let req: ImportModelsRequest =
+            match serde_json::from_reader(std::io::Cursor::new(msg.payload)) {
+                Ok(r) => r,
+                Err(e) => {
+                    self.send_error(
+                        msg.reply,
+                        ErrorCode::InvalidRequest,
+                        format!("Unable to parse import models request: {e:?}"),
+                    )
+                    .await;
+                    return;
+                }
+            };
+
+        let mut results = Vec::with_capacity(req.models.len());
+        let mut records = Vec::new();
+        for model in req.models {
+            let name = model.name.trim().to_owned();
+            let deployed_version = model.deployed_version.clone();
+            trace!(%name, "Importing model");
+            let (result, message) = self
+                .import_model(account_id, lattice_id, model, req.overwrite)
+                .await;
+            match result {
+                ModelImportOutcome::Error => {
+                    warn!("Unable to import model {name}, continuing with remaining models: {message}");
+                }
+                ModelImportOutcome::Created | ModelImportOutcome::Overwritten => {
+                    let outcome = if matches!(result, ModelImportOutcome::Created) {
+                        "created"
+                    } else {
+                        "overwritten"
+                    };
+                    records.push(audit::model_record(
+                        account_id,
+                        lattice_id,
+                        ModelOperation::Import,
+                        &name,
+                        None,
+                        outcome,
+                    ));
+                    // Imported models can be stored as deployed, which deploys them
+                    if let Some(version) = deployed_version.as_deref() {
+                        records.push(audit::model_record(
+                            account_id,
+                            lattice_id,
+                            ModelOperation::Deploy,
+                            &name,
+                            Some(version),
+                            "acknowledged",
+                        ));
+                    }
+                }
+                ModelImportOutcome::Skipped => (),
+            }
+            results.push(ModelImportResult {
+                name,
+                result,
+                message,
+            });
+        }
+
+        let imported = results
+            .iter()
+            .filter(|r| {
+                matches!(
+                    r.result,
+                    ModelImportOutcome::Created | ModelImportOutcome::Overwritten
+                )
+            })
+            .count();
+        let failed = results
+            .iter()
+            .filter(|r| matches!(r.result, ModelImportOutcome::Error))
+            .count();
+        let reply = ImportModelsResponse {
+            result: if failed == 0 {
+                ImportResult::Success
+            } else {
+                ImportResult::Error
+            },
+            message: format!(
+                "Imported {imported} of {} models into lattice {lattice_id}",
+                results.len()
+            ),
+            models: results,
+        };
+        trace!(resp = ?reply, "Sending response");
+        self.send_reply(
+            msg.reply,
+            // NOTE: We are constructing all data here, so this shouldn't fail, but just in
+            // case we unwrap to nothing
+            serde_json::to_vec(&reply).unwrap_or_default(),
+        )
+        .await;
+        for record in records {
+            self.publish_audit_record(record).await;
+        }
+    }
+
+    #[instrument(
+        level = "debug",
+        skip(self, msg, lattice_id),
+        fields(lattice_id = %lattice_id)
+    )]
+    pub async fn server_info(&self, msg: Message, lattice_id: &str) {
+        let schema = match schema_value().await {
+            Ok(schema) => schema,
+            Err(e) => {
+                error!(error = %e, "Unable to load manifest schema");
+                self.send_error(
+                    msg.reply,
+                    ErrorCode::Internal,
+                    "Unable to load manifest schema".to_string(),
+                )
+                .await;
+                return;
+            }
+        };
+        let reply = ServerInfoResponse {
+            result: GetResult::Success,
+            message: "Successfully fetched server info".to_string(),
+            wadm_version: env!("CARGO_PKG_VERSION").to_string(),
+            schema_id: schema
+                .get("$id")
+                .and_then(|id| id.as_str())
+                .map(ToOwned::to_owned),
+            custom_schema: self.config.manifest_schema_path.is_some(),
+            api_version: OAM_VERSION.to_string(),
+            supported_api_versions: SUPPORTED_API_VERSIONS.map(ToOwned::to_owned).to_vec(),
+            latest_version: LATEST_VERSION.to_string(),
+        };
+        trace!(resp = ?reply, "Sending response");
+        self.send_reply(
+            msg.reply,
+            // NOTE: We are constructing all data here, so this shouldn't fail, but just in
+            // case we unwrap to nothing
+            serde_json::to_vec(&reply).unwrap_or_default(),
+        )
+        .await;
+    }
+
+    /// Replies with every lattice that has stored models (for the account, if any), along with how
+    /// many models each has. This scans the whole bucket, so it is meant for occasional use by
+    /// operators
+    #[instrument(
+        level = "debug",
+        skip(self, msg, account_id),
+        fields(account_id = account_id.unwrap_or_default())
+    )]
+    pub async fn list_lattices(&self, msg: Message, account_id: Option<&str>) {
+        let lattices = match self.store.list_lattices(account_id).await {
+            Ok(lattices) => lattices,
+            Err(e) => {
+                error!(error = %e, "Unable to list lattices");
+                self.send_error(msg.reply, storage_error_code(&e), storage_error_message(&e))
+                    .await;
+                return;
+            }
+        };
+        let reply = ListLatticesResponse {
+            result: GetResult::Success,
+            message: format!("Found {} lattices with stored models", lattices.len()),
+            lattices: lattices
+                .into_iter()
+                .map(|(lattice_id, model_count)| LatticeSummary {
+                    lattice_id,
+                    model_count,
+                })
+                .collect(),
+        };
+        self.send_reply(
+            msg.reply,
+            // NOTE: We are constructing all data here, so this shouldn't fail, but just in
+            // case we unwrap to nothing
+            serde_json::to_vec(&reply).unwrap_or_default(),
+        )
+        .await;
+    }
+
+    /// Replies with whether the model store and status stream can be reached. Each check is
+    /// bounded by [`HEALTH_CHECK_TIMEOUT`] and neither reads any models, so this is cheap enough to
+    /// use as a liveness or readiness probe
+    #[instrument(level = "debug", skip(self, msg))]
+    pub async fn health(&self, msg: Message) {
+        let store_check = health_check("store", self.store.probe());
+        let mut status_stream = self.status_stream.clone();
+        let stream_check = health_check("status_stream", async move {
+            status_stream
+                .info()
+                .await
+                .map(|_| ())
+                .map_err(|e| anyhow!("Unable to fetch status stream info: {e:?}"))
+        });
+        let (store_check, stream_check) = tokio::join!(store_check, stream_check);
+        let checks = vec![store_check, stream_check];
+
+        let unhealthy: Vec<&str> = checks
+            .iter()
+            .filter(|check| !check.healthy)
+            .map(|check| check.component.as_str())
+            .collect();
+        let reply = if unhealthy.is_empty() {
+            HealthResponse {
+                result: HealthResult::Ok,
+                message: "All dependencies are reachable".to_string(),
+                checks,
+            }
+        } else {
+            HealthResponse {
+                result: HealthResult::Degraded,
+                message: format!("Unable to reach {}", unhealthy.join(", ")),
+                checks,
+            }
+        };
+        self.send_reply(
+            msg.reply,
+            // NOTE: We are constructing all data here, so this shouldn't fail, but just in
+            // case we unwrap to nothing
+            serde_json::to_vec(&reply).unwrap_or_default(),
+        )
+        .await;
+    }
+
+    #[instrument(
+        level = "debug",
+        skip(self, msg, account_id, lattice_id),
+        fields(account_id = account_id.unwrap_or_default(), lattice_id = %lattice_id)
+    )]
+    pub async fn deploy_history(
+        &self,
+        msg: Message,
+        account_id: Option<&str>,
+        lattice_id: &str,
+        name: &str,
+    ) {
+        let reply = match self.store.get(account_id, lattice_id, name).await {
+            Ok(Some((manifests, _))) => DeployHistoryResponse {
+                result: GetResult::Success,
+                message: format!("Successfully fetched deploy history for model {name}"),
+                events: manifests.deploy_events().cloned().collect(),
+            },
+            Ok(None) => DeployHistoryResponse {
+                result: GetResult::NotFound,
+                message: format!("Model with the name {name} not found"),
+                events: Vec::with_capacity(0),
+            },
+            Err(e) => {
+                error!(error = %e, "Unable to fetch data");
+                self.send_error(msg.reply, storage_error_code(&e), storage_error_message(&e))
+                    .await;
+                return;
+            }
+        };
+        self.send_reply(
+            msg.reply,
+            // NOTE: We are constructing all data here, so this shouldn't fail, but just in
+            // case we unwrap to nothing
+            serde_json::to_vec(&reply).unwrap_or_default(),
+        )
+        .await;
+    }
+
+    #[instrument(
+        level = "debug",
+        skip(self, msg, account_id, lattice_id),
+        fields(account_id = account_id.unwrap_or_default(), lattice_id = %lattice_id)
+    )]
+    pub async fn model_status(
+        &self,
+        msg: Message,
+        account_id: Option<&str>,
+        lattice_id: &str,
+        name: &str,
+    ) {
+        trace!("Fetching current manifest from store");
+        let manifests: StoredManifest = match self.store.get(account_id, lattice_id, name).await {
+            Ok(Some((m, _))) => m,
+            Ok(None) => {
+                self.send_reply(
+                    msg.reply,
+                    // NOTE: We are constructing all data here, so this shouldn't fail, but just in
+                    // case we unwrap to nothing
+                    serde_json::to_vec(&StatusResponse {
+                        result: StatusResult::NotFound,
+                        message: format!("Model with the name {name} not found"),
+                        status: None,
+                    })
+                    .unwrap_or_default(),
+                )
+                .await;
+                return;
+            }
+            Err(e) => {
+                error!(error = %e, "Unable to fetch data");
+                self.send_error(msg.reply, storage_error_code(&e), storage_error_message(&e))
+                    .await;
+                return;
+            }
+        };
+
+        let current = manifests.get_current();
+
+        let status = Status {
+            version: current.version().to_owned(),
+            deployed_version: manifests.deployed_version().map(ToOwned::to_owned),
+            staged_version: manifests.staged_version().map(ToOwned::to_owned),
+            info: match self.get_manifest_status(account_id, lattice_id, name).await {
+                Ok(status) => status.unwrap_or_default(),
+                Err(e) => {
+                    error!(error = %e, "Unable to fetch status for model");
+                    StatusInfo {
+                        message: STATUS_UNAVAILABLE_MESSAGE.to_string(),
+                        ..Default::default()
+                    }
+                }
+            },
+            components: vec![],
+        };
+
+        self.send_reply(
+            msg.reply,
+            // NOTE: We are constructing all data here, so this shouldn't fail, but just in
+            // case we unwrap to nothing
+            serde_json::to_vec(&StatusResponse {
+                result: StatusResult::Ok,
+                message: format!("Successfully fetched status for model {}", name),
+                status: Some(status),
+            })
+            .unwrap_or_default(),
+        )
+        .await;
+    }
+
+    #[instrument(
+        level = "debug",
+        skip(self, msg, account_id, lattice_id),
+        fields(account_id = account_id.unwrap_or_default(), lattice_id = %lattice_id)
+    )]
+    pub async fn model_status_history(
+        &self,
+        msg: Message,
+        account_id: Option<&str>,
+        lattice_id: &str,
+        name: &str,
+    ) {
+        let req: StatusHistoryRequest = if msg.payload.is_empty() {
+            StatusHistoryRequest::default()
+        } else {
+            match serde_json::from_reader(std::io::Cursor::new(msg.payload)) {
+                Ok(r) => r,
+                Err(e) => {
+                    self.send_error(
+                        msg.reply,
+                        ErrorCode::InvalidRequest,
+                        format!("Unable to parse status history request: {e:?}"),
+                    )
+                    .await;
+                    return;
+                }
+            }
+        };
+        let limit = req
+            .limit
+            .unwrap_or(MAX_STATUS_HISTORY)
+            .min(MAX_STATUS_HISTORY);
+
+        match self.store.get(account_id, lattice_id, name).await {
+            Ok(Some(_)) => (),
+            Ok(None) => {
+                self.send_reply(
+                    msg.reply,
+                    // NOTE: We are constructing all data here, so this shouldn't fail, but just in
+                    // case we unwrap to nothing
+                    serde_json::to_vec(&StatusHistoryResponse {
+                        result: StatusResult::NotFound,
+                        message: format!("Model with the name {name} not found"),
+                        history: Vec::with_capacity(0),
+                    })
+                    .unwrap_or_default(),
+                )
+                .await;
+                return;
+            }
+            Err(e) => {
+                error!(error = %e, "Unable to fetch data");
+                self.send_error(msg.reply, storage_error_code(&e), storage_error_message(&e))
+                    .await;
+                return;
+            }
+        };
+
+        let history = match get_status_history(
+            &self.status_stream,
+            account_id,
+            lattice_id,
+            name,
+            limit,
+        )
+        .await
+        {
+            Ok(h) => h,
+            Err(e) => {
+                error!(error = %e, "Unable to fetch status history");
+                self.send_error(
+                    msg.reply,
+                    ErrorCode::Internal,
+                    "Unable to fetch status history from the status stream".to_string(),
+                )
+                .await;
+                return;
+            }
+        };
+
+        self.send_reply(
+            msg.reply,
+            // NOTE: We are constructing all data here, so this shouldn't fail, but just in
+            // case we unwrap to nothing
+            serde_json::to_vec(&StatusHistoryResponse {
+                result: StatusResult::Ok,
+                message: format!("Successfully fetched status history for model {name}"),
+                history,
+            })
+            .unwrap_or_default(),
+        )
+        .await;
+    }
+
+    /// Replies with the topic status updates for the model are published on, so subscribers don't
+    /// have to construct it themselves. This doesn't require the model to exist
+    #[instrument(
+        level = "debug",
+        skip(self, msg, account_id, lattice_id),
+        fields(account_id = account_id.unwrap_or_default(), lattice_id = %lattice_id)
+    )]
+    pub async fn status_topic(
+        &self,
+        msg: Message,
+        account_id: Option<&str>,
+        lattice_id: &str,
+        name: &str,
+    ) {
+        let (result, message, has_status) =
+            match get_status_message(&self.status_stream, account_id, lattice_id, name).await {
+                Ok(status) => (
+                    StatusResult::Ok,
+                    format!("Successfully fetched status topic for model {name}"),
+                    status.is_some(),
+                ),
+                Err(e) => {
+                    error!(error = %e, "Unable to fetch status for model");
+                    (
+                        StatusResult::Error,
+                        "Unable to check the status stream for a status message".to_string(),
+                        false,
+                    )
+                }
+            };
+
+        self.send_reply(
+            msg.reply,
+            // NOTE: We are constructing all data here, so this shouldn't fail, but just in
+            // case we unwrap to nothing
+            serde_json::to_vec(&StatusTopicResponse {
+                result,
+                message,
+                topic: status_topic(account_id, lattice_id, name),
+                has_status,
+            })
+            .unwrap_or_default(),
+        )
+        .await;
+    }
+
+    #[instrument(
+        level = "debug",
+        skip(self, msg, account_id, lattice_id),
+        fields(account_id = account_id.unwrap_or_default(), lattice_id = %lattice_id)
+    )]
+    pub async fn watch_status(
+        &self,
+        msg: Message,
+        account_id: Option<&str>,
+        lattice_id: &str,
+        name: &str,
+    ) {
+        let req: WatchStatusRequest =
+            match serde_json::from_reader(std::io::Cursor::new(msg.payload)) {
+                Ok(r) => r,
+                Err(e) => {
+                    self.send_error(
+                        msg.reply,
+                        ErrorCode::InvalidRequest,
+                        format!("Unable to parse watch status request: {e:?}"),
+                    )
+                    .await;
+                    return;
+                }
+            };
+        trace!(?req, "Got request");
+
+        let subject = req.subject.trim().to_owned();
+        if let Err(message) = self.check_watch_subject(&subject) {
+            self.send_error(msg.reply, ErrorCode::InvalidRequest, message)
+                .await;
+            return;
+        }
+
+        match self.store.get(account_id, lattice_id, name).await {
+            Ok(Some(_)) => (),
+            Ok(None) => {
+                self.send_reply(
+                    msg.reply,
+                    // NOTE: We are constructing all data here, so this shouldn't fail, but just in
+                    // case we unwrap to nothing
+                    serde_json::to_vec(&WatchStatusResponse {
+                        result: StatusResult::NotFound,
+                        message: format!("Model with the name {name} not found"),
+                    })
+                    .unwrap_or_default(),
+                )
+                .await;
+                return;
+            }
+            Err(e) => {
+                error!(error = %e, "Unable to fetch data");
+                self.send_error(msg.reply, storage_error_code(&e), storage_error_message(&e))
+                    .await;
+                return;
+            }
+        };
+
+        let Some(permit) = self.watches.try_acquire(account_id) else {
+            self.send_watch_limit_error(msg.reply).await;
+            return;
+        };
+
+        let duration = req
+            .max_duration_secs
+            .map(Duration::from_secs)
+            .unwrap_or(DEFAULT_STATUS_WATCH)
+            .min(MAX_STATUS_WATCH);
+        let client = self.client.clone();
+        let status_stream = self.status_stream.clone();
+        let account_id = account_id.map(ToOwned::to_owned);
+        let lattice_id = lattice_id.to_owned();
+        let model_name = name.to_owned();
+        let watch_subject = subject.clone();
+        tokio::spawn(
+            async move {
+                // The watch counts against the account's limit until it ends
+                let _permit = permit;
+                if let Err(e) = watch_model_status(
+                    &client,
+                    &status_stream,
+                    account_id.as_deref(),
+                    &lattice_id,
+                    &model_name,
+                    &watch_subject,
+                    duration,
+                )
+                .await
+                {
+                    error!(error = %e, "Error while watching model status");
+                }
+            }
+            .in_current_span(),
+        );
+
+        self.send_reply(
+            msg.reply,
+            // NOTE: We are constructing all data here, so this shouldn't fail, but just in
+            // case we unwrap to nothing
+            serde_json::to_vec(&WatchStatusResponse {
+                result: StatusResult::Ok,
+                message: format!(
+                    "Publishing status updates for model {name} to {subject} for up to {}s",
+                    duration.as_secs()
+                ),
+            })
+            .unwrap_or_default(),
+        )
+        .await;
+    }
+
+    #[instrument(
+        level = "debug",
+        skip(self, msg, account_id, lattice_id),
+        fields(account_id = account_id.unwrap_or_default(), lattice_id = %lattice_id)
+    )]
+    pub async fn watch_model(
+        &self,
+        msg: Message,
+        account_id: Option<&str>,
+        lattice_id: &str,
+        name: &str,
+    ) {
+        let req: WatchModelRequest =
+            match serde_json::from_reader(std::io::Cursor::new(msg.payload)) {
+                Ok(r) => r,
+                Err(e) => {
+                    self.send_error(
+                        msg.reply,
+                        ErrorCode::InvalidRequest,
+                        format!("Unable to parse watch model request: {e:?}"),
+                    )
+                    .await;
+                    return;
+                }
+            };
+        trace!(?req, "Got request");
+
+        let subject = req.subject.trim().to_owned();
+        if let Err(message) = self.check_watch_subject(&subject) {
+            self.send_error(msg.reply, ErrorCode::InvalidRequest, message)
+                .await;
+            return;
+        }
+
+        // Model events are built from the audit records published for each change, which also
+        // means changes handled by other wadm instances are seen
+        let Some(prefix) = self.config.audit_topic_prefix.clone() else {
+            self.send_error(
+                msg.reply,
+                ErrorCode::Unsupported,
+                "Watching models requires audit records to be enabled".to_string(),
+            )
+            .await;
+            return;
+        };
+
+        match self.store.get(account_id, lattice_id, name).await {
+            Ok(Some(_)) => (),
+            Ok(None) => {
+                self.send_reply(
+                    msg.reply,
+                    // NOTE: We are constructing all data here, so this shouldn't fail, but just in
+                    // case we unwrap to nothing
+                    serde_json::to_vec(&WatchModelResponse {
+                        result: StatusResult::NotFound,
+                        message: format!("Model with the name {name} not found"),
+                    })
+                    .unwrap_or_default(),
+                )
+                .await;
+                return;
+            }
+            Err(e) => {
+                error!(error = %e, "Unable to fetch data");
+                self.send_error(msg.reply, storage_error_code(&e), storage_error_message(&e))
+                    .await;
+                return;
+            }
+        };
+
+        let Some(permit) = self.watches.try_acquire(account_id) else {
+            self.send_watch_limit_error(msg.reply).await;
+            return;
+        };
+
+        let duration = req
+            .max_duration_secs
+            .map(Duration::from_secs)
+            .unwrap_or(DEFAULT_STATUS_WATCH)
+            .min(MAX_STATUS_WATCH);
+        let client = self.client.clone();
+        let audit_topic = format!("{prefix}.{lattice_id}");
+        let account_id = account_id.map(ToOwned::to_owned);
+        let model_name = name.to_owned();
+        let watch_subject = subject.clone();
+        tokio::spawn(
+            async move {
+                // The watch counts against the account's limit until it ends
+                let _permit = permit;
+                if let Err(e) = watch_model_events(
+                    &client,
+                    audit_topic,
+                    account_id.as_deref(),
+                    &model_name,
+                    &watch_subject,
+                    duration,
+                )
+                .await
+                {
+                    error!(error = %e, "Error while watching model events");
+                }
+            }
+            .in_current_span(),
+        );
+
+        self.send_reply(
+            msg.reply,
+            // NOTE: We are constructing all data here, so this shouldn't fail, but just in
+            // case we unwrap to nothing
+            serde_json::to_vec(&WatchModelResponse {
+                result: StatusResult::Ok,
+                message: format!(
+                    "Publishing events for model {name} to {subject} for up to {}s",
+                    duration.as_secs()
+                ),
+            })
+            .unwrap_or_default(),
+        )
+        .await;
+    }
+
+    /// Checks that the subject given in a watch request can be published to, returning a message
+    /// describing the problem if it can't. Subjects under any of the prefixes the server listens or
+    /// publishes on are rejected. See [`validate_watch_subject`]
+    fn check_watch_subject(&self, subject: &str) -> Result<(), String> {
+        let mut reserved = vec![
+            self.api_prefix.as_str(),
+            self.notifier.prefix(),
+            STATUS_TOPIC_PREFIX,
+        ];
+        reserved.extend(self.config.audit_topic_prefix.as_deref());
+        validate_watch_subject(subject, &self.config.watch_subject_prefixes, &reserved)
+    }
+
+    /// Sends an error reply for a watch request from an account that already has the maximum number
+    /// of watches running
+    async fn send_watch_limit_error(&self, reply: Option<Subject>) {
+        self.send_error(
+            reply,
+            ErrorCode::QuotaExceeded,
+            format!(
+                "Account has reached the maximum of {} running watches. Retry once a watch has expired",
+                self.config.max_watches_per_account
+            ),
+        )
+        .await;
+    }
+
+    /// Checks that a manifest payload of the given size is within the configured maximum, returning
+    /// a message describing the problem if it isn't
+    fn check_payload_size(&self, len: usize) -> Result<(), String> {
+        if len > self.config.max_manifest_bytes {
+            return Err(format!(
+                "Manifest is too large ({len} bytes). The maximum allowed size is {} bytes",
+                self.config.max_manifest_bytes
+            ));
+        }
+        Ok(())
+    }
+
+    /// Sends a reply to the topic with the given data, logging an error if one occurs when
+    /// sending the reply
+    #[instrument(level = "debug", skip(self, data))]
+    pub async fn send_reply(&self, reply: Option<Subject>, data: Vec<u8>) {
+        let reply_topic = match reply {
+            Some(t) => t,
+            None => {
+                debug!("No reply topic was sent. Skipping reply");
+                return;
+            }
+        };
+        let accepts_gzip = ACCEPTS_GZIP.try_with(|accepts| *accepts).unwrap_or(false);
+        self.reply_sender()
+            .send(reply_topic, data, accepts_gzip, audit::take_context())
+            .await;
+    }
+
+    /// Returns a [`ReplySender`] for sending replies outside of the request's task
+    fn reply_sender(&self) -> ReplySender {
+        ReplySender {
+            client: self.client.clone(),
+            idempotency: self.idempotency.clone(),
+            metrics: self.metrics.clone(),
+            compression_threshold: self.config.reply_compression_threshold,
+            audit_topic_prefix: self.config.audit_topic_prefix.clone(),
+        }
+    }
+
+    /// Publishes an audit record for a request that changed models. This is best effort: the
+    /// request has already been handled, so failures are only logged
+    pub(crate) async fn publish_audit_record(&self, record: AuditRecord) {
+        publish_audit_record(
+            &self.client,
+            self.config.audit_topic_prefix.as_deref(),
+            record,
+        )
+        .await
+    }
+
+    /// Checks the request for an idempotency key. If a response was already recorded for the key,
+    /// it is sent again and `true` is returned, meaning the request should not be executed again.
+    /// Otherwise, the reply to this request will be recorded for the key.
+    ///
+    /// Keys are scoped to the account, lattice, and given operation so they can't collide across
+    /// unrelated requests
+    async fn replay_idempotent_response(
+        &self,
+        msg: &Message,
+        operation: &str,
+        account_id: Option<&str>,
+        lattice_id: &str,
+    ) -> bool {
+        let (key, reply) = match (
+            msg.headers
+                .as_ref()
+                .and_then(|headers| headers.get(IDEMPOTENCY_KEY_HEADER)),
+            msg.reply.as_ref(),
+        ) {
+            (Some(key), Some(reply)) => (key, reply),
+            // Without a reply topic there is no response to replay
+            _ => return false,
+        };
+        let key = format!(
+            "{}.{lattice_id}.{operation}.{}",
+            account_id.unwrap_or_default(),
+            key.as_str()
+        );
+        if let Some(data) = self.idempotency.get(&key) {
+            debug!(
+                "Request with this idempotency key was already handled, sending previous response"
+            );
+            self.send_reply(Some(reply.clone()), data).await;
+            return true;
+        }
+        self.idempotency.track(reply.clone(), key);
+        false
+    }
+
+    /// Sends an error reply
+    #[instrument(level = "error", skip(self, error_message))]
+    pub async fn send_error(
+        &self,
+        reply: Option<Subject>,
+        error_code: ErrorCode,
+        error_message: String,
+    ) {
+        self.send_reply(reply, error_response(error_code, error_message))
+            .await;
+    }
+
+    /// Sends an error reply for a rejected manifest, including the structured schema errors if
+    /// there are any
+    async fn send_rejection(&self, reply: Option<Subject>, rejection: Rejection) {
+        if rejection.errors.is_empty() {
+            return self
+                .send_error(reply, rejection.code, rejection.message)
+                .await;
+        }
+        // SAFETY: We control the construction of the JSON here and all data going in, so this
+        // shouldn't fail except in some sort of really odd case. In those cases, we just unwrap to
+        // a default
+        let mut response = json!({
+            "result": "error",
+            "message": rejection.message,
+            "error_code": rejection.code,
+            "errors": rejection.errors,
+        });
+        if let Some(retry_after_ms) = rejection.code.retry_after_ms() {
+            response["retry_after_ms"] = retry_after_ms.into();
+        }
+        let response = serde_json::to_vec(&response).unwrap_or_default();
+        self.send_reply(reply, response).await;
+    }
+
+    /// Reverts models stored as part of a multi-model request, newest first, by restoring the
+    /// previously stored model or deleting models that were created. Each model is only reverted if
+    /// it is still at the revision the request stored it at, so changes made to it in the meantime
+    /// are kept. Returns the names of the models that could not be reverted
+    async fn revert_stored_models<'a>(
+        &self,
+        account_id: Option<&str>,
+        lattice_id: &str,
+        stored: Vec<(&'a str, Option<StoredManifest>, u64)>,
+    ) -> Vec<&'a str> {
+        let mut current = HashMap::with_capacity(stored.len());
+        for (name, _, _) in stored.iter() {
+            match self.store.get(account_id, lattice_id, name).await {
+                Ok(model) => {
+                    current.insert(*name, model.map(|(_, revision)| revision));
+                }
+                Err(e) => error!(error = %e, %name, "Unable to fetch model to roll back"),
+            }
+        }
+
+        let names = stored.iter().map(|(name, _, _)| *name).collect::<Vec<_>>();
+        let steps = rollback_steps(stored, &current);
+        // Models without a step were changed since they were stored, so they aren't reverted
+        let mut not_reverted = names
+            .into_iter()
+            .filter(|name| !steps.iter().any(|step| step.name() == *name))
+            .collect::<Vec<_>>();
+        for step in steps {
+            let name = step.name();
+            let res = match step {
+                RollbackStep::Restore {
+                    name,
+                    previous,
+                    revision,
+                } => {
+                    trace!(%name, %revision, "Rolling back stored model");
+                    self.store
+                        .set(account_id, lattice_id, *previous, Some(revision))
+                        .await
+                        .map(|_| ())
+                }
+                RollbackStep::Delete { name, revision } => {
+                    trace!(%name, %revision, "Rolling back created model");
+                    self.store
+                        .delete_at_revision(account_id, lattice_id, name, revision)
+                        .await
+                }
+            };
+            if let Err(e) = res {
+                error!(error = %e, %name, "Unable to roll back stored model");
+                not_reverted.push(name);
+            }
+        }
+        not_reverted
+    }
+
+    /// Sends the reply to a multi-model deploy request. The request is only successful if every
+    /// model was deployed, otherwise models without an error of their own are marked as not
+    /// deployed
+    async fn send_deploy_models_reply(
+        &self,
+        reply: Option<Subject>,
+        message: String,
+        mut models: Vec<ModelDeployResult>,
+    ) {
+        let result = if models.iter().all(|m| m.error_code.is_none()) {
+            DeployResult::Acknowledged
+        } else {
+            for model in models
+                .iter_mut()
+                .filter(|m| m.error_code.is_none() && m.message.is_empty())
+            {
+                model.result = DeployResult::Error;
+                model.message =
+                    "Not deployed because other models in the request can't be deployed"
+                        .to_string();
+            }
+            DeployResult::Error
+        };
+        let resp = DeployModelsResponse {
+            result,
+            message,
+            models,
+        };
+        trace!(?resp, "Sending response");
+        self.send_reply(
+            reply,
+            // NOTE: We are constructing all data here, so this shouldn't fail, but just in
+            // case we unwrap to nothing
+            serde_json::to_vec(&resp).unwrap_or_default(),
+        )
+        .await;
+    }
+
+    async fn send_put_models_reply(
+        &self,
+        reply: Option<Subject>,
+        result: PutModelsResult,
+        message: String,
+        models: Vec<ModelPutResult>,
+    ) {
+        let resp = PutModelsResponse {
+            result,
+            message,
+            models,
+        };
+        trace!(?resp, "Sending response");
+        self.send_reply(
+            reply,
+            // NOTE: We are constructing all data here, so this shouldn't fail, but just in case we
+            // unwrap to nothing
+            serde_json::to_vec(&resp).unwrap_or_default(),
+        )
+        .await
+    }
+
+    /// Validates a manifest that is about to be stored as a new version, returning the trimmed
+    /// model name on success or the error code and message to reply with on failure
+    async fn validate_new_manifest(
+        &self,
+        account_id: Option<&str>,
+        manifest: &Manifest,
+    ) -> Result<String, Rejection> {
+        if !self.config.auto_version && manifest.version().trim().is_empty() {
+            return Err(Rejection::from((
+                ErrorCode::ValidationFailed,
+                format!(
+                    "Manifest does not have a version. Set the {VERSION_ANNOTATION_KEY} annotation \
+                     to a version"
+                ),
+            )));
+        }
+        let manifest_validation_output = validate_manifest_version(manifest.version());
+        let manifest_validation_errors = manifest_validation_output.errors();
+        if !manifest_validation_errors.is_empty() {
+            return Err(Rejection::from((
+                ErrorCode::ValidationFailed,
+                format!(
+                    "invalid manifest version, errors: {:#?}",
+                    manifest_validation_errors
+                        .iter()
+                        .map(|e| e.msg.clone())
+                        .collect::<Vec<String>>()
+                        .join("\n")
+                ),
+            )));
+        }
+
+        let manifest_name = manifest.metadata.name.trim().to_string();
+        if let Some(message) = invalid_manifest_name(&manifest_name) {
+            return Err(Rejection::from((ErrorCode::InvalidRequest, message)));
+        }
+        if let Some(message) = self.check_reserved_name(&manifest_name, account_id) {
+            return Err((ErrorCode::InvalidRequest, message).into());
+        }
+
+        let output = validate(manifest, &self.config.manifest_limits).await;
+        for warning in output.warnings.iter() {
+            debug!(%warning, "Manifest has a validation warning");
+        }
+        let errors = output.schema_errors();
+        if let Err(e) = output.into_result() {
+            return Err(Rejection {
+                code: ErrorCode::ValidationFailed,
+                message: e.to_string(),
+                errors,
+            });
+        }
+        Ok(manifest_name)
+    }
+
+    /// Renders, parses, migrates and normalizes a manifest payload into the form a put request
+    /// stores, returning the manifest and the migrations that were applied to it. The payload size
+    /// must already have been checked
+    fn prepare_manifest(
+        &self,
+        payload: Vec<u8>,
+        headers: Option<&HeaderMap>,
+    ) -> Result<(Manifest, Vec<String>), String> {
+        let payload = render_payload(payload, headers)
+            .map_err(|e| format!("Unable to render manifest template: {e:#}"))?;
+        // Parameters can make the rendered manifest larger than the template
+        if payload.len() > self.config.max_manifest_bytes {
+            return Err(format!(
+                "Rendered manifest is too large ({} bytes). The maximum allowed size is {} bytes",
+                payload.len(),
+                self.config.max_manifest_bytes
+            ));
+        }
+
+        trace!("Parsing incoming manifest");
+        let manifest = parse_manifest(payload, headers)
+            .map_err(|e| format!("Unable to parse manifest: {e:?}"))?;
+        Ok(self.prepare_parsed_manifest(manifest))
+    }
+
+    /// Migrates and normalizes an already parsed manifest into the form a put request stores,
+    /// returning the manifest and the migrations that were applied to it
+    fn prepare_parsed_manifest(&self, manifest: Manifest) -> (Manifest, Vec<String>) {
+        let (mut manifest, migrations) = migrate_manifest(manifest);
+        normalize_component_names(&mut manifest, self.config.lowercase_component_names);
+        (manifest, migrations)
+    }
+
+    /// Returns the manifest with a generated version if it doesn't have one, using the versions
+    /// already stored for the model
+    fn with_assigned_version<'a>(
+        &self,
+        manifest_name: &str,
+        manifest: &'a Manifest,
+        current_manifests: &StoredManifest,
+    ) -> Cow<'a, Manifest> {
+        if !manifest.version().trim().is_empty() {
+            return Cow::Borrowed(manifest);
+        }
+        let existing: Vec<&str> = current_manifests
+            .all_versions()
+            .into_iter()
+            .map(String::as_str)
+            .collect();
+        let version = self
+            .version_generator
+            .next_version(manifest_name, &existing);
+        trace!(%version, "Assigning version to manifest without one");
+        let mut manifest = manifest.clone();
+        manifest
+            .metadata
+            .annotations
+            .insert(VERSION_ANNOTATION_KEY.to_string(), version);
+        Cow::Owned(manifest)
+    }
+
+    /// Stores the manifest as the newest version of the named model, creating the model if it
+    /// doesn't exist. Returns the put response along with the model as it was stored before this
+    /// version was added (`None` if it was created), so callers can revert the change if needed
+    async fn store_new_version(
+        &self,
+        account_id: Option<&str>,
+        lattice_id: &str,
+        manifest_name: &str,
+        manifest: &Manifest,
+    ) -> Result<(PutModelResponse, Option<StoredManifest>), (ErrorCode, String)> {
+        let mut attempt = 0;
+        loop {
+            attempt += 1;
+            let (mut current_manifests, current_revision) =
+                match self.store.get(account_id, lattice_id, manifest_name).await {
+                    Ok(Some(data)) => data,
+                    Ok(None) => (StoredManifest::default(), 0),
+                    Err(e) => {
+                        error!(error = %e, "Unable to fetch data from store");
+                        return Err((storage_error_code(&e), storage_error_message(&e)));
+                    }
+                };
+
+            if current_manifests.is_archived() {
+                return Err((
+                    ErrorCode::AlreadyExists,
+                    format!("Model {manifest_name} is archived. Restore it before adding new versions, or delete it to reuse the name"),
+                ));
+            }
+
+            // Only brand new models count against the quota, new versions of an existing model
+            // are always allowed
+            if current_manifests.is_empty() {
+                match self.check_model_quota(account_id, lattice_id).await {
+                    Ok(None) => (),
+                    Ok(Some(message)) => return Err((ErrorCode::QuotaExceeded, message)),
+                    Err(e) => {
+                        error!(error = %e, "Unable to list models to check quota");
+                        return Err((storage_error_code(&e), storage_error_message(&e)));
+                    }
+                }
+                match self
+                    .check_name_collision(account_id, lattice_id, manifest_name)
+                    .await
+                {
+                    Ok(None) => (),
+                    Ok(Some(message)) => return Err((ErrorCode::AlreadyExists, message)),
+                    Err(e) => {
+                        error!(error = %e, "Unable to check other accounts for the model name");
+                        return Err((storage_error_code(&e), storage_error_message(&e)));
+                    }
+                }
+            }
+            let previous = (!current_manifests.is_empty()).then(|| current_manifests.clone());
+
+            let manifest = self.with_assigned_version(manifest_name, manifest, &current_manifests);
+
+            let mut resp = PutModelResponse {
+                // If we successfully insert, the given manifest version will be the new current version
+                current_version: manifest.version().to_owned(),
+                result: if current_manifests.is_empty() {
+                    PutResult::Created
+                } else {
+                    PutResult::NewVersion
+                },
+                name: manifest_name.to_owned(),
+                total_versions: 0,
+                deploy_error: None,
+                errors: Vec::new(),
+                message: format!(
+                    "Successfully put manifest {} {}",
+                    manifest_name,
+                    manifest.version()
+                ),
+                revision: 0,
+            };
+
+            if !current_manifests.add_version(manifest.into_owned()) {
+                return Err((
+                    ErrorCode::VersionConflict,
+                    format!("Manifest version {} already exists", resp.current_version),
+                ));
+            }
+            resp.total_versions = current_manifests.count();
+
+            trace!(total_manifests = %resp.total_versions, "Storing manifests");
+            match self
+                .store
+                .set(
+                    account_id,
+                    lattice_id,
+                    current_manifests,
+                    Some(current_revision),
+                )
+                .await
+            {
+                Ok(revision) => {
+                    resp.revision = revision;
+                    return Ok((resp, previous));
+                }
+                Err(e)
+                    if RevisionConflict::is_conflict(&e)
+                        && attempt < MAX_REVISION_CONFLICT_ATTEMPTS =>
+                {
+                    debug!(error = %e, %attempt, "Model was updated concurrently, retrying");
+                    continue;
+                }
+                Err(e) => {
+                    error!(error = %e, "Unable to store updated data");
+                    return Err((storage_error_code(&e), storage_error_message(&e)));
+                }
+            }
+        }
+    }
+
+    /// Deploys a version of a model that was just put, running the same conflict checks as a deploy
+    /// request and notifying processors of the deploy. Returns the revision of the deployed model,
+    /// or a message describing why the version couldn't be deployed on failure
+    async fn deploy_put_version(
+        &self,
+        account_id: Option<&str>,
+        lattice_id: &str,
+        name: &str,
+        version: &str,
+    ) -> Result<u64, String> {
+        self.with_deploy_locks(
+            account_id,
+            lattice_id,
+            &[name],
+            self.deploy_put_version_locked(account_id, lattice_id, name, version),
+        )
+        .await
+        .map_err(|(_, message)| message)?
+    }
+
+    /// Deploys a version of a model that was just put while holding its deploy lock. See
+    /// [`Handler::deploy_put_version`]
+    async fn deploy_put_version_locked(
+        &self,
+        account_id: Option<&str>,
+        lattice_id: &str,
+        name: &str,
+        version: &str,
+    ) -> Result<u64, String> {
+        let mut attempt = 0;
+        let (manifest, revision) = loop {
+            attempt += 1;
+            let (mut manifests, current_revision) =
+                match self.store.get(account_id, lattice_id, name).await {
+                    Ok(Some(m)) => m,
+                    Ok(None) => {
+                        return Err(format!(
+                            "Model {name} was deleted before it could be deployed"
+                        ))
+                    }
+                    Err(e) => {
+                        error!(error = %e, "Unable to fetch data");
+                        return Err(storage_error_message(&e));
+                    }
+                };
+            let manifest = match manifests.get_version(version) {
+                Some(m) => m.to_owned(),
+                None => {
+                    return Err(format!(
+                        "Version {version} of model {name} was removed before it could be deployed"
+                    ))
+                }
+            };
+            check_minimum_wadm_version(&manifest)?;
+            self.check_deploy_conflicts(account_id, lattice_id, name, &manifest)
+                .await
+                .map_err(|(_, message)| message)?;
+            // We just fetched this version, so it exists to be deployed
+            manifests.deploy(Some(version.to_owned()));
+
+            match self
+                .store
+                .set(account_id, lattice_id, manifests, Some(current_revision))
+                .await
+            {
+                Ok(revision) => break (manifest, revision),
+                Err(e)
+                    if RevisionConflict::is_conflict(&e)
+                        && attempt < MAX_REVISION_CONFLICT_ATTEMPTS =>
+                {
+                    debug!(error = %e, %attempt, "Model was updated concurrently, retrying");
+                    continue;
+                }
+                Err(e) => {
+                    error!(error = %e, "Unable to store updated data");
+                    return Err(storage_error_message(&e));
+                }
+            }
+        };
+
+        self.notifier
+            .deployed(lattice_id, manifest)
+            .await
+            .map(|_| revision)
+            .map_err(|e| {
+                error!(error = ?e, "Error when attempting to send deployed notification");
+                "Error notifying processors of newly deployed manifest. This is likely a transient error, so please retry by sending a deploy request".to_string()
+            })
+    }
+
+    /// Runs the given future while holding the deploy locks of the named models. Every request that
+    /// changes which version of a model is deployed holds its lock, so concurrent requests can't
+    /// interleave their checks and writes. Locks are taken in name order and released however the
+    /// future turns out. Returns the error code and message to reply with if any lock can't be taken
+    pub(crate) async fn with_deploy_locks<T>(
+        &self,
+        account_id: Option<&str>,
+        lattice_id: &str,
+        names: &[impl AsRef<str>],
+        fut: impl Future<Output = T>,
+    ) -> Result<T, (ErrorCode, String)> {
+        let mut names: Vec<&str> = names.iter().map(AsRef::as_ref).collect();
+        names.sort_unstable();
+        names.dedup();
+        let mut locks = Vec::with_capacity(names.len());
+        for name in names {
+            let acquired = match self
+                .store
+                .try_lock_deploy(account_id, lattice_id, name, self.config.deploy_lock_ttl)
+                .await
+            {
+                Ok(Some(lock)) => Ok(lock),
+                Ok(None) => Err((
+                    ErrorCode::DeployInProgress,
+                    format!(
+                        "Another deploy of model {name} is in progress. Retry once it has finished"
+                    ),
+                )),
+                Err(e) => {
+                    error!(error = %e, %name, "Unable to acquire deploy lock");
+                    Err((storage_error_code(&e), storage_error_message(&e)))
+                }
+            };
+            match acquired {
+                Ok(lock) => locks.push((name, lock)),
+                Err(e) => {
+                    self.release_deploy_locks(locks).await;
+                    return Err(e);
+                }
+            }
+        }
+        let output = fut.await;
+        self.release_deploy_locks(locks).await;
+        Ok(output)
+    }
+
+    /// Releases deploy locks taken by [`Handler::with_deploy_locks`]. Failures are only logged, as
+    /// the locks expire on their own
+    async fn release_deploy_locks(&self, locks: Vec<(&str, DeployLock)>) {
+        for (name, lock) in locks {
+            if let Err(e) = self.store.unlock_deploy(lock).await {
+                warn!(
+                    "Unable to release deploy lock for model {name}, it will expire after {:?}: {e:?}",
+                    self.config.deploy_lock_ttl
+                );
+            }
+        }
+    }
+
+    /// Checks that deploying the given manifest won't conflict with what other models in the lattice
+    /// have deployed, such as a provider at a different version or a component ID reused for a
+    /// different image. Returns the error code and message to reply with if there is a conflict
+    async fn check_deploy_conflicts(
+        &self,
+        account_id: Option<&str>,
+        lattice_id: &str,
+        name: &str,
+        staged_model: &Manifest,
+    ) -> Result<(), (ErrorCode, String)> {
+        self.deployed_claims(account_id, lattice_id, &HashSet::from([name]))
+            .await?
+            .check(staged_model)
+    }
+
+    /// Checks that each of the given dependencies is currently deployed at its pinned version.
+    /// Returns the error code and message to reply with for the first one that isn't
+    async fn check_pinned_dependencies(
+        &self,
+        account_id: Option<&str>,
+        lattice_id: &str,
+        dependencies: &[ModelDependency],
+    ) -> Result<(), (ErrorCode, String)> {
+        for dependency in dependencies {
+            let deployed = match self
+                .store
+                .get(account_id, lattice_id, &dependency.name)
+                .await
+            {
+                Ok(Some((manifests, _))) => manifests.deployed_version().map(ToOwned::to_owned),
+                Ok(None) => {
+                    return Err((
+                        ErrorCode::NotFound,
+                        format!("Dependency {} not found", dependency.name),
+                    ))
+                }
+                Err(e) => {
+                    error!(error = %e, "Unable to fetch data");
+                    return Err((storage_error_code(&e), storage_error_message(&e)));
+                }
+            };
+            match deployed {
+                Some(version) if version == dependency.version => (),
+                Some(version) => {
+                    return Err((
+                        ErrorCode::VersionConflict,
+                        format!(
+                            "Dependency {} is deployed at version {version}, but version {} is required",
+                            dependency.name, dependency.version
+                        ),
+                    ))
+                }
+                None => {
+                    return Err((
+                        ErrorCode::VersionConflict,
+                        format!(
+                            "Dependency {} is not deployed, but version {} is required",
+                            dependency.name, dependency.version
+                        ),
+                    ))
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Fetches the named model for a multi-model deploy, returning it with its revision and the
+    /// version to deploy (the latest if not given). The target's pinned dependencies and disabled
+    /// components are checked like a deploy request's. Returns the error code and message to report
+    /// for the model if it can't be deployed
+    async fn resolve_deploy_target(
+        &self,
+        account_id: Option<&str>,
+        lattice_id: &str,
+        name: &str,
+        target: &ModelDeployTarget,
+    ) -> Result<(StoredManifest, u64, Manifest), (ErrorCode, String)> {
+        self.authorize(account_id, lattice_id, ModelOperation::Deploy, name, None)
+            .await?;
+        self.check_pinned_dependencies(account_id, lattice_id, &target.dependencies)
+            .await?;
+        let (manifests, revision) = match self.store.get(account_id, lattice_id, name).await {
+            Ok(Some(m)) => m,
+            Ok(None) => {
+                return Err((
+                    ErrorCode::NotFound,
+                    format!("Model with the name {name} not found"),
+                ))
+            }
+            Err(e) => {
+                error!(error = %e, "Unable to fetch data");
+                return Err((storage_error_code(&e), storage_error_message(&e)));
+            }
+        };
+        if manifests.is_archived() {
+            return Err((
+                ErrorCode::InvalidRequest,
+                format!("Model {name} is archived. Restore it before deploying"),
+            ));
+        }
+        let manifest = match target.version.as_deref() {
+            None | Some(LATEST_VERSION) => manifests.get_current(),
+            Some(v) => manifests.get_version(v).ok_or_else(|| {
+                (
+                    ErrorCode::NotFound,
+                    format!(
+                        "Model with the name {name} does not have the specified version to deploy"
+                    ),
+                )
+            })?,
+        }
+        .to_owned();
+        check_minimum_wadm_version(&manifest)
+            .map_err(|message| (ErrorCode::Unsupported, message))?;
+        check_disabled_components(&manifest, &target.disabled_components)
+            .map_err(|message| (ErrorCode::InvalidRequest, message))?;
+        Ok((manifests, revision, manifest))
+    }
+
+    /// Collects what the deployed versions of all models in the lattice, other than the excluded
+    /// ones, have claimed. Previous versions of the models being deployed are excluded because
+    /// upgrading versions is a valid case for changing provider versions
+    async fn deployed_claims(
+        &self,
+        account_id: Option<&str>,
+        lattice_id: &str,
+        excluded: &HashSet<&str>,
+    ) -> Result<DeployClaims, (ErrorCode, String)> {
+        // Retrieve all stored models in the lattice
+        let stored_models = match self.store.list(account_id, lattice_id).await {
+            Ok(d) => d,
+            Err(e) => {
+                error!(error = %e, "Unable to fetch data");
+                return Err((storage_error_code(&e), storage_error_message(&e)));
+            }
+        };
+
+        let mut claims = DeployClaims::default();
+        for model_summary in stored_models.iter() {
+            // Excluding models that do not have a deployed version at present
+            if model_summary.deployed_version.is_none()
+                || excluded.contains(model_summary.name.as_str())
+            {
+                continue;
+            }
+            let stored_manifest = match self
+                .store
+                .get(account_id, lattice_id, &model_summary.name)
+                .await
+            {
+                Ok(Some((m, _))) => m,
+                Ok(None) => continue,
+                Err(e) => {
+                    error!(error = %e, "Unable to fetch data");
+                    return Err((storage_error_code(&e), storage_error_message(&e)));
+                }
+            };
+            // Disabled components aren't running, so they can't conflict with anything
+            if let Some(deployed_manifest) = stored_manifest.get_deployed_enabled() {
+                claims.add(stored_manifest.name(), &deployed_manifest);
+            }
+        }
+        Ok(claims)
+    }
+
+    /// Checks with the configured [`Authorizer`] that the operation on the given model is allowed,
+    /// fetching the model's current metadata from the store to pass along. Returns the error code
+    /// and message to reply with if the operation isn't allowed
+    async fn authorize(
+        &self,
+        account_id: Option<&str>,
+        lattice_id: &str,
+        operation: ModelOperation,
+        model_name: &str,
+        proposed: Option<&Metadata>,
+    ) -> Result<(), (ErrorCode, String)> {
+        let authorizer = match self.authorizer.as_ref() {
+            Some(authorizer) => authorizer,
+            None => return Ok(()),
+        };
+        let current = match self.store.get(account_id, lattice_id, model_name).await {
+            Ok(stored) => stored.map(|(manifest, _)| manifest.get_current().metadata.clone()),
+            Err(e) => {
+                error!(error = %e, "Unable to fetch data");
+                return Err((storage_error_code(&e), storage_error_message(&e)));
+            }
+        };
+        authorizer
+            .authorize(AuthorizationRequest {
+                account_id,
+                lattice_id,
+                operation,
+                model_name,
+                current: current.as_ref(),
+                proposed,
+            })
+            .await
+            .map_err(|reason| {
+                debug!(%reason, %operation, "Request was denied by the authorizer");
+                (
+                    ErrorCode::Forbidden,
+                    format!("Not allowed to {operation} model {model_name}: {reason}"),
+                )
+            })
+    }
+
+    /// Returns an error message if the given model name uses a reserved prefix that the account
+    /// isn't allowed to use
+    fn check_reserved_name(&self, name: &str, account_id: Option<&str>) -> Option<String> {
+        self.config.reserved_prefix(name, account_id).map(|prefix| {
+            format!(
+                "Manifest name {name} is not allowed: names starting with {prefix} are reserved. Choose a name without a reserved prefix"
+            )
+        })
+    }
+
+    /// Returns a message describing the conflict if model names are unique across accounts and
+    /// another account already has a model with the given name in the lattice
+    async fn check_name_collision(
+        &self,
+        account_id: Option<&str>,
+        lattice_id: &str,
+        name: &str,
+    ) -> anyhow::Result<Option<String>> {
+        if self.config.model_name_policy != ModelNamePolicy::LatticeGlobal {
+            return Ok(None);
+        }
+        let owners = self
+            .store
+            .other_model_owners(account_id, lattice_id, name)
+            .await?;
+        Ok(owners.into_iter().next().map(|owner| {
+            let owner = owner.map_or_else(
+                || "a model stored without an account".to_string(),
+                |owner| format!("account {owner}"),
+            );
+            format!(
+                "Model {name} already exists in lattice {lattice_id} and is owned by {owner}. Model names are unique across all accounts in this lattice, so choose a different name"
+            )
+        }))
+    }
+
+    /// Returns a message describing the problem if the account has already reached its model
+    /// quota, meaning no new models can be created
+    async fn check_model_quota(
+        &self,
+        account_id: Option<&str>,
+        lattice_id: &str,
+    ) -> anyhow::Result<Option<String>> {
+        let quota = match self.config.model_quota(account_id) {
+            Some(quota) => quota,
+            None => return Ok(None),
+        };
+        // Archived models are kept around to be restored, so they don't count
+        let existing_models = self
+            .store
+            .list(account_id, lattice_id)
+            .await?
+            .into_iter()
+            .filter(|model| !model.archived)
+            .count();
+        if existing_models < quota {
+            return Ok(None);
+        }
+        Ok(Some(format!(
+            "Model quota exceeded: the account already has {existing_models} models, which is the maximum of {quota}. Delete an existing model before creating a new one"
+        )))
+    }
+
+    /// Validates and stores a single exported model, notifying processors if the model was marked
+    /// as deployed. Returns the outcome along with a message describing it
+    async fn import_model(
+        &self,
+        account_id: Option<&str>,
+        lattice_id: &str,
+        mut model: ExportedModel,
+        overwrite: bool,
+    ) -> (ModelImportOutcome, String) {
+        let name = model.name.trim();
+        if let Some(message) = invalid_manifest_name(name) {
+            return (ModelImportOutcome::Error, message);
+        }
+        if let Some(message) = self.check_reserved_name(name, account_id) {
+            return (ModelImportOutcome::Error, message);
+        }
+        if model.versions.is_empty() {
+            return (
+                ModelImportOutcome::Error,
+                format!("Model {name} does not have any versions to import"),
+            );
+        }
+
+        let mut stored = StoredManifest::default();
+        for manifest in std::mem::take(&mut model.versions) {
+            // Imported versions are stored in the same shape as put ones
+            let (manifest, migrations) = self.prepare_parsed_manifest(manifest);
+            let version = manifest.version().to_owned();
+            if !migrations.is_empty() {
+                debug!(%version, ?migrations, "Migrated imported manifest to the current schema");
+            }
+            if manifest.metadata.name != name {
+                return (
+                    ModelImportOutcome::Error,
+                    format!(
+                        "Version {version} of model {name} has a different name: {}",
+                        manifest.metadata.name
+                    ),
+                );
+            }
+            let version_validation_output = validate_manifest_version(&version);
+            let version_errors = version_validation_output.errors();
+            if !version_errors.is_empty() {
+                return (
+                    ModelImportOutcome::Error,
+                    format!(
+                        "Version {version} of model {name} is invalid: {}",
+                        version_errors
+                            .iter()
+                            .map(|e| e.msg.clone())
+                            .collect::<Vec<String>>()
+                            .join("\n")
+                    ),
+                );
+            }
+            if let Err(e) = validate(&manifest, &self.config.manifest_limits)
+                .await
+                .into_result()
+            {
+                return (
+                    ModelImportOutcome::Error,
+                    format!("Version {version} of model {name} is invalid: {e}"),
+                );
+            }
+            if !stored.add_version(manifest) {
+                return (
+                    ModelImportOutcome::Error,
+                    format!("Version {version} of model {name} is specified more than once"),
+                );
+            }
+        }
+        if let Err(message) = restore_exported_state(&mut stored, name, &model) {
+            return (ModelImportOutcome::Error, message);
+        }
+
+        if let Err((_, message)) = self
+            .authorize(
+                account_id,
+                lattice_id,
+                ModelOperation::Import,
+                name,
+                Some(&stored.get_current().metadata),
+            )
+            .await
+        {
+            return (ModelImportOutcome::Error, message);
+        }
+
+        // Imports can store the model as deployed, so they hold the deploy lock like a deploy
+        self.with_deploy_locks(
+            account_id,
+            lattice_id,
+            &[name],
+            self.import_locked_model(account_id, lattice_id, name, stored, overwrite),
+        )
+        .await
+        .unwrap_or_else(|(_, message)| (ModelImportOutcome::Error, message))
+    }
+
+    /// Stores a validated imported model while holding its deploy lock, notifying processors if it
+    /// was marked as deployed. See [`Handler::import_model`]
+    async fn import_locked_model(
+        &self,
+        account_id: Option<&str>,
+        lattice_id: &str,
+        name: &str,
+        stored: StoredManifest,
+        overwrite: bool,
+    ) -> (ModelImportOutcome, String) {
+        let (outcome, revision) = match self.store.get(account_id, lattice_id, name).await {
+            Ok(Some(_)) if !overwrite => {
+                return (
+                    ModelImportOutcome::Skipped,
+                    format!("Model {name} already exists. Set overwrite to replace it"),
+                )
+            }
+            Ok(Some((existing, _))) if existing.deployed_version().is_some() => {
+                return (
+                    ModelImportOutcome::Error,
+                    format!("Model {name} is currently deployed and cannot be overwritten. Undeploy it first"),
+                )
+            }
+            Ok(Some((_, revision))) => (ModelImportOutcome::Overwritten, revision),
+            Ok(None) => {
+                match self.check_model_quota(account_id, lattice_id).await {
+                    Ok(None) => (),
+                    Ok(Some(message)) => return (ModelImportOutcome::Error, message),
+                    Err(e) => {
+                        error!(error = %e, "Unable to list models to check quota");
+                        return (ModelImportOutcome::Error, storage_error_message(&e));
+                    }
+                }
+                match self
+                    .check_name_collision(account_id, lattice_id, name)
+                    .await
+                {
+                    Ok(None) => (ModelImportOutcome::Created, 0),
+                    Ok(Some(message)) => return (ModelImportOutcome::Error, message),
+                    Err(e) => {
+                        error!(error = %e, "Unable to check other accounts for the model name");
+                        return (ModelImportOutcome::Error, storage_error_message(&e));
+                    }
+                }
+            }
+            Err(e) => {
+                error!(error = %e, "Unable to fetch data");
+                return (
+                    ModelImportOutcome::Error,
+                    storage_error_message(&e),
+                );
+            }
+        };
+
+        let total_versions = stored.count();
+        let deployed = stored.get_deployed_enabled();
+        // Storing the model as deployed deploys it, so it must pass the same checks as a deploy
+        if let Some(manifest) = deployed.as_ref() {
+            if let Err(message) = check_minimum_wadm_version(manifest) {
+                return (ModelImportOutcome::Error, message);
+            }
+            if let Err((_, message)) = self
+                .check_deploy_conflicts(account_id, lattice_id, name, manifest)
+                .await
+            {
+                return (ModelImportOutcome::Error, message);
+            }
+        }
+        if let Err(e) = self
+            .store
+            .set(account_id, lattice_id, stored, Some(revision))
+            .await
+        {
+            error!(error = %e, "Unable to store imported model");
+            let message = if RevisionConflict::is_conflict(&e) {
+                format!("Model {name} was updated concurrently. Please retry the import")
+            } else {
+                storage_error_message(&e)
+            };
+            return (ModelImportOutcome::Error, message);
+        }
+
+        if let Some(manifest) = deployed {
+            trace!("Sending deployed notification for imported model");
+            if let Err(e) = self.notifier.deployed(lattice_id, manifest).await {
+                error!(error = ?e, "Error when attempting to send deployed notification");
+                return (
+                    ModelImportOutcome::Error,
+                    format!("Imported model {name}, but was unable to notify processors that it is deployed. Deploy the model again to retry"),
+                );
+            }
+        }
+
+        (
+            outcome,
+            format!("Successfully imported model {name} with {total_versions} versions"),
+        )
+    }
+
+    /// Deletes a version of the given model, deleting the whole model if it was the last version and
+    /// undeploying it if the deleted version was deployed. The returned response has `undeploy` set
+    /// if processors need to be notified of the undeploy
+    async fn delete_model_version(
+        &self,
+        account_id: Option<&str>,
+        lattice_id: &str,
+        name: &str,
+        version: &str,
+    ) -> DeleteModelResponse {
+        let mut attempt = 0;
+        loop {
+            attempt += 1;
+            let (mut current, current_revision) =
+                match self.store.get(account_id, lattice_id, name).await {
+                    Ok(Some(m)) => m,
+                    Ok(None) => {
+                        return DeleteModelResponse {
+                            result: DeleteResult::Noop,
+                            message: format!("Model {name} doesn't exist"),
+                            undeploy: false,
+                            error_code: None,
+                            retry_after_ms: None,
+                        }
+                    }
+                    Err(e) => {
+                        error!(error = %e, "Unable to fetch current data data");
+                        return DeleteModelResponse {
+                            result: DeleteResult::Error,
+                            message: storage_error_message(&e),
+                            undeploy: false,
+                            error_code: Some(storage_error_code(&e)),
+                            retry_after_ms: storage_error_code(&e).retry_after_ms(),
+                        };
+                    }
+                };
+            if !current.delete_version(version) {
+                return DeleteModelResponse {
+                    result: DeleteResult::Noop,
+                    message: format!("Model version {} doesn't exist", version),
+                    undeploy: false,
+                    error_code: None,
+                    retry_after_ms: None,
+                };
+            }
+
+            let res = if current.is_empty() {
+                // If we deleted the last one, delete the model from the store
+                self.store
+                    .delete_at_revision(account_id, lattice_id, name, current_revision)
+                    .await
+                    .map(|_| DeleteModelResponse {
+                        result: DeleteResult::Deleted,
+                        message: format!("Successfully deleted last version of model {}", name),
+                        // By default if it is all gone, we definitely undeployed things
+                        undeploy: true,
+                        error_code: None,
+                        retry_after_ms: None,
+                    })
+            } else {
+                // If the version we deleted was the deployed one, undeploy it
+                let deployed_version = current.deployed_version();
+                let mut undeploy = if deployed_version.map(|v| v == version).unwrap_or(false) {
+                    trace!(?deployed_version, deleted_version = %version, "Deployed version matches deleted. Will undeploy");
+                    current.undeploy();
+                    true
+                } else {
+                    trace!(?deployed_version, deleted_version = %version, "Deployed version does not match deleted version. Will not undeploy");
+                    false
+                };
+                let mut message =
+                    format!("Successfully deleted version {} of model {}", version, name);
+                // The deployed version should always still exist at this point, but make sure we
+                // never store a model deployed at a missing version
+                if let Some(missing) = current.clear_missing_deployed_version() {
+                    warn!("Deployed version {missing} of model {name} no longer exists after deleting version {version}, undeploying");
+                    message.push_str(&format!(". Warning: deployed version {missing} no longer exists, so the model was undeployed"));
+                    undeploy = true;
+                }
+                self.store
+                    .set(account_id, lattice_id, current, Some(current_revision))
+                    .await
+                    .map(|_| DeleteModelResponse {
+                        result: DeleteResult::Deleted,
+                        message,
+                        undeploy,
+                        error_code: None,
+                        retry_after_ms: None,
+                    })
+            };
+
+            match res {
+                Ok(resp) => return resp,
+                Err(e)
+                    if RevisionConflict::is_conflict(&e)
+                        && attempt < MAX_REVISION_CONFLICT_ATTEMPTS =>
+                {
+                    debug!(error = %e, %attempt, "Model was updated concurrently, retrying");
+                    continue;
+                }
+                Err(e) => {
+                    error!(error = %e, "Unable to delete data");
+                    return DeleteModelResponse {
+                        result: DeleteResult::Error,
+                        message: storage_error_message(&e),
+                        undeploy: false,
+                        error_code: Some(storage_error_code(&e)),
+                        retry_after_ms: storage_error_code(&e).retry_after_ms(),
+                    };
+                }
+            }
+        }
+    }
+
+    /// Archives the given model, undeploying it if it is deployed. The returned response has
+    /// `undeploy` set if processors need to be notified of the undeploy
+    async fn archive_model(
+        &self,
+        account_id: Option<&str>,
+        lattice_id: &str,
+        name: &str,
+    ) -> DeleteModelResponse {
+        let mut attempt = 0;
+        loop {
+            attempt += 1;
+            let (mut manifests, current_revision) =
+                match self.store.get(account_id, lattice_id, name).await {
+                    Ok(Some(m)) => m,
+                    Ok(None) => {
+                        return DeleteModelResponse {
+                            result: DeleteResult::Noop,
+                            message: format!("Model {name} doesn't exist"),
+                            undeploy: false,
+                            error_code: None,
+                            retry_after_ms: None,
+                        }
+                    }
+                    Err(e) => {
+                        error!(error = %e, "Unable to fetch data");
+                        return DeleteModelResponse {
+                            result: DeleteResult::Error,
+                            message: storage_error_message(&e),
+                            undeploy: false,
+                            error_code: Some(storage_error_code(&e)),
+                            retry_after_ms: storage_error_code(&e).retry_after_ms(),
+                        };
+                    }
+                };
+            if manifests.is_archived() {
+                return DeleteModelResponse {
+                    result: DeleteResult::Noop,
+                    message: format!("Model {name} is already archived"),
+                    undeploy: false,
+                    error_code: None,
+                    retry_after_ms: None,
+                };
+            }
+            let undeploy = manifests.archive();
+
+            match self
+                .store
+                .set(account_id, lattice_id, manifests, Some(current_revision))
+                .await
+            {
+                Ok(_) => {
+                    return DeleteModelResponse {
+                        result: DeleteResult::Deleted,
+                        message: format!(
+                            "Successfully archived model {name}. It can be restored with a restore request"
+                        ),
+                        undeploy,
+                        error_code: None,
+                        retry_after_ms: None,
+                    }
+                }
+                Err(e)
+                    if RevisionConflict::is_conflict(&e)
+                        && attempt < MAX_REVISION_CONFLICT_ATTEMPTS =>
+                {
+                    debug!(error = %e, %attempt, "Model was updated concurrently, retrying");
+                    continue;
+                }
+                Err(e) => {
+                    error!(error = %e, "Unable to store updated data");
+                    return DeleteModelResponse {
+                        result: DeleteResult::Error,
+                        message: storage_error_message(&e),
+                        undeploy: false,
+                        error_code: Some(storage_error_code(&e)),
+                        retry_after_ms: storage_error_code(&e).retry_after_ms(),
+                    };
+                }
+            }
+        }
+    }
+
+    /// Undeploys the given model, retrying on conflicts and sending the undeploy notification. The
+    /// returned response is the reply that should be sent to the requester
+    async fn undeploy(
+        &self,
+        account_id: Option<&str>,
+        lattice_id: &str,
+        name: &str,
+    ) -> DeployModelResponse {
+        if let Err((code, message)) = self
+            .authorize(account_id, lattice_id, ModelOperation::Undeploy, name, None)
+            .await
+        {
+            return DeployModelResponse::error(code, message);
+        }
+
+        match self
+            .with_deploy_locks(
+                account_id,
+                lattice_id,
+                &[name],
+                self.undeploy_locked(account_id, lattice_id, name),
+            )
+            .await
+        {
+            Ok(reply) => reply,
+            Err((code, message)) => DeployModelResponse::error(code, message),
+        }
+    }
+
+    /// Undeploys a model while holding its deploy lock. See [`Handler::undeploy`]
+    async fn undeploy_locked(
+        &self,
+        account_id: Option<&str>,
+        lattice_id: &str,
+        name: &str,
+    ) -> DeployModelResponse {
+        let mut attempt = 0;
+        let reply = loop {
+            attempt += 1;
+            trace!("Fetching current data from store");
+            let (mut manifests, current_revision) =
+                match self.store.get(account_id, lattice_id, name).await {
+                    Ok(Some(m)) => m,
+                    Ok(None) => {
+                        return DeployModelResponse {
+                            result: DeployResult::NotFound,
+                            message: format!("Model with the name {name} not found"),
+                            status: None,
+                            error_code: Some(ErrorCode::NotFound),
+                            retry_after_ms: None,
+                            disabled_components: Vec::new(),
+                            revision: 0,
+                        };
+                    }
+                    Err(e) => {
+                        error!(error = %e, "Unable to fetch data");
+                        return DeployModelResponse {
+                            result: DeployResult::Error,
+                            message: storage_error_message(&e),
+                            status: None,
+                            error_code: Some(storage_error_code(&e)),
+                            retry_after_ms: storage_error_code(&e).retry_after_ms(),
+                            disabled_components: Vec::new(),
+                            revision: 0,
+                        };
+                    }
+                };
+
+            if !manifests.undeploy() {
+                trace!("Manifest was already undeployed");
+                break DeployModelResponse {
+                    result: DeployResult::Acknowledged,
+                    message: format!("Model {} was already undeployed", name),
+                    status: None,
+                    error_code: None,
+                    retry_after_ms: None,
+                    disabled_components: Vec::new(),
+                    revision: current_revision,
+                };
+            }
+            trace!("Manifest undeployed. Storing updated manifest");
+
+            match self
+                .store
+                .set(account_id, lattice_id, manifests, Some(current_revision))
+                .await
+            {
+                Ok(revision) => {
+                    break DeployModelResponse {
+                        result: DeployResult::Acknowledged,
+                        message: format!("Successfully undeployed model {}", name),
+                        status: None,
+                        error_code: None,
+                        retry_after_ms: None,
+                        disabled_components: Vec::new(),
+                        revision,
+                    }
+                }
+                Err(e)
+                    if RevisionConflict::is_conflict(&e)
+                        && attempt < MAX_REVISION_CONFLICT_ATTEMPTS =>
+                {
+                    debug!(error = %e, %attempt, "Model was updated concurrently, retrying");
+                    continue;
+                }
+                Err(e) => {
+                    error!(error = %e, "Unable to store updated data");
+                    break DeployModelResponse {
+                        result: DeployResult::Error,
+                        message: storage_error_message(&e),
+                        status: None,
+                        error_code: Some(storage_error_code(&e)),
+                        retry_after_ms: storage_error_code(&e).retry_after_ms(),
+                        disabled_components: Vec::new(),
+                        revision: 0,
+                    };
+                }
+            }
+        };
+        // We always want to resend in an undeploy in case things failed last time
+        if matches!(reply.result, DeployResult::Acknowledged) {
+            trace!("Sending undeploy notification");
+            if let Err(e) = self.notifier.undeployed(lattice_id, name).await {
+                error!(error = ?e, "Error when attempting to send undeploy notification");
+                return DeployModelResponse {
+                    result: DeployResult::Error,
+                    message: "Error notifying processors of undeployed manifest. This is likely a transient error, so please retry the request".to_string(),
+                    status: None,
+                    error_code: Some(notification_error_code(&e)),
+                    retry_after_ms: notification_error_code(&e).retry_after_ms(),
+                    disabled_components: Vec::new(),
+                    revision: 0,
+                };
+            }
+        }
+        reply
+    }
+
+    /// Stages a version of a model as a canary and notifies processors, returning the response to
+    /// send
+    async fn stage(
+        &self,
+        account_id: Option<&str>,
+        lattice_id: &str,
+        name: &str,
+        version: Option<String>,
+    ) -> DeployModelResponse {
+        if let Err((code, message)) = self
+            .authorize(account_id, lattice_id, ModelOperation::Stage, name, None)
+            .await
+        {
+            return DeployModelResponse::error(code, message);
+        }
+
+        match self
+            .with_deploy_locks(
+                account_id,
+                lattice_id,
+                &[name],
+                self.stage_locked(account_id, lattice_id, name, version),
+            )
+            .await
+        {
+            Ok(reply) => reply,
+            Err((code, message)) => DeployModelResponse::error(code, message),
+        }
+    }
+
+    /// Stages a version of a model while holding its deploy lock. See [`Handler::stage`]
+    async fn stage_locked(
+        &self,
+        account_id: Option<&str>,
+        lattice_id: &str,
+        name: &str,
+        version: Option<String>,
+    ) -> DeployModelResponse {
+        let mut attempt = 0;
+        let (manifest, revision) = loop {
+            attempt += 1;
+            trace!("Fetching current data from store");
+            let (mut manifests, current_revision) =
+                match self.store.get(account_id, lattice_id, name).await {
+                    Ok(Some(m)) => m,
+                    Ok(None) => {
+                        return DeployModelResponse {
+                            result: DeployResult::NotFound,
+                            message: format!("Model with the name {name} not found"),
+                            status: None,
+                            error_code: Some(ErrorCode::NotFound),
+                            retry_after_ms: None,
+                            disabled_components: Vec::new(),
+                            revision: 0,
+                        };
+                    }
+                    Err(e) => {
+                        error!(error = %e, "Unable to fetch data");
+                        return DeployModelResponse::error(
+                            storage_error_code(&e),
+                            storage_error_message(&e),
+                        );
+                    }
+                };
+            if manifests.is_archived() {
+                return DeployModelResponse::error(
+                    ErrorCode::InvalidRequest,
+                    format!("Model {name} is archived. Restore it before staging"),
+                );
+            }
+            if !manifests.stage(version.clone()) {
+                return DeployModelResponse::error(
+                    ErrorCode::NotFound,
+                    format!(
+                        "Model with the name {name} does not have the specified version to stage"
+                    ),
+                );
+            }
+            // SAFETY: We just staged this version, so it exists
+            let manifest = manifests.get_staged().unwrap().to_owned();
+            audit::record_version(manifest.version());
+            if manifests.is_deployed(manifest.version()) {
+                return DeployModelResponse::error(
+                    ErrorCode::InvalidRequest,
+                    format!(
+                        "Model {name} {} is already deployed, so it can't be staged",
+                        manifest.version()
+                    ),
+                );
+            }
+            if let Err(message) = check_minimum_wadm_version(&manifest) {
+                return DeployModelResponse::error(ErrorCode::Unsupported, message);
+            }
+            // Processors start running the staged version, so it must not conflict with what is
+            // already deployed any more than a deploy can
+            if let Err((code, message)) = self
+                .check_deploy_conflicts(account_id, lattice_id, name, &manifest)
+                .await
+            {
+                return DeployModelResponse::error(code, message);
+            }
+
+            match self
+                .store
+                .set(account_id, lattice_id, manifests, Some(current_revision))
+                .await
+            {
+                Ok(revision) => break (manifest, revision),
+                Err(e)
+                    if RevisionConflict::is_conflict(&e)
+                        && attempt < MAX_REVISION_CONFLICT_ATTEMPTS =>
+                {
+                    debug!(error = %e, %attempt, "Model was updated concurrently, retrying");
+                    continue;
+                }
+                Err(e) => {
+                    error!(error = %e, "Unable to store updated data");
+                    return DeployModelResponse::error(
+                        storage_error_code(&e),
+                        storage_error_message(&e),
+                    );
+                }
+            }
+        };
+
+        let version = manifest.version().to_owned();
+        trace!("Sending staged notification");
+        if let Err(e) = self.notifier.staged(lattice_id, manifest).await {
+            error!(error = ?e, "Error when attempting to send staged notification");
+            return DeployModelResponse::error(
+                notification_error_code(&e),
+                "Error notifying processors of newly staged manifest. This is likely a transient error, so please retry the request".to_string(),
+            );
+        }
+        DeployModelResponse {
+            result: DeployResult::Acknowledged,
+            message: format!("Successfully staged model {name} {version}"),
+            status: None,
+            error_code: None,
+            retry_after_ms: None,
+            disabled_components: Vec::new(),
+            revision,
+        }
+    }
+
+    /// Deploys the staged version of a model and notifies processors, returning the response to
+    /// send. This runs the same checks as a deploy request
+    async fn promote(
+        &self,
+        account_id: Option<&str>,
+        lattice_id: &str,
+        name: &str,
+    ) -> DeployModelResponse {
+        if let Err((code, message)) = self
+            .authorize(account_id, lattice_id, ModelOperation::Promote, name, None)
+            .await
+        {
+            return DeployModelResponse::error(code, message);
+        }
+
+        match self
+            .with_deploy_locks(
+                account_id,
+                lattice_id,
+                &[name],
+                self.promote_locked(account_id, lattice_id, name),
+            )
+            .await
+        {
+            Ok(reply) => reply,
+            Err((code, message)) => DeployModelResponse::error(code, message),
+        }
+    }
+
+    /// Promotes the staged version of a model while holding its deploy lock. See
+    /// [`Handler::promote`]
+    async fn promote_locked(
+        &self,
+        account_id: Option<&str>,
+        lattice_id: &str,
+        name: &str,
+    ) -> DeployModelResponse {
+        let mut attempt = 0;
+        let (manifest, revision) = loop {
+            attempt += 1;
+            trace!("Fetching current data from store");
+            let (mut manifests, current_revision) =
+                match self.store.get(account_id, lattice_id, name).await {
+                    Ok(Some(m)) => m,
+                    Ok(None) => {
+                        return DeployModelResponse {
+                            result: DeployResult::NotFound,
+                            message: format!("Model with the name {name} not found"),
+                            status: None,
+                            error_code: Some(ErrorCode::NotFound),
+                            retry_after_ms: None,
+                            disabled_components: Vec::new(),
+                            revision: 0,
+                        };
+                    }
+                    Err(e) => {
+                        error!(error = %e, "Unable to fetch data");
+                        return DeployModelResponse::error(
+                            storage_error_code(&e),
+                            storage_error_message(&e),
+                        );
+                    }
+                };
+            let manifest = match manifests.get_staged() {
+                Some(m) => m.to_owned(),
+                None => {
+                    return DeployModelResponse::error(
+                        ErrorCode::InvalidRequest,
+                        format!("Model {name} does not have a staged version to promote"),
+                    )
+                }
+            };
+            audit::record_version(manifest.version());
+            if let Err(message) = check_minimum_wadm_version(&manifest) {
+                return DeployModelResponse::error(ErrorCode::Unsupported, message);
+            }
+            if let Err((code, message)) = self
+                .check_deploy_conflicts(account_id, lattice_id, name, &manifest)
+                .await
+            {
+                return DeployModelResponse::error(code, message);
+            }
+            manifests.promote();
+
+            match self
+                .store
+                .set(account_id, lattice_id, manifests, Some(current_revision))
+                .await
+            {
+                Ok(revision) => break (manifest, revision),
+                Err(e)
+                    if RevisionConflict::is_conflict(&e)
+                        && attempt < MAX_REVISION_CONFLICT_ATTEMPTS =>
+                {
+                    debug!(error = %e, %attempt, "Model was updated concurrently, retrying");
+                    continue;
+                }
+                Err(e) => {
+                    error!(error = %e, "Unable to store updated data");
+                    return DeployModelResponse::error(
+                        storage_error_code(&e),
+                        storage_error_message(&e),
+                    );
+                }
+            }
+        };
+
+        let version = manifest.version().to_owned();
+        trace!("Sending deployed notification");
+        if let Err(e) = self.notifier.deployed(lattice_id, manifest).await {
+            error!(error = ?e, "Error when attempting to send deployed notification");
+            return DeployModelResponse::error(
+                notification_error_code(&e),
+                "Error notifying processors of newly deployed manifest. This is likely a transient error, so please retry by sending a deploy request".to_string(),
+            );
+        }
+        DeployModelResponse {
+            result: DeployResult::Acknowledged,
+            message: format!("Successfully promoted model {name} {version}"),
+            status: None,
+            error_code: None,
+            retry_after_ms: None,
+            disabled_components: Vec::new(),
+            revision,
+        }
+    }
+
+    /// Applies the metadata patch to a stored version of a model, storing it under the same version
+    /// and notifying processors if that version is deployed. Returns the response to send
+    async fn patch_metadata(
+        &self,
+        account_id: Option<&str>,
+        lattice_id: &str,
+        name: &str,
+        req: PatchMetadataRequest,
+    ) -> PatchMetadataResponse {
+        // The version annotation identifies the stored version, so it can't be patched
+        if req.annotations.contains_key(VERSION_ANNOTATION_KEY)
+            || req
+                .remove_annotations
+                .iter()
+                .any(|key| key == VERSION_ANNOTATION_KEY)
+        {
+            return PatchMetadataResponse::error(
+                ErrorCode::InvalidRequest,
+                format!("The {VERSION_ANNOTATION_KEY} annotation can't be patched. Put a new version instead"),
+            );
+        }
+
+        // Patching a deployed version notifies processors of it, so hold the deploy lock to keep a
+        // deploy of another version from landing between the write and the notification
+        match self
+            .with_deploy_locks(
+                account_id,
+                lattice_id,
+                &[name],
+                self.patch_metadata_locked(account_id, lattice_id, name, req),
+            )
+            .await
+        {
+            Ok(reply) => reply,
+            Err((code, message)) => PatchMetadataResponse::error(code, message),
+        }
+    }
+
+    /// Patches a model's metadata while holding its deploy lock. See [`Handler::patch_metadata`]
+    async fn patch_metadata_locked(
+        &self,
+        account_id: Option<&str>,
+        lattice_id: &str,
+        name: &str,
+        req: PatchMetadataRequest,
+    ) -> PatchMetadataResponse {
+        let mut attempt = 0;
+        let (manifest, deployed, disabled) = loop {
+            attempt += 1;
+            trace!("Fetching current data from store");
+            let (mut manifests, current_revision) =
+                match self.store.get(account_id, lattice_id, name).await {
+                    Ok(Some(m)) => m,
+                    Ok(None) => {
+                        return PatchMetadataResponse {
+                            result: PatchMetadataResult::NotFound,
+                            message: format!("Model with the name {name} not found"),
+                            metadata: None,
+                            error_code: Some(ErrorCode::NotFound),
+                            retry_after_ms: None,
+                        };
+                    }
+                    Err(e) => {
+                        error!(error = %e, "Unable to fetch data");
+                        return PatchMetadataResponse::error(
+                            storage_error_code(&e),
+                            storage_error_message(&e),
+                        );
+                    }
+                };
+            if manifests.is_archived() {
+                return PatchMetadataResponse::error(
+                    ErrorCode::InvalidRequest,
+                    format!("Model {name} is archived. Restore it before patching its metadata"),
+                );
+            }
+            let current = match req.version.as_deref() {
+                None | Some(LATEST_VERSION) => manifests.get_current(),
+                Some(version) => match manifests.get_version(version) {
+                    Some(m) => m,
+                    None => {
+                        return PatchMetadataResponse {
+                            result: PatchMetadataResult::NotFound,
+                            message: format!("Model {name} with version {version} doesn't exist"),
+                            metadata: None,
+                            error_code: Some(ErrorCode::NotFound),
+                            retry_after_ms: None,
+                        };
+                    }
+                },
+            };
+            audit::record_version(current.version());
+
+            let mut manifest = current.to_owned();
+            let metadata = &mut manifest.metadata;
+            for key in req.remove_labels.iter() {
+                metadata.labels.remove(key);
+            }
+            for key in req.remove_annotations.iter() {
+                metadata.annotations.remove(key);
+            }
+            metadata.labels.extend(req.labels.clone());
+            metadata.annotations.extend(req.annotations.clone());
+            if manifest.metadata == current.metadata {
+                return PatchMetadataResponse {
+                    result: PatchMetadataResult::Noop,
+                    message: format!(
+                        "Metadata of model {name} {} is unchanged",
+                        manifest.version()
+                    ),
+                    metadata: Some(manifest.metadata),
+                    error_code: None,
+                    retry_after_ms: None,
+                };
+            }
+
+            if let Err((code, message)) = self
+                .authorize(
+                    account_id,
+                    lattice_id,
+                    ModelOperation::PatchMetadata,
+                    name,
+                    Some(&manifest.metadata),
+                )
+                .await
+            {
+                return PatchMetadataResponse::error(code, message);
+            }
+            if let Err(e) = validate(&manifest, &self.config.manifest_limits)
+                .await
+                .into_result()
+            {
+                return PatchMetadataResponse::error(ErrorCode::ValidationFailed, e.to_string());
+            }
+
+            let deployed = manifests.is_deployed(manifest.version());
+            let disabled = manifests.disabled_components().to_vec();
+            manifests.replace_version(manifest.clone());
+            match self
+                .store
+                .set(account_id, lattice_id, manifests, Some(current_revision))
+                .await
+            {
+                Ok(_) => break (manifest, deployed, disabled),
+                Err(e)
+                    if RevisionConflict::is_conflict(&e)
+                        && attempt < MAX_REVISION_CONFLICT_ATTEMPTS =>
+                {
+                    debug!(error = %e, %attempt, "Model was updated concurrently, retrying");
+                    continue;
+                }
+                Err(e) => {
+                    error!(error = %e, "Unable to store updated data");
+                    return PatchMetadataResponse::error(
+                        storage_error_code(&e),
+                        storage_error_message(&e),
+                    );
+                }
+            }
+        };
+
+        let version = manifest.version().to_owned();
+        let metadata = manifest.metadata.clone();
+        // Processors keep a copy of the deployed manifest, so send them the patched one
+        if deployed {
+            trace!("Sending deployed notification for patched manifest");
+            let manifest = without_components(&manifest, &disabled);
+            if let Err(e) = self.notifier.deployed(lattice_id, manifest).await {
+                error!(error = ?e, "Error when attempting to send deployed notification");
+                return PatchMetadataResponse::error(
+                    notification_error_code(&e),
+                    "Patched metadata but was unable to notify processors of the deployed manifest. This is likely a transient error, so please retry by sending a deploy request with force set".to_string(),
+                );
+            }
+        }
+        PatchMetadataResponse {
+            result: PatchMetadataResult::Patched,
+            message: format!("Successfully patched metadata of model {name} {version}"),
+            metadata: Some(metadata),
+            error_code: None,
+            retry_after_ms: None,
+        }
+    }
+
+    async fn prune(
+        &self,
+        account_id: Option<&str>,
+        lattice_id: &str,
+        name: &str,
+        req: PruneModelRequest,
+    ) -> PruneModelResponse {
+        if let Err((code, message)) = self
+            .authorize(account_id, lattice_id, ModelOperation::Prune, name, None)
+            .await
+        {
+            return PruneModelResponse::error(code, message);
+        }
+
+        let mut attempt = 0;
+        loop {
+            attempt += 1;
+            trace!("Fetching current data from store");
+            let (mut manifests, current_revision) =
+                match self.store.get(account_id, lattice_id, name).await {
+                    Ok(Some(m)) => m,
+                    Ok(None) => {
+                        return PruneModelResponse {
+                            result: PruneResult::NotFound,
+                            message: format!("Model with the name {name} not found"),
+                            removed: Vec::new(),
+                            error_code: Some(ErrorCode::NotFound),
+                            retry_after_ms: None,
+                        };
+                    }
+                    Err(e) => {
+                        error!(error = %e, "Unable to fetch data");
+                        return PruneModelResponse::error(
+                            storage_error_code(&e),
+                            storage_error_message(&e),
+                        );
+                    }
+                };
+            if manifests.is_archived() {
+                return PruneModelResponse::error(
+                    ErrorCode::InvalidRequest,
+                    format!("Model {name} is archived. Restore it before pruning"),
+                );
+            }
+            // Without a deployed version, pruning would delete everything
+            if manifests.deployed_version().is_none() && !req.keep_latest {
+                return PruneModelResponse::error(
+                    ErrorCode::InvalidRequest,
+                    format!("Model {name} isn't deployed. Set keep_latest to keep the latest version when pruning"),
+                );
+            }
+
+            let removed = manifests.prune(req.keep_latest);
+            if removed.is_empty() {
+                return PruneModelResponse {
+                    result: PruneResult::Noop,
+                    message: format!("Model {name} has no versions to prune"),
+                    removed,
+                    error_code: None,
+                    retry_after_ms: None,
+                };
+            }
+            match self
+                .store
+                .set(account_id, lattice_id, manifests, Some(current_revision))
+                .await
+            {
+                Ok(_) => {
+                    return PruneModelResponse {
+                        result: PruneResult::Pruned,
+                        message: format!(
+                            "Successfully pruned {} versions of model {name}",
+                            removed.len()
+                        ),
+                        removed,
+                        error_code: None,
+                        retry_after_ms: None,
+                    }
+                }
+                Err(e)
+                    if RevisionConflict::is_conflict(&e)
+                        && attempt < MAX_REVISION_CONFLICT_ATTEMPTS =>
+                {
+                    debug!(error = %e, %attempt, "Model was updated concurrently, retrying");
+                    continue;
+                }
+                Err(e) => {
+                    error!(error = %e, "Unable to store updated data");
+                    return PruneModelResponse::error(
+                        storage_error_code(&e),
+                        storage_error_message(&e),
+                    );
+                }
+            }
+        }
+    }
+
+    /// Returns the names of the other deployed models that link to components of the deployed
+    /// version of the given model, sorted by name. Returns an empty list if the model isn't
+    /// deployed
+    async fn find_dependents(
+        &self,
+        account_id: Option<&str>,
+        lattice_id: &str,
+        name: &str,
+    ) -> Result<Vec<String>, (ErrorCode, String)> {
+        let storage_error = |e: anyhow::Error| {
+            error!(error = %e, "Unable to fetch data");
+            (storage_error_code(&e), storage_error_message(&e))
+        };
+
+        let provider = match self
+            .store
+            .get(account_id, lattice_id, name)
+            .await
+            .map_err(storage_error)?
+        {
+            Some((stored, _)) => match stored.get_deployed() {
+                Some(deployed) => deployed.clone(),
+                None => return Ok(Vec::new()),
+            },
+            None => return Ok(Vec::new()),
+        };
+
+        let mut dependents = Vec::new();
+        for model_summary in self
+            .store
+            .list(account_id, lattice_id)
+            .await
+            .map_err(storage_error)?
+            .into_iter()
+            .filter(|m| m.name != name && m.deployed_version.is_some())
+        {
+            let deployed = self
+                .store
+                .get(account_id, lattice_id, &model_summary.name)
+                .await
+                .map_err(storage_error)?;
+            if let Some(deployed) = deployed.as_ref().and_then(|(s, _)| s.get_deployed()) {
+                if links_into(deployed, &provider) {
+                    dependents.push(model_summary.name);
+                }
+            }
+        }
+        dependents.sort();
+        Ok(dependents)
+    }
+
+    /// Returns the other deployed models that would lose the components they link to if the given
+    /// model (or only the given version) was deleted. Deleting a version other than the deployed
+    /// one doesn't undeploy anything, so it never has dependents
+    async fn find_deletion_dependents(
+        &self,
+        account_id: Option<&str>,
+        lattice_id: &str,
+        name: &str,
+        version: Option<&str>,
+    ) -> Result<Vec<String>, (ErrorCode, String)> {
+        if let Some(version) = version {
+            let deployed = match self.store.get(account_id, lattice_id, name).await {
+                Ok(stored) => stored.is_some_and(|(stored, _)| stored.is_deployed(version)),
+                Err(e) => {
+                    error!(error = %e, "Unable to fetch data");
+                    return Err((storage_error_code(&e), storage_error_message(&e)));
+                }
+            };
+            if !deployed {
+                return Ok(Vec::new());
+            }
+        }
+        self.find_dependents(account_id, lattice_id, name).await
+    }
+
+    /// Fetches the statuses of the given models concurrently, with at most
+    /// `status_fetch_concurrency` fetches in flight. Models without a status map to `None`. If a
+    /// status couldn't be read from the stream, it is returned as undeployed with a message saying
+    /// the status is unknown
+    async fn fetch_statuses(
+        &self,
+        account_id: Option<&str>,
+        lattice_id: &str,
+        names: impl IntoIterator<Item = String>,
+    ) -> HashMap<String, Option<StatusInfo>> {
+        fetch_concurrently(
+            names,
+            self.config.status_fetch_concurrency,
+            |name| async move {
+                match self
+                    .get_manifest_status(account_id, lattice_id, &name)
+                    .await
+                {
+                    Ok(status) => status,
+                    Err(e) => {
+                        error!(error = %e, %name, "Unable to fetch status for model");
+                        Some(StatusInfo::undeployed(STATUS_UNAVAILABLE_MESSAGE))
+                    }
+                }
+            },
+        )
+        .await
+    }
+
+    async fn get_manifest_status(
+        &self,
+        account_id: Option<&str>,
+        lattice_id: &str,
+        name: &str,
+    ) -> anyhow::Result<Option<StatusInfo>> {
+        get_status_message(&self.status_stream, account_id, lattice_id, name)
+            .await
+            .map(|status| {
+                status.map(|(_, mut status)| {
+                    mark_stale(&mut status, self.config.status_stale_after);
+                    status
+                })
+            })
+    }
+}
+
+/// Runs a single check for a health request, bounded by [`HEALTH_CHECK_TIMEOUT`]
+async fn health_check(
+    component: &str,
+    check: impl std::future::Future<Output = anyhow::Result<()>>,
+) -> HealthCheck {
+    let start = std::time::Instant::now();
+    let result = with_timeout("health check", HEALTH_CHECK_TIMEOUT, check).await;
+    let latency_ms = start.elapsed().as_millis() as u64;
+    match result {
+        Ok(()) => HealthCheck {
+            component: component.to_owned(),
+            healthy: true,
+            message: String::new(),
+            latency_ms,
+        },
+        Err(e) => {
+            warn!("Health check for {component} failed: {e:?}");
+            HealthCheck {
+                component: component.to_owned(),
+                healthy: false,
+                message: e.to_string(),
+                latency_ms,
+            }
+        }
+    }
+}
+
+/// Runs `fetch` for each of the names with at most `concurrency` fetches in flight at once,
+/// returning the results keyed by name
+async fn fetch_concurrently<F, Fut, T>(
+    names: impl IntoIterator<Item = String>,
+    concurrency: usize,
+    fetch: F,
+) -> HashMap<String, T>
+where
+    F: Fn(String) -> Fut,
+    Fut: std::future::Future<Output = T>,
+{
+    futures::stream::iter(names)
+        .map(|name| {
+            let fut = fetch(name.clone());
+            async move { (name, fut.await) }
+        })
+        // A concurrency of 0 would never make progress
+        .buffer_unordered(concurrency.max(1))
+        .collect()
+        .await
+}
+
+/// Returns the subjects the status of the given model can be found on, in the order they should be
+/// checked. Models owned by an account publish to the account scoped subject, but statuses
+/// published before status subjects included the account are on the accountless subject
+fn status_subjects(account_id: Option<&str>, lattice_id: &str, name: &str) -> Vec<String> {
+    let mut subjects = vec![status_topic(account_id, lattice_id, name)];
+    if account_id.is_some() {
+        subjects.push(status_topic(None, lattice_id, name));
+    }
+    subjects
+}
+
+/// Sets the subject filter of a status consumer to the [`status_subjects`] of the given model. A
+/// single subject is set with `filter_subject` so that it works with NATS servers that don't
+/// support multiple filter subjects
+fn filter_status_subjects(
+    mut config: PullConfig,
+    account_id: Option<&str>,
+    lattice_id: &str,
+    name: &str,
+) -> PullConfig {
+    let mut subjects = status_subjects(account_id, lattice_id, name);
+    if subjects.len() == 1 {
+        config.filter_subject = subjects.remove(0);
+    } else {
+        config.filter_subjects = subjects;
+    }
+    config
+}
+
+/// Fetches the latest status message for the given model along with its sequence number in the
+/// status stream. Returns `Ok(None)` if the model has no status (or its status can't be decoded),
+/// which means it is undeployed. Errors are only returned when the stream itself couldn't be read
+async fn get_status_message(
+    status_stream: &Stream,
+    account_id: Option<&str>,
+    lattice_id: &str,
+    name: &str,
+) -> anyhow::Result<Option<(u64, StatusInfo)>> {
+    for subject in status_subjects(account_id, lattice_id, name) {
+        if let Some(status) = get_status_message_on_subject(status_stream, &subject).await? {
+            return Ok(Some(status));
+        }
+    }
+    Ok(None)
+}
+
+/// Fetches the latest status message on the given status subject. See [`get_status_message`]
+async fn get_status_message_on_subject(
+    status_stream: &Stream,
+    subject: &str,
+) -> anyhow::Result<Option<(u64, StatusInfo)>> {
+    // Reads can fail while the stream leader is briefly unavailable, so transient errors are
+    // retried rather than reported as a missing status. A model without a status isn't an error
+    // and returns right away
+    let mut backoff = INITIAL_STATUS_READ_BACKOFF;
+    let mut attempt = 1;
+    let raw = loop {
+        // NOTE(brooksmtownsend): We're getting the last raw message instead of direct get here
+        // to ensure we fetch the latest message from the cluster leader.
+        match status_stream.get_last_raw_message_by_subject(subject).await {
+            Ok(raw) => break raw,
+            Err(e) if matches!(e.kind(), LastRawMessageErrorKind::NoMessageFound) => {
+                return Ok(None)
+            }
+            Err(e) if attempt < MAX_STATUS_READ_ATTEMPTS => {
+                debug!(error = ?e, %attempt, ?backoff, "Unable to read status stream, retrying");
+                tokio::time::sleep(backoff).await;
+                backoff *= 2;
+                attempt += 1;
+            }
+            Err(e) => return Err(anyhow!("Unable to read status stream: {e:?}")),
+        }
+    };
+    match B64decoder
+        .decode(raw.payload)
+        .map(|b| serde_json::from_slice::<StatusInfo>(&b))
+    {
+        Ok(Ok(mut status)) => {
+            // Clocks can drift between us and the NATS server, so a message from the "future" is
+            // treated as brand new
+            let age = Utc::now().timestamp() - raw.time.unix_timestamp();
+            status.age_secs = Some(age.max(0) as u64);
+            Ok(Some((raw.sequence, status)))
+        }
+        // Model status is invalid, assuming undeployed
+        _ => {
+            debug!("Unable to decode status message for model, assuming undeployed");
+            Ok(None)
+        }
+    }
+}
+
+/// Marks the status as stale if it is older than the given threshold. Statuses without an age (or
+/// any status if there is no threshold) are left as is
+fn mark_stale(status: &mut StatusInfo, stale_after: Option<Duration>) {
+    if let (Some(age), Some(stale_after)) = (status.age_secs, stale_after) {
+        status.stale = age > stale_after.as_secs();
+    }
+}
+
+/// Reads the most recent status updates (up to `limit`) for the given model from the status stream
+/// using an ephemeral consumer. Updates are returned in chronological order
+async fn get_status_history(
+    status_stream: &Stream,
+    account_id: Option<&str>,
+    lattice_id: &str,
+    name: &str,
+    limit: usize,
+) -> anyhow::Result<Vec<StatusHistoryEntry>> {
+    let config = PullConfig {
+        description: Some(format!(
+            "Ephemeral wadm status history consumer for model {name} in lattice {lattice_id}"
+        )),
+        ack_policy: AckPolicy::None,
+        deliver_policy: DeliverPolicy::All,
+        // Clean up after ourselves even if we fail to delete the consumer below
+        inactive_threshold: Duration::from_secs(30),
+        ..Default::default()
+    };
+    let consumer = status_stream
+        .create_consumer(filter_status_subjects(config, account_id, lattice_id, name))
+        .await
+        .map_err(|e| anyhow!("Unable to create ephemeral consumer: {e:?}"))?;
+
+    // The stream only keeps a handful of messages per subject, so we can read everything that is
+    // pending and keep the last `limit` of them
+    let pending = consumer.cached_info().num_pending as usize;
+    let mut history = VecDeque::with_capacity(limit);
+    if pending > 0 {
+        let mut messages = consumer
+            .fetch()
+            .max_messages(pending)
+            .messages()
+            .await
+            .map_err(|e| anyhow!("Unable to fetch status messages: {e:?}"))?;
+        while let Some(message) = messages.next().await {
+            let message = message.map_err(|e| anyhow!("Unable to read status message: {e:?}"))?;
+            let published = message
+                .info()
+                .map_err(|e| anyhow!("Unable to read status message info: {e:?}"))?
+                .published;
+            let info = match serde_json::from_slice::<StatusInfo>(&message.payload) {
+                Ok(info) => info,
+                Err(e) => {
+                    warn!("Skipping invalid status message: {e:?}");
+                    continue;
+                }
+            };
+            history.push_back(StatusHistoryEntry {
+                timestamp: DateTime::from_timestamp(
+                    published.unix_timestamp(),
+                    published.nanosecond(),
+                )
+                .unwrap_or_default(),
+                info,
+            });
+            if history.len() > limit {
+                history.pop_front();
+            }
+        }
+    }
+
+    let consumer_name = consumer.cached_info().name.clone();
+    if let Err(e) = status_stream.delete_consumer(&consumer_name).await {
+        debug!(error = ?e, "Unable to delete ephemeral status history consumer, it will be cleaned up once inactive");
+    }
+
+    Ok(history.into())
+}
+
+/// Republishes status updates for the given model to the given subject until the watch duration
+/// elapses or nobody is subscribed to the subject anymore.
+///
+/// Every update is published with a reply subject that we listen on. If the subscriber has gone
+/// away, the NATS server responds to that reply subject with a "no responders" status, which is how
+/// we know to stop watching
+async fn watch_model_status(
+    client: &Client,
+    status_stream: &Stream,
+    account_id: Option<&str>,
+    lattice_id: &str,
+    name: &str,
+    subject: &str,
+    duration: Duration,
+) -> anyhow::Result<()> {
+    let config = PullConfig {
+        description: Some(format!(
+            "Ephemeral wadm status watch consumer for model {name} in lattice {lattice_id}"
+        )),
+        ack_policy: AckPolicy::None,
+        // Start with the current status so the watcher doesn't have to fetch it separately
+        deliver_policy: DeliverPolicy::LastPerSubject,
+        // If we go away without cleaning up, make sure the consumer doesn't leak
+        inactive_threshold: Duration::from_secs(30),
+        ..Default::default()
+    };
+    let consumer = status_stream
+        .create_consumer(filter_status_subjects(config, account_id, lattice_id, name))
+        .await
+        .map_err(|e| anyhow!("Unable to create ephemeral consumer: {e:?}"))?;
+    let consumer_name = consumer.cached_info().name.clone();
+
+    let result = async {
+        let mut messages = consumer
+            .messages()
+            .await
+            .map_err(|e| anyhow!("Unable to subscribe to consumer: {e:?}"))?;
+        let probe_inbox = client.new_inbox();
+        let mut probes = client
+            .subscribe(probe_inbox.clone())
+            .await
+            .map_err(|e| anyhow!("Unable to subscribe to probe inbox: {e:?}"))?;
+        let deadline = tokio::time::sleep(duration);
+        tokio::pin!(deadline);
+
+        loop {
+            tokio::select! {
+                _ = &mut deadline => {
+                    trace!("Status watch expired");
+                    break;
+                }
+                Some(probe) = probes.next() => {
+                    if probe.status == Some(StatusCode::NO_RESPONDERS) {
+                        trace!("Status watcher is no longer subscribed");
+                        break;
+                    }
+                }
+                message = messages.next() => {
+                    let message = match message {
+                        Some(Ok(m)) => m,
+                        Some(Err(e)) => {
+                            warn!("Error receiving status update: {e:?}");
+                            continue;
+                        }
+                        None => anyhow::bail!("Status consumer terminated"),
+                    };
+                    let info = match serde_json::from_slice::<StatusInfo>(&message.payload) {
+                        Ok(info) => info,
+                        Err(e) => {
+                            warn!("Skipping invalid status message: {e:?}");
+                            continue;
+                        }
+                    };
+                    client
+                        .publish_with_reply(
+                            subject.to_owned(),
+                            probe_inbox.clone(),
+                            serde_json::to_vec(&info).unwrap_or_default().into(),
+                        )
+                        .await
+                        .map_err(|e| anyhow!("Unable to publish status update: {e:?}"))?;
+                }
+            }
+        }
+        Ok(())
+    }
+    .await;
+
+    if let Err(e) = status_stream.delete_consumer(&consumer_name).await {
+        debug!(error = ?e, "Unable to delete ephemeral status watch consumer, it will be cleaned up once inactive");
+    }
+    result
+}
+
+/// Publishes the lifecycle events for the given model to the subject until the subscriber goes
+/// away (detected with a "no responders" reply to a published event) or the duration elapses
+async fn watch_model_events(
+    client: &Client,
+    audit_topic: String,
+    account_id: Option<&str>,
+    name: &str,
+    subject: &str,
+    duration: Duration,
+) -> anyhow::Result<()> {
+    let mut records = client
+        .subscribe(audit_topic)
+        .await
+        .map_err(|e| anyhow!("Unable to subscribe to audit records: {e:?}"))?;
+    let probe_inbox = client.new_inbox();
+    let mut probes = client
+        .subscribe(probe_inbox.clone())
+        .await
+        .map_err(|e| anyhow!("Unable to subscribe to probe inbox: {e:?}"))?;
+    let deadline = tokio::time::sleep(duration);
+    tokio::pin!(deadline);
+
+    loop {
+        tokio::select! {
+            _ = &mut deadline => {
+                trace!("Model watch expired");
+                break;
+            }
+            Some(probe) = probes.next() => {
+                if probe.status == Some(StatusCode::NO_RESPONDERS) {
+                    trace!("Model watcher is no longer subscribed");
+                    break;
+                }
+            }
+            record = records.next() => {
+                let Some(record) = record else {
+                    anyhow::bail!("Audit record subscription terminated");
+                };
+                let record = match serde_json::from_slice::<AuditRecord>(&record.payload) {
+                    Ok(record) => record,
+                    Err(e) => {
+                        warn!("Skipping invalid audit record: {e:?}");
+                        continue;
+                    }
+                };
+                if record.account_id.as_deref() != account_id {
+                    continue;
+                }
+                let Some(event) = record.model_event().filter(|e| e.model_name == name) else {
+                    continue;
+                };
+                client
+                    .publish_with_reply(
+                        subject.to_owned(),
+                        probe_inbox.clone(),
+                        serde_json::to_vec(&event).unwrap_or_default().into(),
+                    )
+                    .await
+                    .map_err(|e| anyhow!("Unable to publish model event: {e:?}"))?;
+            }
+        }
+    }
+
+    // Dropping the subscriptions unsubscribes from them
+    Ok(())
+}
+
+/// Trims whitespace from the name of each component (and from the targets of links to them) so they
+/// are stored in their canonical form. If `lowercase` is set, names and link targets are also
+/// lowercased
+fn normalize_component_names(manifest: &mut Manifest, lowercase: bool) {
+    let normalize = |name: &mut String| {
+        *name = if lowercase {
+            name.trim().to_lowercase()
+        } else {
+            name.trim().to_owned()
+        };
+    };
+    for component in manifest.spec.components.iter_mut() {
+        normalize(&mut component.name);
+        for trait_item in component.traits.iter_mut().flatten() {
+            if let TraitProperty::Link(link) = &mut trait_item.properties {
+                normalize(&mut link.target);
+            }
+        }
+    }
+}
+
+/// Returns the message to reply with for a diff of the given model
+fn diff_message(name: &str, diff: &ManifestDiff) -> String {
+    if diff.is_empty() {
+        format!(
+            "Versions {} and {} of model {name} are identical",
+            diff.from_version, diff.to_version
+        )
+    } else {
+        format!(
+            "Successfully diffed model {name} {} to {}",
+            diff.from_version, diff.to_version
+        )
+    }
+}
+
+/// A single step in rolling back a model stored by a multi-model request. Each step is only applied
+/// at the revision the request stored the model at
+#[derive(Debug)]
+enum RollbackStep<'a> {
+    /// Restore the model as it was before the request
+    Restore {
+        name: &'a str,
+        previous: Box<StoredManifest>,
+        revision: u64,
+    },
+    /// Delete a model the request created
+    Delete { name: &'a str, revision: u64 },
+}
+
+impl<'a> RollbackStep<'a> {
+    /// Returns the name of the model this step rolls back
+    fn name(&self) -> &'a str {
+        match self {
+            RollbackStep::Restore { name, .. } | RollbackStep::Delete { name, .. } => name,
+        }
     }
+}
 
-    /// Sends a reply to the topic with the given data, logging an error if one occurs when
-    /// sending the reply
-    #[instrument(level = "debug", skip(self, data))]
-    pub async fn send_reply(&self, reply: Option<Subject>, data: Vec<u8>) {
-        let reply_topic = match reply {
-            Some(t) => t,
-            None => {
-                debug!("No reply topic was sent. Skipping reply");
-                return;
+/// Returns the steps to roll back models stored by a multi-model request, newest first. `current`
+/// holds the revision each model is at now, or `None` if it no longer exists. Models that aren't
+/// at the revision the request stored them at were written by someone else since, so they are
+/// skipped to keep that change
+fn rollback_steps<'a>(
+    stored: Vec<(&'a str, Option<StoredManifest>, u64)>,
+    current: &HashMap<&str, Option<u64>>,
+) -> Vec<RollbackStep<'a>> {
+    stored
+        .into_iter()
+        .rev()
+        .filter_map(|(name, previous, revision)| {
+            if current.get(name).copied().flatten() != Some(revision) {
+                warn!("Model {name} was changed since it was stored, not rolling it back");
+                return None;
             }
-        };
+            Some(match previous {
+                Some(previous) => RollbackStep::Restore {
+                    name,
+                    previous: Box::new(previous),
+                    revision,
+                },
+                None => RollbackStep::Delete { name, revision },
+            })
+        })
+        .collect()
+}
 
-        if let Err(e) = self.client.publish(reply_topic, data.into()).await {
-            error!(error = %e, "Unable to send reply");
-        }
+/// A multi-manifest put request as it is received. Manifests are kept as raw JSON so each one can
+/// be rendered and parsed the same way as the payload of a single put request
+#[derive(Deserialize)]
+struct RawPutModelsRequest {
+    manifests: Vec<serde_json::Value>,
+}
+
+/// Returns a message describing why the manifest name can't be used, if it can't
+fn invalid_manifest_name(name: &str) -> Option<String> {
+    if name.len() > MAX_MANIFEST_NAME_LENGTH {
+        Some(format!(
+            "Manifest name {name} is too long ({} bytes). Manifest names can be at most {MAX_MANIFEST_NAME_LENGTH} bytes.",
+            name.len()
+        ))
+    } else if is_reserved_manifest_name(name) {
+        Some(format!(
+            "Manifest name {name} is reserved because it is used as a keyword by the API. Reserved names are: {}.",
+            RESERVED_MANIFEST_NAMES.join(", ")
+        ))
+    } else if !is_valid_manifest_name(name) {
+        Some(format!(
+            "Manifest name {name} contains invalid characters. Manifest names can only contain alphanumeric characters, dashes, and underscores."
+        ))
+    } else {
+        None
     }
+}
 
-    /// Sends an error reply
-    #[instrument(level = "error", skip(self, error_message))]
-    pub async fn send_error(&self, reply: Option<Subject>, error_message: String) {
-        // SAFETY: We control the construction of the JSON here and all data going in, so this
-        // shouldn't fail except in some sort of really odd case. In those cases, we just unwrap to
-        // a default
-        let response = serde_json::to_vec(&json!({
-            // NOTE: This is a cheating response. Basically all of our API methods have an error
-            // variant in their result enum that serializes to this, so we just make it easy on
-            // ourselves rather than taking concrete types
-            "result": "error",
-            "message": error_message,
-        }))
-        .unwrap_or_default();
-        self.send_reply(reply, response).await;
+/// Checks that every name supplied alongside a manifest (such as in the subject or a header) matches
+/// the name declared in the manifest. Returns a message describing the mismatch if one doesn't
+fn check_name_hint<'a>(
+    manifest_name: &str,
+    hints: impl IntoIterator<Item = Option<&'a str>>,
+) -> Result<(), String> {
+    match hints
+        .into_iter()
+        .flatten()
+        .map(str::trim)
+        .find(|hint| *hint != manifest_name)
+    {
+        Some(hint) => Err(format!(
+            "Manifest name {manifest_name} does not match the requested model name {hint}. Make sure the name in the manifest and in the request are the same"
+        )),
+        None => Ok(()),
     }
+}
 
-    async fn get_manifest_status(&self, lattice_id: &str, name: &str) -> Option<StatusInfo> {
-        // NOTE(brooksmtownsend): We're getting the last raw message instead of direct get here
-        // to ensure we fetch the latest message from the cluster leader.
-        match self
-            .status_stream
-            .get_last_raw_message_by_subject(&format!("wadm.status.{lattice_id}.{name}",))
-            .await
-            .map(|raw| {
-                B64decoder
-                    .decode(raw.payload)
-                    .map(|b| serde_json::from_slice::<StatusInfo>(&b))
-            }) {
-            Ok(Ok(Ok(status))) => Some(status),
-            // Model status doesn't exist or is invalid, assuming undeployed
-            _ => None,
+/// Checks that this version of wadm is at least the minimum version the manifest requires, if it
+/// declares one. Returns a message describing the problem if it isn't
+fn check_minimum_wadm_version(manifest: &Manifest) -> Result<(), String> {
+    let required = match manifest.minimum_wadm_version() {
+        Some(required) => required,
+        None => return Ok(()),
+    };
+    let required_version = parse_wadm_version(required).map_err(|e| {
+        format!("Manifest has an invalid {MINIMUM_WADM_VERSION_ANNOTATION_KEY} annotation {required}: {e}")
+    })?;
+    // SAFETY: The crate version is always valid semver
+    let current =
+        parse_wadm_version(env!("CARGO_PKG_VERSION")).expect("wadm version should be valid semver");
+    if current < required_version {
+        return Err(format!(
+            "Model {} version {} requires wadm {required} or newer, but this server is running wadm {current}",
+            manifest.metadata.name,
+            manifest.version()
+        ));
+    }
+    Ok(())
+}
+
+/// Polls the status of the given model until it is either deployed or failed, returning `None` if
+/// that doesn't happen within the timeout. Any status at or before `after_sequence` is ignored as it
+/// was published before the deploy we are waiting on
+async fn wait_for_reconciled_status(
+    status_stream: &Stream,
+    account_id: Option<&str>,
+    lattice_id: &str,
+    name: &str,
+    after_sequence: Option<u64>,
+    timeout: Duration,
+) -> Option<StatusInfo> {
+    tokio::time::timeout(timeout, async {
+        loop {
+            match get_status_message(status_stream, account_id, lattice_id, name).await {
+                Ok(Some((sequence, status)))
+                    if after_sequence.map(|s| sequence > s).unwrap_or(true)
+                        && matches!(
+                            status.status_type,
+                            StatusType::Deployed | StatusType::Failed
+                        ) =>
+                {
+                    return status;
+                }
+                _ => tokio::time::sleep(DEPLOY_WAIT_POLL_INTERVAL).await,
+            }
         }
+    })
+    .await
+    .ok()
+}
+
+fn parse_image_ref(image_name: &str) -> Option<(String, String)> {
+    if let Some((repository_reference, ref_version)) = image_name.split_once(':') {
+        Some((repository_reference.to_owned(), ref_version.to_owned()))
+    } else {
+        // ignore if image ref is not in the format some_url:some_version
+        None
     }
 }
 
-// Manifest validation
-pub(crate) async fn validate_manifest(manifest: Manifest) -> anyhow::Result<()> {
-    let mut name_registry: HashSet<String> = HashSet::new();
-    let mut id_registry: HashSet<String> = HashSet::new();
-    let mut required_capability_components: HashSet<String> = HashSet::new();
-    JSON_SCHEMA_VALUE
-        .get_or_try_init(|| async {
-            serde_json::from_str(JSON_SCHEMA)
-                .map_err(|e| anyhow!("Unable to parse JSON schema: {}", e))
-        })
-        .await?;
-
-    let ok_schema = OAM_JSON_SCHEMA
-        .get_or_try_init(|| async {
-            JSONSchema::options().with_draft(Draft::Draft7).compile(
-                JSON_SCHEMA_VALUE
-                    .get()
-                    // SAFETY: We just initialized it above
-                    .expect("JSON schema should be initialized"),
-            )
-        })
-        .await?;
-
-    let json_instance = serde_json::to_value(manifest.clone())?;
-    let validation_result = ok_schema.validate(&json_instance);
-    if let Err(errors) = validation_result {
-        let mut error_message = String::new();
-        for error in errors {
-            trace!(error = ?error, "Validation error");
-            let instance_path = error
-                .instance_path
-                .into_iter()
-                .map(|item| match item {
-                    PathChunk::Property(value) => value.to_string(),
-                    PathChunk::Index(idx) => format!(" at index: {idx}"),
-                    PathChunk::Keyword(keyword) => keyword.to_string(),
-                })
-                .collect::<Vec<String>>()
-                .join("/");
-            error_message.push_str(&format!(
-                "Should be able to parse object at: {} \n",
-                // The path of the corresponding JSON error instance in that file
-                instance_path
-            ));
+/// Why a manifest was rejected, along with the structured schema errors (if any) so clients can
+/// point at the offending locations in the manifest
+struct Rejection {
+    code: ErrorCode,
+    message: String,
+    errors: Vec<SchemaError>,
+}
+
+impl From<(ErrorCode, String)> for Rejection {
+    fn from((code, message): (ErrorCode, String)) -> Self {
+        Rejection {
+            code,
+            message,
+            errors: Vec::new(),
         }
-        return Err(anyhow!(
-            "Validation Error: \n{}Please check for missing or incorrect elements",
-            error_message
-        ));
     }
+}
 
-    ensure!(manifest.metadata.labels.iter().all(valid_oam_label));
-    ensure!(manifest.metadata.annotations.iter().all(valid_oam_label));
+/// The provider versions and explicit component IDs claimed by deployed models, used to check that
+/// deploying another model won't conflict with them
+#[derive(Debug, Default)]
+struct DeployClaims {
+    /// Provider image references (without the version) mapped to their version and the name of the
+    /// model that deployed them
+    provider_refs: HashMap<String, (String, String)>,
+    /// Explicitly set component IDs (for both components and providers) mapped to their image and
+    /// the name of the model that deployed them, so we can catch two manifests trying to run
+    /// different things under the same ID
+    component_ids: HashMap<String, (String, String)>,
+}
 
-    for component in manifest.spec.components.iter() {
-        // Component name validation : each component (actors or providers) should have a unique name
-        if !name_registry.insert(component.name.clone()) {
-            return Err(anyhow!(
-                "Duplicate component name in manifest: {}",
-                component.name
-            ));
+impl DeployClaims {
+    /// Records the providers and component IDs of a deployed manifest
+    fn add(&mut self, model_name: &str, manifest: &Manifest) {
+        for component in manifest.spec.components.iter() {
+            if let Some((id, image)) = explicit_component_id(&component.properties) {
+                self.component_ids
+                    .insert(id.to_owned(), (image.to_owned(), model_name.to_string()));
+            }
+            if let Properties::Capability {
+                properties: CapabilityProperties { image, .. },
+            } = &component.properties
+            {
+                if let Some((ref_link, ref_version)) = parse_image_ref(image) {
+                    self.provider_refs
+                        .insert(ref_link, (ref_version, model_name.to_string()));
+                }
+            }
         }
-        // Provider validation :
-        // Provider config should be serializable [For all components that have JSON config, validate that it can serialize.
-        // We need this so it doesn't trigger an error when sending a command down the line]
-        // Providers should have a unique image ref and link name
-        if let Properties::Capability {
-            properties:
-                CapabilityProperties {
-                    id: Some(component_id),
-                    config: _capability_config,
-                    ..
-                },
-        } = &component.properties
-        {
-            if !id_registry.insert(component_id.to_string()) {
-                bail!("Duplicate component identifier in manifest: {component_id}");
+    }
+
+    /// Checks whether deploying the manifest would conflict with the recorded claims, returning
+    /// the error code and message to reply with for the first conflict if it would
+    fn check(&self, staged_model: &Manifest) -> Result<(), (ErrorCode, String)> {
+        match self.conflicts(staged_model).into_iter().next() {
+            Some(conflict) => {
+                error!("{}", conflict.message);
+                Err((ErrorCode::VersionConflict, conflict.message))
             }
+            None => Ok(()),
         }
+    }
 
-        // Actor validation : Actors should have a unique identifier per manifest
-        if let Properties::Component {
-            properties: ComponentProperties { id: Some(id), .. },
-        } = &component.properties
-        {
-            if !id_registry.insert(id.to_string()) {
-                bail!("Duplicate component identifier in manifest: {id}");
-            }
-        }
-
-        // Linkdef validation : A linkdef from a component should have a unique target and reference
-        let mut linkdef_set: HashSet<String> = HashSet::new();
-        if let Some(traits_vec) = &component.traits {
-            for trait_item in traits_vec.iter() {
-                if let Trait {
-                    // TODO : add trait type validation after custom types are done. See TraitProperty enum.
-                    properties:
-                        TraitProperty::Link(LinkProperty {
-                            target: target_name,
-                            ..
-                        }),
-                    ..
-                } = &trait_item
-                {
-                    if !linkdef_set.insert(target_name.to_string()) {
-                        return Err(anyhow!(
-                            "Duplicate target {} for component {} linkdef trait in manifest",
-                            target_name,
-                            component.name,
-                        ));
+    /// Returns every conflict between the manifest and the recorded claims, in component order
+    fn conflicts(&self, staged_model: &Manifest) -> Vec<DeployConflict> {
+        let mut conflicts = Vec::new();
+        for component in staged_model.spec.components.iter() {
+            if let Some((id, image)) = explicit_component_id(&component.properties) {
+                if let Some((old_image, old_manifest_name)) = self.component_ids.get(id) {
+                    if old_image != image {
+                        conflicts.push(DeployConflict {
+                            kind: DeployConflictKind::ComponentId,
+                            image: image.to_owned(),
+                            conflicting_model: old_manifest_name.clone(),
+                            deployed_version: None,
+                            requested_version: None,
+                            component_id: Some(id.to_owned()),
+                            message: format!(
+                                "Component ID {id} is already used by {old_image} in deployed model {old_manifest_name}. Component {} ({image}) must use a different ID.",
+                                component.name
+                            ),
+                        });
+                    }
+                }
+            }
+            if let Properties::Capability {
+                properties:
+                    CapabilityProperties {
+                        image: image_name, ..
+                    },
+            } = &component.properties
+            {
+                if let Some((ref_link, ref_version)) = parse_image_ref(image_name) {
+                    if let Some((old_version, old_manifest_name)) =
+                        self.provider_refs.get(&ref_link)
+                    {
+                        if old_version != &ref_version {
+                            conflicts.push(DeployConflict {
+                                kind: DeployConflictKind::ProviderVersion,
+                                image: image_name.clone(),
+                                conflicting_model: old_manifest_name.clone(),
+                                deployed_version: Some(old_version.clone()),
+                                requested_version: Some(ref_version),
+                                component_id: None,
+                                message: format!(
+                                    "Provider {image_name} is already deployed with a different version in {old_manifest_name}."
+                                ),
+                            });
+                        }
                     }
-
-                    // Multiple components{ with type != 'capability'} can declare the same target, so we don't need to check for duplicates on insert
-                    required_capability_components.insert(target_name.to_string());
                 }
             }
         }
+        conflicts
     }
+}
 
-    let missing_capability_components = required_capability_components
-        .difference(&name_registry)
-        .collect::<Vec<&String>>();
+/// Returns the explicitly set ID and the image of a component or provider, if it has one. Generated
+/// IDs include the manifest name, so they can't conflict across manifests
+fn explicit_component_id(properties: &Properties) -> Option<(&str, &str)> {
+    match properties {
+        Properties::Component {
+            properties: ComponentProperties { id, image, .. },
+        }
+        | Properties::Capability {
+            properties: CapabilityProperties { id, image, .. },
+        } => id.as_deref().map(|id| (id, image.as_str())),
+    }
+}
 
-    if !missing_capability_components.is_empty() {
-        return Err(anyhow!(
-            "The following capability component(s) are missing from the manifest:  {:?}",
-            missing_capability_components
-        ));
+/// Serializes a generic error response with the given code and message. Errors that may succeed
+/// if retried also include a suggested backoff in `retry_after_ms`
+fn error_response(error_code: ErrorCode, error_message: String) -> Vec<u8> {
+    let mut response = json!({
+        // NOTE: This is a cheating response. Basically all of our API methods have an error
+        // variant in their result enum that serializes to this, so we just make it easy on
+        // ourselves rather than taking concrete types
+        "result": "error",
+        "message": error_message,
+        "error_code": error_code,
+    });
+    if let Some(retry_after_ms) = error_code.retry_after_ms() {
+        response["retry_after_ms"] = retry_after_ms.into();
+    }
+    // SAFETY: We control the construction of the JSON here and all data going in, so this
+    // shouldn't fail except in some sort of really odd case. In those cases, we just unwrap to
+    // a default
+    serde_json::to_vec(&response).unwrap_or_default()
+}
+
+/// Publishes an audit record under the given topic prefix, if audit records are enabled
+async fn publish_audit_record(client: &Client, prefix: Option<&str>, record: AuditRecord) {
+    let Some(prefix) = prefix else {
+        return;
+    };
+    let topic = format!("{prefix}.{}", record.lattice_id);
+    let data = match serde_json::to_vec(&record) {
+        Ok(data) => data,
+        Err(e) => {
+            warn!("Unable to serialize audit record: {e:?}");
+            return;
+        }
+    };
+    if let Err(e) = client.publish(topic, data.into()).await {
+        warn!("Unable to publish audit record: {e}");
     }
+}
 
-    Ok(())
+/// Returns the error response to send instead of a reply of the given size, if it is too large to
+/// be published. Some headroom is left for the headers we add. A max payload of 0 means it isn't
+/// known (such as before the client has connected), so every reply is allowed
+fn oversized_reply(size: usize, max_payload: usize) -> Option<Vec<u8>> {
+    const HEADER_HEADROOM: usize = 1024;
+    if max_payload == 0 || size + HEADER_HEADROOM <= max_payload {
+        return None;
+    }
+    Some(error_response(
+        ErrorCode::ResponseTooLarge,
+        format!("Response is too large to send ({size} bytes, the maximum is {max_payload} bytes). Use pagination or filters to request less data at once"),
+    ))
 }
 
-fn parse_image_ref(image_name: &str) -> Option<(String, String)> {
-    if let Some((repository_reference, ref_version)) = image_name.split_once(':') {
-        Some((repository_reference.to_owned(), ref_version.to_owned()))
+/// Returns the error code to reply with when a storage operation fails
+fn storage_error_code(err: &anyhow::Error) -> ErrorCode {
+    if TimedOut::is_timeout(err) {
+        ErrorCode::Timeout
     } else {
-        // ignore if image ref is not in the format some_url:some_version
-        None
+        ErrorCode::StorageError
     }
 }
 
-/// This function validates that a key/value pair is a valid OAM label. It's using fairly
-/// basic validation rules to ensure that the manifest isn't doing anything horribly wrong. Keeping
-/// this function free of regex is intentional to keep this code functional but simple.
-///
-/// See <https://github.com/oam-dev/spec/blob/master/metadata.md#metadata> for details
-fn valid_oam_label(label: (&String, &String)) -> bool {
-    let (key, _) = label;
-    match key.split_once('/') {
-        Some((prefix, name)) => is_valid_dns_subdomain(prefix) && is_valid_label_name(name),
-        None => is_valid_label_name(key),
+/// Returns the message to reply with when a storage operation fails. Details of other storage
+/// errors are only logged
+fn storage_error_message(err: &anyhow::Error) -> String {
+    if TimedOut::is_timeout(err) {
+        format!("{err}. The change may or may not have been applied, so please check the current state before retrying")
+    } else {
+        "Internal storage error".to_string()
+    }
+}
+
+/// Returns the error code to reply with when notifying processors fails
+fn notification_error_code(err: &anyhow::Error) -> ErrorCode {
+    if TimedOut::is_timeout(err) {
+        ErrorCode::Timeout
+    } else {
+        ErrorCode::NotificationError
     }
 }
 
-fn is_valid_dns_subdomain(s: &str) -> bool {
-    if s.is_empty() || s.len() > 253 {
-        return false;
+/// Returns a stored model in the shape it is exported in
+fn exported_model(name: String, stored: &StoredManifest) -> ExportedModel {
+    ExportedModel {
+        versions: stored
+            .all_versions()
+            .into_iter()
+            .filter_map(|v| stored.get_version(v).cloned())
+            .collect(),
+        deployed_version: stored.deployed_version().map(ToOwned::to_owned),
+        staged_version: stored.staged_version().map(ToOwned::to_owned),
+        disabled_components: stored.disabled_components().to_vec(),
+        archived_at: stored.archived_at(),
+        undeployed_at: stored.undeployed_at(),
+        version_created_at: stored
+            .all_versions()
+            .into_iter()
+            .filter_map(|v| Some((v.clone(), stored.version_created_at(v)?)))
+            .collect(),
+        name,
     }
+}
 
-    s.split('.').all(|part| {
-        // Ensure each part is non-empty, <= 63 characters, starts with an alphabetic character,
-        // ends with an alphanumeric character, and contains only alphanumeric characters or hyphens
-        !part.is_empty()
-            && part.len() <= 63
-            && part.starts_with(|c: char| c.is_ascii_alphabetic())
-            && part.ends_with(|c: char| c.is_ascii_alphanumeric())
-            && part.chars().all(|c| c.is_ascii_alphanumeric() || c == '-')
-    })
+/// Restores the deployed, staged, and archived state of an exported model onto the stored model
+/// built from its versions. Returns a message describing the problem if the state is invalid
+fn restore_exported_state(
+    stored: &mut StoredManifest,
+    name: &str,
+    model: &ExportedModel,
+) -> Result<(), String> {
+    if model.archived_at.is_some()
+        && (model.deployed_version.is_some() || model.staged_version.is_some())
+    {
+        return Err(format!(
+            "Model {name} is archived, so it can't be imported as deployed or staged"
+        ));
+    }
+    if let Some(version) = model.deployed_version.as_ref() {
+        if !stored.deploy(Some(version.clone())) {
+            return Err(format!(
+                "Deployed version {version} of model {name} is not one of its versions"
+            ));
+        }
+    }
+    if !model.disabled_components.is_empty() {
+        let Some(deployed) = stored.get_deployed() else {
+            return Err(format!(
+                "Model {name} has disabled components, but isn't deployed"
+            ));
+        };
+        check_disabled_components(deployed, &model.disabled_components)?;
+        stored.set_disabled_components(model.disabled_components.clone());
+    }
+    if let Some(version) = model.staged_version.as_ref() {
+        if !stored.stage(Some(version.clone())) {
+            return Err(format!(
+                "Staged version {version} of model {name} is not one of its versions"
+            ));
+        }
+    }
+    stored.set_imported_timestamps(
+        model.archived_at,
+        model.undeployed_at,
+        model.version_created_at.clone(),
+    );
+    Ok(())
 }
 
-// Ensure each name is non-empty, <= 63 characters, starts with an alphanumeric character,
-// ends with an alphanumeric character, and contains only alphanumeric characters, hyphens,
-// underscores, or periods
-fn is_valid_label_name(name: &str) -> bool {
-    if name.is_empty() || name.len() > 63 {
-        return false;
+/// Checks that the named components can be left out of a deploy of the manifest. Each one must be in
+/// the manifest, and no component that is still deployed can link to one. Returns a message
+/// describing the problem if not
+fn check_disabled_components(manifest: &Manifest, disabled: &[String]) -> Result<(), String> {
+    if let Some(missing) = disabled
+        .iter()
+        .find(|name| !manifest.spec.components.iter().any(|c| &c.name == *name))
+    {
+        return Err(format!(
+            "Component {missing} can't be disabled because it isn't in model {} {}",
+            manifest.metadata.name,
+            manifest.version()
+        ));
+    }
+    for component in manifest
+        .spec
+        .components
+        .iter()
+        .filter(|component| !disabled.contains(&component.name))
+    {
+        for trait_item in component.traits.iter().flatten() {
+            if let TraitProperty::Link(LinkProperty { target, .. }) = &trait_item.properties {
+                if disabled.contains(target) {
+                    return Err(format!(
+                        "Component {target} can't be disabled because component {} links to it. Disable {} as well or remove the link",
+                        component.name, component.name
+                    ));
+                }
+            }
+        }
     }
+    Ok(())
+}
+
+/// Returns true if any link in the dependent manifest targets a component of the provider manifest.
+/// Links target components in their own manifest by name, so a link crosses manifests when its
+/// target has the same explicit ID as a provider component, or when the target isn't in the
+/// dependent manifest at all (which older manifests may have) and is named in the provider
+fn links_into(dependent: &Manifest, provider: &Manifest) -> bool {
+    let provided_ids: HashSet<&str> = provider
+        .spec
+        .components
+        .iter()
+        .filter_map(|component| explicit_component_id(&component.properties))
+        .map(|(id, _)| id)
+        .collect();
+    let provided_names: HashSet<&str> = provider
+        .spec
+        .components
+        .iter()
+        .map(|component| component.name.as_str())
+        .collect();
 
-    name.starts_with(|c: char| c.is_ascii_alphanumeric())
-        && name.ends_with(|c: char| c.is_ascii_alphanumeric())
-        && name
-            .chars()
-            .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_' || c == '.')
+    dependent
+        .links()
+        .filter_map(|link| match &link.properties {
+            TraitProperty::Link(LinkProperty { target, .. }) => Some(target.as_str()),
+            _ => None,
+        })
+        .any(|target| {
+            match dependent
+                .spec
+                .components
+                .iter()
+                .find(|component| component.name == target)
+            {
+                Some(component) => explicit_component_id(&component.properties)
+                    .is_some_and(|(id, _)| provided_ids.contains(id)),
+                None => provided_names.contains(target),
+            }
+        })
 }
 
 #[cfg(test)]
@@ -1107,7 +7058,8 @@ mod test {
     use std::path::Path;
 
     use super::*;
-    use anyhow::{Context, Result};
+    use crate::server::ManifestLimits;
+    use anyhow::Result;
     use serde_yaml;
 
     pub(crate) fn deserialize_yaml(filepath: impl AsRef<Path>) -> Result<Manifest> {
@@ -1117,127 +7069,454 @@ mod test {
         Ok(yaml_string)
     }
 
-    #[tokio::test]
-    async fn test_manifest_validation() {
-        let correct_manifest =
-            deserialize_yaml("./oam/simple1.yaml").expect("Should be able to parse");
+    #[test]
+    fn test_rollback_steps() {
+        let stored = vec![
+            ("first", Some(StoredManifest::default()), 10),
+            ("created", None, 11),
+            ("changed", Some(StoredManifest::default()), 12),
+            ("deleted", None, 13),
+            ("last", Some(StoredManifest::default()), 14),
+        ];
+        let current = HashMap::from([
+            ("first", Some(10)),
+            ("created", Some(11)),
+            // Written by someone else after the request stored it
+            ("changed", Some(20)),
+            // Deleted by someone else after the request created it
+            ("deleted", None),
+            ("last", Some(14)),
+        ]);
+
+        let steps = rollback_steps(stored, &current)
+            .into_iter()
+            .map(|step| match step {
+                RollbackStep::Restore { name, revision, .. } => ("restore", name, revision),
+                RollbackStep::Delete { name, revision } => ("delete", name, revision),
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(
+            steps,
+            vec![
+                ("restore", "last", 14),
+                ("delete", "created", 11),
+                ("restore", "first", 10),
+            ],
+            "Should roll back newest first at the stored revisions, skipping models changed since"
+        );
 
-        assert!(validate_manifest(correct_manifest).await.is_ok());
+        assert!(
+            rollback_steps(vec![("unknown", None, 1)], &HashMap::new()).is_empty(),
+            "Models whose current revision is unknown shouldn't be rolled back"
+        );
+    }
 
-        let manifest = deserialize_yaml("./test/data/incorrect_component.yaml")
-            .expect("Should be able to parse");
+    #[test]
+    fn test_oversized_reply() {
+        assert!(oversized_reply(1024, 1024 * 1024).is_none());
+        assert!(
+            oversized_reply(usize::MAX / 2, 0).is_none(),
+            "An unknown max payload shouldn't block replies"
+        );
 
-        match validate_manifest(manifest).await {
-            Ok(()) => panic!("Should have detected incorrect component"),
-            Err(e) => {
-                assert!(e
-                    .to_string()
-                    // The 0th component in the spec list is incorrect and should be detected (indexing starts from 0)
-                    .contains("Should be able to parse object at: spec/components/ at index: 0"))
+        let error = oversized_reply(2 * 1024 * 1024, 1024 * 1024)
+            .expect("Replies over the max payload should be replaced");
+        let error: serde_json::Value =
+            serde_json::from_slice(&error).expect("Should be able to parse error");
+        assert_eq!(error["result"], "error");
+        assert_eq!(
+            serde_json::from_value::<ErrorCode>(error["error_code"].clone()).unwrap(),
+            ErrorCode::ResponseTooLarge
+        );
+    }
+
+    #[test]
+    fn test_error_response_retry_after() {
+        let error: serde_json::Value = serde_json::from_slice(&error_response(
+            ErrorCode::StorageError,
+            "Unable to read from storage".to_string(),
+        ))
+        .expect("Should be able to parse error");
+        assert_eq!(error["retry_after_ms"], 1000);
+
+        let error: serde_json::Value = serde_json::from_slice(&error_response(
+            ErrorCode::ValidationFailed,
+            "Manifest is invalid".to_string(),
+        ))
+        .expect("Should be able to parse error");
+        assert!(
+            error.get("retry_after_ms").is_none(),
+            "Permanent errors shouldn't have a retry hint"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_component_name_normalization() {
+        let mut manifest = deserialize_yaml("./test/data/shared_link_target.yaml")
+            .expect("Should be able to parse");
+        manifest.spec.components[2].name = " Redis\t".to_string();
+        for component in manifest.spec.components.iter_mut().take(2) {
+            for trait_item in component.traits.iter_mut().flatten() {
+                if let TraitProperty::Link(link) = &mut trait_item.properties {
+                    link.target = "Redis ".to_string();
+                }
             }
         }
 
-        let manifest = deserialize_yaml("./test/data/duplicate_component.yaml")
-            .expect("Should be able to parse");
+        let mut trimmed = manifest.clone();
+        normalize_component_names(&mut trimmed, false);
+        assert_eq!(trimmed.spec.components[2].name, "Redis");
+        validate(&trimmed, &ManifestLimits::default())
+            .await
+            .into_result()
+            .expect("Trimmed link targets should still match the component name");
 
-        match validate_manifest(manifest).await {
-            Ok(()) => panic!("Should have detected duplicate component"),
-            Err(e) => assert!(e
-                .to_string()
-                .contains("Duplicate component name in manifest")),
+        normalize_component_names(&mut manifest, true);
+        assert_eq!(manifest.spec.components[2].name, "redis");
+        assert!(manifest
+            .links()
+            .all(|link| matches!(&link.properties, TraitProperty::Link(l) if l.target == "redis")));
+
+        manifest.spec.components[1].name = "REDIS".to_string();
+        match validate(&manifest, &ManifestLimits::default())
+            .await
+            .into_result()
+        {
+            Ok(()) => panic!("Should have detected names that only differ by case"),
+            Err(e) => assert!(e.to_string().contains(
+                "Duplicate component name in manifest: \"REDIS\" and \"redis\" only differ by case"
+            )),
         }
+    }
+
+    #[test]
+    fn test_mark_stale() {
+        let mut status = StatusInfo::deployed("");
+        mark_stale(&mut status, Some(Duration::from_secs(60)));
+        assert!(!status.stale, "A status without an age can't be stale");
+
+        status.age_secs = Some(3600);
+        mark_stale(&mut status, None);
+        assert!(!status.stale, "Nothing is stale without a threshold");
 
-        let manifest =
-            deserialize_yaml("./test/data/duplicate_id1.yaml").expect("Should be able to parse");
+        mark_stale(&mut status, Some(Duration::from_secs(60)));
+        assert!(status.stale);
 
-        match validate_manifest(manifest).await {
-            Ok(()) => {
-                panic!("Should have detected duplicate component ID in provider properties")
+        status.age_secs = Some(30);
+        mark_stale(&mut status, Some(Duration::from_secs(60)));
+        assert!(!status.stale);
+    }
+
+    #[test]
+    fn test_deploy_claims() {
+        let set_webcap_image = |manifest: &mut Manifest, new_image: &str| {
+            let component = manifest
+                .spec
+                .components
+                .iter_mut()
+                .find(|c| c.name == "webcap")
+                .expect("webcap should exist");
+            if let Properties::Capability {
+                properties: CapabilityProperties { image, .. },
+            } = &mut component.properties
+            {
+                *image = new_image.to_string();
             }
-            Err(e) => assert!(e
-                .to_string()
-                .contains("Duplicate component identifier in manifest")),
-        }
+        };
 
-        let manifest =
-            deserialize_yaml("./test/data/duplicate_id2.yaml").expect("Should be able to parse");
+        let first = deserialize_yaml("./oam/simple1.yaml").expect("Should be able to parse");
+        let mut second = first.clone();
+        second.metadata.name = "second".to_string();
 
-        match validate_manifest(manifest).await {
-            Ok(()) => panic!("Should have detected duplicate component ID in actor properties"),
-            Err(e) => assert!(e
-                .to_string()
-                .contains("Duplicate component identifier in manifest")),
-        }
+        let mut claims = DeployClaims::default();
+        claims
+            .check(&first)
+            .expect("Nothing should conflict before anything is claimed");
+        claims.add(&first.metadata.name, &first);
+        claims
+            .check(&second)
+            .expect("The same provider version should not conflict");
 
-        let manifest = deserialize_yaml("./test/data/duplicate_linkdef.yaml")
-            .expect("Should be able to parse");
+        set_webcap_image(&mut second, "wasmcloud.azurecr.io/httpserver:0.14.0");
+        let (code, message) = claims
+            .check(&second)
+            .expect_err("A different provider version should conflict");
+        assert_eq!(code, ErrorCode::VersionConflict);
+        assert!(message.contains(&first.metadata.name));
 
-        match validate_manifest(manifest).await {
-            Ok(()) => panic!("Should have detected duplicate linkdef"),
-            Err(e) => assert!(e.to_string().contains("Duplicate target")),
+        let conflicts = claims.conflicts(&second);
+        assert_eq!(conflicts.len(), 1, "Should only report the provider");
+        assert_eq!(conflicts[0].kind, DeployConflictKind::ProviderVersion);
+        assert_eq!(conflicts[0].conflicting_model, first.metadata.name);
+        assert_eq!(conflicts[0].requested_version.as_deref(), Some("0.14.0"));
+        assert_eq!(
+            conflicts[0].message, message,
+            "Preflight should report the same conflict a deploy is refused with"
+        );
+    }
+
+    #[test]
+    fn test_disabled_components() {
+        // webcap links to userinfo in this manifest
+        let manifest = deserialize_yaml("./oam/simple2.yaml").expect("Should be able to parse");
+        let names = |names: &[&str]| names.iter().map(ToString::to_string).collect::<Vec<_>>();
+
+        check_disabled_components(&manifest, &[]).expect("Nothing disabled should be valid");
+        check_disabled_components(&manifest, &names(&["ledblinky"]))
+            .expect("Components nothing links to can be disabled");
+        check_disabled_components(&manifest, &names(&["userinfo", "webcap"]))
+            .expect("Links from disabled components shouldn't count");
+
+        let err = check_disabled_components(&manifest, &names(&["userinfo"]))
+            .expect_err("Disabling a link target should orphan the link");
+        assert!(err.contains("component webcap links to it"), "{err}");
+        let err = check_disabled_components(&manifest, &names(&["nope"]))
+            .expect_err("Unknown components can't be disabled");
+        assert!(err.contains("Component nope can't be disabled"), "{err}");
+    }
+
+    #[test]
+    fn test_export_round_trip() {
+        let manifest = deserialize_yaml("./oam/simple2.yaml").expect("Should be able to parse");
+        let name = manifest.metadata.name.clone();
+        let versioned = |version: &str| {
+            let mut manifest = manifest.clone();
+            manifest
+                .metadata
+                .annotations
+                .insert(VERSION_ANNOTATION_KEY.to_string(), version.to_string());
+            manifest
+        };
+        let import = |exported: &ExportedModel| {
+            let exported: ExportedModel = serde_json::from_slice(
+                &serde_json::to_vec(exported).expect("Should be able to serialize"),
+            )
+            .expect("Should be able to deserialize");
+            let mut imported = StoredManifest::default();
+            for version in exported.versions.iter().cloned() {
+                assert!(imported.add_version(version));
+            }
+            restore_exported_state(&mut imported, &name, &exported).map(|_| imported)
+        };
+
+        let mut stored = StoredManifest::default();
+        assert!(stored.add_version(versioned("v1")));
+        assert!(stored.add_version(versioned("v2")));
+        assert!(stored.deploy(Some("v1".to_string())));
+        assert!(stored.undeploy());
+        assert!(!stored.archive(), "The model was already undeployed");
+
+        let exported = exported_model(name.clone(), &stored);
+        let imported = import(&exported).expect("Should import an archived model");
+        assert!(imported.is_archived());
+        assert_eq!(imported.archived_at(), stored.archived_at());
+        assert_eq!(imported.undeployed_at(), stored.undeployed_at());
+        assert!(imported.deployed_version().is_none());
+        for version in ["v1", "v2"] {
+            assert_eq!(
+                imported.version_created_at(version),
+                stored.version_created_at(version),
+                "Version {version} should keep when it was created"
+            );
         }
+        assert_eq!(imported.expires_at(), stored.expires_at());
 
-        let manifest = deserialize_yaml("./test/data/missing_capability_component.yaml")
-            .expect("Should be able to parse");
+        let err = import(&ExportedModel {
+            deployed_version: Some("v1".to_string()),
+            ..exported
+        })
+        .expect_err("Archived models can't be imported as deployed");
+        assert!(err.contains("is archived"), "{err}");
+
+        let mut stored = StoredManifest::default();
+        assert!(stored.add_version(versioned("v1")));
+        assert!(stored.add_version(versioned("v2")));
+        assert!(stored.deploy(Some("v1".to_string())));
+        stored.set_disabled_components(vec!["ledblinky".to_string()]);
+        assert!(stored.stage(Some("v2".to_string())));
 
-        match validate_manifest(manifest).await {
-            Ok(()) => panic!("Should have detected missing capability component"),
-            Err(e) => assert!(e
-                .to_string()
-                .contains("The following capability component(s) are missing from the manifest: ")),
+        let imported =
+            import(&exported_model(name.clone(), &stored)).expect("Should import a deployed model");
+        assert_eq!(imported.deployed_version(), Some("v1"));
+        assert_eq!(imported.staged_version(), Some("v2"));
+        assert_eq!(imported.disabled_components(), ["ledblinky".to_string()]);
+        assert!(!imported.is_archived());
+    }
+
+    #[test]
+    fn test_links_into() {
+        let set_webcap_id = |manifest: &mut Manifest, new_id: Option<&str>| {
+            let component = manifest
+                .spec
+                .components
+                .iter_mut()
+                .find(|c| c.name == "webcap")
+                .expect("webcap should exist");
+            if let Properties::Capability {
+                properties: CapabilityProperties { id, .. },
+            } = &mut component.properties
+            {
+                *id = new_id.map(ToOwned::to_owned);
+            }
+        };
+
+        let mut provider = deserialize_yaml("./oam/simple1.yaml").expect("Should be able to parse");
+        set_webcap_id(&mut provider, Some("shared-http"));
+        let mut dependent = provider.clone();
+        dependent.metadata.name = "dependent".to_string();
+        assert!(
+            links_into(&dependent, &provider),
+            "Links to a component with a shared ID should be found"
+        );
+
+        set_webcap_id(&mut dependent, None);
+        assert!(
+            !links_into(&dependent, &provider),
+            "Links to a component in the same manifest shouldn't be found"
+        );
+
+        dependent.spec.components.retain(|c| c.name != "ledblinky");
+        for link in dependent
+            .spec
+            .components
+            .iter_mut()
+            .flat_map(|c| c.traits.iter_mut().flatten())
+        {
+            if let TraitProperty::Link(props) = &mut link.properties {
+                props.target = "ledblinky".to_string();
+            }
         }
+        assert!(
+            links_into(&dependent, &provider),
+            "Links to a component only in the provider should be found"
+        );
     }
 
-    /// Ensure that a long image ref in a manifest works,
-    /// for both providers and actors
-    #[tokio::test]
-    async fn manifest_name_long_image_ref() -> Result<()> {
-        validate_manifest(
-            deserialize_yaml("./test/data/long_image_refs.yaml")
-                .context("failed to deserialize YAML")?,
-        )
-        .await
-        .context("failed to validate long image ref")?;
-        Ok(())
+    #[test]
+    fn test_status_subjects() {
+        assert_eq!(
+            status_subjects(None, "default", "my-app"),
+            vec!["wadm.status.default.my-app".to_string()]
+        );
+        // Account scoped statuses are checked first, then the accountless subject for statuses
+        // published by older versions of wadm
+        assert_eq!(
+            status_subjects(Some("ACCOUNT"), "default", "my-app"),
+            vec![
+                "wadm.status.ACCOUNT.default.my-app".to_string(),
+                "wadm.status.default.my-app".to_string(),
+            ]
+        );
+
+        let config = filter_status_subjects(PullConfig::default(), None, "default", "my-app");
+        assert_eq!(config.filter_subject, "wadm.status.default.my-app");
+        assert!(config.filter_subjects.is_empty());
+        let config =
+            filter_status_subjects(PullConfig::default(), Some("ACCOUNT"), "default", "my-app");
+        assert!(config.filter_subject.is_empty());
+        assert_eq!(config.filter_subjects.len(), 2);
+    }
+
+    #[test]
+    fn test_diff_message() {
+        let mut diff = ManifestDiff {
+            from_version: "v1".to_string(),
+            to_version: "v2".to_string(),
+            ..Default::default()
+        };
+        assert_eq!(
+            diff_message("my-app", &diff),
+            "Versions v1 and v2 of model my-app are identical"
+        );
+        diff.removed_components.push(
+            deserialize_yaml("./oam/simple1.yaml")
+                .expect("Should be able to parse manifest")
+                .spec
+                .components
+                .remove(0),
+        );
+        assert_eq!(
+            diff_message("my-app", &diff),
+            "Successfully diffed model my-app v1 to v2"
+        );
+    }
+
+    #[test]
+    fn test_invalid_manifest_name() {
+        assert!(invalid_manifest_name("my-app").is_none());
+        assert!(invalid_manifest_name("my.app")
+            .unwrap()
+            .contains("contains invalid characters"));
+        assert!(
+            invalid_manifest_name(&"a".repeat(MAX_MANIFEST_NAME_LENGTH + 1))
+                .unwrap()
+                .contains("is too long")
+        );
+        assert!(invalid_manifest_name("deployed")
+            .unwrap()
+            .contains("is reserved"));
+    }
+
+    #[test]
+    fn test_check_name_hint() {
+        assert!(check_name_hint("echo", [None, None]).is_ok());
+        assert!(check_name_hint("echo", [Some("echo"), None]).is_ok());
+        assert!(check_name_hint("echo", [Some("echo"), Some(" echo ")]).is_ok());
+
+        let err = check_name_hint("echo", [Some("echo"), Some("other")])
+            .expect_err("A mismatched header name should be rejected");
+        assert!(err.contains("other"), "Error should name the mismatch");
+        assert!(
+            check_name_hint("echo", [Some("ech0"), None]).is_err(),
+            "A mismatched subject name should be rejected"
+        );
+    }
+
+    #[test]
+    fn test_minimum_wadm_version() {
+        let mut manifest = deserialize_yaml("./oam/simple1.yaml").expect("Should be able to parse");
+        assert!(
+            check_minimum_wadm_version(&manifest).is_ok(),
+            "Manifests without a minimum version should always be allowed"
+        );
+
+        manifest.metadata.annotations.insert(
+            MINIMUM_WADM_VERSION_ANNOTATION_KEY.to_string(),
+            "v0.1.0".to_string(),
+        );
+        assert!(check_minimum_wadm_version(&manifest).is_ok());
+
+        manifest.metadata.annotations.insert(
+            MINIMUM_WADM_VERSION_ANNOTATION_KEY.to_string(),
+            "999.0.0".to_string(),
+        );
+        let err = check_minimum_wadm_version(&manifest).expect_err("Should require a newer wadm");
+        assert!(err.contains("requires wadm 999.0.0 or newer"));
     }
 
     #[tokio::test]
-    async fn validate_oam_label_rules() {
-        // Valid labels
-        assert!(valid_oam_label((&"foo".to_string(), &"bar".to_string())));
-        assert!(valid_oam_label((
-            &"app.oam.io/name".to_string(),
-            &"wasmcloud".to_string()
-        )));
-        assert!(valid_oam_label((
-            &"justaregularstring".to_string(),
-            &"wasmcloud".to_string()
-        )));
-        assert!(valid_oam_label((
-            &"dash-period.numb3r/any_v4lue".to_string(),
-            &"this can be any string".to_string()
-        )));
-
-        // Invalid labels
-        assert!(!valid_oam_label((
-            &"my_prefix/app-name".to_string(),
-            &"wasmcloud".to_string()
-        )));
-        assert!(!valid_oam_label((
-            &"1my_prefix/app-name".to_string(),
-            &"wasmcloud".to_string()
-        )));
-        assert!(!valid_oam_label((
-            &"my_prefix---/app-name".to_string(),
-            &"wasmcloud".to_string()
-        )));
-        assert!(!valid_oam_label((
-            &"my_prefix/app-name...".to_string(),
-            &"wasmcloud".to_string()
-        )));
-        assert!(!valid_oam_label((
-            &"a".repeat(255).to_string(),
-            &"toolong".to_string()
-        )));
+    async fn test_fetch_concurrently() {
+        use std::sync::atomic::{AtomicUsize, Ordering};
+
+        let in_flight = AtomicUsize::new(0);
+        let max_in_flight = AtomicUsize::new(0);
+        let results = fetch_concurrently((0..10).map(|i| format!("model{i}")), 3, |name| {
+            let in_flight = &in_flight;
+            let max_in_flight = &max_in_flight;
+            async move {
+                let current = in_flight.fetch_add(1, Ordering::SeqCst) + 1;
+                max_in_flight.fetch_max(current, Ordering::SeqCst);
+                tokio::time::sleep(Duration::from_millis(10)).await;
+                in_flight.fetch_sub(1, Ordering::SeqCst);
+                name.len()
+            }
+        })
+        .await;
+
+        assert_eq!(results.len(), 10, "Should have a result for every name");
+        assert_eq!(results.get("model3"), Some(&6));
+        let max = max_in_flight.load(Ordering::SeqCst);
+        assert!(max <= 3, "At most 3 fetches should run at once, got {max}");
+        assert!(max > 1, "Fetches should run concurrently");
     }
 }
@@ -0,0 +1,157 @@
+use std::{
+    collections::{HashMap, VecDeque},
+    sync::Mutex,
+    time::{Duration, Instant},
+};
+
+use async_nats::Subject;
+use tracing::{debug, trace};
+
+/// The name of the header in the NATS request that contains the idempotency key. Requests to
+/// mutating endpoints with the same key are only executed once, with repeats receiving the original
+/// response
+pub const IDEMPOTENCY_KEY_HEADER: &str = "wadm-idempotency-key";
+
+/// The default amount of time a response is remembered for an idempotency key
+pub const DEFAULT_IDEMPOTENCY_TTL: Duration = Duration::from_secs(300);
+/// The default maximum number of idempotency keys to remember responses for
+pub const DEFAULT_IDEMPOTENCY_CACHE_SIZE: usize = 1024;
+
+/// A bounded cache of responses keyed by idempotency key. Entries expire after the configured TTL
+/// and the oldest entries are evicted first once the cache is full.
+///
+/// Responses are recorded by first tracking the reply subject of a request with
+/// [`IdempotencyCache::track`] and then calling [`IdempotencyCache::complete`] when a reply is sent
+/// to that subject. This way the handlers don't need to know about idempotency beyond checking for
+/// a previous response
+pub(crate) struct IdempotencyCache {
+    ttl: Duration,
+    capacity: usize,
+    inner: Mutex<Inner>,
+}
+
+#[derive(Default)]
+struct Inner {
+    responses: HashMap<String, (Instant, Vec<u8>)>,
+    // Keys in insertion order so we can evict the oldest (and therefore first to expire) entries
+    order: VecDeque<(Instant, String)>,
+    // Reply subjects of in flight requests, mapped to the key their response should be stored under
+    pending: HashMap<String, String>,
+}
+
+impl IdempotencyCache {
+    pub fn new(ttl: Duration, capacity: usize) -> IdempotencyCache {
+        IdempotencyCache {
+            ttl,
+            capacity,
+            inner: Mutex::new(Inner::default()),
+        }
+    }
+
+    /// Returns the previously recorded response for the given key, if it hasn't expired
+    pub fn get(&self, key: &str) -> Option<Vec<u8>> {
+        let inner = self.inner.lock().unwrap();
+        inner
+            .responses
+            .get(key)
+            .filter(|(inserted, _)| inserted.elapsed() < self.ttl)
+            .map(|(_, data)| data.clone())
+    }
+
+    /// Records that the response sent to the given reply subject should be stored under the key
+    pub fn track(&self, reply: Subject, key: String) {
+        let mut inner = self.inner.lock().unwrap();
+        // Pending entries are removed once a reply is sent, so this should only fill up if replies
+        // are never sent. In that case, we skip tracking rather than growing without bound
+        if inner.pending.len() >= self.capacity {
+            debug!("Too many in flight idempotent requests, response will not be recorded");
+            return;
+        }
+        inner.pending.insert(reply.to_string(), key);
+    }
+
+    /// Stores the given response if the reply subject was tracked. Error responses aren't stored so
+    /// that retries of failed requests are executed again
+    pub fn complete(&self, reply: &Subject, data: &[u8]) {
+        let mut inner = self.inner.lock().unwrap();
+        let key = match inner.pending.remove(reply.as_str()) {
+            Some(key) => key,
+            None => return,
+        };
+        if is_error_response(data) {
+            trace!("Not recording error response for idempotency key");
+            return;
+        }
+
+        let now = Instant::now();
+        // Clear out anything that has expired or is over capacity before inserting
+        while let Some((inserted, _)) = inner.order.front() {
+            if inserted.elapsed() < self.ttl && inner.responses.len() < self.capacity {
+                break;
+            }
+            // SAFETY: We just checked that the front exists
+            let (inserted, key) = inner.order.pop_front().unwrap();
+            // Only remove the response if it hasn't been replaced by a newer one with the same key
+            if matches!(inner.responses.get(&key), Some((i, _)) if *i == inserted) {
+                inner.responses.remove(&key);
+            }
+        }
+        inner.order.push_back((now, key.clone()));
+        inner.responses.insert(key, (now, data.to_vec()));
+    }
+}
+
+/// Returns true if the serialized response is one of our error responses. All API responses use
+/// `"result": "error"` to indicate an error
+fn is_error_response(data: &[u8]) -> bool {
+    serde_json::from_slice::<serde_json::Value>(data)
+        .map(|v| v.get("result").and_then(|r| r.as_str()) == Some("error"))
+        .unwrap_or(true)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_records_tracked_responses() {
+        let cache = IdempotencyCache::new(Duration::from_secs(60), 2);
+        let ok = br#"{"result":"created","message":"yay"}"#;
+        let err = br#"{"result":"error","message":"boom"}"#;
+
+        // Untracked replies aren't recorded
+        cache.complete(&Subject::from("reply.0"), ok);
+        assert!(cache.get("key0").is_none());
+
+        cache.track(Subject::from("reply.1"), "key1".to_string());
+        cache.complete(&Subject::from("reply.1"), ok);
+        assert_eq!(cache.get("key1").as_deref(), Some(ok.as_slice()));
+
+        cache.track(Subject::from("reply.2"), "key2".to_string());
+        cache.complete(&Subject::from("reply.2"), err);
+        assert!(cache.get("key2").is_none(), "Errors should not be recorded");
+
+        // Filling the cache should evict the oldest entry
+        for i in 3..5 {
+            cache.track(Subject::from(format!("reply.{i}")), format!("key{i}"));
+            cache.complete(&Subject::from(format!("reply.{i}")), ok);
+        }
+        assert!(
+            cache.get("key1").is_none(),
+            "Oldest entry should be evicted"
+        );
+        assert!(cache.get("key3").is_some());
+        assert!(cache.get("key4").is_some());
+    }
+
+    #[test]
+    fn test_entries_expire() {
+        let cache = IdempotencyCache::new(Duration::ZERO, 10);
+        cache.track(Subject::from("reply"), "key".to_string());
+        cache.complete(&Subject::from("reply"), br#"{"result":"created"}"#);
+        assert!(
+            cache.get("key").is_none(),
+            "Expired entries should not be returned"
+        );
+    }
+}
@@ -0,0 +1,190 @@
+use std::sync::Arc;
+
+use wadm_types::{
+    api::{LintSeverity, LintWarning},
+    Manifest, Properties, DAEMONSCALER_TRAIT, SPREADSCALER_TRAIT,
+};
+
+/// A best practice check run against manifests by a lint request. Unlike validation, lint warnings
+/// never stop a manifest from being stored
+pub trait LintRule: Send + Sync {
+    /// A short, unique name for the rule, such as `missing-description`
+    fn name(&self) -> &'static str;
+
+    /// Returns the warnings for the given manifest, or an empty list if it follows the rule
+    fn check(&self, manifest: &Manifest) -> Vec<LintWarning>;
+}
+
+/// Returns the lint rules run for every lint request. Additional rules can be added with
+/// [`Server::with_lint_rule`](super::Server::with_lint_rule)
+pub(crate) fn default_rules() -> Vec<Arc<dyn LintRule>> {
+    vec![
+        Arc::new(MissingDescription),
+        Arc::new(MissingScaler),
+        Arc::new(ProviderWithoutConfig),
+    ]
+}
+
+/// Runs all of the given rules against the manifest, returning their warnings in rule order
+pub(crate) fn lint(manifest: &Manifest, rules: &[Arc<dyn LintRule>]) -> Vec<LintWarning> {
+    rules.iter().flat_map(|rule| rule.check(manifest)).collect()
+}
+
+/// Warns when a manifest doesn't have a `description` annotation
+#[derive(Debug, Default, Clone, Copy)]
+pub struct MissingDescription;
+
+impl LintRule for MissingDescription {
+    fn name(&self) -> &'static str {
+        "missing-description"
+    }
+
+    fn check(&self, manifest: &Manifest) -> Vec<LintWarning> {
+        if manifest
+            .description()
+            .is_some_and(|description| !description.trim().is_empty())
+        {
+            return Vec::new();
+        }
+        vec![LintWarning {
+            rule: self.name().to_owned(),
+            severity: LintSeverity::Info,
+            message: "Manifest has no description annotation".to_owned(),
+            component: None,
+        }]
+    }
+}
+
+/// Warns about components and providers without a spreadscaler or daemonscaler trait, as they won't
+/// be run anywhere
+#[derive(Debug, Default, Clone, Copy)]
+pub struct MissingScaler;
+
+impl LintRule for MissingScaler {
+    fn name(&self) -> &'static str {
+        "missing-scaler"
+    }
+
+    fn check(&self, manifest: &Manifest) -> Vec<LintWarning> {
+        manifest
+            .spec
+            .components
+            .iter()
+            .filter(|component| {
+                !component.traits.iter().flatten().any(|trait_item| {
+                    trait_item.trait_type == SPREADSCALER_TRAIT
+                        || trait_item.trait_type == DAEMONSCALER_TRAIT
+                })
+            })
+            .map(|component| LintWarning {
+                rule: self.name().to_owned(),
+                severity: LintSeverity::Warning,
+                message: format!(
+                    "Component {} has no {SPREADSCALER_TRAIT} or {DAEMONSCALER_TRAIT} trait, so it won't be started",
+                    component.name
+                ),
+                component: Some(component.name.clone()),
+            })
+            .collect()
+    }
+}
+
+/// Warns about providers without any explicit config. Most providers need at least some config to
+/// connect to the service they provide access to
+#[derive(Debug, Default, Clone, Copy)]
+pub struct ProviderWithoutConfig;
+
+impl LintRule for ProviderWithoutConfig {
+    fn name(&self) -> &'static str {
+        "provider-without-config"
+    }
+
+    fn check(&self, manifest: &Manifest) -> Vec<LintWarning> {
+        manifest
+            .spec
+            .components
+            .iter()
+            .filter(|component| {
+                matches!(&component.properties, Properties::Capability { properties } if properties.config.is_empty())
+            })
+            .map(|component| LintWarning {
+                rule: self.name().to_owned(),
+                severity: LintSeverity::Info,
+                message: format!("Provider {} has no config", component.name),
+                component: Some(component.name.clone()),
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use wadm_types::DESCRIPTION_ANNOTATION_KEY;
+
+    #[test]
+    fn test_default_rules() {
+        let mut manifest: Manifest =
+            serde_yaml::from_slice(&std::fs::read("./oam/simple1.yaml").unwrap())
+                .expect("Should be able to parse");
+        manifest
+            .metadata
+            .annotations
+            .remove(DESCRIPTION_ANNOTATION_KEY);
+        manifest.spec.components[0].traits = None;
+        let component_name = manifest.spec.components[0].name.clone();
+
+        let warnings = lint(&manifest, &default_rules());
+        assert!(warnings
+            .iter()
+            .any(|w| w.rule == "missing-description" && w.component.is_none()));
+        assert!(warnings.iter().any(|w| w.rule == "missing-scaler"
+            && w.severity == LintSeverity::Warning
+            && w.component.as_deref() == Some(component_name.as_str())));
+        let scaled = &manifest.spec.components[2].name;
+        assert!(
+            !warnings
+                .iter()
+                .any(|w| w.rule == "missing-scaler" && w.component.as_ref() == Some(scaled)),
+            "Components with a scaler shouldn't be warned about"
+        );
+
+        manifest
+            .metadata
+            .annotations
+            .insert(DESCRIPTION_ANNOTATION_KEY.to_owned(), "An app".to_owned());
+        assert!(!lint(&manifest, &default_rules())
+            .iter()
+            .any(|w| w.rule == "missing-description"));
+    }
+
+    #[test]
+    fn test_custom_rules() {
+        struct NoLatestTags;
+
+        impl LintRule for NoLatestTags {
+            fn name(&self) -> &'static str {
+                "no-latest-tags"
+            }
+
+            fn check(&self, _manifest: &Manifest) -> Vec<LintWarning> {
+                vec![LintWarning {
+                    rule: self.name().to_owned(),
+                    severity: LintSeverity::Warning,
+                    message: "Always warns".to_owned(),
+                    component: None,
+                }]
+            }
+        }
+
+        let manifest: Manifest =
+            serde_yaml::from_slice(&std::fs::read("./oam/simple1.yaml").unwrap())
+                .expect("Should be able to parse");
+        let mut rules = default_rules();
+        rules.push(Arc::new(NoLatestTags));
+        assert!(lint(&manifest, &rules)
+            .iter()
+            .any(|w| w.rule == "no-latest-tags"));
+    }
+}
@@ -0,0 +1,150 @@
+use prometheus::{
+    exponential_buckets, histogram_opts, opts, HistogramTimer, HistogramVec, IntCounterVec,
+    Registry,
+};
+
+tokio::task_local! {
+    /// The API request currently being handled. This is set by the server around each handler call
+    /// so replies can be recorded with the operation they are for
+    pub(crate) static CURRENT_REQUEST: RequestLabels;
+}
+
+/// The labels identifying an API request in metrics
+#[derive(Debug, Clone)]
+pub(crate) struct RequestLabels {
+    pub operation: String,
+    pub account_id: String,
+    pub lattice_id: String,
+}
+
+/// Prometheus metrics for the wadm API server
+#[derive(Clone)]
+pub struct ServerMetrics {
+    operations: IntCounterVec,
+    store_latency: HistogramVec,
+    cache_lookups: IntCounterVec,
+}
+
+impl ServerMetrics {
+    /// Creates the server metrics and registers them with the given registry
+    pub fn new(registry: &Registry) -> prometheus::Result<ServerMetrics> {
+        let operations = IntCounterVec::new(
+            opts!(
+                "wadm_api_operations_total",
+                "The number of API operations handled, by outcome"
+            ),
+            &["operation", "account", "lattice", "result"],
+        )?;
+        let store_latency = HistogramVec::new(
+            histogram_opts!(
+                "wadm_store_operation_duration_seconds",
+                "The latency of model storage operations",
+                // 1ms to ~4s
+                exponential_buckets(0.001, 2.0, 13)?
+            ),
+            &["operation"],
+        )?;
+        let cache_lookups = IntCounterVec::new(
+            opts!(
+                "wadm_store_cache_lookups_total",
+                "The number of model reads served from the model cache (hit) or the store (miss)"
+            ),
+            &["result"],
+        )?;
+        registry.register(Box::new(operations.clone()))?;
+        registry.register(Box::new(store_latency.clone()))?;
+        registry.register(Box::new(cache_lookups.clone()))?;
+        Ok(ServerMetrics {
+            operations,
+            store_latency,
+            cache_lookups,
+        })
+    }
+
+    /// Records a reply to the current request (if any) with the given result
+    pub(crate) fn record_reply(&self, result: &str) {
+        // Replies sent outside of a request (such as from spawned tasks) aren't recorded
+        let _ = CURRENT_REQUEST.try_with(|labels| {
+            self.operations
+                .with_label_values(&[
+                    &labels.operation,
+                    &labels.account_id,
+                    &labels.lattice_id,
+                    result,
+                ])
+                .inc()
+        });
+    }
+
+    /// Starts timing a storage operation. The latency is recorded when the returned timer is dropped
+    pub(crate) fn store_timer(&self, operation: &str) -> HistogramTimer {
+        self.store_latency
+            .with_label_values(&[operation])
+            .start_timer()
+    }
+
+    /// Records a model read that was looked up in the model cache
+    pub(crate) fn record_cache_lookup(&self, hit: bool) {
+        self.cache_lookups
+            .with_label_values(&[if hit { "hit" } else { "miss" }])
+            .inc()
+    }
+}
+
+/// Returns the `result` field of a serialized API response, which all of our responses have. This is
+/// used as the outcome label for metrics
+pub(crate) fn response_result(data: &[u8]) -> String {
+    serde_json::from_slice::<serde_json::Value>(data)
+        .ok()
+        .and_then(|v| {
+            v.get("result")
+                .and_then(|r| r.as_str())
+                .map(ToOwned::to_owned)
+        })
+        // Some responses (like list) are just the data, which means they succeeded
+        .unwrap_or_else(|| "success".to_string())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_records_replies_for_current_request() {
+        let registry = Registry::new();
+        let metrics = ServerMetrics::new(&registry).expect("Should be able to create metrics");
+
+        // Outside of a request, nothing is recorded
+        metrics.record_reply("error");
+
+        CURRENT_REQUEST
+            .scope(
+                RequestLabels {
+                    operation: "put".to_string(),
+                    account_id: String::new(),
+                    lattice_id: "default".to_string(),
+                },
+                async { metrics.record_reply(&response_result(br#"{"result":"created"}"#)) },
+            )
+            .await;
+
+        assert_eq!(
+            metrics
+                .operations
+                .with_label_values(&["put", "", "default", "created"])
+                .get(),
+            1
+        );
+        let total: u64 = registry
+            .gather()
+            .iter()
+            .filter(|family| family.get_name() == "wadm_api_operations_total")
+            .flat_map(|family| family.get_metric())
+            .map(|m| m.get_counter().get_value() as u64)
+            .sum();
+        assert_eq!(
+            total, 1,
+            "Only the reply within a request should be recorded"
+        );
+    }
+}
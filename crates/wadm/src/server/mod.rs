@@ -1,25 +1,296 @@
+use std::{
+    cell::RefCell,
+    collections::{HashMap, HashSet},
+    fmt::Display,
+    path::PathBuf,
+    str::FromStr,
+    sync::Arc,
+    time::Duration,
+};
+
 use async_nats::{
     jetstream::{kv::Store, stream::Stream},
-    Client, Subscriber,
+    Client, Message, Subscriber,
 };
 use futures::StreamExt;
-use tracing::{info, instrument, warn};
-use wadm_types::api::DEFAULT_WADM_TOPIC_PREFIX;
+use tracing::{info, info_span, instrument, warn, Instrument};
+use wadm_types::api::{ErrorCode, DEFAULT_WADM_TOPIC_PREFIX};
 
 use crate::publisher::Publisher;
 
+mod audit;
+mod authz;
+mod cache;
+mod compression;
 mod handlers;
+mod idempotency;
+mod lint;
+mod metrics;
 mod notifier;
 mod parser;
 mod storage;
+mod sweeper;
+mod template;
+mod timeout;
+pub mod validation;
+mod versioning;
+mod watch;
 
+use audit::{AuditContext, CURRENT_AUDIT};
+pub use audit::{AuditRecord, DEFAULT_AUDIT_TOPIC_PREFIX};
+pub use authz::{AllowAll, AuthorizationRequest, Authorizer, ModelOperation};
+use cache::ModelCache;
+use compression::ACCEPTS_GZIP;
+pub use compression::{
+    StorageCodec, ACCEPT_ENCODING_HEADER, CONTENT_ENCODING_HEADER,
+    DEFAULT_REPLY_COMPRESSION_THRESHOLD,
+};
 use handlers::Handler;
-pub use notifier::ManifestNotifier;
+pub use handlers::{DEPLOY_ON_PUT_HEADER, MODEL_NAME_HEADER};
+use idempotency::IdempotencyCache;
+pub use idempotency::{
+    DEFAULT_IDEMPOTENCY_CACHE_SIZE, DEFAULT_IDEMPOTENCY_TTL, IDEMPOTENCY_KEY_HEADER,
+};
+pub use lint::{LintRule, MissingDescription, MissingScaler, ProviderWithoutConfig};
+pub use metrics::ServerMetrics;
+use metrics::{RequestLabels, CURRENT_REQUEST};
+use notifier::CURRENT_TRACE_ID;
+pub use notifier::{ManifestNotifier, TRACE_ID_HEADER};
 pub use parser::CONTENT_TYPE_HEADER;
-pub(crate) use storage::ModelStorage;
+pub use storage::{set_default_account, DeployLock, ModelStorage, DEFAULT_DEPLOY_LOCK_TTL};
+pub use sweeper::DEFAULT_MODEL_SWEEP_INTERVAL;
+pub use template::TEMPLATE_PARAMETERS_HEADER;
+pub use timeout::{DEFAULT_NOTIFY_TIMEOUT, DEFAULT_STORE_TIMEOUT};
+pub use versioning::{SequentialVersions, TimestampVersions, UlidVersions, VersionGenerator};
+use watch::WatchLimiter;
+pub use watch::{DEFAULT_MAX_WATCHES_PER_ACCOUNT, DEFAULT_WATCH_SUBJECT_PREFIX};
 
 const QUEUE_GROUP: &str = "wadm_server";
 
+/// The default maximum size (in bytes) of a manifest that can be put into the store
+pub const DEFAULT_MAX_MANIFEST_BYTES: usize = 512 * 1024;
+/// The default maximum size (in bytes) of a multi-manifest put request
+pub const DEFAULT_MAX_PUT_MODELS_BYTES: usize = 8 * 1024 * 1024;
+
+/// The default maximum number of model statuses fetched at once when listing models
+pub const DEFAULT_STATUS_FETCH_CONCURRENCY: usize = 16;
+
+/// The default maximum number of components allowed in a single manifest
+pub const DEFAULT_MAX_COMPONENTS: usize = 1000;
+/// The default maximum number of traits allowed on a single component
+pub const DEFAULT_MAX_TRAITS_PER_COMPONENT: usize = 100;
+/// The default maximum nesting depth of a component's properties and trait properties
+pub const DEFAULT_MAX_CONFIG_DEPTH: usize = 32;
+/// The default maximum number of instances a single scaler trait can declare
+pub const DEFAULT_MAX_INSTANCES: usize = 10_000;
+
+/// Limits on the size of a manifest's spec, enforced when validating a manifest. These are a safety
+/// valve against manifests that would overwhelm the processors that reconcile them
+#[derive(Debug, Clone)]
+pub struct ManifestLimits {
+    /// The maximum number of components (including providers) in a manifest
+    pub max_components: usize,
+    /// The maximum number of traits on a single component
+    pub max_traits_per_component: usize,
+    /// The maximum nesting depth of a component's properties and the properties of each of its
+    /// traits, where a flat object has a depth of 1
+    pub max_config_depth: usize,
+    /// The maximum number of instances a spreadscaler or daemonscaler trait can declare. For
+    /// daemonscalers, this is the number of instances per matching host
+    pub max_instances: usize,
+    /// The registries (such as `ghcr.io`) that component and provider images may be pulled from.
+    /// When empty, images from any registry are allowed
+    pub allowed_registries: Vec<String>,
+}
+
+impl Default for ManifestLimits {
+    fn default() -> Self {
+        ManifestLimits {
+            max_components: DEFAULT_MAX_COMPONENTS,
+            max_traits_per_component: DEFAULT_MAX_TRAITS_PER_COMPONENT,
+            max_config_depth: DEFAULT_MAX_CONFIG_DEPTH,
+            max_instances: DEFAULT_MAX_INSTANCES,
+            allowed_registries: Vec::new(),
+        }
+    }
+}
+
+/// How model names are shared between accounts in a lattice
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum ModelNamePolicy {
+    /// Each account has its own namespace, so different accounts can create models with the same
+    /// name in a lattice
+    #[default]
+    PerAccount,
+    /// Model names are unique across all accounts in a lattice, so a model can't be created if any
+    /// other account already has a model with that name
+    LatticeGlobal,
+}
+
+impl Display for ModelNamePolicy {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ModelNamePolicy::PerAccount => write!(f, "per-account"),
+            ModelNamePolicy::LatticeGlobal => write!(f, "lattice-global"),
+        }
+    }
+}
+
+impl FromStr for ModelNamePolicy {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_lowercase().as_str() {
+            "per-account" => Ok(ModelNamePolicy::PerAccount),
+            "lattice-global" => Ok(ModelNamePolicy::LatticeGlobal),
+            _ => Err(format!(
+                "unknown model name policy {s:?}, expected one of per-account or lattice-global"
+            )),
+        }
+    }
+}
+
+/// Configuration options for the behavior of the wadm API server
+#[derive(Debug, Clone)]
+pub struct ServerConfig {
+    /// The maximum size (in bytes) of a manifest that can be put into the store. Any larger
+    /// manifests are rejected before they are parsed
+    pub max_manifest_bytes: usize,
+    /// The maximum size (in bytes) of a multi-manifest put request. Each manifest in the request
+    /// is also limited to `max_manifest_bytes`
+    pub max_put_models_bytes: usize,
+    /// The maximum number of models an account can store in a lattice. `None` means there is no
+    /// limit. New versions of existing models do not count against this limit
+    pub max_models_per_account: Option<usize>,
+    /// Per account overrides of `max_models_per_account`, keyed by account ID
+    pub account_model_quotas: HashMap<String, usize>,
+    /// Model name prefixes (such as `system-`) reserved for the accounts in
+    /// `reserved_name_accounts`. Other accounts can't create models whose names start with one of
+    /// these. When not running in multitenant mode there is no account, so reserved names can't be
+    /// used at all
+    pub reserved_name_prefixes: Vec<String>,
+    /// Account IDs allowed to create models with a reserved name prefix
+    pub reserved_name_accounts: HashSet<String>,
+    /// Whether model names only need to be unique within an account or across all accounts in a
+    /// lattice
+    pub model_name_policy: ModelNamePolicy,
+    /// Limits enforced on the spec of every manifest that is stored
+    pub manifest_limits: ManifestLimits,
+    /// How long responses to requests with an idempotency key are remembered
+    pub idempotency_ttl: Duration,
+    /// The maximum number of idempotency keys to remember responses for
+    pub idempotency_cache_size: usize,
+    /// The path to a JSON schema to validate manifests against instead of the embedded OAM schema.
+    /// This allows for validating manifests that use custom OAM extensions
+    pub manifest_schema_path: Option<PathBuf>,
+    /// The maximum number of model statuses fetched at once when listing models or computing stats
+    pub status_fetch_concurrency: usize,
+    /// How old a model's last status can be before it is marked as stale when fetched. Processors
+    /// republish status as the lattice changes, so a status older than this may mean the processor
+    /// responsible for the model has died. If not set, statuses are never marked as stale
+    pub status_stale_after: Option<Duration>,
+    /// Whether manifests put without a version are assigned one by the server's
+    /// [`VersionGenerator`]. If disabled, manifests without a version are rejected
+    pub auto_version: bool,
+    /// Whether component names (and the targets of links to them) are lowercased before manifests
+    /// are validated and stored. Names are always trimmed, and names that only differ by case are
+    /// always rejected as duplicates
+    pub lowercase_component_names: bool,
+    /// The codec used to compress models when they are stored. Models stored with any codec (or
+    /// uncompressed by older versions of wadm) can always be read
+    pub storage_codec: StorageCodec,
+    /// How long to wait for a single model storage operation before replying with a timeout error
+    pub store_timeout: Duration,
+    /// The maximum number of models to cache in memory. Cached models are invalidated by a watch
+    /// on the store, so changes made by other wadm replicas are always seen. If not set, every read
+    /// goes to the store
+    pub model_cache_size: Option<usize>,
+    /// How long a deploy can hold the lock on a model before the lock is considered abandoned and
+    /// another deploy can take it over. Deploys of the same model are serialized by this lock, so
+    /// this should be longer than any deploy takes to store and notify
+    pub deploy_lock_ttl: Duration,
+    /// How long to wait for a notification to processors to be sent (including retries) before
+    /// replying with a timeout error
+    pub notify_timeout: Duration,
+    /// The topic prefix to publish an [`AuditRecord`] under for every request that changes models.
+    /// Records are published on `{prefix}.{lattice-id}` on a best effort basis. If not set, no
+    /// audit records are published
+    pub audit_topic_prefix: Option<String>,
+    /// The size (in bytes) at or above which replies are gzipped for clients that set the
+    /// [`ACCEPT_ENCODING_HEADER`] to accept it. If not set, replies are never compressed
+    pub reply_compression_threshold: Option<usize>,
+    /// The registry to record API and storage metrics in. If not set, no metrics are recorded
+    pub metrics_registry: Option<prometheus::Registry>,
+    /// How often to delete models that have been undeployed for longer than the TTL set by their
+    /// [`TTL_ANNOTATION_KEY`](wadm_types::TTL_ANNOTATION_KEY) annotation. Sweeps run on their own
+    /// task alongside requests. If not set, models are never deleted automatically
+    pub model_sweep_interval: Option<Duration>,
+    /// Whether get_raw requests, which return models exactly as they are stored, are allowed. This
+    /// is meant for debugging and migration tooling, so it is disabled by default
+    pub enable_raw_model_access: bool,
+    /// The subject prefixes (such as `_INBOX`) that status and model watches can publish to. The
+    /// server publishes watch updates with its own credentials, so clients can only have them sent
+    /// to their inboxes. Clients that use a custom inbox prefix need it added here
+    pub watch_subject_prefixes: Vec<String>,
+    /// The maximum number of status and model watches a single account can have running at once
+    pub max_watches_per_account: usize,
+}
+
+impl ServerConfig {
+    /// Returns the maximum number of models the given account can store, if any
+    pub fn model_quota(&self, account_id: Option<&str>) -> Option<usize> {
+        account_id
+            .and_then(|id| self.account_model_quotas.get(id).copied())
+            .or(self.max_models_per_account)
+    }
+
+    /// Returns the reserved prefix the given model name starts with, if the account isn't allowed
+    /// to use it
+    pub fn reserved_prefix(&self, name: &str, account_id: Option<&str>) -> Option<&str> {
+        if account_id.is_some_and(|id| self.reserved_name_accounts.contains(id)) {
+            return None;
+        }
+        self.reserved_name_prefixes
+            .iter()
+            .find(|prefix| name.starts_with(prefix.as_str()))
+            .map(String::as_str)
+    }
+}
+
+impl Default for ServerConfig {
+    fn default() -> Self {
+        ServerConfig {
+            max_manifest_bytes: DEFAULT_MAX_MANIFEST_BYTES,
+            max_put_models_bytes: DEFAULT_MAX_PUT_MODELS_BYTES,
+            max_models_per_account: None,
+            account_model_quotas: HashMap::new(),
+            reserved_name_prefixes: Vec::new(),
+            reserved_name_accounts: HashSet::new(),
+            model_name_policy: ModelNamePolicy::default(),
+            manifest_limits: ManifestLimits::default(),
+            idempotency_ttl: DEFAULT_IDEMPOTENCY_TTL,
+            idempotency_cache_size: DEFAULT_IDEMPOTENCY_CACHE_SIZE,
+            manifest_schema_path: None,
+            status_fetch_concurrency: DEFAULT_STATUS_FETCH_CONCURRENCY,
+            status_stale_after: None,
+            auto_version: true,
+            lowercase_component_names: false,
+            storage_codec: StorageCodec::default(),
+            store_timeout: DEFAULT_STORE_TIMEOUT,
+            model_cache_size: None,
+            notify_timeout: DEFAULT_NOTIFY_TIMEOUT,
+            deploy_lock_ttl: DEFAULT_DEPLOY_LOCK_TTL,
+            audit_topic_prefix: Some(DEFAULT_AUDIT_TOPIC_PREFIX.to_owned()),
+            reply_compression_threshold: Some(DEFAULT_REPLY_COMPRESSION_THRESHOLD),
+            metrics_registry: None,
+            enable_raw_model_access: false,
+            model_sweep_interval: Some(DEFAULT_MODEL_SWEEP_INTERVAL),
+            watch_subject_prefixes: vec![DEFAULT_WATCH_SUBJECT_PREFIX.to_owned()],
+            max_watches_per_account: DEFAULT_MAX_WATCHES_PER_ACCOUNT,
+        }
+    }
+}
+
 /// A server for the wadm API
 pub struct Server<P> {
     handler: Handler<P>,
@@ -29,8 +300,8 @@ pub struct Server<P> {
 }
 
 impl<P: Publisher> Server<P> {
-    /// Returns a new server configured with the given store, NATS client, and optional topic
-    /// prefix. Returns an error if it can't subscribe on the right topics
+    /// Returns a new server configured with the given store, NATS client, optional topic prefix,
+    /// and server config. Returns an error if it can't subscribe on the right topics
     ///
     /// In most cases, you shouldn't need a custom topic prefix, but it is exposed for the cases
     /// when you may need to set a custom prefix for security purposes or topic segregation
@@ -42,6 +313,7 @@ impl<P: Publisher> Server<P> {
         multitenant: bool,
         status_stream: Stream,
         notifier: ManifestNotifier<P>,
+        config: ServerConfig,
     ) -> anyhow::Result<Server<P>> {
         // Trim off any spaces or trailing/preceding dots
         let prefix = topic_prefix
@@ -53,6 +325,18 @@ impl<P: Publisher> Server<P> {
             anyhow::bail!("Given prefix was empty")
         }
 
+        if let Some(path) = config.manifest_schema_path.as_deref() {
+            info!(path = %path.display(), "Loading custom manifest schema");
+            validation::load_custom_schema(path).await?;
+        }
+
+        let metrics = config
+            .metrics_registry
+            .as_ref()
+            .map(ServerMetrics::new)
+            .transpose()
+            .map_err(|e| anyhow::anyhow!("Unable to register metrics: {e}"))?;
+
         let topic_prefix = if multitenant {
             format!("*.{prefix}")
         } else {
@@ -63,20 +347,42 @@ impl<P: Publisher> Server<P> {
         info!(%topic, "Creating API subscriber");
         // NOTE(thomastaylor312): Technically there is a condition where two people try to send an
         // update to the same manifest. We are protected against this overwriting each other (we
-        // ensure the revision is the same in the underlying store), but it will lead to a weird
-        // error reply about a storage error. This is more of an inconvenience that we can probably
-        // solve for when we get concrete error types in NATS
+        // ensure the revision is the same in the underlying store), and the handlers retry a few
+        // times on a revision conflict. If the conflicts persist past those retries, it will still
+        // lead to a weird error reply about a storage error
         let subscriber = client
             .queue_subscribe(topic, QUEUE_GROUP.to_owned())
             .await
             .map_err(|e| anyhow::anyhow!("{e:?}"))?;
 
+        let mut model_store = ModelStorage::new(store.clone())
+            .with_metrics(metrics.clone())
+            .with_timeout(config.store_timeout)
+            .with_codec(config.storage_codec);
+        if let Some(size) = config.model_cache_size {
+            info!(size, "Caching models in memory");
+            let cache = ModelCache::new(size);
+            tokio::spawn(cache.clone().watch(store));
+            model_store = model_store.with_cache(cache);
+        }
+
         Ok(Server {
             handler: Handler {
-                store: ModelStorage::new(store),
+                store: model_store,
                 client,
-                notifier,
+                notifier: notifier.with_timeout(config.notify_timeout),
                 status_stream,
+                idempotency: Arc::new(IdempotencyCache::new(
+                    config.idempotency_ttl,
+                    config.idempotency_cache_size,
+                )),
+                metrics,
+                authorizer: None,
+                version_generator: Arc::new(UlidVersions),
+                lint_rules: lint::default_rules(),
+                api_prefix: prefix.clone(),
+                watches: Arc::new(WatchLimiter::new(config.max_watches_per_account)),
+                config,
             },
             subscriber,
             prefix,
@@ -84,12 +390,42 @@ impl<P: Publisher> Server<P> {
         })
     }
 
+    /// Sets the [`Authorizer`] used to check requests that change models. By default, every request
+    /// is allowed
+    pub fn with_authorizer(mut self, authorizer: impl Authorizer + 'static) -> Self {
+        self.handler.authorizer = Some(Arc::new(authorizer));
+        self
+    }
+
+    /// Sets the [`VersionGenerator`] used to assign versions to manifests put without one. By
+    /// default, a new ULID is used for each version
+    pub fn with_version_generator(mut self, generator: impl VersionGenerator + 'static) -> Self {
+        self.handler.version_generator = Arc::new(generator);
+        self
+    }
+
+    /// Adds a [`LintRule`] to the rules run for lint requests, on top of the built in rules
+    pub fn with_lint_rule(mut self, rule: impl LintRule + 'static) -> Self {
+        self.handler.lint_rules.push(Arc::new(rule));
+        self
+    }
+
     /// Starts the server, consuming it.
     ///
     /// This function will run until it either returns an error (which should always be fatal) or
     /// you stop polling the future
     #[instrument(level = "info", skip_all)]
-    pub async fn serve(mut self) -> anyhow::Result<()> {
+    pub async fn serve(mut self) -> anyhow::Result<()>
+    where
+        P: Clone + Send + Sync + 'static,
+    {
+        // Sweeps can take a while in large buckets, so they run on their own task rather than
+        // holding up requests. The task stops when the server does
+        let _sweeper = self
+            .handler
+            .config
+            .model_sweep_interval
+            .map(|period| self.handler.spawn_model_sweeper(period, self.multitenant));
         while let Some(msg) = self.subscriber.next().await {
             if !msg.subject.starts_with(&self.prefix) && !self.multitenant {
                 warn!(subject = %msg.subject, "Received message on an invalid subject");
@@ -105,116 +441,448 @@ impl<P: Publisher> Server<P> {
                 Ok(p) => p,
                 Err(e) => {
                     self.handler
-                        .send_error(msg.reply, format!("Invalid subject: {e:?}"))
+                        .send_error(
+                            msg.reply,
+                            ErrorCode::InvalidRequest,
+                            format!("Invalid subject: {e:?}"),
+                        )
                         .await;
                     continue;
                 }
             };
 
-            match parsed {
-                ParsedSubject {
-                    account_id,
-                    lattice_id,
-                    category: "model",
-                    operation: "list",
-                    object_name: None,
-                } => self.handler.list_models(msg, account_id, lattice_id).await,
-                ParsedSubject {
-                    account_id,
-                    lattice_id,
-                    category: "model",
-                    operation: "get",
-                    object_name: Some(name),
-                } => {
-                    self.handler
-                        .get_model(msg, account_id, lattice_id, name)
-                        .await
-                }
-                ParsedSubject {
-                    account_id,
-                    lattice_id,
-                    category: "model",
-                    operation: "put",
-                    object_name: None,
-                } => self.handler.put_model(msg, account_id, lattice_id).await,
-                ParsedSubject {
-                    account_id,
-                    lattice_id,
-                    category: "model",
-                    operation: "del",
-                    object_name: Some(name),
-                } => {
-                    self.handler
-                        .delete_model(msg, account_id, lattice_id, name)
-                        .await
-                }
-                ParsedSubject {
-                    account_id,
-                    lattice_id,
-                    category: "model",
-                    operation: "versions",
-                    object_name: Some(name),
-                } => {
-                    self.handler
-                        .list_versions(msg, account_id, lattice_id, name)
-                        .await
-                }
-                ParsedSubject {
-                    account_id,
-                    lattice_id,
-                    category: "model",
-                    operation: "deploy",
-                    object_name: Some(name),
-                } => {
-                    self.handler
-                        .deploy_model(msg, account_id, lattice_id, name)
-                        .await
-                }
-                ParsedSubject {
-                    account_id,
-                    lattice_id,
-                    category: "model",
-                    operation: "undeploy",
-                    object_name: Some(name),
-                } => {
-                    self.handler
-                        .undeploy_model(msg, account_id, lattice_id, name)
-                        .await
-                }
-                ParsedSubject {
-                    account_id,
-                    lattice_id,
-                    category: "model",
-                    operation: "status",
-                    object_name: Some(name),
-                } => {
-                    self.handler
-                        .model_status(msg, account_id, lattice_id, name)
-                        .await
-                }
-                ParsedSubject {
-                    account_id: _,
-                    lattice_id: _,
-                    category: "model",
-                    operation: "history",
-                    object_name: Some(_name),
-                } => {
-                    // TODO(thomastaylor312): For now I don't want to figure out how we want to
-                    // store this history. Obviously it should be a different key (which we don't
-                    // really support custom ones right now with the Store trait), and I honestly
-                    // wonder if it would be better to emit to a NATS topic and collect to a stream
-                    // for retrieval (with rollups) rather than us storing it
-                    self.handler.send_error(msg.reply, "Model deployment history is not currently supported. It may be added in a future version".to_string()).await;
-                }
-                _ => {
-                    let err = format!("Unsupported subject: {}", msg.subject);
-                    self.handler.send_error(msg.reply, err).await;
-                }
-            }
+            let labels = RequestLabels {
+                operation: parsed.operation.to_owned(),
+                account_id: parsed.account_id.unwrap_or_default().to_owned(),
+                lattice_id: parsed.lattice_id.to_owned(),
+            };
+            let trace_id = msg
+                .headers
+                .as_ref()
+                .and_then(|headers| headers.get(TRACE_ID_HEADER))
+                .map(|value| value.as_str().to_owned())
+                .unwrap_or_else(|| uuid::Uuid::new_v4().to_string());
+            let audit = (parsed.category == "model")
+                .then(|| AuditContext::operation(parsed.operation))
+                .flatten()
+                .map(|operation| AuditContext {
+                    operation,
+                    account_id: parsed.account_id.map(ToOwned::to_owned),
+                    lattice_id: parsed.lattice_id.to_owned(),
+                    model_name: parsed.object_name.map(ToOwned::to_owned),
+                    version: None,
+                    trace_id: Some(trace_id.clone()),
+                });
+            let accepts_gzip = compression::accepts_gzip(msg.headers.as_ref());
+            // The account and lattice are set on this span (rather than just the handler spans)
+            // so they are on every log line for the request, including at the default log level
+            let span = info_span!(
+                "request",
+                %trace_id,
+                account_id = parsed.account_id.unwrap_or_default(),
+                lattice_id = parsed.lattice_id,
+            );
+            CURRENT_TRACE_ID
+                .scope(
+                    trace_id,
+                    CURRENT_AUDIT.scope(
+                        RefCell::new(audit),
+                        CURRENT_REQUEST.scope(
+                            labels,
+                            ACCEPTS_GZIP.scope(accepts_gzip, self.handle_request(msg, parsed)),
+                        ),
+                    ),
+                )
+                .instrument(span)
+                .await;
         }
         Err(anyhow::anyhow!("Subscriber terminated"))
     }
 
+    /// Routes the request to the right handler based on its subject
+    async fn handle_request(&self, msg: Message, parsed: ParsedSubject<'_>) {
+        match parsed {
+            ParsedSubject {
+                account_id,
+                lattice_id,
+                category: "model",
+                operation: "list",
+                object_name: None,
+            } => self.handler.list_models(msg, account_id, lattice_id).await,
+            ParsedSubject {
+                account_id,
+                lattice_id,
+                category: "model",
+                operation: "stats",
+                object_name: None,
+            } => self.handler.model_stats(msg, account_id, lattice_id).await,
+            ParsedSubject {
+                account_id,
+                lattice_id,
+                category: "model",
+                operation: "get",
+                object_name: Some(name),
+            } => {
+                self.handler
+                    .get_model(msg, account_id, lattice_id, name)
+                    .await
+            }
+            ParsedSubject {
+                account_id,
+                lattice_id,
+                category: "model",
+                operation: "get_raw",
+                object_name: Some(name),
+            } => {
+                self.handler
+                    .get_raw_model(msg, account_id, lattice_id, name)
+                    .await
+            }
+            ParsedSubject {
+                account_id,
+                lattice_id,
+                category: "model",
+                operation: "put",
+                object_name,
+            } => {
+                self.handler
+                    .put_model(msg, account_id, lattice_id, object_name)
+                    .await
+            }
+            ParsedSubject {
+                account_id,
+                lattice_id,
+                category: "model",
+                operation: "normalize",
+                object_name,
+            } => {
+                self.handler
+                    .normalize_model(msg, account_id, lattice_id, object_name)
+                    .await
+            }
+            ParsedSubject {
+                category: "model",
+                operation: "lint",
+                ..
+            } => self.handler.lint_model(msg).await,
+            ParsedSubject {
+                account_id,
+                lattice_id,
+                category: "model",
+                operation: "diff",
+                object_name: Some(name),
+            } => {
+                self.handler
+                    .diff_model(msg, account_id, lattice_id, name)
+                    .await
+            }
+            ParsedSubject {
+                account_id,
+                lattice_id,
+                category: "model",
+                operation: "diff_candidate",
+                object_name,
+            } => {
+                self.handler
+                    .diff_candidate(msg, account_id, lattice_id, object_name)
+                    .await
+            }
+            ParsedSubject {
+                account_id,
+                lattice_id,
+                category: "model",
+                operation: "copy",
+                object_name: Some(name),
+            } => {
+                self.handler
+                    .copy_model(msg, account_id, lattice_id, name)
+                    .await
+            }
+            ParsedSubject {
+                account_id,
+                lattice_id,
+                category: "model",
+                operation: "del",
+                object_name: Some(name),
+            } => {
+                self.handler
+                    .delete_model(msg, account_id, lattice_id, name)
+                    .await
+            }
+            ParsedSubject {
+                account_id,
+                lattice_id,
+                category: "model",
+                operation: "list_archived",
+                object_name: None,
+            } => {
+                self.handler
+                    .list_archived(msg, account_id, lattice_id)
+                    .await
+            }
+            ParsedSubject {
+                account_id,
+                lattice_id,
+                category: "model",
+                operation: "restore",
+                object_name: Some(name),
+            } => {
+                self.handler
+                    .restore_model(msg, account_id, lattice_id, name)
+                    .await
+            }
+            ParsedSubject {
+                account_id,
+                lattice_id,
+                category: "model",
+                operation: "versions",
+                object_name: Some(name),
+            } => {
+                self.handler
+                    .list_versions(msg, account_id, lattice_id, name)
+                    .await
+            }
+            ParsedSubject {
+                account_id,
+                lattice_id,
+                category: "model",
+                operation: "stage",
+                object_name: Some(name),
+            } => {
+                self.handler
+                    .stage_model(msg, account_id, lattice_id, name)
+                    .await
+            }
+            ParsedSubject {
+                account_id,
+                lattice_id,
+                category: "model",
+                operation: "patch_metadata",
+                object_name: Some(name),
+            } => {
+                self.handler
+                    .patch_model_metadata(msg, account_id, lattice_id, name)
+                    .await
+            }
+            ParsedSubject {
+                account_id,
+                lattice_id,
+                category: "model",
+                operation: "set_deployed",
+                object_name: Some(name),
+            } => {
+                self.handler
+                    .set_deployed_model(msg, account_id, lattice_id, name)
+                    .await
+            }
+            ParsedSubject {
+                account_id,
+                lattice_id,
+                category: "model",
+                operation: "promote",
+                object_name: Some(name),
+            } => {
+                self.handler
+                    .promote_model(msg, account_id, lattice_id, name)
+                    .await
+            }
+            ParsedSubject {
+                account_id,
+                lattice_id,
+                category: "model",
+                operation: "deploy",
+                object_name: Some(name),
+            } => {
+                self.handler
+                    .deploy_model(msg, account_id, lattice_id, name)
+                    .await
+            }
+            ParsedSubject {
+                account_id,
+                lattice_id,
+                category: "model",
+                operation: "preflight",
+                object_name: Some(name),
+            } => {
+                self.handler
+                    .deploy_preflight(msg, account_id, lattice_id, name)
+                    .await
+            }
+            ParsedSubject {
+                account_id,
+                lattice_id,
+                category: "model",
+                operation: "undeploy",
+                object_name: Some(name),
+            } => {
+                self.handler
+                    .undeploy_model(msg, account_id, lattice_id, name)
+                    .await
+            }
+            ParsedSubject {
+                account_id,
+                lattice_id,
+                category: "model",
+                operation: "undeploy_all",
+                object_name: None,
+            } => self.handler.undeploy_all(msg, account_id, lattice_id).await,
+            ParsedSubject {
+                account_id,
+                lattice_id,
+                category: "model",
+                operation: "export",
+                object_name: None,
+            } => {
+                self.handler
+                    .export_models(msg, account_id, lattice_id)
+                    .await
+            }
+            ParsedSubject {
+                account_id,
+                lattice_id,
+                category: "model",
+                operation: "apply",
+                object_name: None,
+            } => self.handler.put_models(msg, account_id, lattice_id).await,
+            ParsedSubject {
+                account_id,
+                lattice_id,
+                category: "model",
+                operation: "deploy_many",
+                object_name: None,
+            } => {
+                self.handler
+                    .deploy_models(msg, account_id, lattice_id)
+                    .await
+            }
+            ParsedSubject {
+                lattice_id,
+                category: "server",
+                operation: "info",
+                object_name: None,
+                ..
+            } => self.handler.server_info(msg, lattice_id).await,
+            ParsedSubject {
+                account_id,
+                category: "server",
+                operation: "lattices",
+                object_name: None,
+                ..
+            } => self.handler.list_lattices(msg, account_id).await,
+            ParsedSubject {
+                category: "server",
+                operation: "health",
+                object_name: None,
+                ..
+            } => self.handler.health(msg).await,
+            ParsedSubject {
+                account_id,
+                lattice_id,
+                category: "model",
+                operation: "import",
+                object_name: None,
+            } => {
+                self.handler
+                    .import_models(msg, account_id, lattice_id)
+                    .await
+            }
+            ParsedSubject {
+                account_id,
+                lattice_id,
+                category: "model",
+                operation: "status",
+                object_name: Some(name),
+            } => {
+                self.handler
+                    .model_status(msg, account_id, lattice_id, name)
+                    .await
+            }
+            ParsedSubject {
+                account_id,
+                lattice_id,
+                category: "model",
+                operation: "status_history",
+                object_name: Some(name),
+            } => {
+                self.handler
+                    .model_status_history(msg, account_id, lattice_id, name)
+                    .await
+            }
+            ParsedSubject {
+                account_id,
+                lattice_id,
+                category: "model",
+                operation: "status_topic",
+                object_name: Some(name),
+            } => {
+                self.handler
+                    .status_topic(msg, account_id, lattice_id, name)
+                    .await
+            }
+            ParsedSubject {
+                account_id,
+                lattice_id,
+                category: "model",
+                operation: "watch_status",
+                object_name: Some(name),
+            } => {
+                self.handler
+                    .watch_status(msg, account_id, lattice_id, name)
+                    .await
+            }
+            ParsedSubject {
+                account_id,
+                lattice_id,
+                category: "model",
+                operation: "watch",
+                object_name: Some(name),
+            } => {
+                self.handler
+                    .watch_model(msg, account_id, lattice_id, name)
+                    .await
+            }
+            ParsedSubject {
+                account_id,
+                lattice_id,
+                category: "model",
+                operation: "prune",
+                object_name: Some(name),
+            } => {
+                self.handler
+                    .prune_model(msg, account_id, lattice_id, name)
+                    .await
+            }
+            ParsedSubject {
+                account_id,
+                lattice_id,
+                category: "model",
+                operation: "describe",
+                object_name: Some(name),
+            } => {
+                self.handler
+                    .describe_model(msg, account_id, lattice_id, name)
+                    .await
+            }
+            ParsedSubject {
+                account_id,
+                lattice_id,
+                category: "model",
+                operation: "history",
+                object_name: Some(name),
+            } => {
+                self.handler
+                    .deploy_history(msg, account_id, lattice_id, name)
+                    .await
+            }
+            _ => {
+                let err = format!("Unsupported subject: {}", msg.subject);
+                self.handler
+                    .send_error(msg.reply, ErrorCode::Unsupported, err)
+                    .await;
+            }
+        }
+    }
+
     fn parse_subject<'a>(&self, subject: &'a str) -> anyhow::Result<ParsedSubject<'a>> {
         // Topic structure: wadm.api.{lattice-id}.{category}.{operation}.{object}
         // Multitenant topic structure: {account-id}.wadm.api.{lattice-id}.{category}.{operation}.{object}
@@ -1,5 +1,7 @@
+use std::time::Duration;
+
 use cloudevents::Event as CloudEvent;
-use tracing::{instrument, trace};
+use tracing::{debug, instrument, trace};
 use wadm_types::Manifest;
 
 use crate::{
@@ -7,10 +9,39 @@ use crate::{
     publisher::Publisher,
 };
 
-/// A notifier that publishes changes about manifests with the given publisher
+use super::timeout::{with_timeout, DEFAULT_NOTIFY_TIMEOUT};
+
+/// The name of the header in the NATS request that contains the trace ID of the request. If it
+/// isn't set, a trace ID is generated. The trace ID is added to the request's span and to any
+/// notifications sent while handling it
+pub const TRACE_ID_HEADER: &str = "wadm-trace-id";
+
+tokio::task_local! {
+    /// The trace ID of the API request currently being handled. This is set by the server around
+    /// each handler call
+    pub(crate) static CURRENT_TRACE_ID: String;
+}
+
+/// Returns the trace ID of the current request, if there is one
+pub(crate) fn current_trace_id() -> Option<String> {
+    CURRENT_TRACE_ID.try_with(Clone::clone).ok()
+}
+
+/// The maximum number of times a notification is attempted before giving up
+const MAX_NOTIFY_ATTEMPTS: u32 = 5;
+/// The delay before the first retry of a failed notification. This doubles on every retry
+const INITIAL_NOTIFY_BACKOFF: Duration = Duration::from_millis(100);
+
+/// A notifier that publishes changes about manifests with the given publisher.
+///
+/// Every notification is sent as a CloudEvents 1.0 envelope (with `type`, `source`, `id`, `time`,
+/// and `data` set), with the structured event payload carried unchanged in `data`. The scaler
+/// manager consumes these envelopes, so this is the only supported format
+#[derive(Clone)]
 pub struct ManifestNotifier<P> {
     prefix: String,
     publisher: P,
+    timeout: Duration,
 }
 
 impl<P: Publisher> ManifestNotifier<P> {
@@ -21,26 +52,69 @@ impl<P: Publisher> ManifestNotifier<P> {
         ManifestNotifier {
             prefix: prefix.trim().trim_matches(trimmer).to_owned(),
             publisher,
+            timeout: DEFAULT_NOTIFY_TIMEOUT,
         }
     }
 
+    /// Sets how long to wait for a notification to be sent (including retries) before failing with
+    /// a timeout error
+    pub fn with_timeout(self, timeout: Duration) -> ManifestNotifier<P> {
+        ManifestNotifier { timeout, ..self }
+    }
+
+    /// Returns the prefix of the topics notifications are published on
+    pub fn prefix(&self) -> &str {
+        &self.prefix
+    }
+
     #[instrument(level = "trace", skip(self))]
     async fn send_event(&self, lattice_id: &str, event: Event) -> anyhow::Result<()> {
         let event: CloudEvent = event.try_into()?;
-        // NOTE(thomastaylor312): A future improvement could be retries here
-        trace!("Sending notification event");
-        self.publisher
-            .publish(
-                serde_json::to_vec(&event)?,
-                Some(&format!("{}.{lattice_id}", self.prefix)),
-            )
-            .await
+        let data = serde_json::to_vec(&event)?;
+        let topic = format!("{}.{lattice_id}", self.prefix);
+        // The manifest has already been stored by the time we notify, so retry transient publish
+        // failures rather than making the caller redo the whole request
+        with_timeout("notification", self.timeout, async {
+            let mut backoff = INITIAL_NOTIFY_BACKOFF;
+            let mut attempt = 1;
+            loop {
+                trace!(%attempt, "Sending notification event");
+                match self.publisher.publish(data.clone(), Some(&topic)).await {
+                    Ok(()) => return Ok(()),
+                    Err(e) if attempt < MAX_NOTIFY_ATTEMPTS => {
+                        debug!(error = ?e, %attempt, ?backoff, "Unable to send notification event, retrying");
+                        tokio::time::sleep(backoff).await;
+                        backoff *= 2;
+                        attempt += 1;
+                    }
+                    Err(e) => return Err(e),
+                }
+            }
+        })
+        .await
     }
 
     pub async fn deployed(&self, lattice_id: &str, manifest: Manifest) -> anyhow::Result<()> {
         self.send_event(
             lattice_id,
-            Event::ManifestPublished(ManifestPublished { manifest }),
+            Event::ManifestPublished(ManifestPublished {
+                manifest,
+                canary: false,
+                trace_id: current_trace_id(),
+            }),
+        )
+        .await
+    }
+
+    /// Notifies processors that the given manifest was staged as a canary
+    pub async fn staged(&self, lattice_id: &str, manifest: Manifest) -> anyhow::Result<()> {
+        self.send_event(
+            lattice_id,
+            Event::ManifestPublished(ManifestPublished {
+                manifest,
+                canary: true,
+                trace_id: current_trace_id(),
+            }),
         )
         .await
     }
@@ -50,8 +124,79 @@ impl<P: Publisher> ManifestNotifier<P> {
             lattice_id,
             Event::ManifestUnpublished(ManifestUnpublished {
                 name: name.to_owned(),
+                trace_id: current_trace_id(),
             }),
         )
         .await
     }
 }
+
+#[cfg(test)]
+mod test {
+    use std::sync::Mutex;
+
+    use super::*;
+    use crate::events::{EventType, WADM_SOURCE};
+
+    #[derive(Default)]
+    struct CapturingPublisher {
+        published: Mutex<Vec<(Vec<u8>, Option<String>)>>,
+    }
+
+    #[async_trait::async_trait]
+    impl Publisher for &CapturingPublisher {
+        async fn publish(&self, data: Vec<u8>, destination: Option<&str>) -> anyhow::Result<()> {
+            self.published
+                .lock()
+                .unwrap()
+                .push((data, destination.map(ToOwned::to_owned)));
+            Ok(())
+        }
+    }
+
+    #[tokio::test]
+    async fn test_notifications_are_cloudevents() {
+        let publisher = CapturingPublisher::default();
+        let notifier = ManifestNotifier::new("wadm.evt.>", &publisher);
+        notifier
+            .undeployed("default", "my-app")
+            .await
+            .expect("Should be able to send notification");
+
+        let published = publisher.published.lock().unwrap();
+        let (data, destination) = published.first().expect("Should have published an event");
+        assert_eq!(destination.as_deref(), Some("wadm.evt.default"));
+
+        let event: serde_json::Value =
+            serde_json::from_slice(data).expect("Should be able to parse event");
+        assert_eq!(event["specversion"], "1.0");
+        assert_eq!(event["type"], ManifestUnpublished::TYPE);
+        assert_eq!(event["source"], WADM_SOURCE);
+        assert!(event["id"].is_string(), "Event should have an ID");
+        assert!(event["time"].is_string(), "Event should have a time");
+        assert_eq!(event["data"]["name"], "my-app");
+        assert!(
+            event["data"].get("trace_id").is_none(),
+            "Notifications outside of a request shouldn't have a trace ID"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_notifications_include_trace_id() {
+        let publisher = CapturingPublisher::default();
+        let notifier = ManifestNotifier::new("wadm.evt", &publisher);
+        CURRENT_TRACE_ID
+            .scope(
+                "my-trace".to_string(),
+                notifier.undeployed("default", "my-app"),
+            )
+            .await
+            .expect("Should be able to send notification");
+
+        let published = publisher.published.lock().unwrap();
+        let (data, _) = published.first().expect("Should have published an event");
+        let event: serde_json::Value =
+            serde_json::from_slice(data).expect("Should be able to parse event");
+        assert_eq!(event["data"]["trace_id"], "my-trace");
+    }
+}
@@ -1,26 +1,157 @@
-use std::collections::BTreeSet;
+use std::{
+    collections::{BTreeMap, BTreeSet, HashMap},
+    sync::OnceLock,
+    time::Duration,
+};
 
 use anyhow::Result;
-use async_nats::jetstream::kv::{Operation, Store};
+use async_nats::jetstream::kv::{Entry, Operation, Store, UpdateError};
+use futures::TryStreamExt;
+use prometheus::HistogramTimer;
+use serde::{Deserialize, Serialize};
 use tracing::{debug, instrument, trace};
 use wadm_types::api::{ModelSummary, StatusType};
 
 use crate::model::StoredManifest;
 
+use super::{
+    cache::ModelCache,
+    compression::StorageCodec,
+    timeout::{with_timeout, DEFAULT_STORE_TIMEOUT},
+    ServerMetrics,
+};
+
 // TODO(thomastaylor312): Once async nats has concrete error types for KV, we should switch out
 // anyhow for concrete error types so we can indicate whether a failure was due to something like a
-// CAS failure or a network error
+// CAS failure or a network error. For now, CAS failures are the only ones we surface concretely (see
+// [`RevisionConflict`])
+
+/// The account models are stored under when a request doesn't have one. See [`set_default_account`]
+static DEFAULT_ACCOUNT: OnceLock<String> = OnceLock::new();
+
+/// Sets the account that models are stored under when no account is given, such as in single
+/// tenant mode or from clients that don't pass one. This applies to the whole process (the API
+/// server and the scalers reading models) and can only be set once, before anything is stored.
+///
+/// Keys are `{account}-{lattice}` for a lattice's list of models and `{account}-{lattice}-{name}`
+/// for a model. Without an account, they are `{lattice}` and `{lattice}-{name}`. With a default
+/// account, models are always written to the account's keys. Reads, listing, and deletes without an
+/// account also check the keys without an account, so models stored before the default account was
+/// set can still be found. They are moved to the account's keys the next time they are changed
+pub fn set_default_account(account: impl Into<String>) -> Result<()> {
+    let account = account.into();
+    if account.trim().is_empty() || account.contains(['.', '-', '*', '>']) {
+        anyhow::bail!("Invalid default account {account:?}. Accounts can't be empty or contain '.', '-', '*', or '>'");
+    }
+    let current = DEFAULT_ACCOUNT.get_or_init(|| account.clone());
+    if *current != account {
+        anyhow::bail!("Default account is already set to {current}");
+    }
+    Ok(())
+}
+
+/// Returns the account to key models with, falling back to the given default account when none is
+/// given
+fn resolve_account<'a>(account_id: Option<&'a str>, default: Option<&'a str>) -> Option<&'a str> {
+    account_id.or(default)
+}
+
+/// Returns true if models stored without an account should also be checked for the given request
+fn check_unkeyed(account_id: Option<&str>) -> bool {
+    account_id.is_none() && DEFAULT_ACCOUNT.get().is_some()
+}
+
+/// The prefix of the keys deploy locks are stored under. Model keys can never contain a `.`, so
+/// these don't collide with a model or a lattice's list of models
+const DEPLOY_LOCK_PREFIX: &str = "deploy_lock.";
+
+/// The default amount of time a deploy lock is held before it is considered abandoned (such as when
+/// the wadm instance holding it died) and can be taken over by another deploy
+pub const DEFAULT_DEPLOY_LOCK_TTL: Duration = Duration::from_secs(120);
+
+/// The data stored in a deploy lock's key
+#[derive(Debug, Serialize, Deserialize)]
+struct DeployLockData {
+    /// A unique ID for the deploy holding the lock, for debugging
+    holder: String,
+    /// When the lock was acquired, in milliseconds since the Unix epoch
+    acquired_at_ms: i64,
+}
+
+/// The account (if any) and lattice a set of models is stored under, along with the model names.
+/// See [`ModelStorage::list_all`]
+pub(crate) type LatticeModels = (Option<String>, String, BTreeSet<String>);
+
+/// A deploy lock held by this process. Release it with [`ModelStorage::unlock_deploy`]
+#[derive(Debug)]
+pub struct DeployLock {
+    key: String,
+    revision: u64,
+}
+
+/// The number of times a read-modify-write of a model should be attempted when it fails due to the
+/// underlying data changing
+pub(crate) const MAX_REVISION_CONFLICT_ATTEMPTS: usize = 3;
+
+/// An error returned (wrapped in an [`anyhow::Error`]) by [`ModelStorage::set`] when the stored model
+/// was changed by another writer after the given revision was fetched
+#[derive(Debug, thiserror::Error)]
+#[error("Model was updated concurrently, expected revision {0}")]
+pub(crate) struct RevisionConflict(u64);
+
+impl RevisionConflict {
+    /// Returns true if the given error was caused by a revision conflict. Callers can safely
+    /// re-fetch the model, reapply their changes, and try again
+    pub fn is_conflict(err: &anyhow::Error) -> bool {
+        err.downcast_ref::<RevisionConflict>().is_some()
+    }
+}
 
 /// Storage for models, with some logic around updating a list of all models in a lattice to make
 /// calls more efficient
 #[derive(Clone)]
-pub(crate) struct ModelStorage {
+pub struct ModelStorage {
     store: Store,
+    metrics: Option<ServerMetrics>,
+    timeout: Duration,
+    codec: StorageCodec,
+    cache: Option<ModelCache>,
 }
 
 impl ModelStorage {
     pub fn new(store: Store) -> ModelStorage {
-        Self { store }
+        Self {
+            store,
+            metrics: None,
+            timeout: DEFAULT_STORE_TIMEOUT,
+            codec: StorageCodec::default(),
+            cache: None,
+        }
+    }
+
+    /// Sets the codec used to compress models when they are stored. Models stored with any codec
+    /// can be read regardless of this setting
+    pub fn with_codec(self, codec: StorageCodec) -> ModelStorage {
+        Self { codec, ..self }
+    }
+
+    /// Sets how long to wait for each storage operation before failing with a timeout error
+    pub fn with_timeout(self, timeout: Duration) -> ModelStorage {
+        Self { timeout, ..self }
+    }
+
+    /// Caches models read from the store in the given cache. The cache must be kept up to date
+    /// with [`ModelCache::watch`], otherwise it is never used
+    pub(crate) fn with_cache(self, cache: ModelCache) -> ModelStorage {
+        Self {
+            cache: Some(cache),
+            ..self
+        }
+    }
+
+    /// Records the latency of storage operations with the given metrics
+    pub fn with_metrics(self, metrics: Option<ServerMetrics>) -> ModelStorage {
+        Self { metrics, ..self }
     }
 
     /// Gets the stored data and its current revision for the given model, returning None if it
@@ -29,27 +160,201 @@ impl ModelStorage {
     // things when fetching in the manager. If we expose this struct outside of the crate, we should
     // either revert this to be `&str` or make it `AsRef<str>` everywhere
     #[instrument(level = "debug", skip(self, model_name), fields(model_name = %model_name.as_ref()))]
-    pub async fn get(
+    pub(crate) async fn get(
         &self,
         account_id: Option<&str>,
         lattice_id: &str,
         model_name: impl AsRef<str>,
     ) -> Result<Option<(StoredManifest, u64)>> {
+        let unkeyed =
+            check_unkeyed(account_id).then(|| model_key(None, lattice_id, model_name.as_ref()));
+        let account_id = resolve_account(account_id, DEFAULT_ACCOUNT.get().map(String::as_str));
         let key = model_key(account_id, lattice_id, model_name.as_ref());
-        debug!(%key, "Fetching model from storage");
+        if let Some(cache) = self.cache.as_ref().filter(|cache| cache.is_active()) {
+            let cached = cache.get(&key);
+            if let Some(metrics) = self.metrics.as_ref() {
+                metrics.record_cache_lookup(cached.is_some());
+            }
+            if let Some(cached) = cached {
+                trace!(%key, "Found model in cache");
+                return Ok(Some(cached));
+            }
+        }
+
+        let _timer = self.store_timer("get");
+        let fetched = with_timeout("model storage get", self.timeout, async {
+            debug!(%key, "Fetching model from storage");
+            self.read_model(&key).await
+        })
+        .await?;
+
+        if let (Some(cache), Some((model, revision))) = (self.cache.as_ref(), fetched.as_ref()) {
+            cache.insert(&key, model.clone(), *revision);
+        }
+        match (fetched, unkeyed) {
+            (None, Some(unkeyed)) => {
+                debug!(key = %unkeyed, "Model not stored under the default account, checking without an account");
+                // The revision is for a different key than the model will be written to, so it is
+                // returned as 0 (meaning there is nothing at the default account's key to compare
+                // against). These reads are also never cached, as the cache is per key
+                with_timeout("model storage get", self.timeout, self.read_model(&unkeyed))
+                    .await
+                    .map(|model| model.map(|(model, _)| (model, 0)))
+            }
+            (fetched, _) => Ok(fetched),
+        }
+    }
+
+    /// Gets the data stored for the given model and its revision, without decoding it. This always
+    /// reads from the store, as the cache only holds decoded models
+    #[instrument(level = "debug", skip(self))]
+    pub async fn get_raw(
+        &self,
+        account_id: Option<&str>,
+        lattice_id: &str,
+        model_name: &str,
+    ) -> Result<Option<(Vec<u8>, u64)>> {
+        let mut keys = vec![model_key(
+            resolve_account(account_id, DEFAULT_ACCOUNT.get().map(String::as_str)),
+            lattice_id,
+            model_name,
+        )];
+        if check_unkeyed(account_id) {
+            keys.push(model_key(None, lattice_id, model_name));
+        }
+        let _timer = self.store_timer("get_raw");
+        with_timeout("model storage get", self.timeout, async {
+            for key in keys {
+                debug!(%key, "Fetching raw model from storage");
+                let entry = self
+                    .store
+                    .entry(&key)
+                    .await
+                    .map_err(|e| anyhow::anyhow!("{e:?}"))?;
+                match entry {
+                    Some(entry) if !is_removed(&entry) => {
+                        return Ok(Some((entry.value.to_vec(), entry.revision)))
+                    }
+                    _ => continue,
+                }
+            }
+            Ok(None)
+        })
+        .await
+    }
+
+    /// Tries to acquire the advisory lock that serializes deploys of the given model, returning
+    /// `None` if another deploy holds it. Locks older than `ttl` are treated as abandoned and taken
+    /// over. The lock must be released with [`ModelStorage::unlock_deploy`]
+    #[instrument(level = "debug", skip(self))]
+    pub async fn try_lock_deploy(
+        &self,
+        account_id: Option<&str>,
+        lattice_id: &str,
+        model_name: &str,
+        ttl: Duration,
+    ) -> Result<Option<DeployLock>> {
+        let key = deploy_lock_key(
+            resolve_account(account_id, DEFAULT_ACCOUNT.get().map(String::as_str)),
+            lattice_id,
+            model_name,
+        );
+        let _timer = self.store_timer("lock_deploy");
+        with_timeout("model storage lock", self.timeout, async {
+            let now = chrono::Utc::now().timestamp_millis();
+            let expected_revision = match self
+                .store
+                .entry(&key)
+                .await
+                .map_err(|e| anyhow::anyhow!("{e:?}"))?
+            {
+                // A revision of 0 means the key must not exist yet
+                None => 0,
+                Some(entry) if matches!(entry.operation, Operation::Delete | Operation::Purge) => {
+                    entry.revision
+                }
+                Some(entry) => {
+                    let held_for = serde_json::from_slice::<DeployLockData>(&entry.value)
+                        .map(|lock| now.saturating_sub(lock.acquired_at_ms))
+                        .unwrap_or(i64::MAX);
+                    if held_for < ttl.as_millis() as i64 {
+                        trace!(%key, %held_for, "Deploy lock is held");
+                        return Ok(None);
+                    }
+                    debug!(%key, %held_for, "Taking over abandoned deploy lock");
+                    entry.revision
+                }
+            };
+
+            let data = serde_json::to_vec(&DeployLockData {
+                holder: ulid::Ulid::new().to_string(),
+                acquired_at_ms: now,
+            })?;
+            match self
+                .store
+                .update(&key, data.into(), expected_revision)
+                .await
+            {
+                Ok(revision) => Ok(Some(DeployLock { key, revision })),
+                // Another deploy acquired the lock between our read and write
+                Err(e) if is_wrong_last_sequence(&e) => Ok(None),
+                Err(e) => Err(anyhow::anyhow!("{e:?}")),
+            }
+        })
+        .await
+    }
+
+    /// Releases a deploy lock acquired with [`ModelStorage::try_lock_deploy`]. If the lock was taken
+    /// over after it expired, it is left for its new holder
+    #[instrument(level = "debug", skip(self))]
+    pub async fn unlock_deploy(&self, lock: DeployLock) -> Result<()> {
+        let _timer = self.store_timer("unlock_deploy");
+        with_timeout("model storage unlock", self.timeout, async {
+            let entry = self
+                .store
+                .entry(&lock.key)
+                .await
+                .map_err(|e| anyhow::anyhow!("{e:?}"))?;
+            // NOTE: The KV client can't delete only if the revision matches, so there is a small
+            // window where a lock taken over right now could be released early. Locks are only taken
+            // over once they are abandoned, so this is very unlikely
+            match entry {
+                Some(entry)
+                    if entry.revision == lock.revision
+                        && !matches!(entry.operation, Operation::Delete | Operation::Purge) =>
+                {
+                    self.store
+                        .delete(&lock.key)
+                        .await
+                        .map_err(|e| anyhow::anyhow!("{e:?}"))
+                }
+                _ => {
+                    debug!(key = %lock.key, "Deploy lock was taken over, leaving it in place");
+                    Ok(())
+                }
+            }
+        })
+        .await
+    }
+
+    /// Reads and decodes the model stored at the given key
+    async fn read_model(&self, key: &str) -> Result<Option<(StoredManifest, u64)>> {
         self.store
-            .entry(&key)
+            .entry(key)
             .await
             .map_err(|e| anyhow::anyhow!("{e:?}"))?
             .and_then(|entry| {
                 // Skip any delete or purge operations
-                if matches!(entry.operation, Operation::Delete | Operation::Purge) {
+                if is_removed(&entry) {
                     return None;
                 }
 
                 Some(
-                    serde_json::from_slice::<StoredManifest>(&entry.value)
-                        .map_err(anyhow::Error::from)
+                    StorageCodec::decode(&entry.value)
+                        .and_then(|data| {
+                            serde_json::from_slice::<StoredManifest>(&data)
+                                .map_err(anyhow::Error::from)
+                        })
                         .map(|m| (m, entry.revision)),
                 )
             })
@@ -58,7 +363,8 @@ impl ModelStorage {
 
     /// Updates the stored data with the given model, overwriting any existing data. The optional
     /// `current_revision` parameter can be used to compare whether or not you're updating the model
-    /// with the latest revision
+    /// with the latest revision. If the revision doesn't match, a [`RevisionConflict`] error is
+    /// returned. Returns the revision the model was stored at
     #[instrument(level = "debug", skip(self, model), fields(model_name = %model.name()))]
     pub async fn set(
         &self,
@@ -66,31 +372,65 @@ impl ModelStorage {
         lattice_id: &str,
         model: StoredManifest,
         current_revision: Option<u64>,
-    ) -> Result<()> {
-        debug!("Storing model in storage");
-        // We need to store the model, then update the set. This is because if we update the set
-        // first and the model fails, it will look like the model exists when it actually doesn't
-        let key = model_key(account_id, lattice_id, model.name());
-        trace!(%key, "Storing manifest at key");
-        let data = serde_json::to_vec(&model).map_err(anyhow::Error::from)?;
-        if let Some(revision) = current_revision.filter(|r| r > &0) {
-            self.store
-                .update(&key, data.into(), revision)
-                .await
-                .map_err(|e| anyhow::anyhow!("{e:?}"))?;
-        } else {
+    ) -> Result<u64> {
+        let account_id = resolve_account(account_id, DEFAULT_ACCOUNT.get().map(String::as_str));
+        let _timer = self.store_timer("set");
+        with_timeout("model storage set", self.timeout, async {
+            debug!("Storing model in storage");
+            // We need to store the model, then update the set. This is because if we update the set
+            // first and the model fails, it will look like the model exists when it actually
+            // doesn't
+            let key = model_key(account_id, lattice_id, model.name());
+            trace!(%key, "Storing manifest at key");
+            let data = self
+                .codec
+                .encode(serde_json::to_vec(&model).map_err(anyhow::Error::from)?)?;
+            let revision = if let Some(revision) = current_revision.filter(|r| r > &0) {
+                self.store
+                    .update(&key, data.into(), revision)
+                    .await
+                    .map_err(|e| {
+                        if is_wrong_last_sequence(&e) {
+                            // Our copy was out of date, so make sure a retry reads the latest data
+                            self.invalidate(&key);
+                            anyhow::Error::from(RevisionConflict(revision))
+                        } else {
+                            anyhow::anyhow!("{e:?}")
+                        }
+                    })?
+            } else {
+                self.store
+                    .put(&key, data.into())
+                    .await
+                    .map_err(|e| anyhow::anyhow!("{e:?}"))?
+            };
+            // The watch will also see this change, but drop it now so this replica never reads its
+            // own writes stale
+            self.invalidate(&key);
+
+            trace!("Adding model to set");
+            self.retry_model_update(
+                account_id,
+                lattice_id,
+                ModelNameOperation::Add(model.name()),
+            )
+            .await?;
+            Ok(revision)
+        })
+        .await
+    }
+
+    /// Checks that the underlying bucket can be reached by fetching its status. This doesn't read
+    /// any models, so it is cheap enough to call frequently
+    pub async fn probe(&self) -> Result<()> {
+        let _timer = self.store_timer("probe");
+        with_timeout("model storage probe", self.timeout, async {
             self.store
-                .put(&key, data.into())
+                .status()
                 .await
-                .map_err(|e| anyhow::anyhow!("{e:?}"))?;
-        }
-
-        trace!("Adding model to set");
-        self.retry_model_update(
-            account_id,
-            lattice_id,
-            ModelNameOperation::Add(model.name()),
-        )
+                .map(|_| ())
+                .map_err(|e| anyhow::anyhow!("{e:?}"))
+        })
         .await
     }
 
@@ -101,40 +441,256 @@ impl ModelStorage {
         account_id: Option<&str>,
         lattice_id: &str,
     ) -> Result<Vec<ModelSummary>> {
-        debug!("Fetching list of models from storage");
-        let futs = self
-            .get_model_set(account_id, lattice_id)
-            .await?
-            .unwrap_or_default()
-            .0
-            .into_iter()
-            // We can't use filter map with futures, but we can use map and then flatten it below
-            .map(|model_name| {
-                async {
-                    let manifest = match self.get(account_id, lattice_id, &model_name).await {
-                        Ok(Some((manifest, _))) => manifest,
-                        Ok(None) => return None,
-                        Err(e) => return Some(Err(e)),
-                    };
-                    Some(Ok(ModelSummary {
-                        name: model_name,
-                        version: manifest.current_version().to_owned(),
-                        description: manifest.get_current().description().map(|s| s.to_owned()),
-                        deployed_version: manifest.get_deployed().map(|m| m.version().to_owned()),
-                        // TODO(thomastaylor312): Actually fetch the status info from the stored
-                        // manifest once we figure it out
-                        status: StatusType::default(),
-                        status_message: None,
-                    }))
+        let _timer = self.store_timer("list");
+        with_timeout("model storage list", self.timeout, async {
+            debug!("Fetching list of models from storage");
+            let mut model_names = self
+                .get_model_set(
+                    resolve_account(account_id, DEFAULT_ACCOUNT.get().map(String::as_str)),
+                    lattice_id,
+                )
+                .await?
+                .unwrap_or_default()
+                .0;
+            if check_unkeyed(account_id) {
+                if let Some((unkeyed, _)) = self.get_model_set(None, lattice_id).await? {
+                    model_names.extend(unkeyed);
                 }
-            });
+            }
+            let futs = model_names
+                .into_iter()
+                // We can't use filter map with futures, but we can use map and then flatten it
+                // below
+                .map(|model_name| {
+                    async {
+                        let manifest = match self.get(account_id, lattice_id, &model_name).await {
+                            Ok(Some((manifest, _))) => manifest,
+                            Ok(None) => return None,
+                            Err(e) => return Some(Err(e)),
+                        };
+                        Some(Ok(ModelSummary {
+                            name: model_name,
+                            version: manifest.current_version().to_owned(),
+                            description: manifest.get_current().description().map(|s| s.to_owned()),
+                            deployed_version: manifest
+                                .get_deployed()
+                                .map(|m| m.version().to_owned()),
+                            // TODO(thomastaylor312): Actually fetch the status info from the stored
+                            // manifest once we figure it out
+                            status: StatusType::default(),
+                            status_message: None,
+                            annotations: manifest.get_current().summary_annotations(),
+                            archived: manifest.is_archived(),
+                            version_count: manifest.count(),
+                            size_bytes: manifest.size_bytes(),
+                        }))
+                    }
+                });
 
-        // Flatten, collect, and sort on name
-        futures::future::join_all(futs)
+            // Flatten, collect, and sort on name
+            futures::future::join_all(futs)
+                .await
+                .into_iter()
+                .flatten()
+                .collect::<Result<Vec<ModelSummary>>>()
+        })
+        .await
+    }
+
+    /// Returns the number of models stored in each lattice that has at least one, keyed by lattice
+    /// ID. If an account is given, only that account's lattices are returned.
+    ///
+    /// Keys are prefixed by lattice ID (and account), so this scans all keys in the bucket (see
+    /// [`ModelStorage::scan_model_sets`])
+    #[instrument(level = "debug", skip(self))]
+    pub async fn list_lattices(&self, account_id: Option<&str>) -> Result<BTreeMap<String, usize>> {
+        let _timer = self.store_timer("list_lattices");
+        with_timeout("model storage lattice scan", self.timeout, async {
+            let account_prefix = account_id.map(|account| format!("{account}-"));
+            let model_sets = self.scan_model_sets(account_prefix.as_deref()).await?;
+
+            Ok(model_sets
+                .into_iter()
+                .filter(|(_, models)| !models.is_empty())
+                .map(|(key, models)| {
+                    let lattice_id = account_prefix
+                        .as_deref()
+                        .and_then(|prefix| key.strip_prefix(prefix))
+                        .map(ToOwned::to_owned)
+                        .unwrap_or(key);
+                    (lattice_id, models.len())
+                })
+                .collect())
+        })
+        .await
+    }
+
+    /// Returns the names of the models in every lattice, along with the account and lattice they are
+    /// stored under. Like [`ModelStorage::list_lattices`], this scans all keys in the bucket.
+    ///
+    /// The keys of a lattice's list of models don't say where the account ends and the lattice
+    /// starts, so with `multitenant` set, anything before the first `-` is taken to be the account.
+    /// Lists stored under the default account (see [`set_default_account`]) are always returned
+    /// without an account, like requests that don't give one
+    #[instrument(level = "debug", skip(self))]
+    pub async fn list_all(&self, multitenant: bool) -> Result<Vec<LatticeModels>> {
+        let _timer = self.store_timer("list_all");
+        with_timeout("model storage scan", self.timeout, async {
+            Ok(self
+                .scan_model_sets(None)
+                .await?
+                .into_iter()
+                .filter(|(_, models)| !models.is_empty())
+                .map(|(key, models)| {
+                    let (account_id, lattice_id) = split_model_set_key(
+                        &key,
+                        DEFAULT_ACCOUNT.get().map(String::as_str),
+                        multitenant,
+                    );
+                    (
+                        account_id.map(ToOwned::to_owned),
+                        lattice_id.to_owned(),
+                        models,
+                    )
+                })
+                .collect())
+        })
+        .await
+    }
+
+    /// Scans all keys in the bucket (or only those with the given prefix) for lattices' lists of
+    /// models, returning each list keyed by its key.
+    ///
+    /// As lattice IDs and model names can both contain `-`, the key alone doesn't say whether it is
+    /// a lattice's list of models or a model. Shorter keys are checked first, so any key that is a
+    /// model in an already found lattice is skipped and the only keys read are the lists of models
+    async fn scan_model_sets(
+        &self,
+        prefix: Option<&str>,
+    ) -> Result<HashMap<String, BTreeSet<String>>> {
+        let mut keys: Vec<String> = self
+            .store
+            .keys()
+            .await
+            .map_err(|e| anyhow::anyhow!("{e:?}"))?
+            .try_collect()
             .await
-            .into_iter()
-            .flatten()
-            .collect::<Result<Vec<ModelSummary>>>()
+            .map_err(|e| anyhow::anyhow!("{e:?}"))?;
+        if let Some(prefix) = prefix {
+            keys.retain(|key| key.starts_with(prefix));
+        }
+        keys.sort_by_key(String::len);
+        debug!(num_keys = keys.len(), "Scanning keys for lattices");
+
+        let mut model_sets: HashMap<String, BTreeSet<String>> = HashMap::new();
+        for key in keys {
+            let is_model = key.match_indices('-').any(|(idx, _)| {
+                model_sets
+                    .get(&key[..idx])
+                    .is_some_and(|models| models.contains(&key[idx + 1..]))
+            });
+            if is_model {
+                continue;
+            }
+            // Anything that isn't a list of models is a model whose lattice couldn't be found (or a
+            // deploy lock), so it is skipped
+            if let Ok(Some((models, _))) = self.get_model_set_by_key(&key).await {
+                model_sets.insert(key, models);
+            }
+        }
+        Ok(model_sets)
+    }
+
+    /// Returns the accounts, other than the given one, that have a model with the given name in the
+    /// lattice. Models stored without an account are returned as `None`.
+    ///
+    /// Like [`ModelStorage::list_lattices`], this scans all keys in the bucket. Keys that could be
+    /// the model are confirmed by checking the owning account's list of models, as lattice IDs can
+    /// contain `-`
+    #[instrument(level = "debug", skip(self))]
+    pub async fn other_model_owners(
+        &self,
+        account_id: Option<&str>,
+        lattice_id: &str,
+        model_name: &str,
+    ) -> Result<Vec<Option<String>>> {
+        let _timer = self.store_timer("other_model_owners");
+        let own_account = resolve_account(account_id, DEFAULT_ACCOUNT.get().map(String::as_str));
+        let owns_unkeyed = own_account.is_none() || check_unkeyed(account_id);
+        with_timeout("model storage owner scan", self.timeout, async {
+            let keys: Vec<String> = self
+                .store
+                .keys()
+                .await
+                .map_err(|e| anyhow::anyhow!("{e:?}"))?
+                .try_collect()
+                .await
+                .map_err(|e| anyhow::anyhow!("{e:?}"))?;
+
+            let mut owners = Vec::new();
+            for key in keys.iter() {
+                let Some(owner) = model_key_owner(key, lattice_id, model_name) else {
+                    continue;
+                };
+                let is_own = match owner {
+                    Some(owner) => own_account == Some(owner),
+                    None => owns_unkeyed,
+                };
+                if is_own {
+                    continue;
+                }
+                if self
+                    .get_model_set(owner, lattice_id)
+                    .await?
+                    .is_some_and(|(models, _)| models.contains(model_name))
+                {
+                    owners.push(owner.map(ToOwned::to_owned));
+                }
+            }
+            Ok(owners)
+        })
+        .await
+    }
+
+    /// Deletes the given model from storage only if it is still at the given revision, returning a
+    /// [`RevisionConflict`] error if it has changed. A revision of 0 deletes the model regardless,
+    /// like [`ModelStorage::delete`]
+    #[instrument(level = "debug", skip(self))]
+    pub async fn delete_at_revision(
+        &self,
+        account_id: Option<&str>,
+        lattice_id: &str,
+        model_name: &str,
+        revision: u64,
+    ) -> Result<()> {
+        if revision > 0 {
+            let key = model_key(
+                resolve_account(account_id, DEFAULT_ACCOUNT.get().map(String::as_str)),
+                lattice_id,
+                model_name,
+            );
+            let _timer = self.store_timer("delete");
+            // NOTE: The KV client can't delete only if the revision matches, so the model is first
+            // replaced with an empty value if it hasn't changed. Reads treat an empty value as
+            // deleted, so the model is gone from here on even if the delete below fails
+            with_timeout("model storage delete", self.timeout, async {
+                self.store
+                    .update(&key, Default::default(), revision)
+                    .await
+                    .map_err(|e| {
+                        if is_wrong_last_sequence(&e) {
+                            // Our copy was out of date, so make sure a retry reads the latest data
+                            self.invalidate(&key);
+                            anyhow::Error::from(RevisionConflict(revision))
+                        } else {
+                            anyhow::anyhow!("{e:?}")
+                        }
+                    })
+            })
+            .await?;
+            self.invalidate(&key);
+        }
+        self.delete(account_id, lattice_id, model_name).await
     }
 
     /// Deletes the given model from storage. This also removes the model from the list of all
@@ -146,24 +702,52 @@ impl ModelStorage {
         lattice_id: &str,
         model_name: &str,
     ) -> Result<()> {
-        debug!("Deleting model from storage");
-        // We need to delete from the set first, then delete the model itself. This is because if we
-        // delete the model but then cannot delete the item from the set, then we end up in a
-        // situation where we say it already exists when creating. If the model doesn't delete it is
-        // fine, because a set operation will overwrite it
-        self.retry_model_update(
-            account_id,
-            lattice_id,
-            ModelNameOperation::Delete(model_name),
-        )
-        .await?;
+        let unkeyed = check_unkeyed(account_id);
+        let account_id = resolve_account(account_id, DEFAULT_ACCOUNT.get().map(String::as_str));
+        let _timer = self.store_timer("delete");
+        with_timeout("model storage delete", self.timeout, async {
+            debug!("Deleting model from storage");
+            // Models stored before the default account was set are deleted too, otherwise they
+            // would show up again once the account's copy is gone
+            if unkeyed {
+                self.retry_model_update(None, lattice_id, ModelNameOperation::Delete(model_name))
+                    .await?;
+                self.store
+                    .purge(model_key(None, lattice_id, model_name))
+                    .await
+                    .map_err(|e| anyhow::anyhow!("{e:?}"))?;
+            }
+            // We need to delete from the set first, then delete the model itself. This is because
+            // if we delete the model but then cannot delete the item from the set, then we end up
+            // in a situation where we say it already exists when creating. If the model doesn't
+            // delete it is fine, because a set operation will overwrite it
+            self.retry_model_update(
+                account_id,
+                lattice_id,
+                ModelNameOperation::Delete(model_name),
+            )
+            .await?;
 
-        let key = model_key(account_id, lattice_id, model_name);
-        trace!("Deleting model from storage");
-        self.store
-            .purge(&key)
-            .await
-            .map_err(|e| anyhow::anyhow!("{e:?}"))
+            let key = model_key(account_id, lattice_id, model_name);
+            trace!("Deleting model from storage");
+            self.store
+                .purge(&key)
+                .await
+                .map_err(|e| anyhow::anyhow!("{e:?}"))?;
+            self.invalidate(&key);
+            Ok(())
+        })
+        .await
+    }
+
+    fn invalidate(&self, key: &str) {
+        if let Some(cache) = self.cache.as_ref() {
+            cache.invalidate(key, None);
+        }
+    }
+
+    fn store_timer(&self, operation: &str) -> Option<HistogramTimer> {
+        self.metrics.as_ref().map(|m| m.store_timer(operation))
     }
 
     /// Helper function that returns the list of models for the given lattice along with the current
@@ -173,9 +757,15 @@ impl ModelStorage {
         account_id: Option<&str>,
         lattice_id: &str,
     ) -> Result<Option<(BTreeSet<String>, u64)>> {
+        self.get_model_set_by_key(&model_set_key(account_id, lattice_id))
+            .await
+    }
+
+    /// Fetches the list of models stored under the given key along with its current revision
+    async fn get_model_set_by_key(&self, key: &str) -> Result<Option<(BTreeSet<String>, u64)>> {
         match self
             .store
-            .entry(model_set_key(account_id, lattice_id))
+            .entry(key)
             .await
             .map_err(|e| anyhow::anyhow!("{e:?}"))?
         {
@@ -197,7 +787,7 @@ impl ModelStorage {
         operation: ModelNameOperation<'a>,
     ) -> Result<()> {
         // Always retry 3 times for now. We can make this configurable later if we want
-        for i in 0..3 {
+        for i in 0..MAX_REVISION_CONFLICT_ATTEMPTS {
             trace!("Fetching current models from storage");
             let (mut model_list, current_revision) =
                 match self.get_model_set(account_id, lattice_id).await? {
@@ -236,9 +826,7 @@ impl ModelStorage {
                 .await
             {
                 Ok(_) => return Ok(()),
-                // NOTE(thomastaylor312): This is brittle but will be replaced once the NATS client
-                // has a concrete error for KV stuff
-                Err(e) if e.to_string().contains("wrong last sequence") => {
+                Err(e) if is_wrong_last_sequence(&e) => {
                     debug!(error = %e, attempt = i+1, "Model list update failed due to the underlying data changing, retrying");
                     continue;
                 }
@@ -260,6 +848,18 @@ enum ModelNameOperation<'a> {
     Delete(&'a str),
 }
 
+// NOTE(thomastaylor312): This is brittle but will be replaced once the NATS client has a concrete
+// error for KV stuff
+/// Returns true if the entry is a deleted model, including models emptied by
+/// [`ModelStorage::delete_at_revision`] that haven't been purged yet
+fn is_removed(entry: &Entry) -> bool {
+    matches!(entry.operation, Operation::Delete | Operation::Purge) || entry.value.is_empty()
+}
+
+fn is_wrong_last_sequence(err: &UpdateError) -> bool {
+    err.to_string().contains("wrong last sequence")
+}
+
 fn model_set_key(account_id: Option<&str>, lattice_id: &str) -> String {
     if let Some(account) = account_id {
         format!("{}-{}", account, lattice_id)
@@ -275,3 +875,100 @@ fn model_key(account_id: Option<&str>, lattice_id: &str, model_name: &str) -> St
         format!("{}-{}", lattice_id, model_name)
     }
 }
+
+/// Splits the key of a lattice's list of models into the account (if any) and lattice. See
+/// [`ModelStorage::list_all`]
+fn split_model_set_key<'a>(
+    key: &'a str,
+    default_account: Option<&str>,
+    multitenant: bool,
+) -> (Option<&'a str>, &'a str) {
+    if let Some(lattice_id) = default_account
+        .and_then(|account| key.strip_prefix(account))
+        .and_then(|rest| rest.strip_prefix('-'))
+    {
+        return (None, lattice_id);
+    }
+    match key.split_once('-') {
+        Some((account_id, lattice_id)) if multitenant => (Some(account_id), lattice_id),
+        _ => (None, key),
+    }
+}
+
+fn deploy_lock_key(account_id: Option<&str>, lattice_id: &str, model_name: &str) -> String {
+    format!(
+        "{DEPLOY_LOCK_PREFIX}{}",
+        model_key(account_id, lattice_id, model_name)
+    )
+}
+
+/// Returns the account that would own the given key if it is the key of the given model, with
+/// `Some(None)` meaning the model is stored without an account
+fn model_key_owner<'a>(
+    key: &'a str,
+    lattice_id: &str,
+    model_name: &str,
+) -> Option<Option<&'a str>> {
+    if key == model_key(None, lattice_id, model_name) {
+        return Some(None);
+    }
+    key.strip_suffix(&format!("-{}", model_key(None, lattice_id, model_name)))
+        .filter(|account| !account.is_empty() && !account.contains('-'))
+        .map(Some)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_key_derivation() {
+        assert_eq!(model_key(None, "default", "app"), "default-app");
+        assert_eq!(model_set_key(None, "default"), "default");
+        assert_eq!(
+            model_key(Some("acme"), "default", "app"),
+            "acme-default-app"
+        );
+        assert_eq!(model_set_key(Some("acme"), "default"), "acme-default");
+        assert_eq!(
+            deploy_lock_key(Some("acme"), "default", "app"),
+            "deploy_lock.acme-default-app"
+        );
+
+        // The default account is only used when a request doesn't have one
+        assert_eq!(resolve_account(None, None), None);
+        assert_eq!(resolve_account(None, Some("acme")), Some("acme"));
+        assert_eq!(resolve_account(Some("other"), Some("acme")), Some("other"));
+
+        assert_eq!(
+            model_key_owner("acme-default-app", "default", "app"),
+            Some(Some("acme"))
+        );
+        assert_eq!(model_key_owner("default-app", "default", "app"), Some(None));
+        assert_eq!(
+            model_key_owner("acme-default-other", "default", "app"),
+            None
+        );
+        assert_eq!(model_key_owner("a-b-default-app", "default", "app"), None);
+
+        assert_eq!(
+            split_model_set_key("acme-my-lattice", None, true),
+            (Some("acme"), "my-lattice")
+        );
+        assert_eq!(
+            split_model_set_key("my-lattice", None, false),
+            (None, "my-lattice")
+        );
+        assert_eq!(
+            split_model_set_key("acme-default", Some("acme"), false),
+            (None, "default")
+        );
+        assert_eq!(
+            split_model_set_key("default", None, true),
+            (None, "default")
+        );
+
+        assert!(set_default_account("").is_err());
+        assert!(set_default_account("bad-account").is_err());
+    }
+}
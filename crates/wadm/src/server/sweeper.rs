@@ -0,0 +1,151 @@
+use std::time::Duration;
+
+use chrono::{DateTime, Utc};
+use tokio::{
+    task::JoinHandle,
+    time::{Instant, MissedTickBehavior},
+};
+use tracing::{debug, info, instrument, trace, warn, Instrument};
+use wadm_types::api::ErrorCode;
+
+use crate::{model::StoredManifest, publisher::Publisher};
+
+use super::{handlers::Handler, storage::RevisionConflict, AuditRecord, ModelOperation};
+
+/// The default amount of time between sweeps for models that have been undeployed for longer than
+/// their TTL
+pub const DEFAULT_MODEL_SWEEP_INTERVAL: Duration = Duration::from_secs(600);
+
+/// A handle to the task sweeping for expired models. The task is stopped when this is dropped
+pub(crate) struct ModelSweeper {
+    handle: JoinHandle<()>,
+}
+
+impl Drop for ModelSweeper {
+    fn drop(&mut self) {
+        self.handle.abort();
+    }
+}
+
+impl<P: Publisher + Clone + Send + Sync + 'static> Handler<P> {
+    /// Spawns a task that calls [`Handler::sweep_expired_models`] every `period`, using its own
+    /// clone of this handler so sweeps never block requests
+    pub(crate) fn spawn_model_sweeper(&self, period: Duration, multitenant: bool) -> ModelSweeper {
+        let handler = self.clone();
+        let handle = tokio::spawn(
+            async move {
+                // The first sweep is a full interval after starting, so restarts don't cause a
+                // burst of work
+                let mut ticker = tokio::time::interval_at(Instant::now() + period, period);
+                ticker.set_missed_tick_behavior(MissedTickBehavior::Delay);
+                loop {
+                    ticker.tick().await;
+                    match handler.sweep_expired_models(multitenant).await {
+                        Ok(0) => (),
+                        Ok(deleted) => info!(%deleted, "Deleted models that were undeployed for longer than their TTL"),
+                        Err(e) => warn!(error = %e, "Unable to sweep for expired models"),
+                    }
+                }
+            }
+            .in_current_span(),
+        );
+        ModelSweeper { handle }
+    }
+}
+
+impl<P: Publisher> Handler<P> {
+    /// Deletes every model that has been undeployed for longer than the TTL set by its
+    /// [`TTL_ANNOTATION_KEY`](wadm_types::TTL_ANNOTATION_KEY) annotation, returning how many were
+    /// deleted. Failures to delete a single model are logged and the model is tried again on the
+    /// next sweep
+    #[instrument(level = "debug", skip(self))]
+    pub(crate) async fn sweep_expired_models(&self, multitenant: bool) -> anyhow::Result<usize> {
+        let now = Utc::now();
+        let mut deleted = 0;
+        for (account_id, lattice_id, names) in self.store.list_all(multitenant).await? {
+            for name in names {
+                match self
+                    .sweep_model(account_id.as_deref(), &lattice_id, &name, now)
+                    .await
+                {
+                    Ok(true) => deleted += 1,
+                    Ok(false) => (),
+                    Err(e) => {
+                        warn!(error = ?e, %name, %lattice_id, "Unable to delete expired model")
+                    }
+                }
+            }
+        }
+        Ok(deleted)
+    }
+
+    /// Deletes the given model if it has expired, returning whether it was deleted
+    async fn sweep_model(
+        &self,
+        account_id: Option<&str>,
+        lattice_id: &str,
+        name: &str,
+        now: DateTime<Utc>,
+    ) -> anyhow::Result<bool> {
+        let expired = |model: &StoredManifest| model.expires_at().is_some_and(|at| at <= now);
+        // Check without the lock first, as almost every model won't have expired
+        match self.store.get(account_id, lattice_id, name).await? {
+            Some((model, _)) if expired(&model) => (),
+            _ => return Ok(false),
+        }
+
+        // Hold the deploy lock so the model can't be deployed while it is being deleted, and check
+        // again in case it was deployed since it was read
+        let deleted = match self
+            .with_deploy_locks(account_id, lattice_id, &[name], async {
+                match self.store.get(account_id, lattice_id, name).await? {
+                    Some((model, revision)) if expired(&model) => match self
+                        .store
+                        .delete_at_revision(account_id, lattice_id, name, revision)
+                        .await
+                    {
+                        Ok(()) => Ok(true),
+                        // The model was changed since it was read, so it is checked again on the
+                        // next sweep
+                        Err(e) if RevisionConflict::is_conflict(&e) => {
+                            trace!(%name, "Model changed while sweeping, skipping it");
+                            Ok(false)
+                        }
+                        Err(e) => Err(e),
+                    },
+                    _ => Ok(false),
+                }
+            })
+            .await
+        {
+            Ok(deleted) => deleted?,
+            Err((ErrorCode::DeployInProgress, _)) => {
+                trace!(%name, "Model is being deployed, skipping it");
+                false
+            }
+            Err((_, message)) => anyhow::bail!(message),
+        };
+        if !deleted {
+            return Ok(false);
+        }
+        debug!(%name, %lattice_id, "Deleted model that was undeployed for longer than its TTL");
+
+        // Expired models aren't deployed, but make sure processors forget the model like any other
+        // delete
+        if let Err(e) = self.notifier.undeployed(lattice_id, name).await {
+            warn!(error = ?e, %name, "Unable to send undeploy notification for expired model");
+        }
+        self.publish_audit_record(AuditRecord {
+            account_id: account_id.map(ToOwned::to_owned),
+            lattice_id: lattice_id.to_owned(),
+            model_name: Some(name.to_owned()),
+            version: None,
+            operation: ModelOperation::Delete.to_string(),
+            result: "deleted".to_string(),
+            timestamp: Utc::now(),
+            trace_id: None,
+        })
+        .await;
+        Ok(true)
+    }
+}
@@ -0,0 +1,164 @@
+//! Parameter substitution for manifests put as templates.
+//!
+//! When a put request sets the [`TEMPLATE_PARAMETERS_HEADER`] to a JSON object of string values,
+//! the manifest is treated as a template and every `${name}` placeholder is replaced with the value
+//! of the `name` parameter before the manifest is parsed. Parameter names can contain ASCII
+//! letters, digits, `_`, `-`, and `.`. To include a literal `${` in a template, escape it as `$${`.
+//! A `$` that isn't followed by `{` is left as is.
+//!
+//! Values are substituted as plain text and aren't quoted or escaped, so they must be valid at the
+//! position of the placeholder (for example, by quoting the placeholder in YAML). Substituted
+//! values aren't themselves searched for placeholders. Rendering fails if any placeholder doesn't
+//! have a parameter, and the rendered manifest (not the template) is what gets stored.
+//!
+//! Requests without the header are never treated as templates, so manifests containing `${` are
+//! stored as is.
+
+use std::collections::{BTreeSet, HashMap};
+
+use anyhow::{bail, Context};
+use async_nats::HeaderMap;
+
+/// The name of the header in a put request containing a JSON object of parameters to substitute
+/// into the manifest
+pub const TEMPLATE_PARAMETERS_HEADER: &str = "wadm-template-parameters";
+
+/// Renders the payload of a put request as a template if the request has parameters, otherwise
+/// returns the payload unchanged
+pub(crate) fn render_payload(
+    payload: Vec<u8>,
+    headers: Option<&HeaderMap>,
+) -> anyhow::Result<Vec<u8>> {
+    let Some(parameters) = headers.and_then(|headers| headers.get(TEMPLATE_PARAMETERS_HEADER))
+    else {
+        return Ok(payload);
+    };
+    let parameters: HashMap<String, String> = serde_json::from_str(parameters.as_str())
+        .context("template parameters must be a JSON object of string values")?;
+    let template = String::from_utf8(payload).context("manifest template must be valid UTF-8")?;
+    render(&template, &parameters).map(String::into_bytes)
+}
+
+/// Replaces every `${name}` placeholder in the template with its parameter, returning an error
+/// listing all placeholders without a parameter
+pub(crate) fn render(
+    template: &str,
+    parameters: &HashMap<String, String>,
+) -> anyhow::Result<String> {
+    let mut rendered = String::with_capacity(template.len());
+    let mut unresolved = BTreeSet::new();
+    let mut rest = template;
+    while let Some(idx) = rest.find('$') {
+        rendered.push_str(&rest[..idx]);
+        rest = &rest[idx..];
+        if let Some(escaped) = rest.strip_prefix("$${") {
+            rendered.push_str("${");
+            rest = escaped;
+        } else if let Some(placeholder) = rest.strip_prefix("${") {
+            let Some(end) = placeholder.find('}') else {
+                bail!(
+                    "unterminated placeholder starting at ${{{}",
+                    truncate(placeholder)
+                );
+            };
+            let name = &placeholder[..end];
+            if name.is_empty() || !name.chars().all(is_parameter_char) {
+                bail!("invalid placeholder ${{{name}}}, parameter names can only contain letters, digits, '_', '-', and '.'");
+            }
+            match parameters.get(name) {
+                Some(value) => rendered.push_str(value),
+                None => {
+                    unresolved.insert(name);
+                }
+            }
+            rest = &placeholder[end + 1..];
+        } else {
+            rendered.push('$');
+            rest = &rest[1..];
+        }
+    }
+    rendered.push_str(rest);
+
+    if !unresolved.is_empty() {
+        bail!(
+            "no value given for template parameter(s): {}",
+            unresolved.into_iter().collect::<Vec<_>>().join(", ")
+        );
+    }
+    Ok(rendered)
+}
+
+fn is_parameter_char(c: char) -> bool {
+    c.is_ascii_alphanumeric() || matches!(c, '_' | '-' | '.')
+}
+
+/// Shortens the start of an unterminated placeholder for error messages
+fn truncate(s: &str) -> &str {
+    s.char_indices()
+        .nth(32)
+        .map(|(idx, _)| &s[..idx])
+        .unwrap_or(s)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn params(pairs: &[(&str, &str)]) -> HashMap<String, String> {
+        pairs
+            .iter()
+            .map(|(k, v)| (k.to_string(), v.to_string()))
+            .collect()
+    }
+
+    #[test]
+    fn test_render() {
+        let parameters = params(&[("env", "prod"), ("image.tag", "0.4.0")]);
+        assert_eq!(
+            render(
+                "name: app-${env}\nimage: kvcounter:${image.tag}\ncost: $5\nliteral: $${env}",
+                &parameters
+            )
+            .expect("Should render"),
+            "name: app-prod\nimage: kvcounter:0.4.0\ncost: $5\nliteral: ${env}"
+        );
+
+        // Substituted values aren't rendered again
+        assert_eq!(
+            render("${a}", &params(&[("a", "${b}")])).expect("Should render"),
+            "${b}"
+        );
+
+        let err = render("${missing} ${env} ${other} ${missing}", &parameters)
+            .expect_err("Unresolved placeholders should fail");
+        assert_eq!(
+            err.to_string(),
+            "no value given for template parameter(s): missing, other"
+        );
+
+        assert!(render("${env", &parameters).is_err());
+        assert!(render("${}", &parameters).is_err());
+        assert!(render("${bad name}", &parameters).is_err());
+    }
+
+    #[test]
+    fn test_render_payload() {
+        let payload = b"name: ${env}".to_vec();
+        assert_eq!(
+            render_payload(payload.clone(), None).expect("Should pass through"),
+            payload,
+            "Payloads without parameters shouldn't be rendered"
+        );
+
+        let mut headers = HeaderMap::new();
+        headers.insert(TEMPLATE_PARAMETERS_HEADER, r#"{"env": "prod"}"#);
+        assert_eq!(
+            render_payload(payload.clone(), Some(&headers)).expect("Should render"),
+            b"name: prod".to_vec()
+        );
+
+        let mut headers = HeaderMap::new();
+        headers.insert(TEMPLATE_PARAMETERS_HEADER, r#"{"env": 1}"#);
+        assert!(render_payload(payload, Some(&headers)).is_err());
+    }
+}
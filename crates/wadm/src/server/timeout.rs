@@ -0,0 +1,63 @@
+use std::{future::Future, time::Duration};
+
+/// The default amount of time to wait for a single model storage operation
+pub const DEFAULT_STORE_TIMEOUT: Duration = Duration::from_secs(30);
+/// The default amount of time to wait for a notification to be sent, including retries
+pub const DEFAULT_NOTIFY_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// An error returned (wrapped in an [`anyhow::Error`]) when a storage or notifier call didn't
+/// complete within its timeout
+#[derive(Debug, thiserror::Error)]
+#[error("Timed out after {timeout:?} waiting for {operation}")]
+pub(crate) struct TimedOut {
+    operation: &'static str,
+    timeout: Duration,
+}
+
+impl TimedOut {
+    /// Returns true if the given error was caused by a timeout. The operation may or may not have
+    /// been applied, so callers should check the current state before retrying
+    pub fn is_timeout(err: &anyhow::Error) -> bool {
+        err.downcast_ref::<TimedOut>().is_some()
+    }
+}
+
+/// Runs the given future, returning a [`TimedOut`] error if it doesn't complete within the timeout
+pub(crate) async fn with_timeout<T>(
+    operation: &'static str,
+    timeout: Duration,
+    fut: impl Future<Output = anyhow::Result<T>>,
+) -> anyhow::Result<T> {
+    tokio::time::timeout(timeout, fut)
+        .await
+        .map_err(|_| anyhow::Error::from(TimedOut { operation, timeout }))?
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_with_timeout() {
+        let ok = with_timeout("fast operation", Duration::from_secs(1), async { Ok(1) })
+            .await
+            .expect("Fast operations should complete");
+        assert_eq!(ok, 1);
+
+        let err = with_timeout("slow operation", Duration::from_millis(10), async {
+            tokio::time::sleep(Duration::from_secs(5)).await;
+            Ok(())
+        })
+        .await
+        .expect_err("Slow operations should time out");
+        assert!(TimedOut::is_timeout(&err));
+        assert!(err.to_string().contains("slow operation"));
+
+        let err = with_timeout("failed operation", Duration::from_secs(1), async {
+            Err::<(), _>(anyhow::anyhow!("boom"))
+        })
+        .await
+        .expect_err("Errors should be passed through");
+        assert!(!TimedOut::is_timeout(&err));
+    }
+}
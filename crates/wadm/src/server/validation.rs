@@ -0,0 +1,1227 @@
+//! Validation of manifests before they are stored. This is the same set of checks the API server
+//! runs for every put, exposed so other tools can validate manifests without a running server
+
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
+use std::path::Path;
+
+use anyhow::{anyhow, bail, ensure, Context};
+use jsonschema::{paths::PathChunk, Draft, JSONSchema};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use tokio::sync::OnceCell;
+use tracing::trace;
+use wadm_types::{
+    api::SchemaError,
+    migration::OAM_V1ALPHA1_VERSION,
+    validation::{validate_manifest, ValidationOutput as _},
+    CapabilityProperties, ComponentProperties, LinkProperty, Manifest, Properties,
+    SpreadScalerProperty, Trait, TraitProperty, DAEMONSCALER_TRAIT,
+    MINIMUM_WADM_VERSION_ANNOTATION_KEY, OAM_VERSION, SPREADSCALER_TRAIT,
+};
+
+use crate::DEFAULT_LINK_NAME;
+
+use super::ManifestLimits;
+
+const JSON_SCHEMA: &str = include_str!("../../oam.schema.json");
+static JSON_SCHEMA_VALUE: OnceCell<serde_json::Value> = OnceCell::const_new();
+static OAM_JSON_SCHEMA: OnceCell<JSONSchema> = OnceCell::const_new();
+
+/// The manifest `apiVersion`s this server understands. Older versions are migrated to
+/// [`OAM_VERSION`] when a manifest is stored
+pub(crate) const SUPPORTED_API_VERSIONS: [&str; 2] = [OAM_VERSION, OAM_V1ALPHA1_VERSION];
+
+/// The maximum size (in bytes) of a single label or annotation value in a manifest
+const MAX_METADATA_VALUE_BYTES: usize = 4 * 1024;
+/// The maximum combined size (in bytes) of all label and annotation keys and values in a manifest.
+/// Metadata is meant for small bits of identifying information, not as a place to store data
+const MAX_METADATA_BYTES: usize = 64 * 1024;
+
+/// The kind of check a validation error or warning came from
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ValidationCategory {
+    /// The manifest doesn't match the manifest schema or uses an unsupported `apiVersion`
+    Schema,
+    /// The manifest exceeds one of the configured [`ManifestLimits`]
+    Limits,
+    /// The manifest's labels or annotations are invalid
+    Metadata,
+    /// A component, its properties, or its scaler traits are invalid
+    Components,
+    /// A link between components is invalid or points at something that doesn't exist
+    Links,
+    /// Config blocks in the manifest conflict with each other
+    Config,
+}
+
+/// A single problem found while validating a manifest
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ValidationIssue {
+    pub category: ValidationCategory,
+    /// A human readable description of the problem
+    pub message: String,
+    /// The locations in the manifest that don't match the schema. This is only set for
+    /// [`ValidationCategory::Schema`] issues
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub schema_errors: Vec<SchemaError>,
+}
+
+impl ValidationIssue {
+    fn new(category: ValidationCategory, message: impl ToString) -> ValidationIssue {
+        ValidationIssue {
+            category,
+            message: message.to_string(),
+            schema_errors: Vec::new(),
+        }
+    }
+}
+
+impl std::fmt::Display for ValidationIssue {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.message)
+    }
+}
+
+/// The result of validating a manifest. A manifest is valid if there are no errors. Warnings point
+/// at things that are likely mistakes but never stop a manifest from being stored
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ValidationOutput {
+    pub errors: Vec<ValidationIssue>,
+    pub warnings: Vec<ValidationIssue>,
+}
+
+impl ValidationOutput {
+    /// Returns true if no errors were found
+    pub fn is_valid(&self) -> bool {
+        self.errors.is_empty()
+    }
+
+    /// Returns all of the schema errors found, across every error
+    pub fn schema_errors(&self) -> Vec<SchemaError> {
+        self.errors
+            .iter()
+            .flat_map(|issue| issue.schema_errors.iter().cloned())
+            .collect()
+    }
+
+    /// Converts the output into an error containing every error message (one per line) if the
+    /// manifest is invalid. Warnings are dropped
+    pub fn into_result(self) -> anyhow::Result<()> {
+        if self.is_valid() {
+            return Ok(());
+        }
+        Err(anyhow!(self
+            .errors
+            .iter()
+            .map(ToString::to_string)
+            .collect::<Vec<_>>()
+            .join("\n")))
+    }
+}
+
+/// Loads the JSON schema at the given path to validate manifests against instead of the embedded
+/// OAM schema. This must be called before any manifests are validated, otherwise the embedded
+/// schema will already be in use and an error is returned
+pub(crate) async fn load_custom_schema(path: &Path) -> anyhow::Result<()> {
+    let raw = tokio::fs::read_to_string(path)
+        .await
+        .with_context(|| format!("Unable to read manifest schema {}", path.display()))?;
+    let value: serde_json::Value = serde_json::from_str(&raw)
+        .with_context(|| format!("Manifest schema {} is not valid JSON", path.display()))?;
+    JSON_SCHEMA_VALUE
+        .set(value)
+        .map_err(|_| anyhow!("A manifest schema has already been loaded"))?;
+    let schema = JSONSchema::options()
+        .with_draft(Draft::Draft7)
+        .compile(
+            JSON_SCHEMA_VALUE
+                .get()
+                // SAFETY: We just set it above
+                .expect("JSON schema should be initialized"),
+        )
+        .map_err(|e| anyhow!("Unable to compile manifest schema {}: {e}", path.display()))?;
+    OAM_JSON_SCHEMA
+        .set(schema)
+        .map_err(|_| anyhow!("A manifest schema has already been loaded"))
+}
+
+/// Returns the JSON schema manifests are validated against, parsing the embedded OAM schema if a
+/// custom one wasn't loaded
+pub(crate) async fn schema_value() -> anyhow::Result<&'static serde_json::Value> {
+    JSON_SCHEMA_VALUE
+        .get_or_try_init(|| async {
+            serde_json::from_str(JSON_SCHEMA)
+                .map_err(|e| anyhow!("Unable to parse JSON schema: {}", e))
+        })
+        .await
+}
+
+/// Runs every check the API server runs before storing a manifest.
+///
+/// The `apiVersion`, limits, and schema are checked first, and nothing else is checked if any of
+/// them fail. Otherwise the remaining checks all run, reporting the first problem found by each
+/// category. Warnings come from [`wadm_types::validation::validate_manifest`]
+pub async fn validate(manifest: &Manifest, limits: &ManifestLimits) -> ValidationOutput {
+    let mut output = ValidationOutput::default();
+    if let Err(issue) = check_schema(manifest, limits).await {
+        output.errors.push(issue);
+        return output;
+    }
+
+    for (category, result) in [
+        (ValidationCategory::Metadata, check_metadata(manifest)),
+        (
+            ValidationCategory::Components,
+            check_components(manifest, limits),
+        ),
+        (ValidationCategory::Config, check_config(manifest)),
+        (ValidationCategory::Links, check_links(manifest)),
+    ] {
+        if let Err(e) = result {
+            output.errors.push(ValidationIssue::new(category, e));
+        }
+    }
+
+    // The checks in wadm-types are all about links and the config they reference
+    match validate_manifest(manifest).await {
+        Ok(failures) => output.warnings.extend(
+            failures
+                .warnings()
+                .into_iter()
+                .map(|failure| ValidationIssue::new(ValidationCategory::Links, &failure.msg)),
+        ),
+        Err(e) => trace!(error = %e, "Unable to check manifest for warnings"),
+    }
+    output
+}
+
+/// Checks the `apiVersion`, limits, and schema of the manifest, in that order
+async fn check_schema(manifest: &Manifest, limits: &ManifestLimits) -> Result<(), ValidationIssue> {
+    let schema_issue = |e: anyhow::Error| ValidationIssue::new(ValidationCategory::Schema, e);
+    let schema = schema_value().await.map_err(schema_issue)?;
+    let ok_schema = OAM_JSON_SCHEMA
+        .get_or_try_init(|| async {
+            JSONSchema::options()
+                .with_draft(Draft::Draft7)
+                .compile(schema)
+                .map_err(|e| anyhow!("Unable to compile JSON schema: {e}"))
+        })
+        .await
+        .map_err(schema_issue)?;
+
+    // Check the apiVersion up front, as the schema errors for an unsupported version are confusing
+    if !SUPPORTED_API_VERSIONS.contains(&manifest.api_version.as_str()) {
+        return Err(ValidationIssue::new(
+            ValidationCategory::Schema,
+            format!(
+                "Unsupported apiVersion {}, supported: [{}]",
+                manifest.api_version,
+                SUPPORTED_API_VERSIONS.join(", ")
+            ),
+        ));
+    }
+
+    // Check the limits next so we don't spend time validating the rest of an oversized manifest
+    check_limits(manifest, limits)
+        .map_err(|e| ValidationIssue::new(ValidationCategory::Limits, e))?;
+
+    let json_instance = serde_json::to_value(manifest).map_err(|e| schema_issue(e.into()))?;
+    let validation_result = ok_schema.validate(&json_instance);
+    if let Err(errors) = validation_result {
+        let mut error_message = String::new();
+        let mut schema_errors = Vec::new();
+        for error in errors {
+            trace!(error = ?error, "Validation error");
+            schema_errors.push(SchemaError {
+                path: error.instance_path.to_string(),
+                message: error.to_string(),
+                keyword: match error.schema_path.last() {
+                    Some(PathChunk::Keyword(keyword)) => keyword.to_string(),
+                    Some(PathChunk::Property(property)) => property.to_string(),
+                    Some(PathChunk::Index(idx)) => idx.to_string(),
+                    None => String::new(),
+                },
+            });
+            let instance_path = error
+                .instance_path
+                .into_iter()
+                .map(|item| match item {
+                    PathChunk::Property(value) => value.to_string(),
+                    PathChunk::Index(idx) => format!(" at index: {idx}"),
+                    PathChunk::Keyword(keyword) => keyword.to_string(),
+                })
+                .collect::<Vec<String>>()
+                .join("/");
+            error_message.push_str(&format!(
+                "Should be able to parse object at: {} \n",
+                // The path of the corresponding JSON error instance in that file
+                instance_path
+            ));
+        }
+        return Err(ValidationIssue {
+            category: ValidationCategory::Schema,
+            message: format!(
+                "Validation Error: \n{error_message}Please check for missing or incorrect elements"
+            ),
+            schema_errors,
+        });
+    }
+    Ok(())
+}
+
+fn check_limits(manifest: &Manifest, limits: &ManifestLimits) -> anyhow::Result<()> {
+    let component_count = manifest.spec.components.len();
+    if component_count > limits.max_components {
+        bail!(
+            "Manifest has {component_count} components, which exceeds the maximum of {} components per manifest",
+            limits.max_components
+        );
+    }
+    for component in manifest.spec.components.iter() {
+        let trait_count = component.traits.as_ref().map(Vec::len).unwrap_or_default();
+        if trait_count > limits.max_traits_per_component {
+            bail!(
+                "Component {} has {trait_count} traits, which exceeds the maximum of {} traits per component",
+                component.name,
+                limits.max_traits_per_component
+            );
+        }
+        let depth = component
+            .traits
+            .iter()
+            .flatten()
+            .map(|t| serde_json::to_value(&t.properties).map(|v| json_depth(&v)))
+            .chain(std::iter::once(
+                serde_json::to_value(&component.properties).map(|v| json_depth(&v)),
+            ))
+            .try_fold(0, |max, depth| depth.map(|depth| max.max(depth)))?;
+        if depth > limits.max_config_depth {
+            bail!(
+                "Component {} has properties nested {depth} levels deep, which exceeds the maximum depth of {}",
+                component.name,
+                limits.max_config_depth
+            );
+        }
+    }
+    Ok(())
+}
+
+fn check_metadata(manifest: &Manifest) -> anyhow::Result<()> {
+    if let Some(required) = manifest.minimum_wadm_version() {
+        if let Err(e) = parse_wadm_version(required) {
+            bail!("Invalid {MINIMUM_WADM_VERSION_ANNOTATION_KEY} annotation {required}, expected a version like 0.13.0: {e}");
+        }
+    }
+    ensure!(manifest.metadata.labels.iter().all(valid_oam_label));
+    ensure!(manifest.metadata.annotations.iter().all(valid_oam_label));
+    let mut metadata_bytes = 0;
+    for (key, value) in manifest
+        .metadata
+        .labels
+        .iter()
+        .chain(manifest.metadata.annotations.iter())
+    {
+        ensure!(
+            value.len() <= MAX_METADATA_VALUE_BYTES,
+            "The value of metadata key {key} is {} bytes, which exceeds the maximum of {MAX_METADATA_VALUE_BYTES} bytes per value",
+            value.len()
+        );
+        metadata_bytes += key.len() + value.len();
+    }
+    ensure!(
+        metadata_bytes <= MAX_METADATA_BYTES,
+        "Manifest labels and annotations are {metadata_bytes} bytes in total, which exceeds the maximum of {MAX_METADATA_BYTES} bytes"
+    );
+    Ok(())
+}
+
+fn check_components(manifest: &Manifest, limits: &ManifestLimits) -> anyhow::Result<()> {
+    let mut name_registry: HashSet<&str> = HashSet::new();
+    // Explicit component IDs mapped to the name of the component that set them
+    let mut id_registry: HashMap<&str, &str> = HashMap::new();
+    // Component names that only differ by case or surrounding whitespace end up referring to the
+    // same thing once deployed, so they are compared in their canonical form
+    let mut canonical_names: HashMap<String, &str> = HashMap::new();
+    for component in manifest.spec.components.iter() {
+        // Component name validation : each component (actors or providers) should have a unique name
+        if !name_registry.insert(&component.name) {
+            bail!("Duplicate component name in manifest: {}", component.name);
+        }
+        if let Some(other) =
+            canonical_names.insert(component.name.trim().to_lowercase(), &component.name)
+        {
+            bail!(
+                "Duplicate component name in manifest: {:?} and {:?} only differ by case or whitespace",
+                other,
+                component.name
+            );
+        }
+        // Serialization validation : Component properties and traits should survive a round trip
+        // through JSON unchanged. We need this so it doesn't trigger an error (or silently change
+        // meaning) when sending a command down the line
+        check_round_trip(&component.properties).map_err(|e| {
+            anyhow!(
+                "Properties of component {} can't be serialized: {e}",
+                component.name
+            )
+        })?;
+        for trait_item in component.traits.iter().flatten() {
+            check_round_trip(trait_item).map_err(|e| {
+                anyhow!(
+                    "Trait {} of component {} can't be serialized: {e}",
+                    trait_item.trait_type,
+                    component.name
+                )
+            })?;
+            validate_scaler_trait(&component.name, trait_item, limits.max_instances)?;
+        }
+
+        if !limits.allowed_registries.is_empty() {
+            let image = component_image(&component.properties);
+            if !image_registry_allowed(image, &limits.allowed_registries) {
+                bail!(
+                    "Image {image} of component {} is not from an allowed registry, allowed: [{}]",
+                    component.name,
+                    limits.allowed_registries.join(", ")
+                );
+            }
+        }
+
+        // Component IDs should be unique per manifest, for both components and providers
+        let id = match &component.properties {
+            Properties::Capability {
+                properties: CapabilityProperties { id, .. },
+            }
+            | Properties::Component {
+                properties: ComponentProperties { id, .. },
+            } => id.as_deref(),
+        };
+        if let Some(id) = id {
+            if id_registry.insert(id, &component.name).is_some() {
+                bail!("Duplicate component identifier in manifest: {id}");
+            }
+        }
+    }
+
+    // An ID that matches the name of a different component makes it ambiguous which component is
+    // being referred to. Using a component's own name as its ID is fine
+    if let Some((id, component_name)) = id_registry
+        .iter()
+        .find(|(id, component_name)| id != component_name && name_registry.contains(*id))
+    {
+        bail!(
+            "Component {component_name} has the ID {id}, which is the same as the name of component {id}. Component IDs and names must not collide"
+        );
+    }
+    Ok(())
+}
+
+fn check_links(manifest: &Manifest) -> anyhow::Result<()> {
+    // Link targets mapped to the components that link to them, so we can point at the offending
+    // links if a target is missing
+    let mut required_capability_components: BTreeMap<&str, BTreeSet<&str>> = BTreeMap::new();
+    for component in manifest.spec.components.iter() {
+        // Linkdef validation : A component can link to the same target more than once (such as for
+        // different WIT packages or link names), but two links with the same target, WIT package,
+        // and link name would be the same linkdef
+        let mut linkdef_set: HashSet<(&str, &str, &str, &str)> = HashSet::new();
+        // Link names should also be unique per WIT package for a component, otherwise it is
+        // ambiguous which target a named link resolves to
+        let mut link_name_set: HashSet<(&str, &str, &str)> = HashSet::new();
+        for trait_item in component.traits.iter().flatten() {
+            // TODO : add trait type validation after custom types are done. See TraitProperty enum.
+            let TraitProperty::Link(LinkProperty {
+                target: target_name,
+                namespace,
+                package,
+                name: link_name,
+                ..
+            }) = &trait_item.properties
+            else {
+                continue;
+            };
+            let link_name = link_name.as_deref().unwrap_or(DEFAULT_LINK_NAME);
+            if !linkdef_set.insert((target_name, namespace, package, link_name)) {
+                bail!(
+                    "Duplicate target {} for component {} linkdef trait with link name {link_name} for {namespace}:{package} in manifest",
+                    target_name,
+                    component.name,
+                );
+            }
+
+            if !link_name_set.insert((namespace, package, link_name)) {
+                bail!(
+                    "Duplicate link name {link_name} for {namespace}:{package} on component {} in manifest",
+                    component.name,
+                );
+            }
+
+            // Multiple components{ with type != 'capability'} can declare the same target, so we don't need to check for duplicates on insert
+            required_capability_components
+                .entry(target_name)
+                .or_default()
+                .insert(&component.name);
+        }
+    }
+
+    // Providers are linked to by link name, so two providers running the same image under the same
+    // link name collide at runtime
+    let mut provider_link_names: HashMap<(&str, &str), &str> = HashMap::new();
+    for component in manifest.spec.components.iter() {
+        let Properties::Capability {
+            properties: CapabilityProperties { image, .. },
+        } = &component.properties
+        else {
+            continue;
+        };
+        let link_names: BTreeSet<&str> = manifest
+            .links()
+            .filter_map(|link| match &link.properties {
+                TraitProperty::Link(LinkProperty { target, name, .. })
+                    if *target == component.name =>
+                {
+                    Some(name.as_deref().unwrap_or(DEFAULT_LINK_NAME))
+                }
+                _ => None,
+            })
+            .collect();
+        for link_name in link_names {
+            if let Some(other) =
+                provider_link_names.insert((image.as_str(), link_name), component.name.as_str())
+            {
+                bail!(
+                    "Capability components {other} and {} both use the image {image} with the link name {link_name}. Use a different link name for one of them",
+                    component.name
+                );
+            }
+        }
+    }
+
+    let missing_capability_components = required_capability_components
+        .iter()
+        .filter(|(target, _)| {
+            !manifest
+                .spec
+                .components
+                .iter()
+                .any(|component| component.name == **target)
+        })
+        .map(|(target, sources)| {
+            format!(
+                "{target} (linked from {})",
+                sources.iter().copied().collect::<Vec<_>>().join(", ")
+            )
+        })
+        .collect::<Vec<String>>();
+
+    if !missing_capability_components.is_empty() {
+        bail!(
+            "The following capability component(s) are missing from the manifest: {}",
+            missing_capability_components.join("; ")
+        );
+    }
+    Ok(())
+}
+
+fn check_config(manifest: &Manifest) -> anyhow::Result<()> {
+    // Each config block with properties becomes a config named after the block, so the same name
+    // defined with different properties would silently overwrite one of them. Blocks without
+    // properties only refer to existing config, so they can share a name with anything
+    let mut config_definitions: HashMap<&str, (&HashMap<String, String>, &str)> = HashMap::new();
+    for component in manifest.spec.components.iter() {
+        let component_config = match &component.properties {
+            Properties::Component {
+                properties: ComponentProperties { config, .. },
+            }
+            | Properties::Capability {
+                properties: CapabilityProperties { config, .. },
+            } => config.iter(),
+        };
+        let link_config = component
+            .traits
+            .iter()
+            .flatten()
+            .filter_map(|trait_item| match &trait_item.properties {
+                TraitProperty::Link(link) => {
+                    Some(link.source_config.iter().chain(link.target_config.iter()))
+                }
+                _ => None,
+            })
+            .flatten();
+        for config in component_config.chain(link_config) {
+            let Some(properties) = config.properties.as_ref() else {
+                continue;
+            };
+            match config_definitions.get(config.name.as_str()) {
+                Some((existing, other)) if *existing != properties => bail!(
+                    "Duplicate config name in manifest: {} is defined with different properties by {other} and {}",
+                    config.name,
+                    component.name
+                ),
+                Some(_) => (),
+                None => {
+                    config_definitions
+                        .insert(config.name.as_str(), (properties, component.name.as_str()));
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Validates the properties of the builtin scaler traits. Other trait types are left alone until
+/// custom trait types are supported (see the TODO in [`check_links`]).
+///
+/// Note that negative instance counts are already rejected when parsing the manifest
+fn validate_scaler_trait(
+    component_name: &str,
+    trait_item: &Trait,
+    max_instances: usize,
+) -> anyhow::Result<()> {
+    let trait_type = trait_item.trait_type.as_str();
+    if trait_type != SPREADSCALER_TRAIT && trait_type != DAEMONSCALER_TRAIT {
+        return Ok(());
+    }
+    let SpreadScalerProperty { instances, spread } = match &trait_item.properties {
+        TraitProperty::SpreadScaler(props) => props,
+        _ => bail!(
+            "The {trait_type} trait on component {component_name} does not have valid {trait_type} properties"
+        ),
+    };
+    if *instances > max_instances {
+        bail!(
+            "The {trait_type} trait on component {component_name} has {instances} instances, which exceeds the maximum of {max_instances} instances"
+        );
+    }
+
+    let mut spread_names = HashSet::new();
+    for requirement in spread.iter() {
+        if !spread_names.insert(requirement.name.as_str()) {
+            bail!(
+                "Duplicate spread name {} in the {trait_type} trait on component {component_name}",
+                requirement.name
+            );
+        }
+        if let Some((label, _)) = requirement
+            .requirements
+            .iter()
+            .find(|label| !valid_oam_label(*label))
+        {
+            bail!(
+                "Spread {} in the {trait_type} trait on component {component_name} has an invalid requirement label {label}",
+                requirement.name
+            );
+        }
+    }
+
+    // If weights are given, they need to add up to something, otherwise nothing can be scheduled
+    // by weight
+    if spread.iter().any(|s| s.weight.is_some()) && spread.iter().all(|s| s.weight == Some(0)) {
+        bail!(
+            "All spread weights in the {trait_type} trait on component {component_name} are 0. At least one spread must have a weight greater than 0"
+        );
+    }
+
+    Ok(())
+}
+
+/// Parses a wadm version, allowing for a leading `v` (e.g. `v0.13.0`)
+pub(crate) fn parse_wadm_version(version: &str) -> Result<semver::Version, semver::Error> {
+    semver::Version::parse(version.trim().trim_start_matches('v'))
+}
+
+/// Returns how deeply nested the given JSON value is, where scalars have a depth of 0 and a flat
+/// object or array has a depth of 1. This walks the value iteratively so it can't overflow the stack
+fn json_depth(value: &serde_json::Value) -> usize {
+    let mut max = 0;
+    let mut stack = vec![(value, 0)];
+    while let Some((value, depth)) = stack.pop() {
+        match value {
+            serde_json::Value::Object(map) => {
+                max = max.max(depth + 1);
+                stack.extend(map.values().map(|v| (v, depth + 1)));
+            }
+            serde_json::Value::Array(items) => {
+                max = max.max(depth + 1);
+                stack.extend(items.iter().map(|v| (v, depth + 1)));
+            }
+            _ => (),
+        }
+    }
+    max
+}
+
+/// Serializes the value to JSON and back, returning an error if either step fails or the value
+/// changes along the way
+fn check_round_trip<T>(value: &T) -> anyhow::Result<()>
+where
+    T: Serialize + DeserializeOwned + PartialEq,
+{
+    let serialized = serde_json::to_vec(value).context("unable to serialize")?;
+    let round_tripped: T =
+        serde_json::from_slice(&serialized).context("unable to deserialize serialized value")?;
+    ensure!(
+        &round_tripped == value,
+        "value is different after being serialized and deserialized: {}",
+        String::from_utf8_lossy(&serialized)
+    );
+    Ok(())
+}
+
+/// Returns the image of a component or provider
+fn component_image(properties: &Properties) -> &str {
+    match properties {
+        Properties::Component {
+            properties: ComponentProperties { image, .. },
+        }
+        | Properties::Capability {
+            properties: CapabilityProperties { image, .. },
+        } => image,
+    }
+}
+
+/// Returns true if the image reference is from one of the allowed registries. Refs without a
+/// registry resolve to Docker Hub (`docker.io`), and refs that aren't OCI references at all (such
+/// as `file://` paths) are never allowed
+fn image_registry_allowed(image: &str, allowed_registries: &[String]) -> bool {
+    let Ok(reference) = oci_distribution::Reference::try_from(image) else {
+        return false;
+    };
+    allowed_registries
+        .iter()
+        .any(|allowed| allowed.trim().eq_ignore_ascii_case(reference.registry()))
+}
+
+/// This function validates that a key/value pair is a valid OAM label. It's using fairly
+/// basic validation rules to ensure that the manifest isn't doing anything horribly wrong. Keeping
+/// this function free of regex is intentional to keep this code functional but simple.
+///
+/// See <https://github.com/oam-dev/spec/blob/master/metadata.md#metadata> for details
+fn valid_oam_label(label: (&String, &String)) -> bool {
+    let (key, _) = label;
+    match key.split_once('/') {
+        Some((prefix, name)) => is_valid_dns_subdomain(prefix) && is_valid_label_name(name),
+        None => is_valid_label_name(key),
+    }
+}
+
+fn is_valid_dns_subdomain(s: &str) -> bool {
+    if s.is_empty() || s.len() > 253 {
+        return false;
+    }
+
+    s.split('.').all(|part| {
+        // Ensure each part is non-empty, <= 63 characters, starts with an alphabetic character,
+        // ends with an alphanumeric character, and contains only alphanumeric characters or hyphens
+        !part.is_empty()
+            && part.len() <= 63
+            && part.starts_with(|c: char| c.is_ascii_alphabetic())
+            && part.ends_with(|c: char| c.is_ascii_alphanumeric())
+            && part.chars().all(|c| c.is_ascii_alphanumeric() || c == '-')
+    })
+}
+
+// Ensure each name is non-empty, <= 63 characters, starts with an alphanumeric character,
+// ends with an alphanumeric character, and contains only alphanumeric characters, hyphens,
+// underscores, or periods
+fn is_valid_label_name(name: &str) -> bool {
+    if name.is_empty() || name.len() > 63 {
+        return false;
+    }
+
+    name.starts_with(|c: char| c.is_ascii_alphanumeric())
+        && name.ends_with(|c: char| c.is_ascii_alphanumeric())
+        && name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_' || c == '.')
+}
+
+#[cfg(test)]
+mod test {
+    use std::io::BufReader;
+
+    use super::*;
+    use anyhow::Result;
+    use serde_json::json;
+
+    pub(crate) fn deserialize_yaml(filepath: impl AsRef<Path>) -> Result<Manifest> {
+        let file = std::fs::File::open(filepath)?;
+        let reader = BufReader::new(file);
+        let yaml_string: Manifest = serde_yaml::from_reader(reader)?;
+        Ok(yaml_string)
+    }
+
+    #[tokio::test]
+    async fn test_manifest_validation() {
+        let correct_manifest =
+            deserialize_yaml("./oam/simple1.yaml").expect("Should be able to parse");
+
+        assert!(validate(&correct_manifest, &ManifestLimits::default())
+            .await
+            .into_result()
+            .is_ok());
+
+        let manifest = deserialize_yaml("./test/data/incorrect_component.yaml")
+            .expect("Should be able to parse");
+
+        let output = validate(&manifest, &ManifestLimits::default()).await;
+        let [error] = output.errors.as_slice() else {
+            panic!("Should have detected incorrect component: {output:?}");
+        };
+        assert_eq!(error.category, ValidationCategory::Schema);
+        // The 0th component in the spec list is incorrect and should be detected (indexing starts from 0)
+        assert!(error
+            .message
+            .contains("Should be able to parse object at: spec/components/ at index: 0"));
+        assert!(
+            error
+                .schema_errors
+                .iter()
+                .any(|err| err.path.starts_with("/spec/components/0") && !err.keyword.is_empty()),
+            "Schema errors should point at the incorrect component: {:?}",
+            error.schema_errors
+        );
+
+        let manifest = deserialize_yaml("./test/data/duplicate_component.yaml")
+            .expect("Should be able to parse");
+
+        match validate(&manifest, &ManifestLimits::default())
+            .await
+            .into_result()
+        {
+            Ok(()) => panic!("Should have detected duplicate component"),
+            Err(e) => assert!(e
+                .to_string()
+                .contains("Duplicate component name in manifest")),
+        }
+
+        let manifest =
+            deserialize_yaml("./test/data/duplicate_id1.yaml").expect("Should be able to parse");
+
+        match validate(&manifest, &ManifestLimits::default())
+            .await
+            .into_result()
+        {
+            Ok(()) => {
+                panic!("Should have detected duplicate component ID in provider properties")
+            }
+            Err(e) => assert!(e
+                .to_string()
+                .contains("Duplicate component identifier in manifest")),
+        }
+
+        let manifest =
+            deserialize_yaml("./test/data/duplicate_id2.yaml").expect("Should be able to parse");
+
+        match validate(&manifest, &ManifestLimits::default())
+            .await
+            .into_result()
+        {
+            Ok(()) => panic!("Should have detected duplicate component ID in actor properties"),
+            Err(e) => assert!(e
+                .to_string()
+                .contains("Duplicate component identifier in manifest")),
+        }
+
+        let mut manifest = deserialize_yaml("./oam/simple1.yaml").expect("Should be able to parse");
+        manifest.metadata.annotations.insert(
+            MINIMUM_WADM_VERSION_ANNOTATION_KEY.to_string(),
+            "not-a-version".to_string(),
+        );
+        match validate(&manifest, &ManifestLimits::default())
+            .await
+            .into_result()
+        {
+            Ok(()) => panic!("Should have detected an invalid minimum wadm version"),
+            Err(e) => assert!(e
+                .to_string()
+                .contains("Invalid minimumWadmVersion annotation not-a-version")),
+        }
+
+        let manifest = deserialize_yaml("./test/data/id_name_collision.yaml")
+            .expect("Should be able to parse");
+
+        match validate(&manifest, &ManifestLimits::default()).await.into_result() {
+            Ok(()) => panic!("Should have detected a component ID colliding with a name"),
+            Err(e) => assert!(e.to_string().contains(
+                "Component webcap has the ID userinfo, which is the same as the name of component userinfo"
+            )),
+        }
+
+        let manifest = deserialize_yaml("./test/data/duplicate_linkdef.yaml")
+            .expect("Should be able to parse");
+
+        match validate(&manifest, &ManifestLimits::default())
+            .await
+            .into_result()
+        {
+            Ok(()) => panic!("Should have detected duplicate linkdef"),
+            Err(e) => assert!(e.to_string().contains("Duplicate target")),
+        }
+
+        let manifest = deserialize_yaml("./test/data/duplicate_named_link_target.yaml")
+            .expect("Should be able to parse");
+
+        match validate(&manifest, &ManifestLimits::default()).await.into_result() {
+            Ok(()) => panic!("Should have detected duplicate linkdef"),
+            Err(e) => assert!(e.to_string().contains(
+                "Duplicate target redis for component counter linkdef trait with link name cache for wasi:keyvalue"
+            )),
+        }
+
+        // Linking to the same target is fine as long as the links are for different WIT packages
+        // or have different names
+        let manifest = deserialize_yaml("./test/data/shared_link_target.yaml")
+            .expect("Should be able to parse");
+        validate(&manifest, &ManifestLimits::default())
+            .await
+            .into_result()
+            .expect("Links sharing a target should be valid");
+
+        let manifest = deserialize_yaml("./test/data/duplicate_config_names.yaml")
+            .expect("Should be able to parse");
+        match validate(&manifest, &ManifestLimits::default()).await.into_result() {
+            Ok(()) => panic!("Should have detected duplicate config names"),
+            Err(e) => assert!(e.to_string().contains(
+                "Duplicate config name in manifest: shared-config is defined with different properties by http and webcap"
+            )),
+        }
+
+        let manifest = deserialize_yaml("./test/data/duplicate_link_name.yaml")
+            .expect("Should be able to parse");
+
+        match validate(&manifest, &ManifestLimits::default())
+            .await
+            .into_result()
+        {
+            Ok(()) => panic!("Should have detected duplicate link name"),
+            Err(e) => assert!(e
+                .to_string()
+                .contains("Duplicate link name cache for wasi:keyvalue on component counter")),
+        }
+
+        let manifest = deserialize_yaml("./test/data/duplicate_provider_link_name.yaml")
+            .expect("Should be able to parse");
+
+        match validate(&manifest, &ManifestLimits::default()).await.into_result() {
+            Ok(()) => panic!("Should have detected duplicate provider image and link name"),
+            Err(e) => assert!(e.to_string().contains(
+                "Capability components redis and redis2 both use the image ghcr.io/wasmcloud/keyvalue-redis:0.24.0 with the link name default"
+            )),
+        }
+
+        let manifest = deserialize_yaml("./test/data/missing_capability_component.yaml")
+            .expect("Should be able to parse");
+
+        match validate(&manifest, &ManifestLimits::default()).await.into_result() {
+            Ok(()) => panic!("Should have detected missing capability component"),
+            Err(e) => assert!(e
+                .to_string()
+                .contains("The following capability component(s) are missing from the manifest: httpclyent (linked from echo)")),
+        }
+
+        let manifest = deserialize_yaml("./test/data/invalid_spread_requirement.yaml")
+            .expect("Should be able to parse");
+
+        match validate(&manifest, &ManifestLimits::default()).await.into_result() {
+            Ok(()) => panic!("Should have detected invalid spread requirement label"),
+            Err(e) => assert!(e.to_string().contains(
+                "Spread east in the spreadscaler trait on component echo has an invalid requirement label"
+            )),
+        }
+
+        let manifest = deserialize_yaml("./test/data/zero_spread_weights.yaml")
+            .expect("Should be able to parse");
+
+        match validate(&manifest, &ManifestLimits::default())
+            .await
+            .into_result()
+        {
+            Ok(()) => panic!("Should have detected all zero spread weights"),
+            Err(e) => assert!(e
+                .to_string()
+                .contains("All spread weights in the daemonscaler trait on component echo are 0")),
+        }
+
+        let mut manifest = deserialize_yaml("./oam/simple1.yaml").expect("Should be able to parse");
+        manifest.api_version = "core.oam.dev/v2".to_string();
+        match validate(&manifest, &ManifestLimits::default()).await.into_result() {
+            Ok(()) => panic!("Should have detected an unsupported apiVersion"),
+            Err(e) => assert!(e.to_string().contains(&format!(
+                "Unsupported apiVersion core.oam.dev/v2, supported: [{OAM_VERSION}, {OAM_V1ALPHA1_VERSION}]"
+            ))),
+        }
+
+        let manifest = deserialize_yaml("./oam/simple1.yaml").expect("Should be able to parse");
+        let limits = ManifestLimits {
+            max_components: 1,
+            ..Default::default()
+        };
+        match validate(&manifest, &limits).await.into_result() {
+            Ok(()) => panic!("Should have detected too many components"),
+            Err(e) => assert!(e
+                .to_string()
+                .contains("exceeds the maximum of 1 components per manifest")),
+        }
+
+        let limits = ManifestLimits {
+            max_traits_per_component: 0,
+            ..Default::default()
+        };
+        match validate(&manifest, &limits).await.into_result() {
+            Ok(()) => panic!("Should have detected too many traits"),
+            Err(e) => assert!(e
+                .to_string()
+                .contains("exceeds the maximum of 0 traits per component")),
+        }
+
+        let manifest = deserialize_yaml("./oam/simple1.yaml").expect("Should be able to parse");
+        let limits = ManifestLimits {
+            max_instances: 3,
+            ..Default::default()
+        };
+        match validate(&manifest, &limits).await.into_result() {
+            Ok(()) => panic!("Should have detected too many instances"),
+            Err(e) => assert!(e
+                .to_string()
+                .contains("The spreadscaler trait on component userinfo has 4 instances, which exceeds the maximum of 3 instances")),
+        }
+
+        let mut manifest = deserialize_yaml("./oam/simple1.yaml").expect("Should be able to parse");
+        let nested = (0..10).fold(
+            serde_json::json!("leaf"),
+            |inner, _| serde_json::json!({ "nested": inner }),
+        );
+        let component = &mut manifest.spec.components[0];
+        component.traits.get_or_insert_with(Vec::new).push(Trait {
+            trait_type: "custom".to_string(),
+            properties: TraitProperty::Custom(nested),
+        });
+        let component_name = component.name.clone();
+        let limits = ManifestLimits {
+            max_config_depth: 5,
+            ..Default::default()
+        };
+        match validate(&manifest, &limits).await.into_result() {
+            Ok(()) => panic!("Should have detected deeply nested properties"),
+            Err(e) => assert!(e.to_string().contains(&format!(
+                "Component {component_name} has properties nested 10 levels deep"
+            ))),
+        }
+
+        let mut manifest = deserialize_yaml("./oam/simple1.yaml").expect("Should be able to parse");
+        manifest
+            .metadata
+            .annotations
+            .insert("huge".to_string(), "a".repeat(MAX_METADATA_VALUE_BYTES + 1));
+        match validate(&manifest, &ManifestLimits::default())
+            .await
+            .into_result()
+        {
+            Ok(()) => panic!("Should have detected an oversized annotation value"),
+            Err(e) => assert!(e
+                .to_string()
+                .contains("The value of metadata key huge is 4097 bytes")),
+        }
+
+        let mut manifest = deserialize_yaml("./oam/simple1.yaml").expect("Should be able to parse");
+        for i in 0..(MAX_METADATA_BYTES / MAX_METADATA_VALUE_BYTES) {
+            manifest
+                .metadata
+                .labels
+                .insert(format!("label{i}"), "a".repeat(MAX_METADATA_VALUE_BYTES));
+        }
+        match validate(&manifest, &ManifestLimits::default())
+            .await
+            .into_result()
+        {
+            Ok(()) => panic!("Should have detected too much metadata"),
+            Err(e) => assert!(e
+                .to_string()
+                .contains("which exceeds the maximum of 65536 bytes")),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_validation_categories() {
+        let mut manifest = deserialize_yaml("./oam/simple1.yaml").expect("Should be able to parse");
+        manifest
+            .metadata
+            .labels
+            .insert("not/a/valid/label".to_string(), "value".to_string());
+        for trait_item in manifest
+            .spec
+            .components
+            .iter_mut()
+            .flat_map(|component| component.traits.iter_mut().flatten())
+        {
+            if let TraitProperty::Link(link) = &mut trait_item.properties {
+                link.target = "nowhere".to_string();
+            }
+        }
+
+        let output = validate(&manifest, &ManifestLimits::default()).await;
+        assert!(!output.is_valid());
+        assert_eq!(
+            output
+                .errors
+                .iter()
+                .map(|issue| issue.category)
+                .collect::<Vec<_>>(),
+            vec![ValidationCategory::Metadata, ValidationCategory::Links],
+            "Every failing category should be reported: {output:?}"
+        );
+        assert!(
+            output.warnings.iter().any(|issue| issue
+                .message
+                .contains("target [nowhere] is not a listed component")),
+            "Warnings from wadm-types should be included: {output:?}"
+        );
+        let err = output.into_result().expect_err("Should be invalid");
+        assert!(err
+            .to_string()
+            .contains("missing from the manifest: nowhere"));
+
+        let manifest = deserialize_yaml("./oam/simple1.yaml").expect("Should be able to parse");
+        let limits = ManifestLimits {
+            max_components: 1,
+            ..Default::default()
+        };
+        let output = validate(&manifest, &limits).await;
+        assert_eq!(
+            output
+                .errors
+                .iter()
+                .map(|issue| issue.category)
+                .collect::<Vec<_>>(),
+            vec![ValidationCategory::Limits],
+            "Nothing else should be checked once the limits are exceeded"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_allowed_registries() {
+        let manifest = deserialize_yaml("./oam/simple1.yaml").expect("Should be able to parse");
+        let limits = ManifestLimits {
+            allowed_registries: vec!["WASMCLOUD.azurecr.io".to_string()],
+            ..Default::default()
+        };
+        validate(&manifest, &limits)
+            .await
+            .into_result()
+            .expect("Images from an allowed registry should be valid");
+
+        let limits = ManifestLimits {
+            allowed_registries: vec!["ghcr.io".to_string(), "docker.io".to_string()],
+            ..Default::default()
+        };
+        match validate(&manifest, &limits).await.into_result() {
+            Ok(()) => panic!("Should have rejected an image from a disallowed registry"),
+            Err(e) => assert!(e.to_string().contains(
+                "Image wasmcloud.azurecr.io/fake:1 of component userinfo is not from an allowed registry, allowed: [ghcr.io, docker.io]"
+            )),
+        }
+
+        let allowed = ["ghcr.io".to_string(), "docker.io".to_string()];
+        assert!(image_registry_allowed(
+            "ghcr.io/wasmcloud/http-server:0.20.0",
+            &allowed
+        ));
+        assert!(
+            image_registry_allowed("nginx:latest", &allowed),
+            "Refs without a registry should resolve to Docker Hub"
+        );
+        assert!(!image_registry_allowed(
+            "ghcr.io.evil.com/wasmcloud/http-server:0.20.0",
+            &allowed
+        ));
+        assert!(!image_registry_allowed(
+            "file:///path/to/component.wasm",
+            &allowed
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_round_trip_validation() {
+        let mut manifest = deserialize_yaml("./oam/simple1.yaml").expect("Should be able to parse");
+        assert!(
+            validate(&manifest, &ManifestLimits::default())
+                .await
+                .into_result()
+                .is_ok(),
+            "Parsed manifests should round trip"
+        );
+
+        // A custom trait that looks like a spreadscaler deserializes as a spreadscaler
+        manifest.spec.components[0]
+            .traits
+            .get_or_insert_with(Vec::new)
+            .push(Trait {
+                trait_type: "custom".to_string(),
+                properties: TraitProperty::Custom(json!({ "instances": 1 })),
+            });
+        let err = validate(&manifest, &ManifestLimits::default())
+            .await
+            .into_result()
+            .expect_err("A trait that changes when serialized should be rejected");
+        assert!(
+            err.to_string().contains("Trait custom"),
+            "Error should name the trait: {err}"
+        );
+    }
+
+    /// Ensure that a long image ref in a manifest works,
+    /// for both providers and actors
+    #[tokio::test]
+    async fn manifest_name_long_image_ref() -> Result<()> {
+        validate(
+            &deserialize_yaml("./test/data/long_image_refs.yaml")
+                .context("failed to deserialize YAML")?,
+            &ManifestLimits::default(),
+        )
+        .await
+        .into_result()
+        .context("failed to validate long image ref")?;
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn validate_oam_label_rules() {
+        // Valid labels
+        assert!(valid_oam_label((&"foo".to_string(), &"bar".to_string())));
+        assert!(valid_oam_label((
+            &"app.oam.io/name".to_string(),
+            &"wasmcloud".to_string()
+        )));
+        assert!(valid_oam_label((
+            &"justaregularstring".to_string(),
+            &"wasmcloud".to_string()
+        )));
+        assert!(valid_oam_label((
+            &"dash-period.numb3r/any_v4lue".to_string(),
+            &"this can be any string".to_string()
+        )));
+
+        // Invalid labels
+        assert!(!valid_oam_label((
+            &"my_prefix/app-name".to_string(),
+            &"wasmcloud".to_string()
+        )));
+        assert!(!valid_oam_label((
+            &"1my_prefix/app-name".to_string(),
+            &"wasmcloud".to_string()
+        )));
+        assert!(!valid_oam_label((
+            &"my_prefix---/app-name".to_string(),
+            &"wasmcloud".to_string()
+        )));
+        assert!(!valid_oam_label((
+            &"my_prefix/app-name...".to_string(),
+            &"wasmcloud".to_string()
+        )));
+        assert!(!valid_oam_label((
+            &"a".repeat(255).to_string(),
+            &"toolong".to_string()
+        )));
+    }
+}
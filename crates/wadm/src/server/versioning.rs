@@ -0,0 +1,81 @@
+use chrono::Utc;
+
+/// A strategy for generating the version of a manifest that is put without one
+pub trait VersionGenerator: Send + Sync {
+    /// Returns the version to assign to a new manifest for the given model. `existing_versions`
+    /// are the versions already stored for the model in creation order (empty for new models). The
+    /// returned version must not be one of them
+    fn next_version(&self, model_name: &str, existing_versions: &[&str]) -> String;
+}
+
+/// Generates a new ULID for each version. This is the default, as ULIDs sort in the order they
+/// were generated
+#[derive(Debug, Default, Clone, Copy)]
+pub struct UlidVersions;
+
+impl VersionGenerator for UlidVersions {
+    fn next_version(&self, _model_name: &str, _existing_versions: &[&str]) -> String {
+        ulid::Ulid::new().to_string()
+    }
+}
+
+/// Generates monotonically increasing versions of the form `v1`, `v2`, etc. The next version is
+/// one more than the highest version of that form already stored, so manually named versions are
+/// skipped over
+#[derive(Debug, Default, Clone, Copy)]
+pub struct SequentialVersions;
+
+impl VersionGenerator for SequentialVersions {
+    fn next_version(&self, _model_name: &str, existing_versions: &[&str]) -> String {
+        let highest = existing_versions
+            .iter()
+            .filter_map(|v| v.strip_prefix('v')?.parse::<u64>().ok())
+            .max()
+            .unwrap_or_default();
+        format!("v{}", highest + 1)
+    }
+}
+
+/// Generates versions from the current UTC time, such as `20240102T150405.123Z`. If a version
+/// for the same millisecond already exists, a counter is appended to keep it unique
+#[derive(Debug, Default, Clone, Copy)]
+pub struct TimestampVersions;
+
+impl VersionGenerator for TimestampVersions {
+    fn next_version(&self, _model_name: &str, existing_versions: &[&str]) -> String {
+        let timestamp = Utc::now().format("%Y%m%dT%H%M%S%.3fZ").to_string();
+        let mut version = timestamp.clone();
+        let mut counter = 1;
+        while existing_versions.contains(&version.as_str()) {
+            version = format!("{timestamp}-{counter}");
+            counter += 1;
+        }
+        version
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_sequential_versions() {
+        let generator = SequentialVersions;
+        assert_eq!(generator.next_version("app", &[]), "v1");
+        assert_eq!(generator.next_version("app", &["v1", "v2"]), "v3");
+        assert_eq!(
+            generator.next_version("app", &["v9", "v0.1.0", "custom", "v3"]),
+            "v10",
+            "Versions not of the form vN should be skipped over"
+        );
+    }
+
+    #[test]
+    fn test_timestamp_versions_are_unique() {
+        let generator = TimestampVersions;
+        let first = generator.next_version("app", &[]);
+        let second = generator.next_version("app", &[first.as_str()]);
+        assert_ne!(first, second);
+        assert!(!second.is_empty());
+    }
+}
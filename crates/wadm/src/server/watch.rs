@@ -0,0 +1,189 @@
+use std::{
+    collections::HashMap,
+    sync::{Arc, Mutex},
+};
+
+/// The default subject prefix that status and model watches may publish to. This is the prefix NATS
+/// clients use for reply inboxes unless configured otherwise
+pub const DEFAULT_WATCH_SUBJECT_PREFIX: &str = "_INBOX";
+/// The default maximum number of status and model watches a single account can have running at
+/// once
+pub const DEFAULT_MAX_WATCHES_PER_ACCOUNT: usize = 32;
+
+/// Checks that a subject given in a watch request is one the server can publish to on the caller's
+/// behalf. The server publishes with its own credentials, so the subject must be a concrete subject
+/// under one of the `allowed` prefixes (such as a client's inbox) and must not fall under any of
+/// the `reserved` prefixes the server itself listens on, including when prefixed by an account.
+/// Returns a message describing the problem if it can't be used
+pub(crate) fn validate_watch_subject(
+    subject: &str,
+    allowed: &[String],
+    reserved: &[&str],
+) -> Result<(), String> {
+    if subject.is_empty()
+        || subject.contains(char::is_whitespace)
+        || subject
+            .split('.')
+            .any(|token| token.is_empty() || token == "*" || token == ">")
+    {
+        return Err(format!(
+            "Invalid subject {subject:?}. Subjects must not be empty or contain wildcards"
+        ));
+    }
+    if !allowed
+        .iter()
+        .any(|prefix| is_under_prefix(subject, prefix))
+    {
+        return Err(format!(
+            "Invalid subject {subject:?}. Subjects must be an inbox starting with one of: {}",
+            allowed.join(", ")
+        ));
+    }
+    // Multitenant subjects start with the account, so check after the first token too
+    let unprefixed = subject.split_once('.').map(|(_, rest)| rest);
+    if reserved.iter().any(|prefix| {
+        is_under_prefix(subject, prefix) || unprefixed.is_some_and(|s| is_under_prefix(s, prefix))
+    }) {
+        return Err(format!(
+            "Invalid subject {subject:?}. Subjects wadm listens or publishes on can't be used"
+        ));
+    }
+    Ok(())
+}
+
+/// Returns true if the subject is the given prefix or has it as its leading tokens
+fn is_under_prefix(subject: &str, prefix: &str) -> bool {
+    let prefix = prefix.trim_matches('.');
+    !prefix.is_empty()
+        && subject
+            .strip_prefix(prefix)
+            .is_some_and(|rest| rest.is_empty() || rest.starts_with('.'))
+}
+
+/// Limits how many watches each account can have running at once. Every watch runs its own task
+/// and status stream consumer until it expires, so this keeps a single account from piling them up
+pub(crate) struct WatchLimiter {
+    max_per_account: usize,
+    active: Mutex<HashMap<String, usize>>,
+}
+
+impl WatchLimiter {
+    pub fn new(max_per_account: usize) -> WatchLimiter {
+        WatchLimiter {
+            max_per_account,
+            active: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Reserves a watch for the given account, returning `None` if it already has the maximum
+    /// number running. The watch is released when the returned permit is dropped
+    pub fn try_acquire(self: &Arc<Self>, account_id: Option<&str>) -> Option<WatchPermit> {
+        let account = account_id.unwrap_or_default().to_owned();
+        let mut active = self.active.lock().unwrap();
+        let count = active.entry(account.clone()).or_default();
+        if *count >= self.max_per_account {
+            return None;
+        }
+        *count += 1;
+        Some(WatchPermit {
+            limiter: self.clone(),
+            account,
+        })
+    }
+}
+
+/// A running watch counted against its account's limit in a [`WatchLimiter`]
+pub(crate) struct WatchPermit {
+    limiter: Arc<WatchLimiter>,
+    account: String,
+}
+
+impl Drop for WatchPermit {
+    fn drop(&mut self) {
+        let mut active = self.limiter.active.lock().unwrap();
+        if let Some(count) = active.get_mut(&self.account) {
+            *count = count.saturating_sub(1);
+            if *count == 0 {
+                active.remove(&self.account);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_validate_watch_subject() {
+        let allowed = vec![
+            DEFAULT_WATCH_SUBJECT_PREFIX.to_string(),
+            "acme.inbox".to_string(),
+        ];
+        let reserved = ["wadm.api", "wadm.evt", "wadm.status", "wadm.audit"];
+
+        for subject in ["_INBOX.abc123", "_INBOX.abc.def", "acme.inbox.abc"] {
+            assert!(
+                validate_watch_subject(subject, &allowed, &reserved).is_ok(),
+                "{subject} should be allowed"
+            );
+        }
+        for subject in [
+            "",
+            "_INBOX.*",
+            "_INBOX.>",
+            "_INBOX..abc",
+            "_INBOX.a b",
+            // Not an inbox
+            "my.subject",
+            "_INBOXES.abc",
+            "wadm.api.default.model.undeploy.app",
+            "OTHERACCOUNT.wadm.api.default.model.undeploy.app",
+            "wadm.evt.default",
+            "wadm.status.default.app",
+            "wadm.audit.default",
+        ] {
+            assert!(
+                validate_watch_subject(subject, &allowed, &reserved).is_err(),
+                "{subject:?} should be rejected"
+            );
+        }
+
+        // Inboxes that overlap what wadm listens on are rejected even if allowed
+        let allowed = vec!["wadm".to_string()];
+        assert!(
+            validate_watch_subject("wadm.api.default.model.list", &allowed, &reserved).is_err()
+        );
+        assert!(validate_watch_subject("wadm.inbox.abc", &allowed, &reserved).is_ok());
+    }
+
+    #[test]
+    fn test_watch_limiter() {
+        let limiter = Arc::new(WatchLimiter::new(2));
+
+        let first = limiter
+            .try_acquire(Some("acme"))
+            .expect("Should acquire a watch");
+        let _second = limiter
+            .try_acquire(Some("acme"))
+            .expect("Should acquire a watch");
+        assert!(
+            limiter.try_acquire(Some("acme")).is_none(),
+            "Should not acquire more than the maximum watches for an account"
+        );
+        assert!(
+            limiter.try_acquire(Some("other")).is_some(),
+            "Accounts should be limited separately"
+        );
+        assert!(
+            limiter.try_acquire(None).is_some(),
+            "Requests without an account should be limited separately"
+        );
+
+        drop(first);
+        assert!(
+            limiter.try_acquire(Some("acme")).is_some(),
+            "Dropping a permit should release its watch"
+        );
+    }
+}
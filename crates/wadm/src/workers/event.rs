@@ -654,7 +654,7 @@ where
         Ok(())
     }
 
-    #[instrument(level = "debug", skip(self, data), fields(name = %data.manifest.metadata.name))]
+    #[instrument(level = "debug", skip(self, data), fields(name = %data.manifest.metadata.name, trace_id = ?data.trace_id))]
     async fn handle_manifest_published(
         &self,
         lattice_id: &str,
@@ -895,12 +895,18 @@ where
                 .handle_provider_health_check(&message.lattice_id, data, Some(true))
                 .await
                 .map(|_| None),
+            // Canary versions are only staged, so we keep reconciling the deployed version rather
+            // than replacing its scalers
+            Event::ManifestPublished(data) if data.canary => {
+                debug!(name = %data.manifest.metadata.name, trace_id = ?data.trace_id, "Ignoring staged canary manifest");
+                return message.ack().await.map_err(WorkError::from);
+            }
             Event::ManifestPublished(data) => self
                 .handle_manifest_published(&message.lattice_id, data)
                 .await
                 .map(|_| None),
             Event::ManifestUnpublished(data) => {
-                debug!("Handling unpublished manifest");
+                debug!(name = %data.name, trace_id = ?data.trace_id, "Handling unpublished manifest");
 
                 match self.scalers.remove_scalers(&data.name).await {
                     Some(Ok(_)) => {
@@ -984,6 +990,7 @@ async fn scaler_status(scalers: &ScalerList) -> StatusInfo {
             })
             .collect::<Vec<_>>()
             .join(", "),
+        ..Default::default()
     }
 }
 
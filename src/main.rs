@@ -1,9 +1,10 @@
+use std::net::SocketAddr;
 use std::path::PathBuf;
 use std::sync::Arc;
 use std::time::Duration;
 
 use async_nats::jetstream::{stream::Stream, Context};
-use clap::Parser;
+use clap::{Parser, ValueEnum};
 use tokio::sync::Semaphore;
 use tracing::log::debug;
 use wadm_types::api::DEFAULT_WADM_TOPIC_PREFIX;
@@ -16,15 +17,24 @@ use wadm::{
     mirror::Mirror,
     nats_utils::LatticeIdParser,
     scaler::manager::{ScalerManager, WADM_NOTIFY_PREFIX},
-    server::{ManifestNotifier, Server},
+    server::{
+        ManifestLimits, ManifestNotifier, ModelNamePolicy, SequentialVersions, Server,
+        ServerConfig, StorageCodec, TimestampVersions, UlidVersions, DEFAULT_AUDIT_TOPIC_PREFIX,
+        DEFAULT_MAX_COMPONENTS, DEFAULT_MAX_CONFIG_DEPTH, DEFAULT_MAX_INSTANCES,
+        DEFAULT_MAX_MANIFEST_BYTES, DEFAULT_MAX_PUT_MODELS_BYTES, DEFAULT_MAX_TRAITS_PER_COMPONENT,
+        DEFAULT_MAX_WATCHES_PER_ACCOUNT, DEFAULT_REPLY_COMPRESSION_THRESHOLD,
+        DEFAULT_STATUS_FETCH_CONCURRENCY, DEFAULT_WATCH_SUBJECT_PREFIX,
+    },
+    status_topic_prefix,
     storage::{nats_kv::NatsKvStore, reaper::Reaper},
     workers::{CommandPublisher, CommandWorker, EventWorker, StatusPublisher},
-    DEFAULT_COMMANDS_TOPIC, DEFAULT_EVENTS_TOPIC, DEFAULT_MULTITENANT_EVENTS_TOPIC,
-    DEFAULT_STATUS_TOPIC, DEFAULT_WADM_EVENTS_TOPIC,
+    DEFAULT_ACCOUNT_STATUS_TOPIC, DEFAULT_COMMANDS_TOPIC, DEFAULT_EVENTS_TOPIC,
+    DEFAULT_MULTITENANT_EVENTS_TOPIC, DEFAULT_STATUS_TOPIC, DEFAULT_WADM_EVENTS_TOPIC,
 };
 
 mod connections;
 mod logging;
+mod metrics;
 mod nats;
 mod observer;
 
@@ -157,6 +167,268 @@ struct Args {
     )]
     manifest_bucket: String,
 
+    /// The maximum size in bytes of a manifest that can be put into wadm
+    #[arg(
+        long = "max-manifest-size",
+        env = "WADM_MAX_MANIFEST_SIZE",
+        default_value_t = DEFAULT_MAX_MANIFEST_BYTES
+    )]
+    max_manifest_size: usize,
+
+    /// The maximum size in bytes of a request that puts multiple manifests at once
+    #[arg(
+        long = "max-put-models-size",
+        env = "WADM_MAX_PUT_MODELS_SIZE",
+        default_value_t = DEFAULT_MAX_PUT_MODELS_BYTES
+    )]
+    max_put_models_size: usize,
+
+    /// The maximum number of models an account can store in a lattice. New versions of existing
+    /// models do not count against this limit. Defaults to no limit
+    #[arg(long = "max-models-per-account", env = "WADM_MAX_MODELS_PER_ACCOUNT")]
+    max_models_per_account: Option<usize>,
+
+    /// Per account overrides of --max-models-per-account in the form ACCOUNT_ID=LIMIT. Can be
+    /// specified multiple times or as a comma separated list
+    #[arg(
+        long = "account-model-quota",
+        env = "WADM_ACCOUNT_MODEL_QUOTAS",
+        value_delimiter = ',',
+        value_parser = parse_account_quota
+    )]
+    account_model_quotas: Vec<(String, usize)>,
+
+    /// Model name prefixes (such as system-) that are reserved for the accounts given with
+    /// --reserved-name-account. Can be specified multiple times or as a comma separated list
+    #[arg(
+        long = "reserved-name-prefix",
+        env = "WADM_RESERVED_NAME_PREFIXES",
+        value_delimiter = ','
+    )]
+    reserved_name_prefixes: Vec<String>,
+
+    /// Account IDs allowed to create models with a reserved name prefix. Can be specified multiple
+    /// times or as a comma separated list
+    #[arg(
+        long = "reserved-name-account",
+        env = "WADM_RESERVED_NAME_ACCOUNTS",
+        value_delimiter = ','
+    )]
+    reserved_name_accounts: Vec<String>,
+
+    /// Whether model names only need to be unique within an account (per-account) or across all
+    /// accounts in a lattice (lattice-global)
+    #[arg(
+        long = "model-name-policy",
+        env = "WADM_MODEL_NAME_POLICY",
+        default_value = "per-account"
+    )]
+    model_name_policy: ModelNamePolicy,
+
+    /// The maximum number of components (including providers) allowed in a single manifest
+    #[arg(
+        long = "max-manifest-components",
+        env = "WADM_MAX_MANIFEST_COMPONENTS",
+        default_value_t = DEFAULT_MAX_COMPONENTS
+    )]
+    max_manifest_components: usize,
+
+    /// The maximum number of traits allowed on a single component in a manifest
+    #[arg(
+        long = "max-component-traits",
+        env = "WADM_MAX_COMPONENT_TRAITS",
+        default_value_t = DEFAULT_MAX_TRAITS_PER_COMPONENT
+    )]
+    max_component_traits: usize,
+
+    /// The maximum nesting depth of the properties of a component or of one of its traits in a
+    /// manifest
+    #[arg(
+        long = "max-config-depth",
+        env = "WADM_MAX_CONFIG_DEPTH",
+        default_value_t = DEFAULT_MAX_CONFIG_DEPTH
+    )]
+    max_config_depth: usize,
+
+    /// The maximum number of instances a single spreadscaler or daemonscaler trait in a manifest can
+    /// declare
+    #[arg(
+        long = "max-instances",
+        env = "WADM_MAX_INSTANCES",
+        default_value_t = DEFAULT_MAX_INSTANCES
+    )]
+    max_instances: usize,
+
+    /// Registries (such as ghcr.io) that component and provider images are allowed to come from.
+    /// Can be specified multiple times or as a comma separated list. If none are given, images from
+    /// any registry are allowed
+    #[arg(
+        long = "allowed-registry",
+        env = "WADM_ALLOWED_REGISTRIES",
+        value_delimiter = ','
+    )]
+    allowed_registries: Vec<String>,
+
+    /// The path to a JSON schema to validate manifests against instead of the built in OAM schema.
+    /// Use this to validate manifests with custom OAM extensions
+    #[arg(long = "manifest-schema", env = "WADM_MANIFEST_SCHEMA")]
+    manifest_schema: Option<PathBuf>,
+
+    /// The maximum number of model statuses fetched at once when listing models
+    #[arg(
+        long = "status-fetch-concurrency",
+        env = "WADM_STATUS_FETCH_CONCURRENCY",
+        default_value_t = DEFAULT_STATUS_FETCH_CONCURRENCY
+    )]
+    status_fetch_concurrency: usize,
+
+    /// The age in seconds after which a model's last reported status is marked as stale when it is
+    /// fetched. If not set, statuses are never marked as stale
+    #[arg(long = "status-stale-after", env = "WADM_STATUS_STALE_AFTER")]
+    status_stale_after: Option<u64>,
+
+    /// The amount of time in seconds to wait for a single model storage operation before failing
+    /// the API request with a timeout error
+    #[arg(
+        long = "store-timeout",
+        env = "WADM_STORE_TIMEOUT",
+        default_value = "30"
+    )]
+    store_timeout: u64,
+
+    /// The amount of time in seconds a deploy can hold the lock on a model before the lock is
+    /// considered abandoned and can be taken over by another deploy
+    #[arg(
+        long = "deploy-lock-ttl",
+        env = "WADM_DEPLOY_LOCK_TTL",
+        default_value = "120"
+    )]
+    deploy_lock_ttl: u64,
+
+    /// The account to store models under for requests without an account, such as in single tenant
+    /// mode. Models stored without an account before this was set can still be read, and are moved
+    /// under the account the next time they change
+    #[arg(long = "default-account", env = "WADM_DEFAULT_ACCOUNT")]
+    default_account: Option<String>,
+
+    /// The maximum number of models to cache in memory. Cached models are invalidated by watching
+    /// the model bucket, so changes from other wadm replicas are always seen. Defaults to no cache
+    #[arg(long = "model-cache-size", env = "WADM_MODEL_CACHE_SIZE")]
+    model_cache_size: Option<usize>,
+
+    /// The amount of time in seconds to wait for a notification to processors to be sent
+    /// (including retries) before failing the API request with a timeout error
+    #[arg(
+        long = "notify-timeout",
+        env = "WADM_NOTIFY_TIMEOUT",
+        default_value = "30"
+    )]
+    notify_timeout: u64,
+
+    /// Reject manifests that are put without a version instead of assigning them one
+    #[arg(
+        long = "require-manifest-version",
+        env = "WADM_REQUIRE_MANIFEST_VERSION"
+    )]
+    require_manifest_version: bool,
+
+    /// Lowercase component names (and the targets of links to them) before manifests are stored.
+    /// Component names are always trimmed of whitespace
+    #[arg(
+        long = "lowercase-component-names",
+        env = "WADM_LOWERCASE_COMPONENT_NAMES"
+    )]
+    lowercase_component_names: bool,
+
+    /// How to generate versions for manifests that are put without one
+    #[arg(
+        long = "version-strategy",
+        env = "WADM_VERSION_STRATEGY",
+        value_enum,
+        default_value_t = VersionStrategy::Ulid
+    )]
+    version_strategy: VersionStrategy,
+
+    /// The topic prefix to publish audit records of every request that changes models under.
+    /// Records are published on {prefix}.{lattice-id}
+    #[arg(
+        long = "audit-topic-prefix",
+        env = "WADM_AUDIT_TOPIC_PREFIX",
+        default_value = DEFAULT_AUDIT_TOPIC_PREFIX
+    )]
+    audit_topic_prefix: String,
+
+    /// Disables publishing audit records
+    #[arg(long = "disable-audit-log", env = "WADM_DISABLE_AUDIT_LOG")]
+    disable_audit_log: bool,
+
+    /// The codec used to compress models stored in the manifest bucket (none, gzip, or zstd).
+    /// Models stored with any codec can always be read, so this can be changed at any time
+    #[arg(
+        long = "manifest-compression",
+        env = "WADM_MANIFEST_COMPRESSION",
+        default_value = "none"
+    )]
+    manifest_compression: StorageCodec,
+
+    /// The size in bytes at or above which API replies are gzipped for clients that send an
+    /// `accept-encoding: gzip` header
+    #[arg(
+        long = "reply-compression-threshold",
+        env = "WADM_REPLY_COMPRESSION_THRESHOLD",
+        default_value_t = DEFAULT_REPLY_COMPRESSION_THRESHOLD
+    )]
+    reply_compression_threshold: usize,
+
+    /// The amount of time in seconds between sweeps for models that have been undeployed for
+    /// longer than the TTL set by their wadm.io/ttl annotation
+    #[arg(
+        long = "model-sweep-interval",
+        env = "WADM_MODEL_SWEEP_INTERVAL",
+        default_value = "600"
+    )]
+    model_sweep_interval: u64,
+
+    /// Never delete models automatically, even if they have a TTL
+    #[arg(long = "disable-model-sweeper", env = "WADM_DISABLE_MODEL_SWEEPER")]
+    disable_model_sweeper: bool,
+
+    /// Allow get_raw requests, which return models exactly as they are stored. Useful for debugging
+    /// storage issues and for migration tooling
+    #[arg(long = "enable-raw-model-access", env = "WADM_ENABLE_RAW_MODEL_ACCESS")]
+    enable_raw_model_access: bool,
+
+    /// The subject prefixes that status and model watches can publish updates to. Clients that use
+    /// a custom inbox prefix need it added here. Can be specified multiple times or as a comma
+    /// separated list
+    #[arg(
+        long = "watch-subject-prefix",
+        env = "WADM_WATCH_SUBJECT_PREFIXES",
+        value_delimiter = ',',
+        default_value = DEFAULT_WATCH_SUBJECT_PREFIX
+    )]
+    watch_subject_prefixes: Vec<String>,
+
+    /// The maximum number of status and model watches a single account can have running at once
+    #[arg(
+        long = "max-watches-per-account",
+        env = "WADM_MAX_WATCHES_PER_ACCOUNT",
+        default_value_t = DEFAULT_MAX_WATCHES_PER_ACCOUNT
+    )]
+    max_watches_per_account: usize,
+
+    /// Never compress API replies, even for clients that accept it
+    #[arg(
+        long = "disable-reply-compression",
+        env = "WADM_DISABLE_REPLY_COMPRESSION"
+    )]
+    disable_reply_compression: bool,
+
+    /// The address to serve Prometheus metrics for API operations on (e.g. 0.0.0.0:9090). Metrics
+    /// are not collected if this isn't set
+    #[arg(long = "metrics-addr", env = "WADM_METRICS_ADDR")]
+    metrics_addr: Option<SocketAddr>,
+
     /// Run wadm in multitenant mode. This is for advanced multitenant use cases with segmented NATS
     /// account traffic and not simple cases where all lattices use credentials from the same
     /// account. See the deployment guide for more information
@@ -164,10 +436,43 @@ struct Args {
     multitenant: bool,
 }
 
+/// Parses an account quota override in the form ACCOUNT_ID=LIMIT
+fn parse_account_quota(s: &str) -> Result<(String, usize), String> {
+    let (account, limit) = s
+        .split_once('=')
+        .ok_or_else(|| format!("invalid account quota {s:?}, expected ACCOUNT_ID=LIMIT"))?;
+    let account = account.trim();
+    if account.is_empty() {
+        return Err(format!(
+            "invalid account quota {s:?}, account ID cannot be empty"
+        ));
+    }
+    let limit = limit
+        .trim()
+        .parse()
+        .map_err(|e| format!("invalid limit in account quota {s:?}: {e}"))?;
+    Ok((account.to_owned(), limit))
+}
+
+/// The strategies for generating versions for manifests put without one
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+enum VersionStrategy {
+    /// A new ULID for each version
+    Ulid,
+    /// Monotonically increasing versions of the form v1, v2, etc.
+    Sequential,
+    /// The current UTC time, such as 20240102T150405.123Z
+    Timestamp,
+}
+
 #[tokio::main]
 async fn main() -> anyhow::Result<()> {
     let args = Args::parse();
 
+    if let Some(account) = args.default_account.as_deref() {
+        wadm::server::set_default_account(account)?;
+    }
+
     logging::configure_tracing(
         args.structured_logging,
         args.tracing_enabled,
@@ -235,7 +540,10 @@ async fn main() -> anyhow::Result<()> {
     let status_stream = nats::ensure_status_stream(
         &context,
         internal_stream_name(STATUS_STREAM_NAME),
-        vec![DEFAULT_STATUS_TOPIC.to_owned()],
+        vec![
+            DEFAULT_STATUS_TOPIC.to_owned(),
+            DEFAULT_ACCOUNT_STATUS_TOPIC.to_owned(),
+        ],
     )
     .await?;
 
@@ -329,6 +637,8 @@ async fn main() -> anyhow::Result<()> {
         event_worker_creator,
     };
 
+    let metrics_registry = args.metrics_addr.map(|_| prometheus::Registry::new());
+
     debug!("Subscribing to API topic");
 
     let server = Server::new(
@@ -338,8 +648,55 @@ async fn main() -> anyhow::Result<()> {
         args.multitenant,
         status_stream,
         ManifestNotifier::new(wadm_event_prefix, context),
+        ServerConfig {
+            max_manifest_bytes: args.max_manifest_size,
+            max_put_models_bytes: args.max_put_models_size,
+            max_models_per_account: args.max_models_per_account,
+            account_model_quotas: args.account_model_quotas.into_iter().collect(),
+            reserved_name_prefixes: args.reserved_name_prefixes,
+            reserved_name_accounts: args.reserved_name_accounts.into_iter().collect(),
+            model_name_policy: args.model_name_policy,
+            manifest_limits: ManifestLimits {
+                max_components: args.max_manifest_components,
+                max_traits_per_component: args.max_component_traits,
+                max_config_depth: args.max_config_depth,
+                max_instances: args.max_instances,
+                allowed_registries: args.allowed_registries,
+            },
+            manifest_schema_path: args.manifest_schema,
+            status_fetch_concurrency: args.status_fetch_concurrency,
+            status_stale_after: args.status_stale_after.map(Duration::from_secs),
+            auto_version: !args.require_manifest_version,
+            lowercase_component_names: args.lowercase_component_names,
+            audit_topic_prefix: (!args.disable_audit_log).then_some(args.audit_topic_prefix),
+            storage_codec: args.manifest_compression,
+            reply_compression_threshold: (!args.disable_reply_compression)
+                .then_some(args.reply_compression_threshold),
+            store_timeout: Duration::from_secs(args.store_timeout),
+            deploy_lock_ttl: Duration::from_secs(args.deploy_lock_ttl),
+            model_cache_size: args.model_cache_size,
+            notify_timeout: Duration::from_secs(args.notify_timeout),
+            metrics_registry: metrics_registry.clone(),
+            enable_raw_model_access: args.enable_raw_model_access,
+            model_sweep_interval: (!args.disable_model_sweeper)
+                .then(|| Duration::from_secs(args.model_sweep_interval)),
+            watch_subject_prefixes: args.watch_subject_prefixes,
+            max_watches_per_account: args.max_watches_per_account,
+            ..Default::default()
+        },
     )
     .await?;
+    let server = match args.version_strategy {
+        VersionStrategy::Ulid => server.with_version_generator(UlidVersions),
+        VersionStrategy::Sequential => server.with_version_generator(SequentialVersions),
+        VersionStrategy::Timestamp => server.with_version_generator(TimestampVersions),
+    };
+    let metrics_server = async {
+        match (args.metrics_addr, metrics_registry) {
+            (Some(addr), Some(registry)) => metrics::serve(addr, registry).await,
+            _ => futures::future::pending().await,
+        }
+    };
     tokio::select! {
         res = server.serve() => {
             res?
@@ -347,6 +704,9 @@ async fn main() -> anyhow::Result<()> {
         res = observer.observe(event_stream_topics) => {
             res?
         }
+        res = metrics_server => {
+            res?
+        }
         _ = tokio::signal::ctrl_c() => {}
     }
     Ok(())
@@ -403,7 +763,7 @@ where
         let status_publisher = StatusPublisher::new(
             self.publisher.clone(),
             Some(self.status_stream.clone()),
-            &format!("wadm.status.{lattice_id}"),
+            &status_topic_prefix(multitenant_prefix, lattice_id),
         );
         let manager = ScalerManager::new(
             self.publisher.clone(),
@@ -0,0 +1,48 @@
+use std::net::SocketAddr;
+
+use prometheus::{Encoder, Registry, TextEncoder};
+use tokio::{
+    io::{AsyncReadExt, AsyncWriteExt},
+    net::{TcpListener, TcpStream},
+};
+use tracing::{debug, info, warn};
+
+/// Serves the metrics in the given registry in the Prometheus text format on the given address.
+/// This is deliberately minimal and responds to every request with the metrics, so it shouldn't be
+/// exposed anywhere other than to a scraper
+pub async fn serve(addr: SocketAddr, registry: Registry) -> anyhow::Result<()> {
+    let listener = TcpListener::bind(addr)
+        .await
+        .map_err(|e| anyhow::anyhow!("Unable to bind metrics listener on {addr}: {e}"))?;
+    info!(%addr, "Serving metrics");
+    loop {
+        let (stream, peer) = listener.accept().await?;
+        let registry = registry.clone();
+        tokio::spawn(async move {
+            if let Err(e) = respond(stream, &registry).await {
+                debug!(error = %e, %peer, "Unable to respond to metrics request");
+            }
+        });
+    }
+}
+
+async fn respond(mut stream: TcpStream, registry: &Registry) -> anyhow::Result<()> {
+    // We don't care what was requested, but read the request so the client doesn't see a reset
+    let mut buf = [0u8; 1024];
+    let _ = stream.read(&mut buf).await?;
+
+    let encoder = TextEncoder::new();
+    let mut body = Vec::new();
+    if let Err(e) = encoder.encode(&registry.gather(), &mut body) {
+        warn!(error = %e, "Unable to encode metrics");
+    }
+    let header = format!(
+        "HTTP/1.1 200 OK\r\nContent-Type: {}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n",
+        encoder.format_type(),
+        body.len()
+    );
+    stream.write_all(header.as_bytes()).await?;
+    stream.write_all(&body).await?;
+    stream.shutdown().await?;
+    Ok(())
+}
@@ -157,7 +157,7 @@ pub async fn ensure_status_stream(
     name: String,
     subjects: Vec<String>,
 ) -> Result<Stream> {
-    context
+    let mut stream = context
         .get_or_create_stream(StreamConfig {
             name,
             description: Some(
@@ -167,13 +167,30 @@ pub async fn ensure_status_stream(
             allow_direct: true,
             retention: async_nats::jetstream::stream::RetentionPolicy::Limits,
             max_messages_per_subject: 10,
-            subjects,
+            subjects: subjects.clone(),
             max_age: std::time::Duration::from_nanos(0),
             storage: async_nats::jetstream::stream::StorageType::File,
             ..Default::default()
         })
         .await
-        .map_err(|e| anyhow::anyhow!("{e:?}"))
+        .map_err(|e| anyhow::anyhow!("{e:?}"))?;
+
+    // Streams created by older versions of wadm don't have the account scoped status subjects, so
+    // add any that are missing
+    let mut config = stream.info().await?.config.clone();
+    let missing: Vec<String> = subjects
+        .into_iter()
+        .filter(|subject| !config.subjects.contains(subject))
+        .collect();
+    if !missing.is_empty() {
+        warn!(?missing, "Adding missing subjects to status stream");
+        config.subjects.extend(missing);
+        context
+            .update_stream(&config)
+            .await
+            .map_err(|e| anyhow::anyhow!("{e:?}"))?;
+    }
+    Ok(stream)
 }
 
 /// A helper that ensures that the notify stream exists
@@ -1,9 +1,11 @@
 use std::collections::{BTreeMap, HashMap, HashSet};
+use std::time::Duration;
 
 use chrono::Utc;
 
 use wadm::{
     events::ProviderInfo,
+    server::ModelStorage,
     storage::{
         nats_kv::NatsKvStore, Component, Host, Provider, ProviderStatus, ReadStore,
         Store as WadmStore, WadmComponentInfo,
@@ -349,3 +351,113 @@ async fn test_store_and_delete_many() {
 
     assert!(all_actors.is_empty(), "All actors should have no items");
 }
+
+#[tokio::test]
+async fn test_deploy_lock() {
+    let store = ModelStorage::new(create_test_store("deploy_lock_test".to_string()).await);
+    let ttl = Duration::from_secs(60);
+
+    let lock = store
+        .try_lock_deploy(None, "default", "app", ttl)
+        .await
+        .expect("Should be able to lock a model")
+        .expect("Should acquire a lock that isn't held");
+
+    assert!(
+        store
+            .try_lock_deploy(None, "default", "app", ttl)
+            .await
+            .expect("Should be able to try locking a model")
+            .is_none(),
+        "Should not acquire a lock that is already held"
+    );
+    let other = store
+        .try_lock_deploy(None, "default", "other", ttl)
+        .await
+        .expect("Should be able to lock a model")
+        .expect("Locks for different models should be independent");
+
+    store
+        .unlock_deploy(lock)
+        .await
+        .expect("Should be able to unlock a model");
+    store
+        .unlock_deploy(other)
+        .await
+        .expect("Should be able to unlock a model");
+    let lock = store
+        .try_lock_deploy(None, "default", "app", ttl)
+        .await
+        .expect("Should be able to lock a model")
+        .expect("Should acquire a lock after it is released");
+    store
+        .unlock_deploy(lock)
+        .await
+        .expect("Should be able to unlock a model");
+}
+
+#[tokio::test]
+async fn test_deploy_lock_contention() {
+    let store =
+        ModelStorage::new(create_test_store("deploy_lock_contention_test".to_string()).await);
+    let ttl = Duration::from_secs(60);
+
+    // Both attempts see the lock as free, so only the create-only write decides which one gets it
+    let (first, second) = tokio::join!(
+        store.try_lock_deploy(None, "default", "app", ttl),
+        store.try_lock_deploy(None, "default", "app", ttl),
+    );
+    let first = first.expect("Should be able to try locking a model");
+    let second = second.expect("Should be able to try locking a model");
+    assert!(
+        first.is_some() != second.is_some(),
+        "Exactly one concurrent attempt should acquire the lock"
+    );
+}
+
+#[tokio::test]
+async fn test_deploy_lock_takeover() {
+    let store = ModelStorage::new(create_test_store("deploy_lock_takeover_test".to_string()).await);
+    let ttl = Duration::from_secs(60);
+
+    let abandoned = store
+        .try_lock_deploy(None, "default", "app", ttl)
+        .await
+        .expect("Should be able to lock a model")
+        .expect("Should acquire a lock that isn't held");
+
+    tokio::time::sleep(Duration::from_millis(10)).await;
+    // Any lock held longer than the TTL is treated as abandoned
+    let taken_over = store
+        .try_lock_deploy(None, "default", "app", Duration::from_millis(1))
+        .await
+        .expect("Should be able to lock a model")
+        .expect("Should take over a lock held longer than the TTL");
+
+    // Releasing the abandoned lock must not release it for its new holder
+    store
+        .unlock_deploy(abandoned)
+        .await
+        .expect("Should be able to unlock a model");
+    assert!(
+        store
+            .try_lock_deploy(None, "default", "app", ttl)
+            .await
+            .expect("Should be able to try locking a model")
+            .is_none(),
+        "A lock that was taken over should stay held after the old holder unlocks it"
+    );
+
+    store
+        .unlock_deploy(taken_over)
+        .await
+        .expect("Should be able to unlock a model");
+    assert!(
+        store
+            .try_lock_deploy(None, "default", "app", ttl)
+            .await
+            .expect("Should be able to try locking a model")
+            .is_some(),
+        "Should acquire the lock once its new holder releases it"
+    );
+}
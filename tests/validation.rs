@@ -1,6 +1,9 @@
 use anyhow::{Context as _, Result};
 
-use wadm_types::validation::{validate_manifest_file, ValidationFailureLevel, ValidationOutput};
+use wadm_types::validation::{
+    validate_manifest_file, validate_manifest_with_options, ValidationFailureLevel,
+    ValidationOptions, ValidationOutput,
+};
 
 /// Ensure that valid YAML manifests are valid
 #[tokio::test]
@@ -91,3 +94,143 @@ async fn validate_custom_interface() -> Result<()> {
     );
     Ok(())
 }
+
+/// Ensure that we can detect link config references that aren't declared in the manifest
+#[tokio::test]
+async fn validate_unresolved_link_config() -> Result<()> {
+    let (_manifest, failures) =
+        validate_manifest_file("./tests/fixtures/manifests/unresolved-link-config.wadm.yaml")
+            .await
+            .context("failed to validate manifest")?;
+    assert!(
+        failures.len() == 1
+            && failures[0].level == ValidationFailureLevel::Warning
+            && failures[0].msg.contains("[default-http-addres]"),
+        "only the undeclared config reference should be flagged"
+    );
+    assert!(
+        failures.valid(),
+        "manifest should be valid (undeclared config could be managed externally)"
+    );
+    Ok(())
+}
+
+/// Ensure that we can detect link targets missing config keys the link requires
+#[tokio::test]
+async fn validate_missing_required_link_config() -> Result<()> {
+    let (manifest, failures) =
+        validate_manifest_file("./tests/fixtures/manifests/missing-required-link-config.wadm.yaml")
+            .await
+            .context("failed to validate manifest")?;
+    assert!(
+        failures.len() == 1
+            && failures[0].level == ValidationFailureLevel::Warning
+            && failures[0].msg.contains("(name [cache])")
+            && failures[0].msg.contains("[password, username]"),
+        "only the keys missing from the target's config should be flagged"
+    );
+    assert!(
+        failures.valid(),
+        "manifest should be valid (missing keys are a warning by default)"
+    );
+
+    let failures = validate_manifest_with_options(
+        &manifest,
+        &ValidationOptions {
+            strict_link_config: true,
+            ..Default::default()
+        },
+    )
+    .await?;
+    assert!(
+        !failures.valid(),
+        "manifest should be invalid when link config is checked strictly"
+    );
+    Ok(())
+}
+
+/// Ensure that spread requirements contradicting the manifest labels are only flagged when asked
+#[tokio::test]
+async fn validate_conflicting_labels() -> Result<()> {
+    let (manifest, failures) =
+        validate_manifest_file("./tests/fixtures/manifests/conflicting-labels.wadm.yaml")
+            .await
+            .context("failed to validate manifest")?;
+    assert!(
+        failures.is_empty(),
+        "label conflicts are not checked by default"
+    );
+
+    let failures = validate_manifest_with_options(
+        &manifest,
+        &ValidationOptions {
+            check_label_conflicts: true,
+            ..Default::default()
+        },
+    )
+    .await?;
+    assert!(
+        failures.len() == 1
+            && failures[0].level == ValidationFailureLevel::Warning
+            && failures[0].msg.contains("[zone=west]")
+            && failures[0].msg.contains("[zone=east]"),
+        "only the conflicting zone requirement should be flagged"
+    );
+
+    let failures = validate_manifest_with_options(
+        &manifest,
+        &ValidationOptions {
+            check_label_conflicts: true,
+            strict_label_conflicts: true,
+            ..Default::default()
+        },
+    )
+    .await?;
+    assert!(
+        !failures.valid(),
+        "manifest should be invalid when label conflicts are checked strictly"
+    );
+    Ok(())
+}
+
+/// Ensure that components with more than one scaler trait are rejected
+#[tokio::test]
+async fn validate_conflicting_scalers() -> Result<()> {
+    let (_manifest, failures) =
+        validate_manifest_file("./tests/fixtures/manifests/conflicting-scalers.wadm.yaml")
+            .await
+            .context("failed to validate manifest")?;
+    assert!(
+        failures.len() == 2
+            && failures
+                .iter()
+                .all(|f| f.level == ValidationFailureLevel::Error),
+        "each component with conflicting scalers should be an error"
+    );
+    assert!(
+        failures[0].msg.contains("[two-spreadscalers]")
+            && failures[0].msg.contains("[spreadscaler, spreadscaler]"),
+        "failure should name the component and its scalers"
+    );
+    assert!(
+        failures[1].msg.contains("[spread-and-daemon]")
+            && failures[1].msg.contains("[daemonscaler, spreadscaler]"),
+        "failure should name the component and its scalers"
+    );
+    assert!(!failures.valid(), "manifest should be invalid");
+    Ok(())
+}
+
+/// Ensure that a single scaler can be combined with links in any order
+#[tokio::test]
+async fn validate_multi_trait_component() -> Result<()> {
+    let (_manifest, failures) =
+        validate_manifest_file("./tests/fixtures/manifests/multi-trait-component.wadm.yaml")
+            .await
+            .context("failed to validate manifest")?;
+    assert!(
+        failures.is_empty(),
+        "a scaler combined with links should not fail validation: {failures:?}"
+    );
+    Ok(())
+}